@@ -0,0 +1,163 @@
+"""Generates the service models used by the chat `use_aws` tool from the botocore service models.
+
+Usage: python3 build-scripts/use_aws_models.py
+"""
+
+import json
+from pathlib import Path
+import re
+
+from botocore.loaders import Loader
+
+OUTPUT = Path(__file__).parent.parent / "crates/chat-cli/src/cli/chat/tools/use_aws/service_models.json"
+
+# CLI names of the services with a model
+SERVICES = [
+    "athena",
+    "cloudtrail",
+    "codebuild",
+    "dynamodb",
+    "ec2",
+    "ecr",
+    "ecs",
+    "events",
+    "glue",
+    "iam",
+    "kinesis",
+    "kms",
+    "lambda",
+    "logs",
+    "secretsmanager",
+    "ssm",
+    "stepfunctions",
+]
+
+# HTTP methods of operations that don't change anything, for the protocols that map operations to them
+READ_ONLY_METHODS = {"GET", "HEAD"}
+REST_PROTOCOLS = {"rest-json", "rest-xml"}
+
+# Read-only operations that return secrets or credentials
+SENSITIVE_OPERATIONS = {
+    "BatchGetSecretValue",
+    "GetAuthorizationToken",
+    "GetDownloadUrlForLayer",
+    "GetParameter",
+    "GetParameterHistory",
+    "GetParameters",
+    "GetParametersByPath",
+    "GetPasswordData",
+    "GetRandomPassword",
+    "GetSecretValue",
+    "GetShardIterator",
+    "GetRecords",
+}
+
+
+def read_only_operations(protocol: str, operations: dict) -> list[str] | None:
+    """Returns the operations the model marks read-only through the `readonly` trait or their HTTP
+    method, or None if the model doesn't say which operations are read-only"""
+    rest = protocol in REST_PROTOCOLS
+    if not rest and not any("readonly" in operation for operation in operations.values()):
+        return None
+    return sorted(
+        op
+        for op, operation in operations.items()
+        if op not in SENSITIVE_OPERATIONS
+        and (
+            operation.get("readonly", False)
+            or (rest and operation.get("http", {}).get("method") in READ_ONLY_METHODS)
+        )
+    )
+
+
+def simple_paginator(paginator: dict) -> dict | None:
+    """Returns the paginator if it can be followed natively, which needs a single token and result key"""
+    tokens = [paginator.get("input_token"), paginator.get("output_token"), paginator.get("result_key")]
+    if not all(isinstance(token, str) and re.fullmatch(r"\w+", token) for token in tokens):
+        return None
+    return {"input_token": tokens[0], "output_token": tokens[1], "result_key": tokens[2]}
+
+
+# Shape types the members of the input shapes are coerced to, referenced by their type instead of their name
+SCALAR_TYPES = {"string", "integer", "long", "boolean", "double", "float", "timestamp", "blob"}
+
+
+def input_shapes(operations: dict, shapes: dict) -> tuple[dict, dict]:
+    """Returns the input shape of each operation, and the structures, lists and maps reachable from
+    them. Members reference scalar shapes by their type, e.g. `integer`, and the others by name"""
+    reachable = {}
+
+    def reference(name: str) -> str:
+        shape = shapes[name]
+        if shape["type"] in SCALAR_TYPES:
+            return shape["type"]
+        if name not in reachable:
+            # Marks the shape as visited before its members, which may reference it again
+            reachable[name] = None
+            if shape["type"] == "structure":
+                members = {member: reference(m["shape"]) for member, m in shape["members"].items()}
+                reachable[name] = {"type": "structure", "members": members}
+            elif shape["type"] == "list":
+                reachable[name] = {"type": "list", "member": reference(shape["member"]["shape"])}
+            else:
+                reachable[name] = {"type": "map", "value": reference(shape["value"]["shape"])}
+        return name
+
+    inputs = {
+        op: reference(operation["input"]["shape"]) for op, operation in operations.items() if "input" in operation
+    }
+    return inputs, dict(sorted(reachable.items()))
+
+
+def service_model(loader: Loader, name: str) -> dict:
+    service = loader.load_service_model(name, "service-2")
+    try:
+        paginators = loader.load_service_model(name, "paginators-1")["pagination"]
+    except Exception:
+        paginators = {}
+
+    metadata = service["metadata"]
+    operations = service["operations"]
+    shapes = service["shapes"]
+
+    read_only = read_only_operations(metadata["protocol"], operations)
+    sensitive = sorted(op for op in operations if op in SENSITIVE_OPERATIONS)
+    dry_run = sorted(
+        op
+        for op, operation in operations.items()
+        if "DryRun" in shapes.get(operation.get("input", {}).get("shape"), {}).get("members", {})
+    )
+
+    model = {"protocol": metadata["protocol"]}
+    if metadata["protocol"] == "json":
+        model["endpoint_prefix"] = metadata["endpointPrefix"]
+        model["signing_name"] = metadata.get("signingName", metadata["endpointPrefix"])
+        model["json_version"] = metadata["jsonVersion"]
+        model["target_prefix"] = metadata["targetPrefix"]
+        model["input_shapes"], model["shapes"] = input_shapes(operations, shapes)
+    if read_only is not None:
+        model["read_only_operations"] = read_only
+    if sensitive:
+        model["sensitive_operations"] = sensitive
+    model["paginated_operations"] = sorted(op for op in paginators if op in operations)
+    if dry_run:
+        model["dry_run_operations"] = dry_run
+    model["paginators"] = {
+        op: simple
+        for op, paginator in sorted(paginators.items())
+        if op in operations
+        and op not in SENSITIVE_OPERATIONS
+        and (read_only is None or op in read_only)
+        and (simple := simple_paginator(paginator)) is not None
+    }
+    return model
+
+
+def main():
+    loader = Loader()
+    models = {name: service_model(loader, name) for name in SERVICES}
+    OUTPUT.write_text(json.dumps(models, indent=2) + "\n")
+
+
+if __name__ == "__main__":
+    main()
@@ -101,6 +101,55 @@ fn parse_legacy_color(color: &[u8]) -> Option<Rgb> {
     })
 }
 
+/// The path of a `file://` URL, as sent in OSC 7 to report the current directory. The host is
+/// ignored.
+fn parse_file_url(url: &[u8]) -> Option<String> {
+    let url = url.strip_prefix(b"file://")?;
+    let path = &url[url.iter().position(|b| *b == b'/')?..];
+
+    let mut decoded = Vec::with_capacity(path.len());
+    let mut i = 0;
+    while i < path.len() {
+        match (path[i], path.get(i + 1..i + 3)) {
+            (b'%', Some(hex)) => {
+                let hex = str::from_utf8(hex).ok()?;
+                decoded.push(u8::from_str_radix(hex, 16).ok()?);
+                i += 3;
+            },
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            },
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+/// Unescapes a value of an OSC 633 sequence, where `\\` is a backslash and `\xAB` is the byte
+/// `0xAB`.
+fn unescape_osc_633(value: &[u8]) -> Option<String> {
+    let mut unescaped = Vec::with_capacity(value.len());
+    let mut i = 0;
+    while i < value.len() {
+        match (value[i], value.get(i + 1)) {
+            (b'\\', Some(b'\\')) => {
+                unescaped.push(b'\\');
+                i += 2;
+            },
+            (b'\\', Some(b'x')) => {
+                let hex = str::from_utf8(value.get(i + 2..i + 4)?).ok()?;
+                unescaped.push(u8::from_str_radix(hex, 16).ok()?);
+                i += 4;
+            },
+            (byte, _) => {
+                unescaped.push(byte);
+                i += 1;
+            },
+        }
+    }
+    String::from_utf8(unescaped).ok()
+}
+
 fn parse_number(input: &[u8]) -> Option<u8> {
     if input.is_empty() {
         return None;
@@ -128,6 +177,14 @@ struct ProcessorState {
 
     /// State for synchronized terminal updates.
     sync_state: SyncState,
+
+    /// Whether the shell sends our own `697` sequences. Standard semantic prompt sequences are
+    /// ignored then, so that prompts aren't handled twice.
+    fig_integration: bool,
+
+    /// Whether the last prompt started with a semantic prompt sequence is a continuation or
+    /// secondary prompt, whose end doesn't start a new command.
+    secondary_prompt: bool,
 }
 
 #[derive(Debug)]
@@ -1171,8 +1228,66 @@ where
             // Reset text cursor color.
             b"112" => self.handler.reset_color(NamedColor::Cursor as usize),
 
+            // Set the current working directory.
+            b"7" => {
+                if params.len() >= 2 {
+                    if let Some(path) = parse_file_url(&params[1..].join(&b';')) {
+                        self.handler.dir(Path::new(&path));
+                        return;
+                    }
+                }
+                unhandled!();
+            },
+
+            // Semantic prompt marks, from FinalTerm, and VS Code's extension of them.
+            b"133" | b"633" => {
+                if self.state.fig_integration {
+                    return;
+                }
+
+                match (params[0], params.get(1).copied()) {
+                    // Prompt start, or the start of a new command and prompt
+                    (b"133", Some(b"A" | b"N" | b"P")) | (b"633", Some(b"A")) => {
+                        self.state.secondary_prompt = params[2..].iter().any(|param| matches!(*param, b"k=s" | b"k=c"));
+                        self.handler.start_prompt();
+                    },
+                    // Prompt end, where the command starts
+                    (_, Some(b"B")) => {
+                        self.handler.end_prompt();
+                        if !self.state.secondary_prompt {
+                            self.handler.new_cmd("");
+                        }
+                    },
+                    // The command is executed
+                    (_, Some(b"C")) => self.handler.pre_exec(),
+                    // The command finished, optionally with its exit code
+                    (_, Some(b"D")) => {
+                        let exit_code = params
+                            .get(2)
+                            .and_then(|code| str::from_utf8(code).ok())
+                            .and_then(|code| code.parse::<i32>().ok());
+                        if let Some(exit_code) = exit_code {
+                            self.handler.exit_code(exit_code);
+                        }
+                    },
+                    // Set a property, of which only the current working directory is used
+                    (b"633", Some(b"P")) => {
+                        let cwd = params
+                            .get(2)
+                            .and_then(|property| property.strip_prefix(b"Cwd="))
+                            .and_then(unescape_osc_633);
+                        match cwd {
+                            Some(cwd) => self.handler.dir(Path::new(&cwd)),
+                            None => unhandled!(),
+                        }
+                    },
+                    _ => unhandled!(),
+                }
+            },
+
             // feeg
             b"697" => {
+                self.state.fig_integration = true;
                 if let Some(fig_osc) = params.get(1) {
                     match *fig_osc {
                         b"NewCmd" => self.handler.new_cmd(""),
@@ -1686,9 +1801,34 @@ mod tests {
         index: CharsetIndex,
         charset: StandardCharset,
         attr: Option<Attr>,
+        shell_events: Vec<String>,
     }
 
     impl Handler for MockHandler {
+        fn new_cmd(&mut self, session_id: &str) {
+            self.shell_events.push(format!("NewCmd={session_id}"));
+        }
+
+        fn start_prompt(&mut self) {
+            self.shell_events.push("StartPrompt".into());
+        }
+
+        fn end_prompt(&mut self) {
+            self.shell_events.push("EndPrompt".into());
+        }
+
+        fn pre_exec(&mut self) {
+            self.shell_events.push("PreExec".into());
+        }
+
+        fn dir(&mut self, directory: &Path) {
+            self.shell_events.push(format!("Dir={}", directory.display()));
+        }
+
+        fn exit_code(&mut self, exit_code: i32) {
+            self.shell_events.push(format!("ExitCode={exit_code}"));
+        }
+
         fn terminal_attribute(&mut self, attr: Attr) {
             self.attr = Some(attr);
         }
@@ -1713,6 +1853,7 @@ mod tests {
                 index: CharsetIndex::G0,
                 charset: StandardCharset::Ascii,
                 attr: None,
+                shell_events: Vec::new(),
             }
         }
     }
@@ -1904,4 +2045,62 @@ mod tests {
     fn parse_number_too_large() {
         assert_eq!(parse_number(b"321"), None);
     }
+
+    fn shell_events(bytes: &[u8]) -> Vec<String> {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+        for byte in bytes {
+            parser.advance(&mut handler, *byte);
+        }
+        handler.shell_events
+    }
+
+    #[test]
+    fn parse_semantic_prompt() {
+        let bytes = b"\x1b]133;D;1\x07\x1b]7;file://host/home/user/my%20dir\x07\x1b]133;A;k=i\x07$ \
+                      \x1b]133;B\x07ls\r\n\x1b]133;C\x07";
+        assert_eq!(shell_events(bytes), vec![
+            "ExitCode=1",
+            "Dir=/home/user/my dir",
+            "StartPrompt",
+            "EndPrompt",
+            "NewCmd=",
+            "PreExec"
+        ]);
+
+        // Continuation prompts don't start a new command
+        assert_eq!(shell_events(b"\x1b]133;A;k=s\x07> \x1b]133;B\x07"), vec![
+            "StartPrompt",
+            "EndPrompt"
+        ]);
+        assert_eq!(shell_events(b"\x1b]133;D\x07"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn parse_vscode_prompt() {
+        let bytes = b"\x1b]633;P;Cwd=C:\\\\a\\x3bb\x07\x1b]633;A\x07\x1b]633;B\x07\x1b]633;E;ls\x07\x1b]633;C\x07\x1b]633;D;0\x07";
+        assert_eq!(shell_events(bytes), vec![
+            "Dir=C:\\a;b",
+            "StartPrompt",
+            "EndPrompt",
+            "NewCmd=",
+            "PreExec",
+            "ExitCode=0"
+        ]);
+    }
+
+    #[test]
+    fn parse_semantic_prompt_with_fig_integration() {
+        let bytes =
+            b"\x1b]697;StartPrompt\x07\x1b]133;A\x07\x1b]697;EndPrompt\x07\x1b]697;NewCmd=abc\x07\x1b]133;B\x07";
+        assert_eq!(shell_events(bytes), vec!["StartPrompt", "EndPrompt", "NewCmd=abc"]);
+    }
+
+    #[test]
+    fn test_parse_file_url() {
+        assert_eq!(parse_file_url(b"file:///tmp/a%2Fb").as_deref(), Some("/tmp/a/b"));
+        assert_eq!(parse_file_url(b"file://host"), None);
+        assert_eq!(parse_file_url(b"https://host/tmp"), None);
+        assert_eq!(unescape_osc_633(b"a\\\\b\\x3b").as_deref(), Some("a\\b;"));
+    }
 }
@@ -169,6 +169,9 @@ pub struct CommandInfo {
     pub end_time: Option<SystemTime>,
     pub username: Option<String>,
     pub exit_code: Option<i32>,
+    /// The text the command printed to the primary screen
+    #[serde(default)]
+    pub output: Option<String>,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -203,6 +206,8 @@ pub struct ShellState {
     pub preexec: bool,
     /// Position of start of cmd
     pub cmd_cursor: Option<Point>,
+    /// Position of start of the output of the running command
+    pub output_start: Option<Point>,
     /// Fish suggestion color
     pub fish_suggestion_color: Option<shell_color::SuggestionColor>,
     /// Zsh autosuggestion color
@@ -509,6 +514,9 @@ impl<T> Term<T> {
         if let Some(ref mut cursor) = self.shell_state.cmd_cursor {
             cursor.line += lines as i32;
         }
+        if let Some(ref mut start) = self.shell_state.output_start {
+            start.line += lines as i32;
+        }
 
         lines = min(lines, (self.scroll_region.end - self.scroll_region.start).0 as usize);
         lines = min(lines, (self.scroll_region.end - origin).0 as usize);
@@ -530,6 +538,9 @@ impl<T> Term<T> {
         if let Some(ref mut cursor) = self.shell_state.cmd_cursor {
             cursor.line -= lines as i32;
         }
+        if let Some(ref mut start) = self.shell_state.output_start {
+            start.line -= lines as i32;
+        }
 
         lines = min(lines, (self.scroll_region.end - self.scroll_region.start).0 as usize);
 
@@ -789,6 +800,23 @@ impl<T> Term<T> {
         }
     }
 
+    /// The text from `start` up to the cursor, which is where the output of a command ends once
+    /// the next prompt starts. Lines that were scrolled out of the history are lost.
+    fn text_to_cursor(&self, start: Point) -> String {
+        let cursor = self.grid.cursor.point;
+        let start = Point::new(max(start.line, self.topmost_line()), Column(0));
+        let end = match cursor.column {
+            Column(0) => Point::new(cursor.line - 1i32, self.last_column()),
+            column => Point::new(cursor.line, column - 1),
+        };
+
+        if end < start {
+            String::new()
+        } else {
+            self.bounds_to_string(start, end)
+        }
+    }
+
     pub fn set_windows_delay_end_prompt(&mut self, delay_end_prompt: bool) {
         self.windows_delay_end_prompt = delay_end_prompt;
     }
@@ -1734,6 +1762,15 @@ impl<T: EventListener> Handler for Term<T> {
         trace!("Fig start prompt");
         self.shell_state.has_seen_prompt = true;
 
+        if let Some(start) = self.shell_state.output_start.take() {
+            if !self.mode.contains(TermMode::ALT_SCREEN) {
+                let output = self.text_to_cursor(start);
+                if let Some(command_info) = &mut self.shell_state.command_info {
+                    command_info.output = Some(output);
+                }
+            }
+        }
+
         self.grid.cursor.template.fig_flags.insert(FigFlags::IN_PROMPT);
     }
 
@@ -1768,7 +1805,9 @@ impl<T: EventListener> Handler for Term<T> {
             username: context.username.clone(),
             exit_code: None,
             end_time: None,
+            output: None,
         });
+        self.shell_state.output_start = Some(Point::new(self.grid.cursor.point.line, Column(0)));
     }
 
     #[inline]
@@ -2124,6 +2163,32 @@ mod tests {
         Point,
     };
 
+    #[test]
+    fn command_output() {
+        let size = SizeInfo::new(4, 20);
+        let mut term = Term::new_test(size, VoidListener, 10_000);
+
+        term.pre_exec();
+        for line in ["one", "two", "three", "four", "five"] {
+            for c in line.chars() {
+                term.input(c);
+            }
+            term.carriage_return();
+            term.linefeed();
+        }
+        for c in "no newline".chars() {
+            term.input(c);
+        }
+        term.start_prompt();
+
+        let command_info = term.shell_state().command_info.as_ref().unwrap();
+        assert_eq!(
+            command_info.output.as_deref(),
+            Some("one\ntwo\nthree\nfour\nfive\nno newline")
+        );
+        assert!(term.shell_state().output_start.is_none());
+    }
+
     #[test]
     fn scroll_display_page_up() {
         let size = SizeInfo::new(10, 5);
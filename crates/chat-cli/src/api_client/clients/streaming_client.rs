@@ -5,10 +5,13 @@ use std::sync::{
 
 use amzn_codewhisperer_streaming_client::Client as CodewhispererStreamingClient;
 use amzn_qdeveloper_streaming_client::Client as QDeveloperStreamingClient;
+use aws_smithy_runtime_api::client::orchestrator::HttpRequest;
+use aws_smithy_types::body::SdkBody;
 use aws_types::request_id::RequestId;
 use tracing::{
     debug,
     error,
+    warn,
 };
 
 use super::shared::{
@@ -136,6 +139,12 @@ impl StreamingClient {
             user_input_message,
             history,
         } = conversation_state;
+        let model_id = user_input_message.model_id.clone();
+        let add_model_id = move |request: &mut HttpRequest| {
+            if let Some(model_id) = &model_id {
+                set_model_id(request, model_id);
+            }
+        };
 
         match &self.inner {
             inner::Inner::Codewhisperer(client) => {
@@ -158,6 +167,8 @@ impl StreamingClient {
                     .generate_assistant_response()
                     .conversation_state(conversation_state)
                     .set_profile_arn(self.profile.as_ref().map(|p| p.arn.clone()))
+                    .customize()
+                    .mutate_request(add_model_id)
                     .send()
                     .await;
 
@@ -197,6 +208,8 @@ impl StreamingClient {
                     client
                         .send_message()
                         .conversation_state(conversation_state_builder.build().expect("fix me"))
+                        .customize()
+                        .mutate_request(add_model_id)
                         .send()
                         .await?,
                 ))
@@ -210,6 +223,40 @@ impl StreamingClient {
     }
 }
 
+/// Sets the `modelId` of the current message on a serialized request.
+///
+/// The generated clients don't model `modelId` yet, so it is added to the JSON body instead of
+/// through the `UserInputMessage` builders. The model is the only inference setting the service
+/// accepts per request, there are no sampling parameters such as temperature or max tokens.
+fn set_model_id(request: &mut HttpRequest, model_id: &str) {
+    let Some(mut body) = request
+        .body()
+        .bytes()
+        .and_then(|bytes| serde_json::from_slice::<serde_json::Value>(bytes).ok())
+    else {
+        warn!("unable to set the model id on a request without a JSON body");
+        return;
+    };
+    let Some(message) = body
+        .pointer_mut("/conversationState/currentMessage/userInputMessage")
+        .and_then(serde_json::Value::as_object_mut)
+    else {
+        warn!("unable to set the model id on a request without a user input message");
+        return;
+    };
+    message.insert("modelId".to_string(), model_id.into());
+
+    let body = match serde_json::to_vec(&body) {
+        Ok(body) => body,
+        Err(err) => {
+            warn!(?err, "unable to serialize the request with the model id");
+            return;
+        },
+    };
+    request.headers_mut().insert("content-length", body.len().to_string());
+    *request.body_mut() = SdkBody::from(body);
+}
+
 #[derive(Debug)]
 pub enum SendMessageOutput {
     Codewhisperer(
@@ -270,6 +317,27 @@ mod tests {
         let _ = StreamingClient::new_qdeveloper_client(&database, &endpoint).await;
     }
 
+    #[test]
+    fn test_set_model_id() {
+        let body = r#"{"conversationState":{"currentMessage":{"userInputMessage":{"content":"Hello"}}}}"#;
+        let mut request = HttpRequest::new(SdkBody::from(body));
+        set_model_id(&mut request, "claude-sonnet");
+
+        let body = request.body().bytes().unwrap();
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(body).unwrap(),
+            serde_json::json!({
+                "conversationState": {
+                    "currentMessage": { "userInputMessage": { "content": "Hello", "modelId": "claude-sonnet" } }
+                }
+            })
+        );
+        assert_eq!(
+            request.headers().get("content-length"),
+            Some(body.len().to_string().as_str())
+        );
+    }
+
     #[tokio::test]
     async fn test_mock() {
         let client = StreamingClient::mock(vec![vec![
@@ -291,6 +359,7 @@ mod tests {
                     content: "Hello".into(),
                     user_input_message_context: None,
                     user_intent: None,
+                    model_id: None,
                 },
                 history: None,
             })
@@ -317,6 +386,7 @@ mod tests {
                     content: "How about rustc?".into(),
                     user_input_message_context: None,
                     user_intent: None,
+                    model_id: None,
                 },
                 history: Some(vec![
                     ChatMessage::UserInputMessage(UserInputMessage {
@@ -324,6 +394,7 @@ mod tests {
                         content: "What language is the linux kernel written in, and who wrote it?".into(),
                         user_input_message_context: None,
                         user_intent: None,
+                        model_id: None,
                     }),
                     ChatMessage::AssistantResponseMessage(AssistantResponseMessage {
                         content: "It is written in C by Linus Torvalds.".into(),
@@ -859,6 +859,7 @@ pub struct UserInputMessage {
     pub user_input_message_context: Option<UserInputMessageContext>,
     pub user_intent: Option<UserIntent>,
     pub images: Option<Vec<ImageBlock>>,
+    pub model_id: Option<String>,
 }
 
 impl From<UserInputMessage> for amzn_codewhisperer_streaming_client::types::UserInputMessage {
@@ -976,6 +977,7 @@ mod tests {
                 })]),
             }),
             user_intent: Some(UserIntent::ApplyCommonBestPractices),
+            model_id: Some("test model".to_string()),
         };
 
         let codewhisper_input =
@@ -989,6 +991,7 @@ mod tests {
             content: "test content".to_string(),
             user_input_message_context: None,
             user_intent: None,
+            model_id: None,
         };
 
         let codewhisper_minimal =
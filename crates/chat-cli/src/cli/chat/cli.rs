@@ -25,6 +25,9 @@ pub struct Chat {
     /// Context profile to use
     #[arg(long = "profile")]
     pub profile: Option<String>,
+    /// Model to respond with, see /model list for the available models
+    #[arg(long)]
+    pub model: Option<String>,
     /// Allows the model to use any tool to run commands without asking for confirmation.
     #[arg(long)]
     pub trust_all_tools: bool,
@@ -116,6 +119,56 @@ impl std::fmt::Display for Scope {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Subcommand)]
+pub enum Config {
+    /// Write the installed settings, MCP servers, hooks and profiles to a bundle
+    Export(ConfigExport),
+    /// Install a bundle, keeping anything that was changed locally
+    Apply(ConfigApply),
+    /// Show what applying a bundle would change
+    Diff(ConfigDiff),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Args)]
+pub struct ConfigExport {
+    /// File to write the bundle to, printed if unset
+    #[arg(long, short)]
+    pub output: Option<String>,
+    /// Format of the bundle, inferred from the output file's extension if unset
+    #[arg(long, value_enum)]
+    pub format: Option<BundleFormat>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Args)]
+pub struct ConfigApply {
+    /// Path to the bundle
+    pub file: String,
+    /// Show the changes without applying them
+    #[arg(long)]
+    pub dry_run: bool,
+    /// Overwrite anything that was changed locally
+    #[arg(long)]
+    pub force: bool,
+    /// Apply the changes without asking for confirmation
+    #[arg(long, short = 'y')]
+    pub yes: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Args)]
+pub struct ConfigDiff {
+    /// Path to the bundle
+    pub file: String,
+    /// Show the changes as if anything that was changed locally would be overwritten
+    #[arg(long)]
+    pub force: bool,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum BundleFormat {
+    Toml,
+    Json,
+}
+
 #[derive(Debug)]
 struct EnvVarParseError(String);
 
@@ -16,6 +16,11 @@ use serde::{
     Serialize,
 };
 
+use super::mentions::{
+    Mention,
+    OutputMention,
+};
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum Command {
     Ask {
@@ -51,6 +56,12 @@ pub enum Command {
         subcommand: Option<PromptsSubcommand>,
     },
     Usage,
+    Todos {
+        subcommand: TodosSubcommand,
+    },
+    Model {
+        subcommand: ModelSubcommand,
+    },
     Load {
         path: String,
     },
@@ -119,12 +130,22 @@ pub enum HooksSubcommand {
     Add {
         name: String,
 
-        #[arg(long, value_parser = ["per_prompt", "conversation_start"])]
+        #[arg(long, value_parser = [
+            "per_prompt",
+            "conversation_start",
+            "pre_tool_use",
+            "post_tool_use",
+            "assistant_response",
+            "session_end",
+        ])]
         trigger: String,
 
         #[arg(long, value_parser = clap::value_parser!(String))]
         command: String,
 
+        #[arg(long)]
+        matcher: Option<String>,
+
         #[arg(long)]
         global: bool,
     },
@@ -208,8 +229,10 @@ impl ContextSubcommand {
 
   <em>hooks add [--global] <<name>></em>        <black!>Add a new command context hook</black!>
                                          <black!>--global: Add to global hooks</black!>
-         <em>--trigger <<trigger>></em>           <black!>When to trigger the hook, valid options: `per_prompt` or `conversation_start`</black!>
+         <em>--trigger <<trigger>></em>           <black!>When to trigger the hook, valid options: `per_prompt`, `conversation_start`,</black!>
+                                         <black!>`pre_tool_use`, `post_tool_use`, `assistant_response` or `session_end`</black!>
          <em>--command <<command>></em>             <black!>Shell command to execute</black!>
+         <em>--matcher <<glob>></em>                <black!>Tool names to run a tool use hook for, e.g. `fs_*`</black!>
 
   <em>hooks rm [--global] <<name>></em>         <black!>Remove an existing context hook</black!>
                                          <black!>--global: Remove from global hooks</black!>
@@ -255,6 +278,8 @@ the output as context to Amazon Q.
 • You can add specific files or use glob patterns (e.g., "*.py", "src/**/*.js")
 • Profile rules apply only to the current profile
 • Global rules apply across all profiles
+• AmazonQ.md and .amazonq/rules are also read from parent directories up to the git root
+• Rules in subdirectories are loaded once Amazon Q reads or writes files inside them
 • Context is preserved between chat sessions
 "#,
             Self::AVAILABLE_COMMANDS
@@ -282,6 +307,11 @@ in global or local profiles.
 • Hooks are executed in parallel
 • 'conversation_start' hooks run on the first user prompt and are attached once to the conversation history sent to Amazon Q
 • 'per_prompt' hooks run on each user prompt and are attached to the prompt, but are not stored in conversation history
+• 'pre_tool_use', 'post_tool_use', 'assistant_response' and 'session_end' hooks receive the event as JSON on stdin
+• A tool use hook that exits with code 2 blocks the tool use, sending its stderr back to Amazon Q
+• A 'pre_tool_use' hook may also print {{"decision": "allow" | "deny" | "modify", "reason": ..., "tool_input": ...}}
+• "allow" only skips the approval prompt when the 'chat.hooks.allowTools' setting is enabled, and never for hooks of a workspace profile
+• Hooks of a workspace profile only run once you approve them
 "#,
             Self::HOOKS_AVAILABLE_COMMANDS
         )
@@ -341,6 +371,111 @@ trust so that no confirmation is required. These settings will last only for thi
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TodosSubcommand {
+    Show,
+    Add { description: String },
+    Done { id: usize },
+    Undo { id: usize },
+    Remove { id: usize },
+    Clear,
+    Help,
+}
+
+impl TodosSubcommand {
+    const AVAILABLE_COMMANDS: &str = color_print::cstr! {"<cyan!>Available subcommands</cyan!>
+  <em>help</em>                <black!>Show an explanation for the todos command</black!>
+  <em>show</em>                <black!>Show the todo list</black!>
+  <em>add <<description>></em>   <black!>Add an item to the todo list</black!>
+  <em>done <<id>></em>           <black!>Mark an item as completed</black!>
+  <em>undo <<id>></em>           <black!>Mark a completed item as pending again</black!>
+  <em>rm <<id>></em>             <black!>Remove an item from the todo list</black!>
+  <em>clear</em>               <black!>Remove all items from the todo list</black!>"};
+    const BASE_COMMAND: &str = color_print::cstr! {"<cyan!>Usage: /todos [SUBCOMMAND]</cyan!>
+
+<cyan!>Description</cyan!>
+  Show the todo list Amazon Q maintains while working through multi-step tasks.
+  Alternatively, specify a subcommand to edit the list."};
+
+    fn usage_msg(header: impl AsRef<str>) -> String {
+        format!(
+            "{}\n\n{}\n\n{}",
+            header.as_ref(),
+            Self::BASE_COMMAND,
+            Self::AVAILABLE_COMMANDS
+        )
+    }
+
+    pub fn help_text() -> String {
+        color_print::cformat!(
+            r#"
+<magenta,em>Todo List</magenta,em>
+
+For tasks that take several steps, Amazon Q keeps a todo list of the steps and checks them off as it
+goes. The list is shown above the prompt while it has open items, is kept when the conversation is
+compacted, and is saved with /save.
+
+{}
+
+{}"#,
+            Self::BASE_COMMAND,
+            Self::AVAILABLE_COMMANDS
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModelSubcommand {
+    List,
+    Set { name: String },
+    Help,
+}
+
+impl ModelSubcommand {
+    const AVAILABLE_COMMANDS: &str = color_print::cstr! {"<cyan!>Available subcommands</cyan!>
+  <em>help</em>                <black!>Show an explanation for the model command</black!>
+  <em>list</em>                <black!>List the available models</black!>
+  <em>set <<name>></em>          <black!>Respond with the specified model for the rest of the conversation</black!>"};
+    const BASE_COMMAND: &str = color_print::cstr! {"<cyan!>Usage: /model [SUBCOMMAND]</cyan!>
+
+<cyan!>Description</cyan!>
+  Show the available models and the one currently responding.
+  Alternatively, specify a subcommand to switch models."};
+
+    fn usage_msg(header: impl AsRef<str>) -> String {
+        format!(
+            "{}\n\n{}\n\n{}",
+            header.as_ref(),
+            Self::BASE_COMMAND,
+            Self::AVAILABLE_COMMANDS
+        )
+    }
+
+    pub fn help_text() -> String {
+        color_print::cformat!(
+            r#"
+<magenta,em>Model Selection</magenta,em>
+
+Pick a faster model for quick questions or a stronger one for larger changes. The model is saved
+with the conversation and also sets the context window size used for /usage and the context limit
+warnings.
+
+Start a conversation with a specific model using <em>q chat --model <<name>></em>, or set a default with
+<em>q settings chat.defaultModel <<name>></em>. Profiles and custom commands may also specify a model.
+Additional models can be configured in <em>~/.aws/amazonq/models.json</em>.
+
+The model is the only inference setting sent with each request. Sampling settings such as the
+temperature and the maximum response length are chosen by the service and can't be changed.
+
+{}
+
+{}"#,
+            Self::BASE_COMMAND,
+            Self::AVAILABLE_COMMANDS
+        )
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum PromptsSubcommand {
     List { search_word: Option<String> },
@@ -818,6 +953,49 @@ impl Command {
                     }
                 },
                 "usage" => Self::Usage,
+                "todos" => {
+                    let id = || {
+                        parts
+                            .get(2)
+                            .and_then(|id| id.parse::<usize>().ok())
+                            .ok_or_else(|| TodosSubcommand::usage_msg("A valid item id is required."))
+                    };
+                    let subcommand = match parts.get(1).map(|s| s.to_lowercase()).as_deref() {
+                        None | Some("show") => TodosSubcommand::Show,
+                        Some("add") => {
+                            let description = parts[2..].join(" ");
+                            if description.is_empty() {
+                                return Err(TodosSubcommand::usage_msg("A description is required."));
+                            }
+                            TodosSubcommand::Add { description }
+                        },
+                        Some("done") => TodosSubcommand::Done { id: id()? },
+                        Some("undo") => TodosSubcommand::Undo { id: id()? },
+                        Some("rm") => TodosSubcommand::Remove { id: id()? },
+                        Some("clear") => TodosSubcommand::Clear,
+                        Some("help") => TodosSubcommand::Help,
+                        Some(other) => {
+                            return Err(TodosSubcommand::usage_msg(format!("Unknown subcommand '{}'.", other)));
+                        },
+                    };
+                    Self::Todos { subcommand }
+                },
+                "model" => {
+                    let subcommand = match parts.get(1).map(|s| s.to_lowercase()).as_deref() {
+                        None | Some("list") => ModelSubcommand::List,
+                        Some("set") => match parts.get(2) {
+                            Some(name) => ModelSubcommand::Set {
+                                name: (*name).to_string(),
+                            },
+                            None => return Err(ModelSubcommand::usage_msg("A model name is required.")),
+                        },
+                        Some("help") => ModelSubcommand::Help,
+                        Some(other) => {
+                            return Err(ModelSubcommand::usage_msg(format!("Unknown subcommand '{}'.", other)));
+                        },
+                    };
+                    Self::Model { subcommand }
+                },
                 "load" => {
                     let Some(path) = parts.get(1) else {
                         return Err("path is required".to_string());
@@ -861,7 +1039,12 @@ impl Command {
             });
         }
 
-        if let Some(command) = input.strip_prefix('@') {
+        // A leading mention of an existing path or of a command output, e.g. `@src/main.rs explain
+        // this`, is a prompt with an attachment rather than an MCP prompt.
+        let first_word = input.split_whitespace().next().unwrap_or_default();
+        let mentions_path = OutputMention::parse(first_word).is_some()
+            || Mention::parse(first_word).is_some_and(|mention| std::path::Path::new(&mention.path).exists());
+        if let (Some(command), false) = (input.strip_prefix('@'), mentions_path) {
             let get_command = parse_input_to_prompts_get_command(command)?;
             let subcommand = Some(PromptsSubcommand::Get { get_command });
             return Ok(Self::Prompts { subcommand });
@@ -944,7 +1127,38 @@ mod tests {
                 }
             };
         }
+        macro_rules! todos {
+            ($subcommand:expr) => {
+                Command::Todos {
+                    subcommand: $subcommand,
+                }
+            };
+        }
+        macro_rules! model {
+            ($subcommand:expr) => {
+                Command::Model {
+                    subcommand: $subcommand,
+                }
+            };
+        }
         let tests = &[
+            ("/model", model!(ModelSubcommand::List)),
+            (
+                "/model set claude-4-sonnet",
+                model!(ModelSubcommand::Set {
+                    name: "claude-4-sonnet".to_string()
+                }),
+            ),
+            ("/todos", todos!(TodosSubcommand::Show)),
+            (
+                "/todos add write the tests",
+                todos!(TodosSubcommand::Add {
+                    description: "write the tests".to_string()
+                }),
+            ),
+            ("/todos done 2", todos!(TodosSubcommand::Done { id: 2 })),
+            ("/todos rm 3", todos!(TodosSubcommand::Remove { id: 3 })),
+            ("/todos clear", todos!(TodosSubcommand::Clear)),
             ("/compact", compact!(None, true)),
             (
                 "/compact custom prompt",
@@ -1034,7 +1248,20 @@ mod tests {
                         name: "test".to_string(),
                         global: true,
                         trigger: "per_prompt".to_string(),
-                        command: "echo 1".to_string()
+                        command: "echo 1".to_string(),
+                        matcher: None,
+                    })
+                }),
+            ),
+            (
+                "/context hooks add fmt --trigger post_tool_use --matcher fs_write --command 'cargo fmt'",
+                context!(ContextSubcommand::Hooks {
+                    subcommand: Some(HooksSubcommand::Add {
+                        name: "fmt".to_string(),
+                        global: false,
+                        trigger: "post_tool_use".to_string(),
+                        command: "cargo fmt".to_string(),
+                        matcher: Some("fs_write".to_string()),
                     })
                 }),
             ),
@@ -1090,6 +1317,30 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_leading_mention() {
+        let mut stdout = std::io::stdout();
+        // Tests run from the crate's directory
+        assert_eq!(
+            Command::parse("@Cargo.toml:1-5 what is this?", &mut stdout).unwrap(),
+            Command::Ask {
+                prompt: "@Cargo.toml:1-5 what is this?".to_string()
+            }
+        );
+        assert_eq!(
+            Command::parse("@last-output why did this fail?", &mut stdout).unwrap(),
+            Command::Ask {
+                prompt: "@last-output why did this fail?".to_string()
+            }
+        );
+        assert!(matches!(
+            Command::parse("@some-prompt arg", &mut stdout).unwrap(),
+            Command::Prompts {
+                subcommand: Some(PromptsSubcommand::Get { .. })
+            }
+        ));
+    }
+
     #[test]
     fn test_common_command_suggestions() {
         let mut stdout = std::io::stdout();
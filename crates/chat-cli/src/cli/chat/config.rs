@@ -0,0 +1,653 @@
+use std::collections::{
+    BTreeMap,
+    BTreeSet,
+};
+use std::io::{
+    IsTerminal,
+    Write,
+};
+use std::path::Path;
+use std::process::ExitCode;
+
+use crossterm::style::Stylize;
+use eyre::{
+    Result,
+    bail,
+    eyre,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use serde_json::{
+    Map,
+    Value,
+};
+use similar::{
+    ChangeTag,
+    TextDiff,
+};
+
+use crate::cli::chat::cli::{
+    BundleFormat,
+    Config,
+    ConfigApply,
+    ConfigDiff,
+    ConfigExport,
+};
+use crate::cli::chat::context::{
+    ContextConfig,
+    load_global_config,
+    validate_profile_name,
+};
+use crate::cli::chat::hooks::Hook;
+use crate::cli::chat::mcp::expand_path;
+use crate::cli::chat::tool_manager::{
+    McpServerConfig,
+    global_mcp_config_path,
+};
+use crate::cli::chat::tools::custom_tool::CustomToolConfig;
+use crate::cli::chat::util::shared_writer::SharedWriter;
+use crate::database::Database;
+use crate::database::settings::Setting;
+use crate::platform::Context;
+use crate::util::{
+    CHAT_BINARY_NAME,
+    directories,
+};
+
+/// The newest bundle format this version can apply.
+const BUNDLE_VERSION: u32 = 1;
+
+/// Settings that hold the state of this install rather than a preference, so are never exported.
+const LOCAL_SETTINGS: &[Setting] = &[Setting::OldClientId, Setting::McpLoadedBefore];
+
+/// Settings that send authenticated requests to another endpoint, so a bundle can never set them.
+const ENDPOINT_SETTINGS: &[Setting] = &[Setting::ApiCodeWhispererService, Setting::ApiQService];
+
+const SETTINGS: &str = "settings";
+const MCP_SERVERS: &str = "mcp_servers";
+const HOOKS: &str = "hooks";
+const PROFILES: &str = "profiles";
+
+fn is_local_setting(key: &str) -> bool {
+    LOCAL_SETTINGS.iter().any(|local| local.as_ref() == key)
+}
+
+fn is_endpoint_setting(key: &str) -> bool {
+    ENDPOINT_SETTINGS.iter().any(|endpoint| endpoint.as_ref() == key)
+}
+
+/// Settings, global MCP servers, global hooks and global profiles (including their hooks and
+/// trusted tools) that can be shared with a team as a single file.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ConfigBundle {
+    pub version: u32,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub settings: BTreeMap<String, Value>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub mcp_servers: BTreeMap<String, CustomToolConfig>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub hooks: BTreeMap<String, Hook>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, ContextConfig>,
+}
+
+impl ConfigBundle {
+    pub fn parse(contents: &str, format: BundleFormat) -> Result<Self> {
+        let bundle: Self = match format {
+            BundleFormat::Toml => toml::from_str(contents)?,
+            BundleFormat::Json => serde_json::from_str(contents)?,
+        };
+
+        if bundle.version > BUNDLE_VERSION {
+            bail!(
+                "Bundle version {} is not supported, update {CHAT_BINARY_NAME} to apply it",
+                bundle.version
+            );
+        }
+        if let Some(key) = bundle.settings.keys().find(|key| is_local_setting(key)) {
+            bail!("Setting `{key}` belongs to a single install and can't be bundled");
+        }
+        if let Some(key) = bundle.settings.keys().find(|key| is_endpoint_setting(key)) {
+            bail!("Setting `{key}` changes where requests are sent and can't be bundled");
+        }
+        for name in bundle.profiles.keys() {
+            validate_profile_name(name)?;
+        }
+
+        Ok(bundle)
+    }
+
+    pub fn to_string(&self, format: BundleFormat) -> Result<String> {
+        // Going through the untyped values sorts the hooks of each profile, so exports are stable
+        Ok(match format {
+            BundleFormat::Toml => toml::to_string_pretty(&toml::Value::try_from(self)?)?,
+            BundleFormat::Json => serde_json::to_string_pretty(&serde_json::to_value(self)?)?,
+        })
+    }
+
+    /// Reads the installed configuration that bundles manage. Workspace MCP servers and profiles
+    /// are left out, they are already shared through the workspace's repository.
+    pub async fn load_installed(ctx: &Context, database: &Database) -> Result<Self> {
+        let settings = database
+            .settings
+            .map()
+            .iter()
+            .filter(|(key, _)| !is_local_setting(key) && !is_endpoint_setting(key))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+
+        let mcp_path = global_mcp_config_path(ctx)?;
+        let mcp_servers = match ctx.fs().exists(&mcp_path) {
+            true => McpServerConfig::load_from_file(ctx, &mcp_path)
+                .await?
+                .mcp_servers
+                .into_iter()
+                .collect(),
+            false => BTreeMap::new(),
+        };
+
+        let hooks = load_global_config(ctx).await?.hooks.into_iter().collect();
+
+        let mut profiles = BTreeMap::new();
+        let profiles_dir = directories::chat_profiles_dir(ctx)?;
+        if ctx.fs().exists(&profiles_dir) {
+            let mut read_dir = ctx.fs().read_dir(&profiles_dir).await?;
+            while let Some(entry) = read_dir.next_entry().await? {
+                let name = entry.file_name().to_string_lossy().to_string();
+                let path = profiles_dir.join(&name).join("context.json");
+                if !ctx.fs().exists(&path) {
+                    continue;
+                }
+                let contents = ctx.fs().read_to_string(&path).await?;
+                let config: ContextConfig = serde_json::from_str(&contents)
+                    .map_err(|err| eyre!("Failed to parse {}: {err}", path.display()))?;
+                profiles.insert(name, config);
+            }
+        }
+
+        Ok(Self {
+            version: BUNDLE_VERSION,
+            settings,
+            mcp_servers,
+            hooks,
+            profiles,
+        })
+    }
+
+    /// Flattens the bundle into one entry per setting, MCP server, hook and profile, which are the
+    /// units that are compared and applied.
+    fn entries(&self) -> Result<BTreeMap<String, Value>> {
+        let mut entries = BTreeMap::new();
+        for (key, value) in &self.settings {
+            entries.insert(format!("{SETTINGS}.{key}"), value.clone());
+        }
+        for (name, server) in &self.mcp_servers {
+            entries.insert(format!("{MCP_SERVERS}.{name}"), serde_json::to_value(server)?);
+        }
+        for (name, hook) in &self.hooks {
+            entries.insert(format!("{HOOKS}.{name}"), serde_json::to_value(hook)?);
+        }
+        for (name, profile) in &self.profiles {
+            entries.insert(format!("{PROFILES}.{name}"), serde_json::to_value(profile)?);
+        }
+        Ok(entries)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Action {
+    Add,
+    Update,
+    Remove,
+    /// Already matches the bundle
+    Unchanged,
+    /// Changed locally since a bundle was last applied, so the local value is kept
+    Override,
+}
+
+#[derive(Debug)]
+struct Change {
+    key: String,
+    action: Action,
+    installed: Option<Value>,
+    bundled: Option<Value>,
+}
+
+/// Decides what applying the `bundled` entries does to each `installed` one. Entries are only
+/// overwritten or removed if they still have the value that the last bundle `applied`, so local
+/// changes are kept unless `force` is set.
+fn plan(
+    bundled: &BTreeMap<String, Value>,
+    installed: &BTreeMap<String, Value>,
+    applied: &Map<String, Value>,
+    force: bool,
+) -> Vec<Change> {
+    let keys = bundled.keys().chain(applied.keys()).collect::<BTreeSet<_>>();
+    keys.into_iter()
+        .filter_map(|key| {
+            let bundled = bundled.get(key);
+            let installed = installed.get(key);
+            let action = match (bundled, installed) {
+                (None, None) => return None,
+                (Some(bundled), Some(installed)) if bundled == installed => Action::Unchanged,
+                _ if !force && installed != applied.get(key) => Action::Override,
+                (Some(_), None) => Action::Add,
+                (Some(_), Some(_)) => Action::Update,
+                (None, Some(_)) => Action::Remove,
+            };
+            Some(Change {
+                key: key.clone(),
+                action,
+                installed: installed.cloned(),
+                bundled: bundled.cloned(),
+            })
+        })
+        .collect()
+}
+
+fn print_changes(output: &mut impl Write, changes: &[Change]) -> Result<()> {
+    let pretty = |value: &Option<Value>| {
+        value
+            .as_ref()
+            .map(|value| format!("{}\n", serde_json::to_string_pretty(value).unwrap_or_default()))
+            .unwrap_or_default()
+    };
+
+    for change in changes {
+        match change.action {
+            Action::Unchanged => continue,
+            Action::Add => writeln!(output, "{} {}", "+".green(), change.key.as_str().bold())?,
+            Action::Update => writeln!(output, "{} {}", "~".yellow(), change.key.as_str().bold())?,
+            Action::Remove => writeln!(output, "{} {}", "-".red(), change.key.as_str().bold())?,
+            Action::Override => writeln!(
+                output,
+                "{} {} {}",
+                "!".dark_grey(),
+                change.key.as_str().bold(),
+                "(changed locally, kept)".dark_grey()
+            )?,
+        }
+
+        let (installed, bundled) = (pretty(&change.installed), pretty(&change.bundled));
+        for line in TextDiff::from_lines(&installed, &bundled).iter_all_changes() {
+            let text = line.value().trim_end();
+            match line.tag() {
+                ChangeTag::Equal => writeln!(output, "    {text}")?,
+                ChangeTag::Delete => writeln!(output, "  {}", format!("- {text}").red())?,
+                ChangeTag::Insert => writeln!(output, "  {}", format!("+ {text}").green())?,
+            }
+        }
+    }
+
+    let count = |action: Action| changes.iter().filter(|change| change.action == action).count();
+    let (add, update, remove, kept) = (
+        count(Action::Add),
+        count(Action::Update),
+        count(Action::Remove),
+        count(Action::Override),
+    );
+    if add + update + remove + kept == 0 {
+        writeln!(output, "Everything is up to date")?;
+    } else {
+        writeln!(
+            output,
+            "\n{add} to add, {update} to update, {remove} to remove, {kept} changed locally"
+        )?;
+    }
+    if kept > 0 {
+        writeln!(output, "Use --force to overwrite the local changes")?;
+    }
+    Ok(())
+}
+
+/// Writes the entries that are added, updated or removed by `changes`.
+async fn write_changes(ctx: &Context, database: &mut Database, changes: &[Change]) -> Result<()> {
+    let mcp_path = global_mcp_config_path(ctx)?;
+    let mut mcp_config = match ctx.fs().exists(&mcp_path) {
+        true => McpServerConfig::load_from_file(ctx, &mcp_path).await?,
+        false => McpServerConfig::default(),
+    };
+    let mut global_config = load_global_config(ctx).await?;
+    let (mut mcp_changed, mut hooks_changed) = (false, false);
+
+    for change in changes {
+        let value = match change.action {
+            Action::Add | Action::Update => change.bundled.clone(),
+            Action::Remove => None,
+            Action::Unchanged | Action::Override => continue,
+        };
+        let Some((section, name)) = change.key.split_once('.') else {
+            bail!("Invalid bundle entry `{}`", change.key);
+        };
+
+        match section {
+            SETTINGS => match value {
+                Some(value) => database.settings.set_key(name, value).await?,
+                None => {
+                    database.settings.remove_key(name).await?;
+                },
+            },
+            MCP_SERVERS => {
+                mcp_changed = true;
+                match value {
+                    Some(value) => {
+                        mcp_config
+                            .mcp_servers
+                            .insert(name.to_owned(), serde_json::from_value(value)?);
+                    },
+                    None => {
+                        mcp_config.mcp_servers.remove(name);
+                    },
+                }
+            },
+            HOOKS => {
+                hooks_changed = true;
+                match value {
+                    Some(value) => {
+                        global_config
+                            .hooks
+                            .insert(name.to_owned(), serde_json::from_value(value)?);
+                    },
+                    None => {
+                        global_config.hooks.remove(name);
+                    },
+                }
+            },
+            PROFILES => {
+                let profile_dir = directories::chat_profiles_dir(ctx)?.join(name);
+                match value {
+                    Some(value) => {
+                        let config: ContextConfig = serde_json::from_value(value)?;
+                        ctx.fs().create_dir_all(&profile_dir).await?;
+                        ctx.fs()
+                            .write(profile_dir.join("context.json"), serde_json::to_string_pretty(&config)?)
+                            .await?;
+                    },
+                    // Only the configuration is removed, anything else kept in the profile is left alone
+                    None => ctx.fs().remove_file(profile_dir.join("context.json")).await?,
+                }
+            },
+            _ => bail!("Invalid bundle entry `{}`", change.key),
+        }
+    }
+
+    if mcp_changed {
+        if let Some(parent) = mcp_path.parent() {
+            ctx.fs().create_dir_all(parent).await?;
+        }
+        mcp_config.save_to_file(ctx, &mcp_path).await?;
+    }
+    if hooks_changed {
+        let global_path = directories::chat_global_context_path(ctx)?;
+        if let Some(parent) = global_path.parent() {
+            ctx.fs().create_dir_all(parent).await?;
+        }
+        ctx.fs()
+            .write(&global_path, serde_json::to_string_pretty(&global_config)?)
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Asks whether to write `changes`, pointing out the ones that run commands or trust tools.
+fn confirm_changes(output: &mut impl Write, changes: &[Change]) -> Result<bool> {
+    let commands = changes
+        .iter()
+        .filter(|change| matches!(change.action, Action::Add | Action::Update))
+        .filter(|change| [MCP_SERVERS, HOOKS, PROFILES].contains(&change.key.split('.').next().unwrap_or_default()))
+        .map(|change| change.key.as_str())
+        .collect::<Vec<_>>();
+    if !commands.is_empty() {
+        writeln!(
+            output,
+            "\n{} {}",
+            "!".yellow(),
+            "These entries run commands or trust tools on this machine:"
+                .yellow()
+                .bold()
+        )?;
+        for key in commands {
+            writeln!(output, "    {key}")?;
+        }
+    }
+    output.flush()?;
+
+    if !std::io::stdin().is_terminal() {
+        bail!("Refusing to apply the bundle without confirmation, run again with --yes to apply it");
+    }
+    Ok(dialoguer::Confirm::with_theme(&crate::util::dialoguer_theme())
+        .with_prompt("Apply these changes?")
+        .default(false)
+        .interact()?)
+}
+
+/// Shows what applying `bundle` changes and, unless `dry_run` is set, applies it and remembers its
+/// entries so that later bundles can tell local changes apart. Unless `yes` is set, the changes
+/// are only applied once confirmed. Returns whether the bundle was applied.
+pub async fn apply_bundle(
+    ctx: &Context,
+    database: &mut Database,
+    output: &mut impl Write,
+    bundle: &ConfigBundle,
+    force: bool,
+    dry_run: bool,
+    yes: bool,
+) -> Result<bool> {
+    let bundled = bundle.entries()?;
+    let installed = ConfigBundle::load_installed(ctx, database).await?.entries()?;
+    let applied = database.get_applied_config_bundle()?.unwrap_or_default();
+
+    let changes = plan(&bundled, &installed, &applied, force);
+    print_changes(output, &changes)?;
+    if dry_run {
+        return Ok(false);
+    }
+
+    let writes = changes
+        .iter()
+        .any(|change| matches!(change.action, Action::Add | Action::Update | Action::Remove));
+    if writes && !yes && !confirm_changes(output, &changes)? {
+        return Ok(false);
+    }
+
+    write_changes(ctx, database, &changes).await?;
+    database.set_applied_config_bundle(&bundled.into_iter().collect())?;
+    Ok(true)
+}
+
+fn bundle_format(path: &Path) -> BundleFormat {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("json") => BundleFormat::Json,
+        _ => BundleFormat::Toml,
+    }
+}
+
+async fn read_bundle(ctx: &Context, file: &str) -> Result<ConfigBundle> {
+    let path = expand_path(ctx, file)?;
+    let contents = ctx
+        .fs()
+        .read_to_string(&path)
+        .await
+        .map_err(|err| eyre!("Failed to read {}: {err}", path.display()))?;
+    ConfigBundle::parse(&contents, bundle_format(&path))
+        .map_err(|err| eyre!("Failed to parse {}: {err}", path.display()))
+}
+
+pub async fn execute_config(database: &mut Database, args: Config) -> Result<ExitCode> {
+    let ctx = Context::new();
+    let mut output = SharedWriter::stdout();
+
+    match args {
+        Config::Export(ConfigExport {
+            output: output_file,
+            format,
+        }) => {
+            let bundle = ConfigBundle::load_installed(&ctx, database).await?;
+            match output_file {
+                Some(file) => {
+                    let path = expand_path(&ctx, &file)?;
+                    let contents = bundle.to_string(format.unwrap_or_else(|| bundle_format(&path)))?;
+                    ctx.fs().write(&path, contents).await?;
+                    writeln!(output, "✓ Exported the configuration to {}", path.display())?;
+                },
+                None => write!(output, "{}", bundle.to_string(format.unwrap_or(BundleFormat::Toml))?)?,
+            }
+        },
+        Config::Apply(ConfigApply {
+            file,
+            dry_run,
+            force,
+            yes,
+        }) => {
+            let bundle = read_bundle(&ctx, &file).await?;
+            if apply_bundle(&ctx, database, &mut output, &bundle, force, dry_run, yes).await? {
+                writeln!(output, "\n✓ Applied {file}")?;
+            } else if !dry_run {
+                writeln!(output, "\nNothing was applied")?;
+            }
+        },
+        Config::Diff(ConfigDiff { file, force }) => {
+            let bundle = read_bundle(&ctx, &file).await?;
+            apply_bundle(&ctx, database, &mut output, &bundle, force, true, false).await?;
+        },
+    }
+
+    output.flush()?;
+    Ok(ExitCode::SUCCESS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BUNDLE: &str = r#"
+        version = 1
+
+        [settings]
+        "chat.defaultModel" = "claude-4-sonnet"
+
+        [mcp_servers.git]
+        command = "uvx"
+        args = ["mcp-server-git"]
+
+        [hooks.branch]
+        trigger = "conversation_start"
+        type = "inline"
+        command = "git branch --show-current"
+
+        [profiles.team]
+        paths = ["docs/**/*.md"]
+        trusted_tools = ["fs_read"]
+    "#;
+
+    #[test]
+    fn test_parse_bundle() {
+        let bundle = ConfigBundle::parse(BUNDLE, BundleFormat::Toml).unwrap();
+        assert_eq!(bundle.settings["chat.defaultModel"], "claude-4-sonnet");
+        assert_eq!(bundle.mcp_servers["git"].args, vec!["mcp-server-git"]);
+        assert_eq!(
+            bundle.hooks["branch"].command.as_deref(),
+            Some("git branch --show-current")
+        );
+        assert_eq!(bundle.profiles["team"].trusted_tools, vec!["fs_read"]);
+
+        let json = bundle.to_string(BundleFormat::Json).unwrap();
+        let reparsed = ConfigBundle::parse(&json, BundleFormat::Json).unwrap();
+        assert_eq!(reparsed.entries().unwrap(), bundle.entries().unwrap());
+
+        assert!(ConfigBundle::parse("version = 2", BundleFormat::Toml).is_err());
+        assert!(
+            ConfigBundle::parse(
+                "version = 1\n[settings]\n\"mcp.loadedBefore\" = true",
+                BundleFormat::Toml
+            )
+            .is_err()
+        );
+        assert!(ConfigBundle::parse("version = 1\n[profiles.\"../team\"]", BundleFormat::Toml).is_err());
+        let unknown =
+            ConfigBundle::parse("version = 1\n[settings]\n\"newer.setting\" = 1", BundleFormat::Toml).unwrap();
+        assert_eq!(unknown.settings["newer.setting"], 1);
+        assert!(ConfigBundle::parse("[settings]", BundleFormat::Toml).is_err());
+        assert!(
+            ConfigBundle::parse(
+                "version = 1\n[settings]\n\"api.q.service\" = \"https://example.com\"",
+                BundleFormat::Toml
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_plan() {
+        let entries = |pairs: &[(&str, i64)]| {
+            pairs
+                .iter()
+                .map(|(key, value)| (key.to_string(), Value::from(*value)))
+                .collect::<BTreeMap<_, _>>()
+        };
+        let bundled = entries(&[("added", 1), ("same", 1), ("updated", 2), ("overridden", 2)]);
+        let installed = entries(&[("same", 1), ("updated", 1), ("overridden", 3), ("removed", 1)]);
+        let applied: Map<String, Value> = entries(&[("updated", 1), ("overridden", 1), ("removed", 1)])
+            .into_iter()
+            .collect();
+
+        let actions = |force| {
+            plan(&bundled, &installed, &applied, force)
+                .into_iter()
+                .map(|change| (change.key, change.action))
+                .collect::<BTreeMap<_, _>>()
+        };
+
+        let changes = actions(false);
+        assert_eq!(changes["added"], Action::Add);
+        assert_eq!(changes["same"], Action::Unchanged);
+        assert_eq!(changes["updated"], Action::Update);
+        assert_eq!(changes["overridden"], Action::Override);
+        assert_eq!(changes["removed"], Action::Remove);
+
+        assert_eq!(actions(true)["overridden"], Action::Update);
+    }
+
+    #[tokio::test]
+    async fn test_apply_bundle() {
+        let ctx = Context::builder().with_test_home().await.unwrap().build_fake();
+        let mut database = Database::new().await.unwrap();
+        let bundle = ConfigBundle::parse(BUNDLE, BundleFormat::Toml).unwrap();
+
+        apply_bundle(&ctx, &mut database, &mut std::io::sink(), &bundle, false, true, false)
+            .await
+            .unwrap();
+        let installed = ConfigBundle::load_installed(&ctx, &database).await.unwrap();
+        assert!(installed.profiles.is_empty(), "a dry run must not write anything");
+
+        apply_bundle(&ctx, &mut database, &mut std::io::sink(), &bundle, false, false, true)
+            .await
+            .unwrap();
+        let installed = ConfigBundle::load_installed(&ctx, &database).await.unwrap();
+        assert_eq!(installed.entries().unwrap(), bundle.entries().unwrap());
+
+        // A local change to the profile is kept when the bundle is applied again
+        let profile_path = directories::chat_profiles_dir(&ctx)
+            .unwrap()
+            .join("team")
+            .join("context.json");
+        ctx.fs()
+            .write(&profile_path, r#"{ "paths": ["README.md"] }"#)
+            .await
+            .unwrap();
+        apply_bundle(&ctx, &mut database, &mut std::io::sink(), &bundle, false, false, true)
+            .await
+            .unwrap();
+        let installed = ConfigBundle::load_installed(&ctx, &database).await.unwrap();
+        assert_eq!(installed.profiles["team"].paths, vec!["README.md"]);
+
+        apply_bundle(&ctx, &mut database, &mut std::io::sink(), &bundle, true, false, true)
+            .await
+            .unwrap();
+        let installed = ConfigBundle::load_installed(&ctx, &database).await.unwrap();
+        assert_eq!(installed.profiles["team"].paths, vec!["docs/**/*.md"]);
+    }
+}
@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 use std::io::Write;
 use std::path::{
+    Component,
     Path,
     PathBuf,
 };
@@ -21,7 +22,9 @@ use tracing::debug;
 use super::consts::CONTEXT_FILES_MAX_SIZE;
 use super::hooks::{
     Hook,
+    HookEvent,
     HookExecutor,
+    HookOutcome,
 };
 use super::util::drop_matched_context_files;
 use crate::platform::Context;
@@ -29,7 +32,17 @@ use crate::util::directories;
 
 pub const AMAZONQ_FILENAME: &str = "AmazonQ.md";
 
+/// Directory, relative to a workspace directory, containing markdown rules for the agent.
+pub const RULES_DIR: &str = ".amazonq/rules";
+
+/// Directory, relative to the current working directory, containing profiles that can be shared
+/// through the workspace's repository. These take precedence over global profiles of the same name.
+pub const WORKSPACE_PROFILES_DIR: &str = ".amazonq/profiles";
+
 /// Configuration for context files, containing paths to include in the context.
+///
+/// The remaining fields are only used in profile configurations, and are applied when switching
+/// to the profile.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(default)]
 pub struct ContextConfig {
@@ -38,6 +51,23 @@ pub struct ContextConfig {
 
     /// Map of Hook Name to [`Hook`]. The hook name serves as the hook's ID.
     pub hooks: HashMap<String, Hook>,
+
+    /// Tools that are trusted while the profile is active.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub trusted_tools: Vec<String>,
+
+    /// Names of the MCP servers whose tools are available while the profile is active. All
+    /// configured servers are available if unset.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mcp_servers: Option<Vec<String>>,
+
+    /// Instructions included in the context of every request while the profile is active.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instructions: Option<String>,
+
+    /// The model to respond with while the profile is active.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
 }
 
 #[allow(dead_code)]
@@ -59,8 +89,18 @@ pub struct ContextManager {
     /// Context configuration for the current profile.
     pub profile_config: ContextConfig,
 
+    /// Directories below the current working directory whose rules were loaded after the agent
+    /// accessed files inside them.
+    #[serde(default)]
+    pub rule_dirs: Vec<PathBuf>,
+
     #[serde(skip)]
     pub hook_executor: HookExecutor,
+
+    /// Whether the user approved the current workspace profile. Until then its hooks don't run,
+    /// its instructions are ignored and only its files inside the workspace are included.
+    #[serde(skip)]
+    pub workspace_profile_approved: bool,
 }
 
 #[allow(dead_code)]
@@ -96,7 +136,9 @@ impl ContextManager {
             global_config,
             current_profile,
             profile_config,
+            rule_dirs: Vec::new(),
             hook_executor: HookExecutor::new(),
+            workspace_profile_approved: false,
         })
     }
 
@@ -229,15 +271,19 @@ impl ContextManager {
         // Always include default profile
         profiles.push("default".to_string());
 
-        // Read profile directory and extract profile names
-        let profiles_dir = directories::chat_profiles_dir(&self.ctx)?;
-        if profiles_dir.exists() {
-            let mut read_dir = self.ctx.fs().read_dir(&profiles_dir).await?;
-            while let Some(entry) = read_dir.next_entry().await? {
-                let path = entry.path();
-                if let (true, Some(name)) = (path.is_dir(), path.file_name()) {
-                    if name != "default" {
-                        profiles.push(name.to_string_lossy().to_string());
+        // Read the global and workspace profile directories and extract profile names
+        for profiles_dir in [
+            directories::chat_profiles_dir(&self.ctx)?,
+            workspace_profiles_dir(&self.ctx)?,
+        ] {
+            if profiles_dir.exists() {
+                let mut read_dir = self.ctx.fs().read_dir(&profiles_dir).await?;
+                while let Some(entry) = read_dir.next_entry().await? {
+                    let path = entry.path();
+                    if let (true, Some(name)) = (path.is_dir(), path.file_name()) {
+                        if name != "default" {
+                            profiles.push(name.to_string_lossy().to_string());
+                        }
                     }
                 }
             }
@@ -246,6 +292,7 @@ impl ContextManager {
         // Sort non-default profiles alphabetically
         if profiles.len() > 1 {
             profiles[1..].sort();
+            profiles.dedup();
         }
 
         Ok(profiles)
@@ -263,15 +310,19 @@ impl ContextManager {
         // Always include default profile
         profiles.push("default".to_string());
 
-        // Read profile directory and extract profile names
-        let profiles_dir = directories::chat_profiles_dir(&self.ctx)?;
-        if profiles_dir.exists() {
-            for entry in std::fs::read_dir(profiles_dir)? {
-                let entry = entry?;
-                let path = entry.path();
-                if let (true, Some(name)) = (path.is_dir(), path.file_name()) {
-                    if name != "default" {
-                        profiles.push(name.to_string_lossy().to_string());
+        // Read the global and workspace profile directories and extract profile names
+        for profiles_dir in [
+            directories::chat_profiles_dir(&self.ctx)?,
+            workspace_profiles_dir(&self.ctx)?,
+        ] {
+            if profiles_dir.exists() {
+                for entry in std::fs::read_dir(profiles_dir)? {
+                    let entry = entry?;
+                    let path = entry.path();
+                    if let (true, Some(name)) = (path.is_dir(), path.file_name()) {
+                        if name != "default" {
+                            profiles.push(name.to_string_lossy().to_string());
+                        }
                     }
                 }
             }
@@ -280,6 +331,7 @@ impl ContextManager {
         // Sort non-default profiles alphabetically
         if profiles.len() > 1 {
             profiles[1..].sort();
+            profiles.dedup();
         }
 
         Ok(profiles)
@@ -387,11 +439,14 @@ impl ContextManager {
             return Err(eyre!("Profile '{}' not found", old_name));
         }
 
-        let new_profile_path = profile_dir_path(&self.ctx, new_name)?;
-        if new_profile_path.exists() {
+        if profile_context_path(&self.ctx, new_name)?.exists() {
             return Err(eyre!("Profile '{}' already exists", new_name));
         }
 
+        // Keep the profile in the same directory, so renaming a workspace profile doesn't move it
+        // to the global profiles directory.
+        let new_profile_path = old_profile_path.with_file_name(new_name);
+
         self.ctx.fs().rename(&old_profile_path, &new_profile_path).await?;
 
         // If the current profile is being renamed, update the current_profile field
@@ -439,6 +494,50 @@ impl ContextManager {
         Ok(())
     }
 
+    /// Whether the current profile is shared through the workspace rather than defined globally.
+    pub fn is_workspace_profile(&self) -> bool {
+        match (
+            profile_dir_path(&self.ctx, &self.current_profile),
+            workspace_profiles_dir(&self.ctx),
+        ) {
+            (Ok(profile_dir), Ok(workspace_dir)) => profile_dir.starts_with(workspace_dir),
+            _ => false,
+        }
+    }
+
+    /// Whether the current profile is a workspace profile hiding a global profile of the same
+    /// name.
+    pub fn shadows_global_profile(&self) -> bool {
+        self.is_workspace_profile()
+            && directories::chat_profiles_dir(&self.ctx)
+                .is_ok_and(|dir| dir.join(&self.current_profile).join("context.json").exists())
+    }
+
+    /// The instructions of the current profile, unless it is a workspace profile that the user
+    /// hasn't approved.
+    pub fn profile_instructions(&self) -> Option<&str> {
+        self.profile_config
+            .instructions
+            .as_deref()
+            .filter(|_| self.workspace_profile_approved || !self.is_workspace_profile())
+    }
+
+    /// The paths of the current profile that may include files outside of the workspace, i.e.
+    /// absolute paths, paths in the home directory and paths leaving the workspace with `..`.
+    pub fn paths_outside_workspace(&self) -> Vec<String> {
+        self.profile_config
+            .paths
+            .iter()
+            .filter(|path| {
+                let path = Path::new(path.as_str());
+                path.is_absolute()
+                    || path.starts_with("~")
+                    || path.components().any(|component| component == Component::ParentDir)
+            })
+            .cloned()
+            .collect()
+    }
+
     /// Get all context files (global + profile-specific).
     ///
     /// This method:
@@ -455,8 +554,8 @@ impl ContextManager {
 
         self.collect_context_files(&self.global_config.paths, &mut context_files)
             .await?;
-        self.collect_context_files(&self.profile_config.paths, &mut context_files)
-            .await?;
+        context_files.append(&mut self.get_current_profile_context_files().await?);
+        context_files.append(&mut self.get_discovered_rule_files().await?);
 
         context_files.sort_by(|a, b| a.0.cmp(&b.0));
         context_files.dedup_by(|a, b| a.0 == b.0);
@@ -480,16 +579,107 @@ impl ContextManager {
         Ok(context_files)
     }
 
-    /// Get all context files from the current profile configuration.
+    /// Get all context files from the current profile configuration. Files of a workspace profile
+    /// that the user hasn't approved are only included if they are inside the workspace.
     pub async fn get_current_profile_context_files(&self) -> Result<Vec<(String, String)>> {
         let mut context_files = Vec::new();
 
         self.collect_context_files(&self.profile_config.paths, &mut context_files)
             .await?;
 
+        if self.is_workspace_profile() && !self.workspace_profile_approved {
+            let cwd = self.ctx.fs().canonicalize(self.ctx.env().current_dir()?).await?;
+            let mut inside = Vec::new();
+            for (path, content) in context_files {
+                match self.ctx.fs().canonicalize(&path).await {
+                    Ok(resolved) if resolved.starts_with(&cwd) => inside.push((path, content)),
+                    _ => debug!(
+                        ?path,
+                        "Skipping a file of the workspace profile outside of the workspace"
+                    ),
+                }
+            }
+            context_files = inside;
+        }
+
         Ok(context_files)
     }
 
+    /// Get all rule files from the directories in [Self::rule_dirs].
+    pub async fn get_discovered_rule_files(&self) -> Result<Vec<(String, String)>> {
+        let mut context_files = Vec::new();
+
+        for dir in &self.rule_dirs {
+            for path in self.rule_paths() {
+                process_path_in(&self.ctx, dir, path, &mut context_files, false).await?;
+            }
+        }
+
+        Ok(context_files)
+    }
+
+    /// Loads the rules of the directories between the current working directory and `path`, a file
+    /// or directory the agent accessed. Rules of directories outside of the current working
+    /// directory are never loaded.
+    ///
+    /// # Returns
+    /// The directories whose rules were newly loaded
+    pub async fn discover_rules(&mut self, path: impl AsRef<Path>) -> Vec<PathBuf> {
+        let Ok(cwd) = self.ctx.env().current_dir() else {
+            return Vec::new();
+        };
+        let path = cwd.join(path);
+        let dir = if self.ctx.fs().chroot_path(&path).is_dir() {
+            path.as_path()
+        } else {
+            match path.parent() {
+                Some(parent) => parent,
+                None => return Vec::new(),
+            }
+        };
+        let Ok(relative) = dir.strip_prefix(&cwd) else {
+            return Vec::new();
+        };
+        if relative.components().any(|c| !matches!(c, Component::Normal(_))) {
+            return Vec::new();
+        }
+
+        let mut discovered = Vec::new();
+        let mut dir = cwd.clone();
+        for component in relative.components() {
+            dir.push(component);
+            if self.rule_dirs.contains(&dir) {
+                continue;
+            }
+            let mut context_files = Vec::new();
+            for rule_path in self.rule_paths() {
+                if let Err(err) = process_path_in(&self.ctx, &dir, rule_path, &mut context_files, false).await {
+                    debug!(?err, ?dir, "failed to load rules");
+                }
+            }
+            if !context_files.is_empty() {
+                self.rule_dirs.push(dir.clone());
+                discovered.push(dir.clone());
+            }
+        }
+
+        discovered
+    }
+
+    /// The configured paths that are rules, see [is_rule_path].
+    fn rule_paths(&self) -> impl Iterator<Item = &String> {
+        self.global_config
+            .paths
+            .iter()
+            .chain(self.profile_config.paths.iter())
+            .filter(|path| is_rule_path(path))
+    }
+
+    /// The maximum total size, in tokens, of the files included in the context.
+    pub fn max_context_files_size(&self) -> usize {
+        self.max_context_files_size
+    }
+
     /// Collects context files and optionally drops files if the total size exceeds the limit.
     /// Returns (files_to_use, dropped_files)
     pub async fn collect_context_files_with_limit(&self) -> Result<(Vec<(String, String)>, Vec<(String, String)>)> {
@@ -591,41 +781,97 @@ impl ContextManager {
     /// # Returns
     /// A vector containing pairs of a [`Hook`] definition and its execution output
     pub async fn run_hooks(&mut self, updates: Option<&mut impl Write>) -> Vec<(Hook, String)> {
+        let from_workspace = self.is_workspace_profile();
+        let hooks = Self::collect_hooks(
+            &mut self.global_config,
+            &mut self.profile_config,
+            from_workspace,
+            self.workspace_profile_approved,
+        );
+        self.hook_executor.run_hooks(hooks, updates).await
+    }
+
+    /// Run the enabled hooks from both the global and profile contexts that match `event`.
+    /// # Arguments
+    /// * `event` - the event passed to the hooks on stdin
+    /// * `updates` - output stream to write hook failures to if Some, else do nothing if None
+    /// # Returns
+    /// A vector containing pairs of a [`Hook`] definition and its [`HookOutcome`]
+    pub async fn run_event_hooks(
+        &mut self,
+        event: &HookEvent,
+        updates: Option<&mut impl Write>,
+    ) -> Vec<(Hook, HookOutcome)> {
+        let from_workspace = self.is_workspace_profile();
+        let hooks = Self::collect_hooks(
+            &mut self.global_config,
+            &mut self.profile_config,
+            from_workspace,
+            self.workspace_profile_approved,
+        );
+        self.hook_executor.run_event_hooks(hooks, event, updates).await
+    }
+
+    /// Collects the hooks to run. Hooks of a workspace profile are skipped unless the profile is
+    /// approved.
+    fn collect_hooks<'a>(
+        global_config: &'a mut ContextConfig,
+        profile_config: &'a mut ContextConfig,
+        from_workspace: bool,
+        workspace_profile_approved: bool,
+    ) -> Vec<&'a Hook> {
         let mut hooks: Vec<&Hook> = Vec::new();
 
         // Set internal hook states
-        let configs = [
-            (&mut self.global_config.hooks, true),
-            (&mut self.profile_config.hooks, false),
-        ];
+        let configs = [(&mut global_config.hooks, true), (&mut profile_config.hooks, false)];
 
         for (hook_list, is_global) in configs {
-            hooks.extend(hook_list.iter_mut().map(|(name, h)| {
-                h.name = name.to_string();
-                h.is_global = is_global;
-                &*h
-            }));
+            let from_workspace = from_workspace && !is_global;
+            hooks.extend(
+                hook_list
+                    .iter_mut()
+                    .map(|(name, h)| {
+                        h.name = name.to_string();
+                        h.is_global = is_global;
+                        h.from_workspace = from_workspace;
+                        &*h
+                    })
+                    .filter(|_| !from_workspace || workspace_profile_approved),
+            );
         }
 
-        self.hook_executor.run_hooks(hooks, updates).await
+        hooks
     }
 }
 
+/// The directory containing the profiles shared through the current workspace.
+fn workspace_profiles_dir(ctx: &Context) -> Result<PathBuf> {
+    Ok(ctx
+        .fs()
+        .chroot_path(ctx.env().current_dir()?.join(WORKSPACE_PROFILES_DIR)))
+}
+
+/// The directory of the profile named `profile_name`, preferring a workspace profile over a global
+/// one. The default profile is always global.
 fn profile_dir_path(ctx: &Context, profile_name: &str) -> Result<PathBuf> {
+    if profile_name != "default" {
+        let workspace_profile_dir = workspace_profiles_dir(ctx)?.join(profile_name);
+        if workspace_profile_dir.join("context.json").exists() {
+            return Ok(workspace_profile_dir);
+        }
+    }
     Ok(directories::chat_profiles_dir(ctx)?.join(profile_name))
 }
 
 /// Path to the context config file for `profile_name`.
 pub fn profile_context_path(ctx: &Context, profile_name: &str) -> Result<PathBuf> {
-    Ok(directories::chat_profiles_dir(ctx)?
-        .join(profile_name)
-        .join("context.json"))
+    Ok(profile_dir_path(ctx, profile_name)?.join("context.json"))
 }
 
 /// Load the global context configuration.
 ///
 /// If the global configuration file doesn't exist, returns a default configuration.
-async fn load_global_config(ctx: &Context) -> Result<ContextConfig> {
+pub async fn load_global_config(ctx: &Context) -> Result<ContextConfig> {
     let global_path = directories::chat_global_context_path(ctx)?;
     debug!(?global_path, "loading profile config");
     if ctx.fs().exists(&global_path) {
@@ -641,7 +887,7 @@ async fn load_global_config(ctx: &Context) -> Result<ContextConfig> {
                 "README.md".to_string(),
                 AMAZONQ_FILENAME.to_string(),
             ],
-            hooks: HashMap::new(),
+            ..Default::default()
         })
     }
 }
@@ -663,6 +909,63 @@ async fn load_profile_config(ctx: &Context, profile_name: &str) -> Result<Contex
     }
 }
 
+/// Whether `path` is a rule, which is looked up in every directory from the root of the git
+/// repository down to the current working directory rather than only the current working
+/// directory.
+fn is_rule_path(path: &str) -> bool {
+    path == AMAZONQ_FILENAME || path.strip_prefix(RULES_DIR).is_some_and(|rest| rest.starts_with('/'))
+}
+
+/// Returns the directories from the root of the git repository containing `cwd` down to `cwd`
+/// itself, or only `cwd` if it is not inside a git repository.
+fn rule_search_dirs(ctx: &Context, cwd: &Path) -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    for dir in cwd.ancestors() {
+        dirs.push(dir.to_path_buf());
+        if ctx.fs().exists(dir.join(".git")) {
+            dirs.reverse();
+            return dirs;
+        }
+    }
+    vec![cwd.to_path_buf()]
+}
+
+/// Process a path relative to the current working directory. Rules (see [is_rule_path]) are also
+/// looked up in the parent directories up to the root of the git repository.
+///
+/// # Arguments
+/// * `path` - The path to process
+/// * `context_files` - The collection to add files to
+/// * `is_validation` - If true, error when nothing matches; if false, silently skip
+///
+/// # Returns
+/// A Result indicating success or an error
+async fn process_path(
+    ctx: &Context,
+    path: &str,
+    context_files: &mut Vec<(String, String)>,
+    is_validation: bool,
+) -> Result<()> {
+    let cwd = ctx.env().current_dir()?;
+    if !is_rule_path(path) {
+        return process_path_in(ctx, &cwd, path, context_files, is_validation).await;
+    }
+
+    let found_before = context_files.len();
+    for dir in rule_search_dirs(ctx, &cwd) {
+        process_path_in(ctx, &dir, path, context_files, false).await?;
+    }
+    if is_validation && context_files.len() == found_before {
+        return Err(eyre!(
+            "No files found matching '{}' in '{}' or its parent directories",
+            path,
+            cwd.display()
+        ));
+    }
+
+    Ok(())
+}
+
 /// Process a path, handling glob patterns and file types.
 ///
 /// This method:
@@ -673,14 +976,16 @@ async fn load_profile_config(ctx: &Context, profile_name: &str) -> Result<Contex
 /// 5. With force=true, includes paths that don't exist yet
 ///
 /// # Arguments
+/// * `base_dir` - The directory relative paths are resolved against
 /// * `path` - The path to process
 /// * `context_files` - The collection to add files to
 /// * `is_validation` - If true, error when glob patterns don't match; if false, silently skip
 ///
 /// # Returns
 /// A Result indicating success or an error
-async fn process_path(
+async fn process_path_in(
     ctx: &Context,
+    base_dir: &Path,
     path: &str,
     context_files: &mut Vec<(String, String)>,
     is_validation: bool,
@@ -700,11 +1005,7 @@ async fn process_path(
     let full_path = if expanded_path.starts_with('/') {
         expanded_path
     } else {
-        ctx.env()
-            .current_dir()?
-            .join(&expanded_path)
-            .to_string_lossy()
-            .to_string()
+        base_dir.join(&expanded_path).to_string_lossy().to_string()
     };
 
     // Required in chroot testing scenarios so that we can use `Path::exists`.
@@ -777,11 +1078,31 @@ async fn process_path(
 /// A Result indicating success or an error
 async fn add_file_to_context(ctx: &Context, path: &Path, context_files: &mut Vec<(String, String)>) -> Result<()> {
     let filename = path.to_string_lossy().to_string();
-    let content = ctx.fs().read_to_string(path).await?;
-    context_files.push((filename, content));
+    let bytes = ctx.fs().read(path).await?;
+    match String::from_utf8(bytes) {
+        Ok(content) if !is_binary(&content) => context_files.push((filename, content)),
+        _ => debug!(?path, "Skipping binary file"),
+    }
     Ok(())
 }
 
+/// Whether file content looks binary, i.e. contains a NUL byte near its start.
+fn is_binary(content: &str) -> bool {
+    content.bytes().take(8192).any(|b| b == 0)
+}
+
+/// Collects the files at `path` for attaching to a single prompt, e.g. for `@` mentions.
+///
+/// Unlike context paths, `path` is only resolved against the current directory, so rules in parent
+/// directories are never pulled in. Globs and directories (non-recursive) are supported. Errors if
+/// nothing matches `path`.
+pub async fn collect_files_at_path(ctx: &Context, path: &str) -> Result<Vec<(String, String)>> {
+    let cwd = ctx.env().current_dir()?;
+    let mut files = Vec::new();
+    process_path_in(ctx, &cwd, path, &mut files, true).await?;
+    Ok(files)
+}
+
 /// Validate a profile name.
 ///
 /// Profile names can only contain alphanumeric characters, hyphens, and underscores.
@@ -791,7 +1112,7 @@ async fn add_file_to_context(ctx: &Context, path: &Path, context_files: &mut Vec
 ///
 /// # Returns
 /// A Result indicating if the name is valid
-fn validate_profile_name(name: &str) -> Result<()> {
+pub fn validate_profile_name(name: &str) -> Result<()> {
     // Check if name is empty
     if name.is_empty() {
         return Err(eyre!("Profile name cannot be empty"));
@@ -885,6 +1206,99 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_workspace_profiles() -> Result<()> {
+        let mut manager = create_test_context_manager(None).await?;
+        let ctx = Arc::clone(&manager.ctx);
+        let workspace_profile_path = Path::new("/").join(WORKSPACE_PROFILES_DIR).join("infra-oncall");
+        ctx.fs().create_dir_all(&workspace_profile_path).await?;
+        ctx.fs()
+            .write(
+                workspace_profile_path.join("context.json"),
+                r#"{
+                    "paths": ["runbooks/**/*.md"],
+                    "trusted_tools": ["use_aws"],
+                    "mcp_servers": ["cloudwatch"],
+                    "instructions": "Prefer read-only AWS calls."
+                }"#,
+            )
+            .await?;
+
+        let profiles = manager.list_profiles().await?;
+        assert_eq!(profiles, vec!["default".to_string(), "infra-oncall".to_string()]);
+        assert_eq!(manager.list_profiles_blocking()?, profiles);
+
+        assert!(!manager.is_workspace_profile());
+        manager.switch_profile("infra-oncall").await?;
+        assert!(manager.is_workspace_profile());
+        assert!(!manager.shadows_global_profile());
+        assert_eq!(manager.profile_config.paths, vec!["runbooks/**/*.md".to_string()]);
+        assert_eq!(manager.profile_config.trusted_tools, vec!["use_aws".to_string()]);
+        assert_eq!(manager.profile_config.mcp_servers, Some(vec!["cloudwatch".to_string()]));
+        assert_eq!(
+            manager.profile_config.instructions.as_deref(),
+            Some("Prefer read-only AWS calls.")
+        );
+        assert!(manager.profile_config.model.is_none());
+
+        // Changes to a workspace profile are saved to the workspace.
+        manager
+            .add_paths(vec!["docs/oncall.md".to_string()], false, true)
+            .await?;
+        let saved = ctx
+            .fs()
+            .read_to_string(workspace_profile_path.join("context.json"))
+            .await?;
+        assert!(saved.contains("docs/oncall.md"));
+        assert!(!directories::chat_profiles_dir(&ctx)?.join("infra-oncall").exists());
+
+        // Global profiles of the same name are shadowed.
+        assert!(manager.create_profile("infra-oncall").await.is_err());
+        let global_profile_path = directories::chat_profiles_dir(&ctx)?.join("infra-oncall");
+        ctx.fs().create_dir_all(&global_profile_path).await?;
+        ctx.fs().write(global_profile_path.join("context.json"), "{}").await?;
+        assert!(manager.shadows_global_profile());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rule_discovery() -> Result<()> {
+        assert!(is_rule_path(AMAZONQ_FILENAME));
+        assert!(is_rule_path(".amazonq/rules/**/*.md"));
+        assert!(!is_rule_path(".amazonq/rules-old/a.md"));
+        assert!(!is_rule_path("README.md"));
+
+        let mut manager = create_test_context_manager(None).await?;
+        let ctx = Arc::clone(&manager.ctx);
+        ctx.fs().write(AMAZONQ_FILENAME, "root rules").await?;
+        ctx.fs().create_dir_all("/service/api/.amazonq/rules").await?;
+        ctx.fs()
+            .write("/service/api/.amazonq/rules/style.md", "api style")
+            .await?;
+        ctx.fs().write("/service/api/handler.rs", "").await?;
+
+        // Subdirectory rules are not loaded until a file inside them is accessed.
+        assert_eq!(manager.get_context_files().await?.len(), 1);
+
+        assert_eq!(manager.discover_rules("service/api/handler.rs").await, vec![
+            PathBuf::from("/service/api")
+        ]);
+        assert!(manager.discover_rules("/service/api/handler.rs").await.is_empty());
+        assert!(manager.discover_rules("../outside/handler.rs").await.is_empty());
+
+        let files = manager.get_context_files().await?;
+        assert_eq!(files.len(), 2);
+        assert!(
+            files
+                .iter()
+                .any(|(name, content)| name.ends_with("/service/api/.amazonq/rules/style.md") && content == "api style")
+        );
+        assert_eq!(manager.get_discovered_rule_files().await?.len(), 1);
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_collect_exceeds_limit() -> Result<()> {
         let mut manager = create_test_context_manager(Some(2)).await?;
@@ -957,6 +1371,80 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_workspace_profile_hooks_need_approval() -> Result<()> {
+        let mut manager = create_test_context_manager(None).await?;
+        let ctx = Arc::clone(&manager.ctx);
+        let workspace_profile_path = Path::new("/").join(WORKSPACE_PROFILES_DIR).join("infra-oncall");
+        ctx.fs().create_dir_all(&workspace_profile_path).await?;
+        ctx.fs()
+            .write(
+                workspace_profile_path.join("context.json"),
+                r#"{ "hooks": { "status": { "trigger": "per_prompt", "type": "inline", "command": "echo hi" } } }"#,
+            )
+            .await?;
+        let hook = Hook::new_inline_hook(HookTrigger::PerPrompt, "echo global".to_string());
+        manager.add_hook("global_hook".to_string(), hook, true).await?;
+        manager.switch_profile("infra-oncall").await?;
+
+        let collect = |manager: &mut ContextManager| {
+            ContextManager::collect_hooks(
+                &mut manager.global_config,
+                &mut manager.profile_config,
+                true,
+                manager.workspace_profile_approved,
+            )
+            .into_iter()
+            .map(|hook| (hook.name.clone(), hook.from_workspace))
+            .collect::<Vec<_>>()
+        };
+        assert_eq!(collect(&mut manager), vec![("global_hook".to_string(), false)]);
+
+        manager.workspace_profile_approved = true;
+        let mut hooks = collect(&mut manager);
+        hooks.sort();
+        assert_eq!(hooks, vec![
+            ("global_hook".to_string(), false),
+            ("status".to_string(), true)
+        ]);
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_workspace_profile_files_need_approval() -> Result<()> {
+        let mut manager = create_test_context_manager(None).await?;
+        let ctx = Arc::clone(&manager.ctx);
+        let outside = tempfile::tempdir()?;
+        std::fs::write(outside.path().join("secret.md"), "secret")?;
+        std::os::unix::fs::symlink(outside.path().join("secret.md"), ctx.fs().chroot_path("/link.md"))?;
+        ctx.fs().write("/notes.md", "notes").await?;
+        let workspace_profile_path = Path::new("/").join(WORKSPACE_PROFILES_DIR).join("infra-oncall");
+        ctx.fs().create_dir_all(&workspace_profile_path).await?;
+        ctx.fs()
+            .write(
+                workspace_profile_path.join("context.json"),
+                r#"{ "paths": ["notes.md", "link.md", "~/.aws/credentials"], "instructions": "Be brief" }"#,
+            )
+            .await?;
+        manager.switch_profile("infra-oncall").await?;
+
+        assert_eq!(manager.paths_outside_workspace(), vec![
+            "~/.aws/credentials".to_string()
+        ]);
+        assert_eq!(manager.profile_instructions(), None);
+        let files = manager.get_current_profile_context_files().await?;
+        assert_eq!(files.len(), 1);
+        assert!(files[0].0.ends_with("notes.md"));
+
+        manager.workspace_profile_approved = true;
+        assert_eq!(manager.profile_instructions(), Some("Be brief"));
+        assert_eq!(manager.get_current_profile_context_files().await?.len(), 2);
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_remove_hook() -> Result<()> {
         let mut manager = create_test_context_manager(None).await?;
@@ -22,8 +22,8 @@ use tracing::{
 };
 
 use super::consts::{
+    CONTEXT_WINDOW_SIZE,
     DUMMY_TOOL_NAME,
-    MAX_CHARS,
     MAX_CONVERSATION_STATE_HISTORY_LEN,
     MAX_USER_MESSAGE_SIZE,
 };
@@ -40,11 +40,14 @@ use super::message::{
     UserMessageContent,
     build_env_state,
 };
+use super::models::ModelInfo;
 use super::token_counter::{
     CharCount,
     CharCounter,
+    TokenCounter,
 };
 use super::tool_manager::ToolManager;
+use super::tools::todo_list::SharedTodos;
 use super::tools::{
     InputSchema,
     QueuedTool,
@@ -99,10 +102,20 @@ pub struct ConversationState {
     /// Tool manager for handling tool and mcp related activities
     #[serde(skip)]
     pub tool_manager: ToolManager,
+    /// Names of the MCP servers whose tools are sent to the model, or [None] for all servers.
+    #[serde(skip)]
+    enabled_mcp_servers: Option<Vec<String>>,
     /// Cached value representing the length of the user context message.
     context_message_length: Option<usize>,
     /// Stores the latest conversation summary created by /compact
     latest_summary: Option<String>,
+    /// The todo list maintained by the `todo_list` tool. Kept outside of the history so that it
+    /// survives /compact.
+    #[serde(default)]
+    pub todos: SharedTodos,
+    /// The model responding in this conversation, or [None] for the backend's default model.
+    #[serde(default)]
+    model: Option<ModelInfo>,
     #[serde(skip)]
     pub updates: Option<SharedWriter>,
 }
@@ -154,8 +167,11 @@ impl ConversationState {
                 }),
             context_manager,
             tool_manager,
+            enabled_mcp_servers: None,
             context_message_length: None,
             latest_summary: None,
+            todos: SharedTodos::default(),
+            model: None,
             updates,
         }
     }
@@ -191,6 +207,21 @@ impl ConversationState {
         }
     }
 
+    pub fn model(&self) -> Option<&ModelInfo> {
+        self.model.as_ref()
+    }
+
+    pub fn set_model(&mut self, model: Option<ModelInfo>) {
+        self.model = model;
+    }
+
+    /// The size of the context window of the current model, in tokens.
+    pub fn context_window_tokens(&self) -> usize {
+        self.model
+            .as_ref()
+            .map_or(CONTEXT_WINDOW_SIZE, |model| model.context_window_tokens)
+    }
+
     pub fn latest_summary(&self) -> Option<&str> {
         self.latest_summary.as_deref()
     }
@@ -262,17 +293,54 @@ impl ConversationState {
         self.next_message = Some(msg);
     }
 
+    /// Attaches images, eg ones pasted into the prompt, to the currently set [Self::next_message].
+    pub fn add_images_to_next_user_message(&mut self, images: Vec<ImageBlock>) {
+        if images.is_empty() {
+            return;
+        }
+
+        if let Some(next_message) = self.next_message.as_mut() {
+            next_message.images.get_or_insert_with(Vec::new).extend(images);
+        }
+    }
+
+    /// Attaches content, eg files mentioned with `@`, to the currently set [Self::next_message]
+    /// for this turn only.
+    pub fn add_attachments_to_next_user_message(&mut self, files: Vec<(String, String)>) {
+        if files.is_empty() {
+            return;
+        }
+
+        if let Some(next_message) = self.next_message.as_mut() {
+            let mut attachments = String::new();
+            attachments.push_str(CONTEXT_ENTRY_START_HEADER);
+            attachments.push_str("The user attached the following files to their message by mentioning them with @. Use them to answer the message, they will not be available in later messages unless they are read again.\n\n");
+            for (filename, content) in files {
+                attachments.push_str(&format!("[{}]\n{}\n", filename, content));
+            }
+            attachments.push_str(CONTEXT_ENTRY_END_HEADER);
+            next_message.attachments = attachments;
+        }
+    }
+
     /// Sets the response message according to the currently set [Self::next_message].
     pub fn push_assistant_message(&mut self, message: AssistantMessage, database: &mut Database) {
+        self.push_unsaved_assistant_message(message);
+
+        if let Ok(cwd) = std::env::current_dir() {
+            database.set_conversation_by_path(cwd, self).ok();
+        }
+    }
+
+    /// Like [Self::push_assistant_message], but without saving the conversation as the one to
+    /// resume in the current directory. Used for conversations that aren't shown to the user, e.g.
+    /// sub-agents.
+    pub fn push_unsaved_assistant_message(&mut self, message: AssistantMessage) {
         debug_assert!(self.next_message.is_some(), "next_message should exist");
         let next_user_message = self.next_message.take().expect("next user message should exist");
 
         self.append_assistant_transcript(&message);
         self.history.push_back((next_user_message, message));
-
-        if let Ok(cwd) = std::env::current_dir() {
-            database.set_conversation_by_path(cwd, self).ok();
-        }
     }
 
     /// Returns the conversation id.
@@ -471,10 +539,15 @@ impl ConversationState {
         }
         self.tool_manager.update().await;
         // TODO: make this more targeted so we don't have to clone the entire list of tools
+        let enabled_mcp_servers = &self.enabled_mcp_servers;
         self.tools = self
             .tool_manager
             .schema
             .values()
+            .filter(|v| match (&v.tool_origin, enabled_mcp_servers) {
+                (ToolOrigin::McpServer(server), Some(servers)) => servers.contains(server),
+                _ => true,
+            })
             .fold(HashMap::<ToolOrigin, Vec<Tool>>::new(), |mut acc, v| {
                 let tool = Tool::ToolSpecification(ToolSpecification {
                     name: v.name.clone(),
@@ -493,6 +566,15 @@ impl ConversationState {
         self.enforce_tool_use_history_invariants();
     }
 
+    /// Limits the tools sent to the model to those of the given MCP servers and the built-in tools.
+    /// Tools from all servers are sent if `servers` is [None].
+    pub async fn set_enabled_mcp_servers(&mut self, servers: Option<Vec<String>>) {
+        if self.enabled_mcp_servers != servers {
+            self.enabled_mcp_servers = servers;
+            self.update_state(true).await;
+        }
+    }
+
     /// Returns a conversation state representation which reflects the exact conversation to send
     /// back to the model.
     pub async fn backend_conversation_state(&mut self, run_hooks: bool, quiet: bool) -> BackendConversationState<'_> {
@@ -528,6 +610,7 @@ impl ConversationState {
             context_messages,
             dropped_context_files,
             tools: &self.tools,
+            model_id: self.model.as_ref().map(|model| model.model_id.as_str()),
         }
     }
 
@@ -599,6 +682,7 @@ impl ConversationState {
             user_input_message_context: None,
             user_intent: None,
             images: None,
+            model_id: self.model.as_ref().map(|model| model.model_id.clone()),
         };
 
         // If the last message contains tool uses, then add cancelled tool results to the summary
@@ -681,6 +765,27 @@ impl ConversationState {
             context_content.push_str(CONTEXT_ENTRY_END_HEADER);
         }
 
+        if let Some(checklist) = self.todo_checklist() {
+            context_content.push_str(CONTEXT_ENTRY_START_HEADER);
+            context_content.push_str("This is the todo list you are maintaining with the todo_list tool, where [x] marks completed items and [~] marks items in progress:\n");
+            context_content.push_str(&checklist);
+            context_content.push('\n');
+            context_content.push_str(CONTEXT_ENTRY_END_HEADER);
+        }
+
+        if let Some(context_manager) = self.context_manager.as_ref() {
+            if let Some(instructions) = context_manager.profile_instructions() {
+                context_content.push_str(CONTEXT_ENTRY_START_HEADER);
+                context_content.push_str(&format!(
+                    "Follow these instructions from the user's \"{}\" profile:\n",
+                    context_manager.current_profile
+                ));
+                context_content.push_str(instructions);
+                context_content.push('\n');
+                context_content.push_str(CONTEXT_ENTRY_END_HEADER);
+            }
+        }
+
         // Add context files if available
         if let Some(context_manager) = self.context_manager.as_mut() {
             match context_manager.collect_context_files_with_limit().await {
@@ -717,6 +822,12 @@ impl ConversationState {
         }
     }
 
+    /// The todo list as a plain text checklist, if it has any items.
+    fn todo_checklist(&self) -> Option<String> {
+        let todos = self.todos.lock().ok()?;
+        (!todos.is_empty()).then(|| todos.to_checklist())
+    }
+
     /// The length of the user message used as context, if any.
     pub fn context_message_length(&self) -> Option<usize> {
         self.context_message_length
@@ -731,7 +842,7 @@ impl ConversationState {
     pub async fn get_token_warning_level(&mut self) -> TokenWarningLevel {
         let total_chars = self.calculate_char_count().await;
 
-        if *total_chars >= MAX_CHARS {
+        if *total_chars >= TokenCounter::token_to_chars(self.context_window_tokens()) {
             TokenWarningLevel::Critical
         } else {
             TokenWarningLevel::None
@@ -830,6 +941,7 @@ pub struct BackendConversationStateImpl<'a, T, U> {
     pub context_messages: U,
     pub dropped_context_files: Vec<(String, String)>,
     pub tools: &'a HashMap<ToolOrigin, Vec<Tool>>,
+    pub model_id: Option<&'a str>,
 }
 
 impl
@@ -849,6 +961,7 @@ impl
         if let Some(ctx) = user_input_message.user_input_message_context.as_mut() {
             ctx.tools = Some(self.tools.values().flatten().cloned().collect::<Vec<_>>());
         }
+        user_input_message.model_id = self.model_id.map(str::to_string);
 
         Ok(FigConversationState {
             conversation_id: Some(self.conversation_id.to_string()),
@@ -0,0 +1,421 @@
+use std::path::{
+    Path,
+    PathBuf,
+};
+use std::sync::LazyLock;
+use std::time::Duration;
+
+use eyre::Result;
+use regex::Regex;
+use tracing::{
+    debug,
+    warn,
+};
+
+use super::prompt::COMMANDS;
+use crate::platform::Context;
+use crate::util::directories;
+
+/// Directory, relative to the current working directory, containing workspace commands.
+pub const WORKSPACE_COMMANDS_DIR: &str = ".amazonq/commands";
+
+/// Placeholder in a command template that is replaced with the arguments to the command.
+const ARGUMENTS_PLACEHOLDER: &str = "$ARGUMENTS";
+
+/// Matches `` !`command` `` in a template, capturing the shell command.
+static SHELL_COMMAND_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"!`([^`]+)`").unwrap());
+
+/// Matches either a shell command, capturing the command, or an `@path` include, capturing the
+/// whitespace before it and the path.
+static EXPANSION_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"!`([^`]+)`|(^|\s)@([^\s`]+)").unwrap());
+
+/// How long a shell command in a template may run before it is killed.
+const SHELL_COMMAND_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Files larger than this are not included by `@path`.
+const MAX_INCLUDE_SIZE: u64 = 256 * 1024;
+
+/// Commands that can't be overridden by a custom command. `/prompts` and `/acceptall` are parsed
+/// by [`super::command::Command`] but aren't offered for completion.
+const RESERVED_COMMANDS: &[&str] = &["prompts", "acceptall"];
+
+/// A slash command defined by a markdown prompt template.
+///
+/// `.amazonq/commands/review.md` in the workspace, or `~/.aws/amazonq/commands/review.md`, defines
+/// `/review`. The template may start with a frontmatter block:
+///
+/// ```markdown
+/// ---
+/// description: Review a file
+/// allowed-tools: fs_read, execute_bash
+/// model: <model id>
+/// ---
+/// Review @src/main.rs, focusing on $ARGUMENTS.
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CustomCommand {
+    pub name: String,
+    pub description: Option<String>,
+    /// Tools that are trusted while responding to this command.
+    pub allowed_tools: Vec<String>,
+    pub model: Option<String>,
+    pub template: String,
+    pub is_global: bool,
+}
+
+impl CustomCommand {
+    /// Parses a command named `name` from the contents of its markdown file.
+    pub fn parse(name: impl Into<String>, content: &str, is_global: bool) -> Self {
+        let mut command = Self {
+            name: name.into(),
+            is_global,
+            ..Default::default()
+        };
+
+        let frontmatter = content
+            .strip_prefix("---\n")
+            .or_else(|| content.strip_prefix("---\r\n"))
+            .and_then(|rest| {
+                let end = rest.find("\n---")?;
+                let body = rest[end + 4..].trim_start_matches(['-']);
+                Some((
+                    &rest[..end],
+                    body.strip_prefix('\n').or(body.strip_prefix("\r\n")).unwrap_or(body),
+                ))
+            });
+
+        let Some((frontmatter, template)) = frontmatter else {
+            command.template = content.to_string();
+            return command;
+        };
+        command.template = template.to_string();
+
+        for line in frontmatter.lines() {
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim().trim_matches(['"', '\'']);
+            if value.is_empty() {
+                continue;
+            }
+            match key.trim() {
+                "description" => command.description = Some(value.to_string()),
+                "model" => command.model = Some(value.to_string()),
+                "allowed-tools" | "allowed_tools" => {
+                    command.allowed_tools = value
+                        .trim_start_matches('[')
+                        .trim_end_matches(']')
+                        .split(',')
+                        .map(|tool| tool.trim().trim_matches(['"', '\'']).to_string())
+                        .filter(|tool| !tool.is_empty())
+                        .collect();
+                },
+                other => debug!(
+                    "ignoring unknown frontmatter key '{other}' in command /{}",
+                    command.name
+                ),
+            }
+        }
+
+        command
+    }
+
+    /// The shell commands that [`Self::expand`] runs.
+    pub fn shell_commands(&self) -> Vec<String> {
+        SHELL_COMMAND_REGEX
+            .captures_iter(&self.template)
+            .filter_map(|c| Some(c.get(1)?.as_str().to_string()))
+            .collect()
+    }
+
+    /// Workspace commands come from whatever repository is checked out, so they have to be approved
+    /// by the user before they run shell commands or trust tools. They can only include files from
+    /// inside the workspace, see [`Self::expand`].
+    pub fn needs_approval(&self) -> bool {
+        !self.is_global && (!self.allowed_tools.is_empty() || !self.shell_commands().is_empty())
+    }
+
+    /// Expands the template into a prompt, substituting `args` for `$ARGUMENTS` and replacing
+    /// `` !`command` `` with the output of the shell command and `@path` with the file's contents.
+    ///
+    /// Arguments are appended to the prompt if the template doesn't reference them. Only the
+    /// template itself is expanded, so command output and included files are inserted as is.
+    /// Workspace commands are left with the `@path` of any file outside of the current working
+    /// directory.
+    pub async fn expand(&self, ctx: &Context, args: &str) -> String {
+        let template = self.template.trim();
+        let cwd = ctx.env().current_dir().unwrap_or_default();
+        let args = args.trim();
+
+        let mut prompt = String::new();
+        let mut last = 0;
+        for captures in EXPANSION_REGEX.captures_iter(template) {
+            let Some(pattern) = captures.get(0) else {
+                continue;
+            };
+            let replacement = match (captures.get(1), captures.get(2), captures.get(3)) {
+                (Some(command), ..) => self.run_shell_command(command.as_str()).await,
+                (None, Some(leading), Some(path)) => self
+                    .include_file(ctx, &cwd, path.as_str())
+                    .await
+                    .map(|content| format!("{}{content}", leading.as_str())),
+                _ => None,
+            };
+            if let Some(replacement) = replacement {
+                prompt.push_str(&template[last..pattern.start()].replace(ARGUMENTS_PLACEHOLDER, args));
+                prompt.push_str(&replacement);
+                last = pattern.end();
+            }
+        }
+        prompt.push_str(&template[last..].replace(ARGUMENTS_PLACEHOLDER, args));
+
+        if !template.contains(ARGUMENTS_PLACEHOLDER) && !args.is_empty() {
+            prompt.push_str("\n\n");
+            prompt.push_str(args);
+        }
+
+        prompt
+    }
+
+    /// Runs a `` !`command` `` from the template, returning its output.
+    async fn run_shell_command(&self, command: &str) -> Option<String> {
+        let output = tokio::process::Command::new("bash")
+            .arg("-c")
+            .arg(command)
+            .kill_on_drop(true)
+            .output();
+        match tokio::time::timeout(SHELL_COMMAND_TIMEOUT, output).await {
+            Ok(Ok(output)) => Some(String::from_utf8_lossy(&output.stdout).trim_end().to_string()),
+            Ok(Err(err)) => {
+                warn!(?err, "failed to run '{command}' for command /{}", self.name);
+                None
+            },
+            Err(_) => {
+                warn!(
+                    "'{command}' for command /{} timed out after {}s",
+                    self.name,
+                    SHELL_COMMAND_TIMEOUT.as_secs()
+                );
+                None
+            },
+        }
+    }
+
+    /// Reads an `@path` from the template, returning the file's contents as a code block.
+    async fn include_file(&self, ctx: &Context, cwd: &Path, path: &str) -> Option<String> {
+        let resolved = resolve_path(ctx, cwd, path);
+        if !self.is_global && !is_inside(ctx, cwd, &resolved).await {
+            warn!(
+                "not including {path} in command /{} since it is outside of the workspace",
+                self.name
+            );
+            return None;
+        }
+        let size = ctx.fs().open(&resolved).await.ok()?.metadata().await.ok()?.len();
+        if size > MAX_INCLUDE_SIZE {
+            warn!(
+                "not including {path} in command /{} since it is larger than {MAX_INCLUDE_SIZE} bytes",
+                self.name
+            );
+            return None;
+        }
+        let content = ctx.fs().read_to_string(&resolved).await.ok()?;
+        Some(format!("\n```{path}\n{}\n```\n", content.trim_end()))
+    }
+}
+
+fn resolve_path(ctx: &Context, cwd: &Path, path: &str) -> PathBuf {
+    match path.strip_prefix("~/") {
+        Some(rest) => directories::home_dir(ctx).map_or_else(|_| cwd.join(path), |home| home.join(rest)),
+        None => cwd.join(path),
+    }
+}
+
+/// Whether `path` is inside of `root` once symlinks and `..` are resolved.
+async fn is_inside(ctx: &Context, root: &Path, path: &Path) -> bool {
+    match (ctx.fs().canonicalize(root).await, ctx.fs().canonicalize(path).await) {
+        (Ok(root), Ok(path)) => path.starts_with(root),
+        _ => false,
+    }
+}
+
+/// Whether `name` is already used by a built-in command.
+fn is_builtin(name: &str) -> bool {
+    RESERVED_COMMANDS.contains(&name)
+        || COMMANDS
+            .iter()
+            .any(|command| command[1..].split(' ').next() == Some(name))
+}
+
+/// Loads the global and workspace commands, sorted by name. Workspace commands take precedence
+/// over global commands of the same name, and commands that would shadow a built-in are skipped.
+pub async fn load_custom_commands(ctx: &Context) -> Vec<CustomCommand> {
+    let mut commands: Vec<CustomCommand> = Vec::new();
+
+    let global_dir = directories::chat_global_commands_dir(ctx).ok();
+    let workspace_dir = ctx.env().current_dir().ok().map(|cwd| cwd.join(WORKSPACE_COMMANDS_DIR));
+    for (dir, is_global) in [(global_dir, true), (workspace_dir, false)] {
+        let Some(dir) = dir else { continue };
+        for command in read_commands_dir(ctx, &dir, is_global).await.unwrap_or_default() {
+            if is_builtin(&command.name) {
+                warn!(
+                    "custom command /{} is ignored since it shadows a built-in command",
+                    command.name
+                );
+                continue;
+            }
+            commands.retain(|c| c.name != command.name);
+            commands.push(command);
+        }
+    }
+
+    commands.sort_by(|a, b| a.name.cmp(&b.name));
+    commands
+}
+
+async fn read_commands_dir(ctx: &Context, dir: &Path, is_global: bool) -> Result<Vec<CustomCommand>> {
+    let mut commands = Vec::new();
+    let mut entries = ctx.fs().read_dir(dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if path.extension().is_none_or(|ext| ext != "md") {
+            continue;
+        }
+        let Some(name) = path.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            continue;
+        }
+        match ctx.fs().read_to_string(&path).await {
+            Ok(content) => commands.push(CustomCommand::parse(name, &content, is_global)),
+            Err(err) => warn!(?err, "failed to read custom command {}", path.display()),
+        }
+    }
+    Ok(commands)
+}
+
+/// Splits `input` of the form `/name args...` into the matching custom command and its arguments.
+pub fn find_custom_command<'a>(input: &'a str, commands: &'a [CustomCommand]) -> Option<(&'a CustomCommand, &'a str)> {
+    let input = input.trim().strip_prefix('/')?;
+    let (name, args) = input.split_once(char::is_whitespace).unwrap_or((input, ""));
+    commands.iter().find(|c| c.name == name).map(|c| (c, args))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_custom_command() {
+        let command = CustomCommand::parse(
+            "review",
+            "---\ndescription: Review a file\nallowed-tools: [fs_read, \"execute_bash\"]\nmodel: some-model\n---\nReview $ARGUMENTS\n",
+            false,
+        );
+        assert_eq!(command, CustomCommand {
+            name: "review".to_string(),
+            description: Some("Review a file".to_string()),
+            allowed_tools: vec!["fs_read".to_string(), "execute_bash".to_string()],
+            model: Some("some-model".to_string()),
+            template: "Review $ARGUMENTS\n".to_string(),
+            is_global: false,
+        });
+
+        let command = CustomCommand::parse("plain", "Just a prompt\n---\nwith a rule", true);
+        assert_eq!(command.template, "Just a prompt\n---\nwith a rule");
+        assert!(command.description.is_none());
+    }
+
+    #[test]
+    fn test_custom_command_needs_approval() {
+        let command = CustomCommand::parse("status", "Explain !`git status` and !`git diff`", false);
+        assert_eq!(command.shell_commands(), vec!["git status", "git diff"]);
+        assert!(command.needs_approval());
+
+        let command = CustomCommand::parse("status", "Explain !`git status`", true);
+        assert!(!command.needs_approval(), "global commands are written by the user");
+
+        let command = CustomCommand::parse("aws", "---\nallowed-tools: use_aws\n---\nList my buckets", false);
+        assert!(command.needs_approval());
+
+        let command = CustomCommand::parse("plain", "Review $ARGUMENTS", false);
+        assert!(!command.needs_approval());
+    }
+
+    #[tokio::test]
+    async fn test_expand_custom_command() {
+        let ctx = Context::builder().with_test_home().await.unwrap().build_fake();
+        ctx.fs().write("/notes.txt", "some notes\n").await.unwrap();
+
+        let command = CustomCommand::parse("test", "Fix $ARGUMENTS using @notes.txt and !`echo shell`", false);
+        assert_eq!(
+            command.expand(&ctx, " the bug ").await,
+            "Fix the bug using \n```notes.txt\nsome notes\n```\n and shell"
+        );
+
+        // Command output is inserted as is, not expanded again
+        let command = CustomCommand::parse("test", "Run !`echo @notes.txt`", false);
+        assert_eq!(command.expand(&ctx, "").await, "Run @notes.txt");
+
+        ctx.fs()
+            .write("/big.txt", "x".repeat(MAX_INCLUDE_SIZE as usize + 1))
+            .await
+            .unwrap();
+        let command = CustomCommand::parse("test", "Read @big.txt", false);
+        assert_eq!(command.expand(&ctx, "").await, "Read @big.txt");
+
+        let command = CustomCommand::parse("test", "Summarize @missing.txt", false);
+        assert_eq!(
+            command.expand(&ctx, "briefly").await,
+            "Summarize @missing.txt\n\nbriefly"
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_expand_workspace_command_outside_workspace() {
+        let ctx = Context::builder().with_test_home().await.unwrap().build_fake();
+        let outside = tempfile::tempdir().unwrap();
+        std::fs::write(outside.path().join("secret.txt"), "secret").unwrap();
+        std::os::unix::fs::symlink(outside.path().join("secret.txt"), ctx.fs().chroot_path("/link.txt")).unwrap();
+
+        let command = CustomCommand::parse("test", "Read @link.txt", false);
+        assert_eq!(command.expand(&ctx, "").await, "Read @link.txt");
+
+        let command = CustomCommand::parse("test", "Read @link.txt", true);
+        assert_eq!(command.expand(&ctx, "").await, "Read \n```link.txt\nsecret\n```\n");
+    }
+
+    #[tokio::test]
+    async fn test_load_custom_commands() {
+        let ctx = Context::builder().with_test_home().await.unwrap().build_fake();
+        let global_dir = directories::chat_global_commands_dir(&ctx).unwrap();
+        ctx.fs().create_dir_all(&global_dir).await.unwrap();
+        ctx.fs()
+            .write(global_dir.join("review.md"), "global review")
+            .await
+            .unwrap();
+        ctx.fs().write(global_dir.join("deploy.md"), "deploy").await.unwrap();
+        ctx.fs().write(global_dir.join("help.md"), "shadowed").await.unwrap();
+        let workspace_dir = Path::new("/").join(WORKSPACE_COMMANDS_DIR);
+        ctx.fs().create_dir_all(&workspace_dir).await.unwrap();
+        ctx.fs()
+            .write(workspace_dir.join("review.md"), "workspace review")
+            .await
+            .unwrap();
+        ctx.fs().write(workspace_dir.join("notes.txt"), "").await.unwrap();
+
+        let commands = load_custom_commands(&ctx).await;
+        let names = commands.iter().map(|c| c.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, vec!["deploy", "review"]);
+        assert_eq!(commands[1].template, "workspace review");
+        assert!(!commands[1].is_global);
+
+        let (command, args) = find_custom_command("/review  src/main.rs", &commands).unwrap();
+        assert_eq!(command.name, "review");
+        assert_eq!(args, " src/main.rs");
+        assert!(find_custom_command("/unknown", &commands).is_none());
+        assert!(find_custom_command("review", &commands).is_none());
+    }
+}
@@ -1,6 +1,9 @@
 use std::collections::HashMap;
 use std::io::Write;
-use std::process::Stdio;
+use std::process::{
+    ExitStatus,
+    Stdio,
+};
 use std::time::{
     Duration,
     Instant,
@@ -26,6 +29,7 @@ use futures::stream::{
     FuturesUnordered,
     StreamExt,
 };
+use globset::GlobBuilder;
 use serde::{
     Deserialize,
     Serialize,
@@ -34,6 +38,8 @@ use spinners::{
     Spinner,
     Spinners,
 };
+use tokio::io::AsyncWriteExt;
+use tracing::warn;
 
 use super::util::truncate_safe;
 
@@ -62,6 +68,10 @@ pub struct Hook {
     #[serde(default = "Hook::default_cache_ttl_seconds")]
     pub cache_ttl_seconds: u64,
 
+    /// Glob matched against the tool name for tool use hooks. Matches every tool if unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub matcher: Option<String>,
+
     // Type-specific fields
     /// The bash command to execute
     pub command: Option<String>, // For inline hooks
@@ -71,6 +81,9 @@ pub struct Hook {
     pub name: String,
     #[serde(skip)]
     pub is_global: bool,
+    /// Whether the hook comes from a workspace profile. Such hooks can't allow tool uses.
+    #[serde(skip)]
+    pub from_workspace: bool,
 }
 
 impl Hook {
@@ -82,8 +95,10 @@ impl Hook {
             timeout_ms: Self::default_timeout_ms(),
             max_output_size: Self::default_max_output_size(),
             cache_ttl_seconds: Self::default_cache_ttl_seconds(),
+            matcher: None,
             command: Some(command),
             is_global: false,
+            from_workspace: false,
             name: "new hook".to_string(),
         }
     }
@@ -103,6 +118,24 @@ impl Hook {
     fn default_cache_ttl_seconds() -> u64 {
         DEFAULT_CACHE_TTL_SECONDS
     }
+
+    /// Whether this hook should run for `event`.
+    fn matches(&self, event: &HookEvent) -> bool {
+        if self.disabled || self.trigger != event.trigger() {
+            return false;
+        }
+
+        match (&self.matcher, event.tool_name()) {
+            (Some(matcher), Some(tool_name)) => match GlobBuilder::new(matcher).build() {
+                Ok(glob) => glob.compile_matcher().is_match(tool_name),
+                Err(err) => {
+                    warn!(?err, "invalid matcher for hook {}", self.name);
+                    false
+                },
+            },
+            _ => true,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
@@ -112,11 +145,146 @@ pub enum HookType {
     Inline,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Eq, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub enum HookTrigger {
     ConversationStart,
     PerPrompt,
+    /// Before a tool is used. The hook may allow, deny, or modify the tool use.
+    PreToolUse,
+    /// After a tool is used. The hook may block, sending its reason back to the model.
+    PostToolUse,
+    /// After the assistant finishes a response.
+    AssistantResponse,
+    /// When the chat session ends.
+    SessionEnd,
+}
+
+impl HookTrigger {
+    pub const ALL: [HookTrigger; 6] = [
+        HookTrigger::ConversationStart,
+        HookTrigger::PerPrompt,
+        HookTrigger::PreToolUse,
+        HookTrigger::PostToolUse,
+        HookTrigger::AssistantResponse,
+        HookTrigger::SessionEnd,
+    ];
+
+    /// Whether the output of hooks with this trigger is added to the conversation context. All
+    /// other triggers run in response to a [`HookEvent`].
+    pub fn adds_context(&self) -> bool {
+        matches!(self, HookTrigger::ConversationStart | HookTrigger::PerPrompt)
+    }
+}
+
+/// An event that event hooks are run for, written to the hook's stdin as JSON.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "hook_event_name", rename_all = "snake_case")]
+pub enum HookEvent {
+    PreToolUse {
+        tool_name: String,
+        tool_input: serde_json::Value,
+    },
+    PostToolUse {
+        tool_name: String,
+        tool_input: serde_json::Value,
+        tool_response: String,
+        success: bool,
+    },
+    AssistantResponse {
+        response: String,
+    },
+    SessionEnd,
+}
+
+impl HookEvent {
+    pub fn trigger(&self) -> HookTrigger {
+        match self {
+            HookEvent::PreToolUse { .. } => HookTrigger::PreToolUse,
+            HookEvent::PostToolUse { .. } => HookTrigger::PostToolUse,
+            HookEvent::AssistantResponse { .. } => HookTrigger::AssistantResponse,
+            HookEvent::SessionEnd => HookTrigger::SessionEnd,
+        }
+    }
+
+    fn tool_name(&self) -> Option<&str> {
+        match self {
+            HookEvent::PreToolUse { tool_name, .. } | HookEvent::PostToolUse { tool_name, .. } => Some(tool_name),
+            HookEvent::AssistantResponse { .. } | HookEvent::SessionEnd => None,
+        }
+    }
+}
+
+/// The result of running an event hook.
+///
+/// A hook exiting with code 0 continues, unless its stdout is a JSON object with a `decision` of
+/// `allow`, `deny` (or `block`), or `modify`. A hook exiting with code 2 blocks, using its stderr
+/// as the reason. Any other exit code, or a timeout, fails the hook without blocking.
+#[derive(Debug, Clone, PartialEq)]
+pub enum HookOutcome {
+    /// No decision was made. Contains the hook's stdout.
+    Continue(String),
+    /// The tool use is allowed without asking the user. Only honored when the
+    /// `chat.hooks.allowTools` setting is enabled, and never for workspace profile hooks.
+    Allow,
+    /// The tool use is blocked, with a reason for the model.
+    Block(String),
+    /// The tool use continues with the given input instead.
+    Modify(serde_json::Value),
+}
+
+#[derive(Debug, Deserialize)]
+struct HookDecision {
+    decision: HookDecisionKind,
+    #[serde(default)]
+    reason: Option<String>,
+    #[serde(default)]
+    tool_input: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum HookDecisionKind {
+    Allow,
+    #[serde(alias = "block")]
+    Deny,
+    Modify,
+}
+
+impl HookOutcome {
+    fn from_command_output(hook: &Hook, output: CommandOutput) -> Result<Self> {
+        match output.status.code() {
+            Some(0) => Ok(match serde_json::from_str::<HookDecision>(output.stdout.trim()) {
+                Ok(HookDecision {
+                    decision: HookDecisionKind::Allow,
+                    ..
+                }) => HookOutcome::Allow,
+                Ok(HookDecision {
+                    decision: HookDecisionKind::Deny,
+                    reason,
+                    ..
+                }) => HookOutcome::Block(reason.unwrap_or_else(|| format!("Blocked by hook {}", hook.name))),
+                Ok(HookDecision {
+                    decision: HookDecisionKind::Modify,
+                    tool_input: Some(tool_input),
+                    ..
+                }) => HookOutcome::Modify(tool_input),
+                _ => HookOutcome::Continue(output.stdout),
+            }),
+            Some(2) => Ok(HookOutcome::Block(match output.stderr.trim() {
+                "" => format!("Blocked by hook {}", hook.name),
+                stderr => stderr.to_string(),
+            })),
+            _ => Err(eyre!("command returned non-zero exit code: {}", output.status)),
+        }
+    }
+}
+
+/// Exit status and truncated output of an inline hook command.
+struct CommandOutput {
+    status: ExitStatus,
+    stdout: String,
+    stderr: String,
 }
 
 #[derive(Debug, Clone)]
@@ -125,11 +293,19 @@ pub struct CachedHook {
     expiry: Option<Instant>,
 }
 
+#[derive(Debug, Clone)]
+pub struct CachedOutcome {
+    outcome: HookOutcome,
+    expiry: Instant,
+}
+
 /// Maps a hook name to a [`CachedHook`]
 #[derive(Debug, Clone, Default)]
 pub struct HookExecutor {
     pub global_cache: HashMap<String, CachedHook>,
     pub profile_cache: HashMap<String, CachedHook>,
+    /// Maps a hook name and event payload to the [`HookOutcome`] of an event hook
+    pub event_cache: HashMap<String, CachedOutcome>,
 }
 
 impl HookExecutor {
@@ -137,6 +313,7 @@ impl HookExecutor {
         Self {
             global_cache: HashMap::new(),
             profile_cache: HashMap::new(),
+            event_cache: HashMap::new(),
         }
     }
 
@@ -146,7 +323,8 @@ impl HookExecutor {
     /// If `updates` is `Some`, progress on hook execution will be written to it.
     /// Errors encountered with write operations to `updates` are ignored.
     ///
-    /// Note: [`HookTrigger::ConversationStart`] hooks never leave the cache.
+    /// Note: [`HookTrigger::ConversationStart`] hooks never leave the cache. Event hooks are
+    /// skipped, see [`HookExecutor::run_event_hooks`].
     pub async fn run_hooks(&mut self, hooks: Vec<&Hook>, mut updates: Option<&mut impl Write>) -> Vec<(Hook, String)> {
         let mut results = Vec::with_capacity(hooks.len());
         let mut futures = FuturesUnordered::new();
@@ -156,7 +334,7 @@ impl HookExecutor {
         // however, for output display we want to process hooks as they complete rather than the
         // order they were started in. The index will be used later to sort them back to output order.
         for (index, hook) in hooks.into_iter().enumerate() {
-            if hook.disabled {
+            if hook.disabled || !hook.trigger.adds_context() {
                 continue;
             }
 
@@ -270,7 +448,11 @@ impl HookExecutor {
         results.iter().skip(start_cache_index).for_each(|(_, (hook, output))| {
             let expiry = match hook.trigger {
                 HookTrigger::ConversationStart => None,
-                HookTrigger::PerPrompt => Some(Instant::now() + Duration::from_secs(hook.cache_ttl_seconds)),
+                HookTrigger::PerPrompt
+                | HookTrigger::PreToolUse
+                | HookTrigger::PostToolUse
+                | HookTrigger::AssistantResponse
+                | HookTrigger::SessionEnd => Some(Instant::now() + Duration::from_secs(hook.cache_ttl_seconds)),
             };
             self.insert_cache(hook, CachedHook {
                 output: output.clone(),
@@ -283,6 +465,75 @@ impl HookExecutor {
         results.into_iter().map(|(_, r)| r).collect()
     }
 
+    /// Run the event hooks in `hooks` that match `event`, writing the event to their stdin as JSON.
+    /// Outcomes are cached by hook and event payload for the hook's `cache_ttl_seconds`. Hooks
+    /// that fail to execute will not be returned.
+    ///
+    /// If `updates` is `Some`, hook failures will be written to it.
+    /// Errors encountered with write operations to `updates` are ignored.
+    pub async fn run_event_hooks(
+        &mut self,
+        hooks: Vec<&Hook>,
+        event: &HookEvent,
+        mut updates: Option<&mut impl Write>,
+    ) -> Vec<(Hook, HookOutcome)> {
+        let payload = match serde_json::to_string(event) {
+            Ok(payload) => payload,
+            Err(err) => {
+                warn!(?err, "failed to serialize hook event");
+                return Vec::new();
+            },
+        };
+
+        let mut results = Vec::new();
+        for hook in hooks.into_iter().filter(|hook| hook.matches(event)) {
+            let cache_key = format!("{}:{}:{payload}", hook.is_global, hook.name);
+            if let Some(cached) = self.event_cache.get(&cache_key).filter(|c| Instant::now() < c.expiry) {
+                results.push((hook.clone(), cached.outcome.clone()));
+                continue;
+            }
+
+            let start_time = Instant::now();
+            let result = match hook.r#type {
+                HookType::Inline => self
+                    .run_inline_command(hook, Some(payload.as_bytes()))
+                    .await
+                    .and_then(|output| HookOutcome::from_command_output(hook, output)),
+            };
+
+            match result {
+                Ok(outcome) => {
+                    if hook.cache_ttl_seconds > 0 {
+                        self.event_cache.insert(cache_key, CachedOutcome {
+                            outcome: outcome.clone(),
+                            expiry: Instant::now() + Duration::from_secs(hook.cache_ttl_seconds),
+                        });
+                    }
+                    results.push((hook.clone(), outcome));
+                },
+                Err(e) => {
+                    if let Some(updates) = updates.as_deref_mut() {
+                        let _ = queue!(
+                            updates,
+                            style::SetForegroundColor(style::Color::Red),
+                            style::Print("✗ "),
+                            style::SetForegroundColor(style::Color::Blue),
+                            style::Print(&hook.name),
+                            style::ResetColor,
+                            style::Print(" failed after "),
+                            style::SetForegroundColor(style::Color::Yellow),
+                            style::Print(format!("{:.2} s", start_time.elapsed().as_secs_f32())),
+                            style::ResetColor,
+                            style::Print(format!(": {}\n", e)),
+                        );
+                    }
+                },
+            }
+        }
+
+        results
+    }
+
     async fn execute_hook<'a>(&self, hook: &'a Hook) -> (&'a Hook, Result<String>, Duration) {
         let start_time = Instant::now();
         let result = match hook.r#type {
@@ -293,36 +544,59 @@ impl HookExecutor {
     }
 
     async fn execute_inline_hook(&self, hook: &Hook) -> Result<String> {
+        let output = self.run_inline_command(hook, None).await?;
+        if output.status.success() {
+            Ok(output.stdout)
+        } else {
+            Err(eyre!("command returned non-zero exit code: {}", output.status))
+        }
+    }
+
+    /// Runs the hook's command, writing `stdin` to it if provided. Output is truncated to the
+    /// hook's `max_output_size`.
+    async fn run_inline_command(&self, hook: &Hook, stdin: Option<&[u8]>) -> Result<CommandOutput> {
         let command = hook.command.as_ref().ok_or_else(|| eyre!("no command specified"))?;
 
-        let command_future = tokio::process::Command::new("bash")
+        let mut child = tokio::process::Command::new("bash")
             .arg("-c")
             .arg(command)
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
-            .output();
+            .kill_on_drop(true)
+            .spawn()?;
+
+        let child_stdin = child.stdin.take();
+        let write_stdin = async move {
+            if let (Some(mut child_stdin), Some(stdin)) = (child_stdin, stdin) {
+                // The hook is free to ignore its input, so a closed pipe is not an error.
+                let _ = child_stdin.write_all(stdin).await;
+            }
+        };
+        let command_future = async move { tokio::join!(write_stdin, child.wait_with_output()).1 };
         let timeout = Duration::from_millis(hook.timeout_ms);
 
         // Run with timeout
         match tokio::time::timeout(timeout, command_future).await {
             Ok(result) => {
                 let result = result?;
-                if result.status.success() {
-                    let stdout = result.stdout.to_str_lossy();
-                    let stdout = format!(
+                let truncate = |output: &[u8]| {
+                    let output = output.to_str_lossy();
+                    format!(
                         "{}{}",
-                        truncate_safe(&stdout, hook.max_output_size),
-                        if stdout.len() > hook.max_output_size {
+                        truncate_safe(&output, hook.max_output_size),
+                        if output.len() > hook.max_output_size {
                             " ... truncated"
                         } else {
                             ""
                         }
-                    );
-                    Ok(stdout)
-                } else {
-                    Err(eyre!("command returned non-zero exit code: {}", result.status))
-                }
+                    )
+                };
+                Ok(CommandOutput {
+                    status: result.status,
+                    stdout: truncate(&result.stdout),
+                    stderr: truncate(&result.stderr),
+                })
             },
             Err(_) => Err(eyre!("command timed out after {} ms", timeout.as_millis())),
         }
@@ -554,4 +828,111 @@ mod tests {
 
         assert!(results[0].1.len() <= hook.max_output_size + " ... truncated".len());
     }
+
+    fn pre_tool_use_event(tool_name: &str) -> HookEvent {
+        HookEvent::PreToolUse {
+            tool_name: tool_name.to_string(),
+            tool_input: serde_json::json!({ "command": "ls" }),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_event_hook_receives_event_on_stdin() {
+        let mut executor = HookExecutor::new();
+        let hook = Hook::new_inline_hook(HookTrigger::PreToolUse, "cat".to_string());
+
+        let results = executor
+            .run_event_hooks(vec![&hook], &pre_tool_use_event("execute_bash"), None::<&mut Stdout>)
+            .await;
+
+        assert_eq!(results.len(), 1);
+        let HookOutcome::Continue(stdout) = &results[0].1 else {
+            panic!("expected the hook to continue");
+        };
+        let event: serde_json::Value = serde_json::from_str(stdout).unwrap();
+        assert_eq!(event["hook_event_name"], "pre_tool_use");
+        assert_eq!(event["tool_name"], "execute_bash");
+        assert_eq!(event["tool_input"]["command"], "ls");
+    }
+
+    #[tokio::test]
+    async fn test_event_hook_outcomes() {
+        let mut executor = HookExecutor::new();
+        let event = pre_tool_use_event("execute_bash");
+        let cases = [
+            ("echo '{\"decision\": \"allow\"}'", HookOutcome::Allow),
+            (
+                "echo '{\"decision\": \"deny\", \"reason\": \"no\"}'",
+                HookOutcome::Block("no".to_string()),
+            ),
+            (
+                "echo 'not allowed' >&2; exit 2",
+                HookOutcome::Block("not allowed".to_string()),
+            ),
+            (
+                "echo '{\"decision\": \"modify\", \"tool_input\": {\"command\": \"pwd\"}}'",
+                HookOutcome::Modify(serde_json::json!({ "command": "pwd" })),
+            ),
+        ];
+
+        for (command, expected) in cases {
+            let hook = Hook::new_inline_hook(HookTrigger::PreToolUse, command.to_string());
+            let results = executor.run_event_hooks(vec![&hook], &event, None::<&mut Stdout>).await;
+            assert_eq!(results[0].1, expected, "{command}");
+        }
+
+        // Other exit codes fail the hook without blocking.
+        let hook = Hook::new_inline_hook(HookTrigger::PreToolUse, "exit 1".to_string());
+        let results = executor.run_event_hooks(vec![&hook], &event, None::<&mut Stdout>).await;
+        assert!(results.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_event_hook_filtering() {
+        let mut executor = HookExecutor::new();
+        let mut fs_hook = Hook::new_inline_hook(HookTrigger::PreToolUse, "echo fs".to_string());
+        fs_hook.matcher = Some("fs_*".to_string());
+        let post_hook = Hook::new_inline_hook(HookTrigger::PostToolUse, "echo post".to_string());
+        let context_hook = Hook::new_inline_hook(HookTrigger::PerPrompt, "echo context".to_string());
+        let hooks = || vec![&fs_hook, &post_hook, &context_hook];
+
+        let results = executor
+            .run_event_hooks(hooks(), &pre_tool_use_event("fs_write"), None::<&mut Stdout>)
+            .await;
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].1, HookOutcome::Continue("fs\n".to_string()));
+
+        let results = executor
+            .run_event_hooks(hooks(), &pre_tool_use_event("execute_bash"), None::<&mut Stdout>)
+            .await;
+        assert!(results.is_empty());
+
+        // Event hooks never add context.
+        let results = executor.run_hooks(hooks(), None::<&mut Stdout>).await;
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].0.trigger, HookTrigger::PerPrompt);
+    }
+
+    #[tokio::test]
+    async fn test_event_hook_cache() {
+        let mut executor = HookExecutor::new();
+        let mut hook = Hook::new_inline_hook(HookTrigger::AssistantResponse, "date +%s%N".to_string());
+        hook.cache_ttl_seconds = 60;
+        let event = |response: &str| HookEvent::AssistantResponse {
+            response: response.to_string(),
+        };
+
+        let first = executor
+            .run_event_hooks(vec![&hook], &event("a"), None::<&mut Stdout>)
+            .await;
+        let cached = executor
+            .run_event_hooks(vec![&hook], &event("a"), None::<&mut Stdout>)
+            .await;
+        let other = executor
+            .run_event_hooks(vec![&hook], &event("b"), None::<&mut Stdout>)
+            .await;
+
+        assert_eq!(first[0].1, cached[0].1);
+        assert_ne!(first[0].1, other[0].1);
+    }
 }
@@ -1,9 +1,18 @@
+use std::path::PathBuf;
+
 use eyre::Result;
 use rustyline::error::ReadlineError;
+use tracing::warn;
 
+use super::consts::MAX_NUMBER_OF_IMAGES_PER_REQUEST;
 use super::prompt::rl;
 #[cfg(unix)]
 use super::skim_integration::SkimCommandSelector;
+use super::util::clipboard::extract_inline_images;
+use super::util::images::{
+    RichImageBlocks,
+    pasted_image_placeholder,
+};
 use crate::database::Database;
 
 #[derive(Debug)]
@@ -31,8 +40,14 @@ impl InputSource {
         database: &Database,
         sender: std::sync::mpsc::Sender<Option<String>>,
         receiver: std::sync::mpsc::Receiver<Vec<String>>,
+        history_path: Option<PathBuf>,
     ) -> Result<Self> {
-        Ok(Self(inner::Inner::Readline(rl(database, sender, receiver)?)))
+        Ok(Self(inner::Inner::Readline(rl(
+            database,
+            sender,
+            receiver,
+            history_path,
+        )?)))
     }
 
     #[cfg(unix)]
@@ -41,6 +56,7 @@ impl InputSource {
         database: &crate::database::Database,
         context_manager: std::sync::Arc<super::context::ContextManager>,
         tool_names: Vec<String>,
+        custom_commands: Vec<String>,
     ) {
         use rustyline::{
             EventHandler,
@@ -56,11 +72,24 @@ impl InputSource {
             };
             rl.bind_sequence(
                 KeyEvent::ctrl(key_char),
-                EventHandler::Conditional(Box::new(SkimCommandSelector::new(context_manager, tool_names))),
+                EventHandler::Conditional(Box::new(SkimCommandSelector::new(
+                    context_manager,
+                    tool_names,
+                    custom_commands,
+                ))),
             );
         }
     }
 
+    /// Sets the custom slash commands offered for completion.
+    pub fn set_custom_commands(&mut self, custom_commands: Vec<String>) {
+        if let inner::Inner::Readline(rl) = &mut self.0 {
+            if let Some(helper) = rl.helper_mut() {
+                helper.set_custom_commands(custom_commands);
+            }
+        }
+    }
+
     #[allow(dead_code)]
     pub fn new_mock(lines: Vec<String>) -> Self {
         Self(inner::Inner::Mock { index: 0, lines })
@@ -72,10 +101,7 @@ impl InputSource {
                 let prompt = prompt.unwrap_or_default();
                 let curr_line = rl.readline(prompt);
                 match curr_line {
-                    Ok(line) => {
-                        let _ = rl.add_history_entry(line.as_str());
-                        Ok(Some(line))
-                    },
+                    Ok(line) => Ok(Some(line)),
                     Err(ReadlineError::Interrupted | ReadlineError::Eof) => Ok(None),
                     Err(err) => Err(err),
                 }
@@ -87,6 +113,55 @@ impl InputSource {
         }
     }
 
+    /// Adds a prompt to the history, saving it to the history file if there is one.
+    pub fn add_history_entry(&mut self, line: &str) {
+        if let inner::Inner::Readline(rl) = &mut self.0 {
+            if !rl.add_history_entry(line).unwrap_or(false) {
+                return;
+            }
+            let history_path = rl.helper().and_then(|helper| {
+                if let Ok(mut history) = helper.history.lock() {
+                    history.push(line.to_string());
+                }
+                helper.history_path.clone()
+            });
+            if let Some(path) = history_path {
+                if let Some(parent) = path.parent() {
+                    let _ = std::fs::create_dir_all(parent);
+                }
+                if let Err(err) = rl.append_history(&path) {
+                    warn!(?err, ?path, "failed to save the prompt history");
+                }
+            }
+        }
+    }
+
+    /// Takes the images that were pasted into `line`. Images pasted as inline terminal image
+    /// escape sequences are replaced with placeholders, and images whose placeholder was deleted
+    /// from the line are dropped.
+    pub fn take_pasted_images(&mut self, line: &mut String) -> RichImageBlocks {
+        let mut images = match &self.0 {
+            inner::Inner::Readline(rl) => rl
+                .helper()
+                .and_then(|helper| {
+                    helper
+                        .pasted_images
+                        .lock()
+                        .ok()
+                        .map(|mut images| std::mem::take(&mut *images))
+                })
+                .unwrap_or_default(),
+            inner::Inner::Mock { .. } => RichImageBlocks::new(),
+        };
+
+        let (replaced, inline_images) = extract_inline_images(line, images.len() + 1);
+        *line = replaced;
+        images.extend(inline_images);
+        images.retain(|(_, metadata)| line.contains(&pasted_image_placeholder(metadata)));
+        images.truncate(MAX_NUMBER_OF_IMAGES_PER_REQUEST);
+        images
+    }
+
     // We're keeping this method for potential future use
     #[allow(dead_code)]
     pub fn set_buffer(&mut self, content: &str) {
@@ -113,4 +188,20 @@ mod tests {
         assert_eq!(input.read_line(None).unwrap().unwrap(), l3);
         assert!(input.read_line(None).unwrap().is_none());
     }
+
+    #[test]
+    fn test_take_pasted_images() {
+        use base64::Engine;
+
+        let png = base64::engine::general_purpose::STANDARD.encode(b"\x89PNG\r\n\x1a\n");
+        let mut input = InputSource::new_mock(vec![]);
+        let mut line = format!("describe \x1b]1337;File=inline=1:{png}\x07 please");
+
+        let images = input.take_pasted_images(&mut line);
+        assert_eq!(line, "describe [image #1] please");
+        assert_eq!(images.len(), 1);
+
+        let mut line = "nothing pasted".to_string();
+        assert!(input.take_pasted_images(&mut line).is_empty());
+    }
 }
@@ -254,7 +254,7 @@ fn resolve_scope_profile(ctx: &Context, scope: Option<Scope>) -> Result<PathBuf>
     })
 }
 
-fn expand_path(ctx: &Context, p: &str) -> Result<PathBuf> {
+pub(super) fn expand_path(ctx: &Context, p: &str) -> Result<PathBuf> {
     let p = shellexpand::tilde(p);
     let mut path = PathBuf::from(p.as_ref());
     if path.is_relative() {
@@ -0,0 +1,373 @@
+use std::io::Write;
+use std::sync::LazyLock;
+
+use crossterm::style::Color;
+use crossterm::{
+    queue,
+    style,
+};
+use regex::Regex;
+use serde::Deserialize;
+
+use super::context::collect_files_at_path;
+use super::util::drop_matched_context_files;
+use super::util::images::{
+    RichImageBlocks,
+    handle_images_from_paths,
+    is_supported_image_type,
+};
+use crate::platform::Context;
+use crate::util::CLI_BINARY_NAME;
+use crate::util::env_var::QTERM_SESSION_ID;
+
+static LINE_RANGE_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^(.+):(\d+)(?:-(\d+))?$").unwrap());
+
+/// A file or directory mentioned in a prompt with `@`, e.g. `@src/main.rs:40-80` or `@src/`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mention {
+    pub path: String,
+    /// The 1-based, inclusive range of lines to attach.
+    pub lines: Option<(usize, usize)>,
+}
+
+impl Mention {
+    /// Parses a single word of a prompt, including its leading `@`. Trailing punctuation is
+    /// ignored so that mentions can end a sentence.
+    pub fn parse(word: &str) -> Option<Self> {
+        if OutputMention::parse(word).is_some() {
+            return None;
+        }
+        let word = word
+            .strip_prefix('@')?
+            .trim_end_matches([',', ';', '!', '?', ')', '\'', '"', '.']);
+        if word.is_empty() {
+            return None;
+        }
+
+        match LINE_RANGE_REGEX.captures(word) {
+            Some(captures) => {
+                let start = captures[2].parse::<usize>().ok()?;
+                let end = match captures.get(3) {
+                    Some(end) => end.as_str().parse::<usize>().ok()?,
+                    None => start,
+                };
+                if start == 0 || end < start {
+                    return None;
+                }
+                Some(Self {
+                    path: captures[1].to_string(),
+                    lines: Some((start, end)),
+                })
+            },
+            None => Some(Self {
+                path: word.to_string(),
+                lines: None,
+            }),
+        }
+    }
+
+    /// Whether the mention was meant as a path, i.e. it has a line range, contains a `/` or `.`
+    /// or exists.
+    fn looks_like_path(&self, ctx: &Context) -> bool {
+        self.lines.is_some()
+            || self.path.contains(['/', '.'])
+            || ctx
+                .env()
+                .current_dir()
+                .is_ok_and(|cwd| ctx.fs().exists(cwd.join(&self.path)))
+    }
+
+    fn label(&self) -> String {
+        match self.lines {
+            Some((start, end)) if start == end => format!("{}:{start}", self.path),
+            Some((start, end)) => format!("{}:{start}-{end}", self.path),
+            None => self.path.clone(),
+        }
+    }
+}
+
+/// The output of a command recently run in the terminal, mentioned with `@last-output` or
+/// `@cmd:N`, where `@cmd:1` is the last command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutputMention {
+    /// How many commands back the command was run, starting at 1.
+    pub n: u32,
+}
+
+impl OutputMention {
+    pub fn parse(word: &str) -> Option<Self> {
+        let word = word
+            .strip_prefix('@')?
+            .trim_end_matches([',', ';', '!', '?', ')', '\'', '"', '.']);
+        let n = match word {
+            "last-output" => 1,
+            _ => word.strip_prefix("cmd:")?.parse().ok().filter(|n| *n > 0)?,
+        };
+        Some(Self { n })
+    }
+
+    fn label(&self) -> String {
+        match self.n {
+            1 => "last-output".into(),
+            n => format!("cmd:{n}"),
+        }
+    }
+}
+
+/// A command output as printed by the `command-output` internal command of the CLI.
+#[derive(Debug, Deserialize)]
+struct CommandOutput {
+    command: Option<String>,
+    exit_code: Option<i32>,
+    output: String,
+    truncated: bool,
+}
+
+impl CommandOutput {
+    /// The attachment name and content of the output.
+    fn into_attachment(self) -> (String, String) {
+        let command = self.command.as_deref().unwrap_or("unknown command");
+        let name = match self.exit_code {
+            Some(code) => format!("output of `{command}` (exit code {code})"),
+            None => format!("output of `{command}`"),
+        };
+        let content = match self.truncated {
+            true => format!("[earlier output truncated]\n{}", self.output),
+            false => self.output,
+        };
+        (name, content)
+    }
+}
+
+/// Returns the unique mentions in `input`, in order of appearance.
+pub fn parse_mentions(input: &str) -> Vec<Mention> {
+    let mut mentions = Vec::new();
+    for mention in input.split_whitespace().filter_map(Mention::parse) {
+        if !mentions.contains(&mention) {
+            mentions.push(mention);
+        }
+    }
+    mentions
+}
+
+/// Returns the unique command output mentions in `input`, in order of appearance.
+pub fn parse_output_mentions(input: &str) -> Vec<OutputMention> {
+    let mut mentions = Vec::new();
+    for mention in input.split_whitespace().filter_map(OutputMention::parse) {
+        if !mentions.contains(&mention) {
+            mentions.push(mention);
+        }
+    }
+    mentions
+}
+
+/// Gets the output of the command mentioned by `mention` from the terminal, which keeps the output
+/// of the last commands run in it.
+async fn command_output(ctx: &Context, mention: OutputMention) -> Result<(String, String), String> {
+    if ctx.env().get(QTERM_SESSION_ID).is_err() {
+        return Err("command output is only captured in terminals with shell integrations".into());
+    }
+
+    let output = tokio::process::Command::new(CLI_BINARY_NAME)
+        .args(["_", "command-output", "--index", &(mention.n - 1).to_string()])
+        .output()
+        .await
+        .map_err(|err| err.to_string())?;
+    if !output.status.success() {
+        return Err("failed to get the command output from the terminal".into());
+    }
+
+    serde_json::from_slice::<Vec<CommandOutput>>(&output.stdout)
+        .map_err(|err| err.to_string())?
+        .into_iter()
+        .next()
+        .map(CommandOutput::into_attachment)
+        .ok_or_else(|| "no output was captured for that command".into())
+}
+
+/// The content attached to a single prompt through its mentions.
+#[derive(Debug, Default)]
+pub struct Attachments {
+    /// (filename, content) pairs of the attached text files.
+    pub files: Vec<(String, String)>,
+    pub images: RichImageBlocks,
+}
+
+/// Collects the files, directories, images and command outputs mentioned in `input`.
+///
+/// Mentions that can't be attached are reported to `output`. Like context files, text files are
+/// dropped largest first once their total size exceeds `max_size` tokens.
+pub async fn collect_attachments(
+    ctx: &Context,
+    output: &mut impl Write,
+    input: &str,
+    max_size: usize,
+) -> Result<Attachments, std::io::Error> {
+    let mut attachments = Attachments::default();
+    let mut image_paths = Vec::new();
+
+    for mention in parse_mentions(input) {
+        if mention.lines.is_none() && is_supported_image_type(&mention.path) {
+            image_paths.push(mention.path);
+            continue;
+        }
+
+        match collect_files_at_path(ctx, &mention.path).await {
+            Ok(files) if files.is_empty() => {
+                print_warning(
+                    output,
+                    &format!("Could not attach @{}: no text files found", mention.label()),
+                )?;
+            },
+            Ok(files) => {
+                for (filename, content) in files {
+                    match mention.lines {
+                        Some(lines) => attachments.files.push((
+                            format!("{filename}:{}-{}", lines.0, lines.1),
+                            select_lines(&content, lines),
+                        )),
+                        None => attachments.files.push((filename, content)),
+                    }
+                }
+            },
+            // Words like `@alice` are most likely not meant as paths, so only report those that are
+            Err(err) if mention.looks_like_path(ctx) => {
+                print_warning(output, &format!("Could not attach @{}: {err}", mention.label()))?;
+            },
+            Err(_) => (),
+        }
+    }
+
+    for mention in parse_output_mentions(input) {
+        match command_output(ctx, mention).await {
+            Ok(attachment) => attachments.files.push(attachment),
+            Err(err) => print_warning(output, &format!("Could not attach @{}: {err}", mention.label()))?,
+        }
+    }
+
+    if !image_paths.is_empty() {
+        attachments.images = handle_images_from_paths(output, &image_paths);
+    }
+
+    let dropped = drop_matched_context_files(&mut attachments.files, max_size).unwrap_or_default();
+    attachments
+        .files
+        .retain(|file| !dropped.iter().any(|dropped| dropped.0 == file.0));
+    for (filename, _) in dropped {
+        print_warning(
+            output,
+            &format!("Could not attach {filename}: the attached files exceed the size limit"),
+        )?;
+    }
+
+    Ok(attachments)
+}
+
+/// Returns the 1-based, inclusive range of `lines` of `content`.
+fn select_lines(content: &str, (start, end): (usize, usize)) -> String {
+    content
+        .lines()
+        .skip(start - 1)
+        .take(end - start + 1)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn print_warning(output: &mut impl Write, message: &str) -> Result<(), std::io::Error> {
+    queue!(
+        output,
+        style::SetForegroundColor(Color::DarkYellow),
+        style::Print(format!("{message}\n")),
+        style::SetForegroundColor(Color::Reset),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_mentions() {
+        assert_eq!(
+            parse_mentions("explain @src/main.rs:40-80, then compare with @src/ and @src/main.rs:40-80."),
+            vec![
+                Mention {
+                    path: "src/main.rs".to_string(),
+                    lines: Some((40, 80)),
+                },
+                Mention {
+                    path: "src/".to_string(),
+                    lines: None,
+                },
+            ]
+        );
+        assert_eq!(Mention::parse("@README.md:3").unwrap().lines, Some((3, 3)));
+        assert_eq!(Mention::parse("@a.rs:9-3"), None);
+        assert_eq!(Mention::parse("@"), None);
+        assert!(parse_mentions("email me at someone@example.com").is_empty());
+    }
+
+    #[test]
+    fn test_parse_output_mentions() {
+        assert_eq!(
+            parse_output_mentions("why did @last-output fail? compare with @cmd:3 and @cmd:1."),
+            vec![OutputMention { n: 1 }, OutputMention { n: 3 },]
+        );
+        assert_eq!(OutputMention::parse("@cmd:0"), None);
+        assert_eq!(OutputMention::parse("@cmd:x"), None);
+        assert_eq!(Mention::parse("@last-output"), None);
+        assert_eq!(Mention::parse("@cmd:2"), None);
+        assert_eq!(OutputMention { n: 2 }.label(), "cmd:2");
+    }
+
+    #[test]
+    fn test_command_output_attachment() {
+        let outputs: Vec<CommandOutput> = serde_json::from_str(
+            r#"[{"command":"cargo tset","exit_code":101,"cwd":"/repo","output":"error: no such command","truncated":true}]"#,
+        )
+        .unwrap();
+        let (name, content) = outputs.into_iter().next().unwrap().into_attachment();
+        assert_eq!(name, "output of `cargo tset` (exit code 101)");
+        assert_eq!(content, "[earlier output truncated]\nerror: no such command");
+    }
+
+    #[test]
+    fn test_select_lines() {
+        let content = "one\ntwo\nthree\nfour";
+        assert_eq!(select_lines(content, (2, 3)), "two\nthree");
+        assert_eq!(select_lines(content, (4, 10)), "four");
+        assert_eq!(select_lines(content, (8, 10)), "");
+    }
+
+    #[tokio::test]
+    async fn test_collect_attachments() {
+        let ctx = Context::builder().with_test_home().await.unwrap().build_fake();
+        let cwd = ctx.env().current_dir().unwrap();
+        ctx.fs().create_dir_all(cwd.join("src")).await.unwrap();
+        ctx.fs().write(cwd.join("src/lib.rs"), "a\nb\nc\n").await.unwrap();
+        ctx.fs()
+            .write(cwd.join("src/data.bin"), [0u8, 159, 146, 150])
+            .await
+            .unwrap();
+
+        let mut output = Vec::new();
+        let attachments = collect_attachments(
+            &ctx,
+            &mut output,
+            "ask @alice to look at @src/lib.rs:2-3 and @missing.rs",
+            1000,
+        )
+        .await
+        .unwrap();
+        assert_eq!(attachments.files.len(), 1);
+        assert!(attachments.files[0].0.ends_with("src/lib.rs:2-3"));
+        assert_eq!(attachments.files[0].1, "b\nc");
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("Could not attach @missing.rs"));
+        assert!(!output.contains("@alice"), "words that aren't paths should be ignored");
+
+        let mut output = Vec::new();
+        let attachments = collect_attachments(&ctx, &mut output, "@src/", 1000).await.unwrap();
+        assert_eq!(attachments.files.len(), 1, "binary files should be skipped");
+    }
+}
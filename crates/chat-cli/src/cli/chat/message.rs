@@ -34,6 +34,10 @@ const USER_ENTRY_END_HEADER: &str = "--- USER MESSAGE END ---\n\n";
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserMessage {
     pub additional_context: String,
+    /// Content attached to this message only, e.g. files mentioned with `@`. Unlike the prompt,
+    /// this is not kept in the history.
+    #[serde(default)]
+    pub attachments: String,
     pub env_context: UserEnvContext,
     pub content: UserMessageContent,
     pub images: Option<Vec<ImageBlock>>,
@@ -62,6 +66,7 @@ impl UserMessage {
         Self {
             images: None,
             additional_context: String::new(),
+            attachments: String::new(),
             env_context: UserEnvContext::generate_new(),
             content: UserMessageContent::Prompt { prompt },
         }
@@ -71,6 +76,7 @@ impl UserMessage {
         Self {
             images: None,
             additional_context: String::new(),
+            attachments: String::new(),
             env_context: UserEnvContext::generate_new(),
             content: UserMessageContent::CancelledToolUses {
                 prompt,
@@ -90,6 +96,7 @@ impl UserMessage {
     pub fn new_tool_use_results(results: Vec<ToolUseResult>) -> Self {
         Self {
             additional_context: String::new(),
+            attachments: String::new(),
             env_context: UserEnvContext::generate_new(),
             content: UserMessageContent::ToolUseResults {
                 tool_use_results: results,
@@ -101,6 +108,7 @@ impl UserMessage {
     pub fn new_tool_use_results_with_images(results: Vec<ToolUseResult>, images: Vec<ImageBlock>) -> Self {
         Self {
             additional_context: String::new(),
+            attachments: String::new(),
             env_context: UserEnvContext::generate_new(),
             content: UserMessageContent::ToolUseResults {
                 tool_use_results: results,
@@ -128,6 +136,7 @@ impl UserMessage {
                 ..Default::default()
             }),
             user_intent: None,
+            model_id: None,
         }
     }
 
@@ -142,7 +151,7 @@ impl UserMessage {
         };
         UserInputMessage {
             images: self.images,
-            content: format!("{} {}", self.additional_context, formatted_prompt)
+            content: format!("{} {} {}", self.additional_context, self.attachments, formatted_prompt)
                 .trim()
                 .to_string(),
             user_input_message_context: Some(UserInputMessageContext {
@@ -158,6 +167,7 @@ impl UserMessage {
                 ..Default::default()
             }),
             user_intent: None,
+            model_id: None,
         }
     }
 
@@ -180,6 +190,10 @@ impl UserMessage {
         &self.additional_context
     }
 
+    pub fn attachments(&self) -> &str {
+        &self.attachments
+    }
+
     pub fn content(&self) -> &UserMessageContent {
         &self.content
     }
@@ -1,12 +1,17 @@
 pub mod cli;
 mod command;
+pub mod config;
 mod consts;
 mod context;
 mod conversation_state;
+mod custom_commands;
 mod hooks;
 mod input_source;
 pub mod mcp;
+mod mentions;
 mod message;
+mod models;
+mod notifications;
 mod parse;
 mod parser;
 mod prompt;
@@ -42,15 +47,19 @@ use std::{
 
 use command::{
     Command,
+    ModelSubcommand,
     PromptsSubcommand,
+    TodosSubcommand,
     ToolsSubcommand,
 };
 use consts::{
     CONTEXT_FILES_MAX_SIZE,
-    CONTEXT_WINDOW_SIZE,
     DUMMY_TOOL_NAME,
 };
-use context::ContextManager;
+use context::{
+    ContextManager,
+    WORKSPACE_PROFILES_DIR,
+};
 pub use conversation_state::ConversationState;
 use conversation_state::TokenWarningLevel;
 use crossterm::style::{
@@ -65,6 +74,12 @@ use crossterm::{
     style,
     terminal,
 };
+use custom_commands::{
+    CustomCommand,
+    WORKSPACE_COMMANDS_DIR,
+    find_custom_command,
+    load_custom_commands,
+};
 use eyre::{
     ErrReport,
     Result,
@@ -72,15 +87,26 @@ use eyre::{
 };
 use hooks::{
     Hook,
+    HookEvent,
+    HookOutcome,
     HookTrigger,
 };
 use input_source::InputSource;
+use mentions::collect_attachments;
 use message::{
     AssistantMessage,
     AssistantToolUse,
     ToolUseResult,
     ToolUseResultBlock,
 };
+use models::{
+    ModelInfo,
+    Models,
+};
+use notifications::{
+    NotificationEvent,
+    Notifier,
+};
 use parse::{
     ParseState,
     interpret_markdown,
@@ -112,14 +138,19 @@ use tool_manager::{
     ToolManager,
     ToolManagerBuilder,
 };
+use tools::delegate_task::DelegateTaskContext;
 use tools::gh_issue::GhIssueContext;
+use tools::todo_list::TodoStatus;
+use tools::web_fetch::DomainFilter;
 use tools::{
+    InvokeOutput,
     OutputKind,
     QueuedTool,
     Tool,
     ToolOrigin,
     ToolPermissions,
     ToolSpec,
+    supports_truecolor,
 };
 use tracing::{
     debug,
@@ -129,7 +160,10 @@ use tracing::{
     warn,
 };
 use unicode_width::UnicodeWidthStr;
-use util::images::RichImageBlock;
+use util::images::{
+    RichImageBlock,
+    RichImageBlocks,
+};
 use util::shared_writer::{
     NullWriter,
     SharedWriter,
@@ -138,12 +172,14 @@ use util::ui::draw_box;
 use util::{
     animate_output,
     drop_matched_context_files,
-    play_notification_bell,
     region_check,
 };
 use uuid::Uuid;
 use winnow::Partial;
-use winnow::stream::Offset;
+use winnow::stream::{
+    Offset,
+    StreamIsPartial,
+};
 
 use crate::api_client::StreamingClient;
 use crate::api_client::clients::SendMessageOutput;
@@ -162,6 +198,7 @@ use crate::platform::Context;
 use crate::telemetry::TelemetryThread;
 use crate::telemetry::core::ToolUseEventBuilder;
 use crate::util::CLI_BINARY_NAME;
+use crate::util::directories::chat_history_path;
 
 /// Help text for the compact command
 fn compact_help_text() -> String {
@@ -265,6 +302,8 @@ const HELP_TEXT: &str = color_print::cstr! {"
   <em>clear</em>       <black!>Clear all files from current context [--global]</black!>
   <em>hooks</em>       <black!>View and manage context hooks</black!>
 <em>/usage</em>        <black!>Show current session's context window usage</black!>
+<em>/todos</em>        <black!>View and edit the todo list of multi-step tasks</black!>
+<em>/model</em>        <black!>List the available models or switch models</black!>
 <em>/load</em>         <black!>Load conversation state from a JSON file</black!>
 <em>/save</em>         <black!>Save conversation state to a JSON file</black!>
 
@@ -273,7 +312,11 @@ const HELP_TEXT: &str = color_print::cstr! {"
 
 <cyan,em>Tips:</cyan,em>
 <em>!{command}</em>            <black!>Quickly execute a command in your current session</black!>
-<em>Ctrl(^) + j</em>           <black!>Insert new-line to provide multi-line prompt. Alternatively, [Alt(⌥) + Enter(⏎)]</black!>
+<em>/{name} [args]</em>        <black!>Run a custom command from .amazonq/commands/{name}.md or ~/.aws/amazonq/commands/{name}.md</black!>
+<em>Ctrl(^) + j</em>           <black!>Insert new-line to provide multi-line prompt. Alternatively, [Alt(⌥) + Enter(⏎)] or [Shift(⇧) + Enter(⏎)]</black!>
+<em>Ctrl(^) + r</em>           <black!>Fuzzy search the prompts previously entered in this directory</black!>
+<em>@{path}[:start-end]</em>  <black!>Attach a file, lines of a file, directory or image to this prompt only. Press Tab after @ to fuzzy complete paths</black!>
+<em>@last-output</em>         <black!>Attach the output of the last command run in this terminal. Use @cmd:N for the command N commands back</black!>
 <em>Ctrl(^) + s</em>           <black!>Fuzzy search commands and context files. Use Tab to select multiple items.</black!>
                       <black!>Change the keybind to ctrl+x with: q settings chat.skimCommandKey x (where x is any key)</black!>
 
@@ -304,6 +347,7 @@ pub async fn launch_chat(database: &mut Database, telemetry: &TelemetryThread, a
         args.resume,
         args.accept_all,
         args.profile,
+        args.model,
         args.trust_all_tools,
         trust_tools,
     )
@@ -319,6 +363,7 @@ pub async fn chat(
     resume_conversation: bool,
     accept_all: bool,
     profile: Option<String>,
+    model: Option<String>,
     trust_all_tools: bool,
     trust_tools: Option<Vec<String>>,
 ) -> Result<ExitCode> {
@@ -355,7 +400,7 @@ pub async fn chat(
         _ => StreamingClient::new(database).await?,
     };
 
-    let mcp_server_configs = match McpServerConfig::load_config(&mut output).await {
+    let mut mcp_server_configs = match McpServerConfig::load_config(&mut output).await {
         Ok(config) => {
             if interactive && !database.settings.get_bool(Setting::McpLoadedBefore).unwrap_or(false) {
                 execute!(
@@ -374,11 +419,10 @@ pub async fn chat(
         },
     };
 
-    // If profile is specified, verify it exists before starting the chat
-    if let Some(ref profile_name) = profile {
-        // Create a temporary context manager to check if the profile exists
-        match ContextManager::new(Arc::clone(&ctx), None).await {
-            Ok(context_manager) => {
+    // Create a temporary context manager to verify the profile exists and read its MCP servers
+    match ContextManager::new(Arc::clone(&ctx), None).await {
+        Ok(mut context_manager) => {
+            if let Some(ref profile_name) = profile {
                 let profiles = context_manager.list_profiles().await?;
                 if !profiles.contains(profile_name) {
                     bail!(
@@ -387,12 +431,16 @@ pub async fn chat(
                         profiles.join(", ")
                     );
                 }
-            },
-            Err(e) => {
-                warn!("Failed to initialize context manager to verify profile: {}", e);
-                // Continue without verification if context manager can't be initialized
-            },
-        }
+                context_manager.switch_profile(profile_name).await?;
+            }
+            if let Some(servers) = &context_manager.profile_config.mcp_servers {
+                mcp_server_configs.mcp_servers.retain(|name, _| servers.contains(name));
+            }
+        },
+        Err(e) => {
+            warn!("Failed to initialize context manager to verify profile: {}", e);
+            // Continue without verification if context manager can't be initialized
+        },
     }
 
     let conversation_id = Alphanumeric.sample_string(&mut rand::rng(), 9);
@@ -440,13 +488,16 @@ pub async fn chat(
         }
     }
 
+    let history_path = std::env::current_dir()
+        .ok()
+        .and_then(|cwd| chat_history_path(&ctx, &cwd).ok());
     let mut chat = ChatContext::new(
         ctx,
         database,
         &conversation_id,
         output,
         input,
-        InputSource::new(database, prompt_request_sender, prompt_response_receiver)?,
+        InputSource::new(database, prompt_request_sender, prompt_response_receiver, history_path)?,
         interactive,
         resume_conversation,
         client,
@@ -458,6 +509,19 @@ pub async fn chat(
     )
     .await?;
 
+    // --model takes precedence over the default model, the profile and a resumed conversation
+    if let Some(name) = model {
+        let Some(model) = chat.models.find(&name).cloned() else {
+            bail!(
+                "Model '{}' does not exist. Available models: {}",
+                name,
+                chat.models.names()
+            );
+        };
+        chat.conversation_state.set_model(Some(model));
+        chat.pinned_model = true;
+    }
+
     let result = chat.try_chat(database, telemetry).await.map(|_| ExitCode::SUCCESS);
     drop(chat); // Explicit drop for clarity
 
@@ -523,6 +587,34 @@ pub struct ChatContext {
     failed_request_ids: Vec<String>,
     /// Pending prompts to be sent
     pending_prompts: VecDeque<Prompt>,
+    /// Images pasted into the last prompt read from the user
+    pasted_images: RichImageBlocks,
+    /// Slash commands defined by prompt templates in the workspace or global commands directory
+    custom_commands: Vec<CustomCommand>,
+    /// Tools trusted while responding to the last custom command
+    command_allowed_tools: Vec<String>,
+    /// Workspace commands approved this session, as they were when approved
+    approved_commands: HashMap<String, CustomCommand>,
+    /// Tools trusted by the current profile's `trusted_tools`, removed again on profile switch
+    profile_trusted_tools: Vec<String>,
+    /// Workspace profiles approved this session, as they were when approved
+    approved_profiles: HashMap<String, WorkspaceProfileReview>,
+    /// Domains the `web_fetch` tool may fetch from without asking, or never fetches from
+    web_fetch_domains: DomainFilter,
+    /// Whether `use_aws` calls services in-process rather than through the AWS CLI
+    use_aws_native: bool,
+    /// Whether pre tool use hooks may approve tool uses that would otherwise ask the user
+    hooks_allow_tools: bool,
+    /// Sends bell, desktop and terminal notifications when the user's attention is needed
+    notifier: Notifier,
+    /// The models available through `/model`
+    models: Models,
+    /// The model to switch back to once the custom command that switched models is answered
+    #[allow(clippy::option_option)]
+    model_before_command: Option<Option<ModelInfo>>,
+    /// Whether the model was restored with the conversation or given with `--model`, in which case
+    /// profiles don't change it
+    pinned_model: bool,
 }
 
 impl ChatContext {
@@ -588,7 +680,9 @@ impl ChatContext {
             .await
         };
 
-        Ok(Self {
+        let models = Models::load(&ctx).await;
+        let pinned_model = existing_conversation && conversation_state.model().is_some();
+        let mut chat = Self {
             ctx,
             output,
             initial_input: input,
@@ -604,7 +698,117 @@ impl ChatContext {
             tool_use_status: ToolUseStatus::Idle,
             failed_request_ids: Vec::new(),
             pending_prompts: VecDeque::new(),
-        })
+            pasted_images: Vec::new(),
+            custom_commands: Vec::new(),
+            command_allowed_tools: Vec::new(),
+            approved_commands: HashMap::new(),
+            profile_trusted_tools: Vec::new(),
+            approved_profiles: HashMap::new(),
+            web_fetch_domains: DomainFilter {
+                allowed: database
+                    .settings
+                    .get_string_array(Setting::WebFetchAllowedDomains)
+                    .unwrap_or_default(),
+                denied: database
+                    .settings
+                    .get_string_array(Setting::WebFetchDeniedDomains)
+                    .unwrap_or_default(),
+            },
+            use_aws_native: database.settings.get_bool(Setting::UseAwsNative).unwrap_or(false),
+            hooks_allow_tools: database
+                .settings
+                .get_bool(Setting::ChatHooksAllowTools)
+                .unwrap_or(false),
+            notifier: Notifier::new(&database.settings),
+            models,
+            model_before_command: None,
+            pinned_model,
+        };
+        if chat.conversation_state.model().is_none() {
+            if let Some(name) = database.settings.get_string(Setting::ChatDefaultModel) {
+                match chat.models.find(&name) {
+                    Some(model) => chat.conversation_state.set_model(Some(model.clone())),
+                    None => warn!("ignoring unknown default model '{name}'"),
+                }
+            }
+        }
+        chat.apply_profile_settings().await?;
+
+        Ok(chat)
+    }
+
+    /// Applies the trusted tools and enabled MCP servers of the current profile, undoing the
+    /// settings applied for the previous one.
+    ///
+    /// Tools trusted by a workspace profile are only trusted, and its hooks only run, once the user
+    /// approves them.
+    async fn apply_profile_settings(&mut self) -> Result<(), ChatError> {
+        for tool in self.profile_trusted_tools.drain(..) {
+            if self.tool_permissions.is_trusted(&tool) {
+                self.tool_permissions.permissions.remove(&tool);
+            }
+        }
+
+        let Some((profile, config, from_workspace, shadows_global, paths)) =
+            self.conversation_state.context_manager.as_ref().map(|cm| {
+                (
+                    cm.current_profile.clone(),
+                    cm.profile_config.clone(),
+                    cm.is_workspace_profile(),
+                    cm.shadows_global_profile(),
+                    cm.paths_outside_workspace(),
+                )
+            })
+        else {
+            return Ok(());
+        };
+
+        if shadows_global {
+            warn!("workspace profile '{profile}' shadows the global profile of the same name");
+        }
+
+        // Explicit `--trust-tools` or `/tools` choices take precedence over the profile.
+        let trusted_tools: Vec<String> = config
+            .trusted_tools
+            .into_iter()
+            .filter(|tool| !self.tool_permissions.has(tool))
+            .collect();
+        let mut hooks: Vec<(String, String)> = config
+            .hooks
+            .iter()
+            .filter_map(|(name, hook)| Some((name.clone(), hook.command.clone()?)))
+            .collect();
+        hooks.sort();
+        let review = WorkspaceProfileReview {
+            tools: trusted_tools,
+            hooks,
+            paths,
+            instructions: config.instructions,
+            shadows_global,
+        };
+        let approved = !from_workspace
+            || review == WorkspaceProfileReview::default()
+            || self.approve_workspace_profile(&profile, &review)?;
+        if approved {
+            for tool in review.tools {
+                self.tool_permissions.trust_tool(&tool);
+                self.profile_trusted_tools.push(tool);
+            }
+        }
+        if let Some(context_manager) = self.conversation_state.context_manager.as_mut() {
+            context_manager.workspace_profile_approved = from_workspace && approved;
+        }
+        if let Some(name) = config.model.as_ref().filter(|_| !self.pinned_model) {
+            match self.models.find(name) {
+                Some(model) => self.conversation_state.set_model(Some(model.clone())),
+                None => warn!("ignoring unknown model '{name}' set by the current profile"),
+            }
+        }
+        self.conversation_state
+            .set_enabled_mcp_servers(config.mcp_servers)
+            .await;
+
+        Ok(())
     }
 }
 
@@ -629,6 +833,20 @@ impl Drop for ChatContext {
     }
 }
 
+/// What a workspace profile does that the user has to approve before it takes effect.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct WorkspaceProfileReview {
+    /// Tools the profile trusts
+    tools: Vec<String>,
+    /// Names and commands of the profile's hooks
+    hooks: Vec<(String, String)>,
+    /// Context paths that may include files outside of the workspace
+    paths: Vec<String>,
+    instructions: Option<String>,
+    /// Whether the profile replaces a global profile of the same name
+    shadows_global: bool,
+}
+
 /// The chat execution state.
 ///
 /// Intended to provide more robust handling around state transitions while dealing with, e.g.,
@@ -735,6 +953,13 @@ impl ChatContext {
     }
 
     async fn try_chat(&mut self, database: &mut Database, telemetry: &TelemetryThread) -> Result<()> {
+        let result = self.chat_loop(database, telemetry).await;
+        // Session end hooks run however the session ends, including on errors.
+        self.run_event_hooks(&HookEvent::SessionEnd).await;
+        result
+    }
+
+    async fn chat_loop(&mut self, database: &mut Database, telemetry: &TelemetryThread) -> Result<()> {
         let is_small_screen = self.terminal_width() < GREETING_BREAK_POINT;
         if self.interactive && database.settings.get_bool(Setting::ChatGreetingEnabled).unwrap_or(true) {
             let welcome_text = match self.existing_conversation {
@@ -803,6 +1028,7 @@ impl ChatContext {
         });
 
         if let Some(user_input) = self.initial_input.take() {
+            self.custom_commands = load_custom_commands(&self.ctx).await;
             next_state = Some(ChatState::HandleInput {
                 input: user_input,
                 tool_uses: None,
@@ -859,7 +1085,7 @@ impl ChatContext {
                 ChatState::ExecuteTools(tool_uses) => {
                     let tool_uses_clone = tool_uses.clone();
                     tokio::select! {
-                        res = self.tool_use_execute(database, telemetry, tool_uses) => res,
+                        res = self.tool_use_execute(telemetry, tool_uses) => res,
                         Ok(_) = ctrl_c_stream => Err(ChatError::Interrupted { tool_uses: Some(tool_uses_clone) })
                     }
                 },
@@ -880,6 +1106,14 @@ impl ChatContext {
         }
     }
 
+    /// Runs the context hooks for `event`, writing any hook failures to the output.
+    async fn run_event_hooks(&mut self, event: &HookEvent) -> Vec<(Hook, HookOutcome)> {
+        match self.conversation_state.context_manager.as_mut() {
+            Some(context_manager) => context_manager.run_event_hooks(event, Some(&mut self.output)).await,
+            None => Vec::new(),
+        }
+    }
+
     /// Handles the result of processing a [ChatState], returning the next [ChatState] to change
     /// to.
     async fn handle_state_execution_result(
@@ -932,6 +1166,10 @@ impl ChatContext {
                         cursor::MoveToColumn(0),
                     )?;
                 }
+                if !matches!(e, ChatError::Interrupted { .. }) {
+                    self.notifier
+                        .notify(&mut self.output, NotificationEvent::Error, &e.to_string());
+                }
                 match e {
                     ChatError::Interrupted { tool_uses: inter } => {
                         execute!(self.output, style::Print("\n\n"))?;
@@ -1239,6 +1477,16 @@ impl ChatContext {
                     warn!("Failed to display character limit warnings: {}", e);
                 }
             }
+
+            // Keep the todo list in view while there is still work left on it.
+            if let Ok(todos) = self.conversation_state.todos.lock() {
+                if todos.has_open_items() {
+                    if let Err(err) = todos.queue_checklist(&mut self.output) {
+                        warn!(?err, "Failed to display the todo list");
+                    }
+                    queue!(self.output, style::Print("\n"))?;
+                }
+            }
         }
 
         let show_tool_use_confirmation_dialog = !skip_printing_tools && pending_tool_index.is_some();
@@ -1267,6 +1515,15 @@ impl ChatContext {
             )?;
         }
 
+        // Pick up custom commands added or edited since the last prompt.
+        self.custom_commands = load_custom_commands(&self.ctx).await;
+        let custom_command_names = self
+            .custom_commands
+            .iter()
+            .map(|command| format!("/{}", command.name))
+            .collect::<Vec<_>>();
+        self.input_source.set_custom_commands(custom_command_names.clone());
+
         // Do this here so that the skim integration sees an updated view of the context *during the current
         // q session*. (e.g., if I add files to context, that won't show up for skim for the current
         // q session unless we do this in prompt_user... unless you can find a better way)
@@ -1280,18 +1537,40 @@ impl ChatContext {
                 .filter(|name| *name != DUMMY_TOOL_NAME)
                 .cloned()
                 .collect::<Vec<_>>();
-            self.input_source
-                .put_skim_command_selector(database, Arc::new(context_manager.clone()), tool_names);
+            self.input_source.put_skim_command_selector(
+                database,
+                Arc::new(context_manager.clone()),
+                tool_names,
+                custom_command_names,
+            );
         }
         execute!(
             self.output,
             style::SetForegroundColor(Color::Reset),
             style::SetAttribute(Attribute::Reset)
         )?;
-        let user_input = match self.read_user_input(&self.generate_tool_trust_prompt(), false) {
+        let mut user_input = match self.read_user_input(&self.generate_tool_trust_prompt(), false) {
             Some(input) => input,
             None => return Ok(ChatState::Exit),
         };
+        // Answers to a pending tool approval aren't prompts worth recalling.
+        if pending_tool_index.is_none() || !["y", "Y", "n", "N", "t", "T"].contains(&user_input.trim()) {
+            self.input_source.add_history_entry(&user_input);
+        }
+
+        self.pasted_images = self.input_source.take_pasted_images(&mut user_input);
+        for (_, metadata) in &self.pasted_images {
+            queue!(
+                self.output,
+                style::SetForegroundColor(Color::DarkGrey),
+                style::Print(format!(
+                    "Attached {} ({} KB)\n",
+                    metadata.filename,
+                    metadata.size.div_ceil(1024)
+                )),
+                style::SetForegroundColor(Color::Reset),
+            )?;
+        }
 
         self.conversation_state.append_user_transcript(&user_input);
         Ok(ChatState::HandleInput {
@@ -1308,7 +1587,47 @@ impl ChatContext {
         tool_uses: Option<Vec<QueuedTool>>,
         pending_tool_index: Option<usize>,
     ) -> Result<ChatState, ChatError> {
-        let command_result = Command::parse(&user_input, &mut self.output);
+        // Switch back from the model of the previous custom command once it has been answered.
+        if pending_tool_index.is_none() {
+            if let Some(model) = self.model_before_command.take() {
+                self.conversation_state.set_model(model);
+            }
+        }
+
+        // Custom commands expand into a prompt, which is sent as is rather than parsed as a command.
+        let command_result = match find_custom_command(&user_input, &self.custom_commands)
+            .map(|(command, args)| (command.clone(), args.to_string()))
+        {
+            Some((command, args)) => {
+                if command.needs_approval() && !self.approve_custom_command(&command)? {
+                    return Ok(ChatState::PromptUser {
+                        tool_uses,
+                        pending_tool_index,
+                        skip_printing_tools: true,
+                    });
+                }
+                if let Some(name) = &command.model {
+                    match self.models.find(name).cloned() {
+                        Some(model) => {
+                            self.model_before_command = Some(self.conversation_state.model().cloned());
+                            self.conversation_state.set_model(Some(model));
+                        },
+                        None => warn!("ignoring unknown model '{name}' for command /{}", command.name),
+                    }
+                }
+                self.command_allowed_tools = command.allowed_tools.clone();
+                user_input = command.expand(&self.ctx, &args).await;
+                Ok(Command::Ask {
+                    prompt: user_input.clone(),
+                })
+            },
+            None => {
+                if pending_tool_index.is_none() {
+                    self.command_allowed_tools.clear();
+                }
+                Command::parse(&user_input, &mut self.output)
+            },
+        };
 
         if let Err(error_message) = &command_result {
             // Display error message for command parsing errors
@@ -1331,6 +1650,8 @@ impl ChatContext {
 
         Ok(match command {
             Command::Ask { prompt } => {
+                self.notifier.start_turn();
+
                 // Check for a pending tool approval
                 if let Some(index) = pending_tool_index {
                     let tool_use = &mut tool_uses[index];
@@ -1358,7 +1679,48 @@ impl ChatContext {
                 if pending_tool_index.is_some() {
                     self.conversation_state.abandon_tool_use(tool_uses, user_input);
                 } else {
+                    let max_size = self
+                        .conversation_state
+                        .context_manager
+                        .as_ref()
+                        .map_or(CONTEXT_FILES_MAX_SIZE, |cm| cm.max_context_files_size());
+                    let attachments = collect_attachments(&self.ctx, &mut self.output, &user_input, max_size).await?;
+                    for (filename, content) in &attachments.files {
+                        queue!(
+                            self.output,
+                            style::SetForegroundColor(Color::DarkGrey),
+                            style::Print(format!(
+                                "Attached {} (~{} tokens)\n",
+                                filename,
+                                TokenCounter::count_tokens(content)
+                            )),
+                            style::SetForegroundColor(Color::Reset),
+                        )?;
+                    }
+                    for (_, metadata) in &attachments.images {
+                        queue!(
+                            self.output,
+                            style::SetForegroundColor(Color::DarkGrey),
+                            style::Print(format!(
+                                "Attached {} ({} KB)\n",
+                                metadata.filename,
+                                metadata.size.div_ceil(1024)
+                            )),
+                            style::SetForegroundColor(Color::Reset),
+                        )?;
+                    }
+
                     self.conversation_state.set_next_user_message(user_input).await;
+                    self.conversation_state
+                        .add_attachments_to_next_user_message(attachments.files);
+                    let images = std::mem::take(&mut self.pasted_images);
+                    self.conversation_state.add_images_to_next_user_message(
+                        images
+                            .into_iter()
+                            .chain(attachments.images)
+                            .map(|(image, _)| image)
+                            .collect(),
+                    );
                 }
 
                 let conv_state = self.conversation_state.as_sendable_conversation_state(true).await;
@@ -1446,7 +1808,31 @@ impl ChatContext {
                 .await?
             },
             Command::Help => {
-                execute!(self.output, style::Print(HELP_TEXT))?;
+                queue!(self.output, style::Print(HELP_TEXT))?;
+                if !self.custom_commands.is_empty() {
+                    queue!(
+                        self.output,
+                        style::SetForegroundColor(Color::Cyan),
+                        style::SetAttribute(Attribute::Bold),
+                        style::Print("Custom commands:\n"),
+                        style::SetAttribute(Attribute::Reset),
+                    )?;
+                    for command in &self.custom_commands {
+                        queue!(
+                            self.output,
+                            style::Print(format!("/{:<13}", command.name)),
+                            style::SetForegroundColor(Color::DarkGrey),
+                            style::Print(format!(
+                                " {}{}\n",
+                                command.description.as_deref().unwrap_or_default(),
+                                if command.is_global { " (global)" } else { "" }
+                            )),
+                            style::SetForegroundColor(Color::Reset),
+                        )?;
+                    }
+                    queue!(self.output, style::Print("\n"))?;
+                }
+                self.output.flush()?;
                 ChatState::PromptUser {
                     tool_uses: Some(tool_uses),
                     pending_tool_index,
@@ -1526,6 +1912,16 @@ impl ChatContext {
             },
             Command::Quit => ChatState::Exit,
             Command::Profile { subcommand } => {
+                let loaded_servers = self
+                    .conversation_state
+                    .tool_manager
+                    .schema
+                    .values()
+                    .filter_map(|spec| match &spec.tool_origin {
+                        ToolOrigin::McpServer(server) => Some(server.clone()),
+                        ToolOrigin::Native => None,
+                    })
+                    .collect::<HashSet<_>>();
                 if let Some(context_manager) = &mut self.conversation_state.context_manager {
                     macro_rules! print_err {
                         ($err:expr) => {
@@ -1611,9 +2007,38 @@ impl ChatContext {
                                 execute!(
                                     self.output,
                                     style::SetForegroundColor(Color::Green),
-                                    style::Print(format!("\nSwitched to profile: {}\n\n", name)),
+                                    style::Print(format!("\nSwitched to profile: {}\n", name)),
                                     style::SetForegroundColor(Color::Reset)
                                 )?;
+
+                                let config = &context_manager.profile_config;
+                                if !config.trusted_tools.is_empty() {
+                                    execute!(
+                                        self.output,
+                                        style::Print(format!("  Trusted tools: {}\n", config.trusted_tools.join(", ")))
+                                    )?;
+                                }
+                                if let Some(servers) = &config.mcp_servers {
+                                    execute!(
+                                        self.output,
+                                        style::Print(format!("  MCP servers: {}\n", servers.join(", ")))
+                                    )?;
+                                    for server in servers.iter().filter(|s| !loaded_servers.contains(*s)) {
+                                        execute!(
+                                            self.output,
+                                            style::SetForegroundColor(Color::Yellow),
+                                            style::Print(format!(
+                                                "  MCP server '{}' is not loaded in this session; restart with --profile {} to load it\n",
+                                                server, name
+                                            )),
+                                            style::SetForegroundColor(Color::Reset)
+                                        )?;
+                                    }
+                                }
+                                if config.instructions.is_some() {
+                                    execute!(self.output, style::Print("  Custom instructions: yes\n"))?;
+                                }
+                                execute!(self.output, style::Print("\n"))?;
                             },
                             Err(e) => print_err!(e),
                         },
@@ -1640,6 +2065,7 @@ impl ChatContext {
                         },
                     }
                 }
+                self.apply_profile_settings().await?;
                 ChatState::PromptUser {
                     tool_uses: Some(tool_uses),
                     pending_tool_index,
@@ -1697,19 +2123,10 @@ impl ChatContext {
                                     style::SetForegroundColor(Color::DarkYellow),
                                     style::Print("\n    🔧 Hooks:\n")
                                 )?;
-                                print_hook_section(
-                                    &mut self.output,
-                                    &context_manager.global_config.hooks,
-                                    HookTrigger::ConversationStart,
-                                )
-                                .map_err(map_chat_error)?;
-
-                                print_hook_section(
-                                    &mut self.output,
-                                    &context_manager.global_config.hooks,
-                                    HookTrigger::PerPrompt,
-                                )
-                                .map_err(map_chat_error)?;
+                                for trigger in HookTrigger::ALL {
+                                    print_hook_section(&mut self.output, &context_manager.global_config.hooks, trigger)
+                                        .map_err(map_chat_error)?;
+                                }
                             }
 
                             // Display profile context
@@ -1756,22 +2173,44 @@ impl ChatContext {
                                     style::SetForegroundColor(Color::DarkYellow),
                                     style::Print("    🔧 Hooks:\n")
                                 )?;
-                                print_hook_section(
-                                    &mut self.output,
-                                    &context_manager.profile_config.hooks,
-                                    HookTrigger::ConversationStart,
-                                )
-                                .map_err(map_chat_error)?;
-                                print_hook_section(
-                                    &mut self.output,
-                                    &context_manager.profile_config.hooks,
-                                    HookTrigger::PerPrompt,
-                                )
-                                .map_err(map_chat_error)?;
+                                for trigger in HookTrigger::ALL {
+                                    print_hook_section(
+                                        &mut self.output,
+                                        &context_manager.profile_config.hooks,
+                                        trigger,
+                                    )
+                                    .map_err(map_chat_error)?;
+                                }
+                                execute!(self.output, style::Print("\n"))?;
+                            }
+
+                            // Display rules loaded from subdirectories the agent worked in
+                            let discovered_rule_files =
+                                context_manager.get_discovered_rule_files().await.unwrap_or_default();
+                            if !context_manager.rule_dirs.is_empty() {
+                                execute!(
+                                    self.output,
+                                    style::SetAttribute(Attribute::Bold),
+                                    style::SetForegroundColor(Color::Magenta),
+                                    style::Print("📂 discovered rules:\n"),
+                                    style::SetAttribute(Attribute::Reset),
+                                )?;
+                                for dir in &context_manager.rule_dirs {
+                                    execute!(
+                                        self.output,
+                                        style::Print(format!("    {} ", dir.display())),
+                                        style::SetForegroundColor(Color::DarkGrey),
+                                        style::Print("(loaded after accessing files inside it)\n"),
+                                        style::SetForegroundColor(Color::Reset),
+                                    )?;
+                                }
                                 execute!(self.output, style::Print("\n"))?;
                             }
 
-                            if global_context_files.is_empty() && profile_context_files.is_empty() {
+                            if global_context_files.is_empty()
+                                && profile_context_files.is_empty()
+                                && discovered_rule_files.is_empty()
+                            {
                                 execute!(
                                     self.output,
                                     style::SetForegroundColor(Color::DarkGrey),
@@ -1779,15 +2218,15 @@ impl ChatContext {
                                     style::SetForegroundColor(Color::Reset)
                                 )?;
                             } else {
-                                let total = global_context_files.len() + profile_context_files.len();
+                                let total = global_context_files.len()
+                                    + profile_context_files.len()
+                                    + discovered_rule_files.len();
                                 let total_tokens = global_context_files
                                     .iter()
+                                    .chain(profile_context_files.iter())
+                                    .chain(discovered_rule_files.iter())
                                     .map(|(_, content)| TokenCounter::count_tokens(content))
-                                    .sum::<usize>()
-                                    + profile_context_files
-                                        .iter()
-                                        .map(|(_, content)| TokenCounter::count_tokens(content))
-                                        .sum::<usize>();
+                                    .sum::<usize>();
                                 execute!(
                                     self.output,
                                     style::SetForegroundColor(Color::Green),
@@ -1839,6 +2278,25 @@ impl ChatContext {
                                     }
                                 }
 
+                                for (filename, content) in &discovered_rule_files {
+                                    let est_tokens = TokenCounter::count_tokens(content);
+                                    execute!(
+                                        self.output,
+                                        style::Print(format!("📂 {} ", filename)),
+                                        style::SetForegroundColor(Color::DarkGrey),
+                                        style::Print(format!("(~{} tkns)\n", est_tokens)),
+                                        style::SetForegroundColor(Color::Reset),
+                                    )?;
+                                    if expand {
+                                        execute!(
+                                            self.output,
+                                            style::SetForegroundColor(Color::DarkGrey),
+                                            style::Print(format!("{}\n\n", content)),
+                                            style::SetForegroundColor(Color::Reset)
+                                        )?;
+                                    }
+                                }
+
                                 if expand {
                                     execute!(self.output, style::Print(format!("{}\n\n", "▔".repeat(3))),)?;
                                 }
@@ -1846,6 +2304,7 @@ impl ChatContext {
                                 let mut combined_files: Vec<(String, String)> = global_context_files
                                     .iter()
                                     .chain(profile_context_files.iter())
+                                    .chain(discovered_rule_files.iter())
                                     .cloned()
                                     .collect();
 
@@ -2010,17 +2469,21 @@ impl ChatContext {
                                         name,
                                         trigger,
                                         command,
+                                        matcher,
                                         global,
                                     } => {
-                                        let trigger = if trigger == "conversation_start" {
-                                            HookTrigger::ConversationStart
-                                        } else {
-                                            HookTrigger::PerPrompt
+                                        let trigger = match trigger.as_str() {
+                                            "conversation_start" => HookTrigger::ConversationStart,
+                                            "pre_tool_use" => HookTrigger::PreToolUse,
+                                            "post_tool_use" => HookTrigger::PostToolUse,
+                                            "assistant_response" => HookTrigger::AssistantResponse,
+                                            "session_end" => HookTrigger::SessionEnd,
+                                            _ => HookTrigger::PerPrompt,
                                         };
 
-                                        let result = context_manager
-                                            .add_hook(name.clone(), Hook::new_inline_hook(trigger, command), global)
-                                            .await;
+                                        let mut hook = Hook::new_inline_hook(trigger, command);
+                                        hook.matcher = matcher;
+                                        let result = context_manager.add_hook(name.clone(), hook, global).await;
                                         match result {
                                             Ok(_) => {
                                                 execute!(
@@ -2173,18 +2636,10 @@ impl ChatContext {
                                     style::SetAttribute(Attribute::Reset),
                                 )?;
 
-                                print_hook_section(
-                                    &mut self.output,
-                                    &context_manager.global_config.hooks,
-                                    HookTrigger::ConversationStart,
-                                )
-                                .map_err(map_chat_error)?;
-                                print_hook_section(
-                                    &mut self.output,
-                                    &context_manager.global_config.hooks,
-                                    HookTrigger::PerPrompt,
-                                )
-                                .map_err(map_chat_error)?;
+                                for trigger in HookTrigger::ALL {
+                                    print_hook_section(&mut self.output, &context_manager.global_config.hooks, trigger)
+                                        .map_err(map_chat_error)?;
+                                }
 
                                 queue!(
                                     self.output,
@@ -2194,18 +2649,14 @@ impl ChatContext {
                                     style::SetAttribute(Attribute::Reset),
                                 )?;
 
-                                print_hook_section(
-                                    &mut self.output,
-                                    &context_manager.profile_config.hooks,
-                                    HookTrigger::ConversationStart,
-                                )
-                                .map_err(map_chat_error)?;
-                                print_hook_section(
-                                    &mut self.output,
-                                    &context_manager.profile_config.hooks,
-                                    HookTrigger::PerPrompt,
-                                )
-                                .map_err(map_chat_error)?;
+                                for trigger in HookTrigger::ALL {
+                                    print_hook_section(
+                                        &mut self.output,
+                                        &context_manager.profile_config.hooks,
+                                        trigger,
+                                    )
+                                    .map_err(map_chat_error)?;
+                                }
 
                                 execute!(
                                     self.output,
@@ -2720,15 +3171,16 @@ impl ChatContext {
                 let total_token_used: TokenCount =
                     (data.context_messages + data.user_messages + data.assistant_messages).into();
 
+                let context_window_size = self.conversation_state.context_window_tokens();
                 let window_width = self.terminal_width();
                 // set a max width for the progress bar for better aesthetic
                 let progress_bar_width = std::cmp::min(window_width, 80);
 
-                let context_width = ((context_token_count.value() as f64 / CONTEXT_WINDOW_SIZE as f64)
+                let context_width = ((context_token_count.value() as f64 / context_window_size as f64)
                     * progress_bar_width as f64) as usize;
-                let assistant_width = ((assistant_token_count.value() as f64 / CONTEXT_WINDOW_SIZE as f64)
+                let assistant_width = ((assistant_token_count.value() as f64 / context_window_size as f64)
                     * progress_bar_width as f64) as usize;
-                let user_width = ((user_token_count.value() as f64 / CONTEXT_WINDOW_SIZE as f64)
+                let user_width = ((user_token_count.value() as f64 / context_window_size as f64)
                     * progress_bar_width as f64) as usize;
 
                 let left_over_width = progress_bar_width
@@ -2742,7 +3194,7 @@ impl ChatContext {
                         style::Print(format!(
                             "\nCurrent context window ({} of {}k tokens used)\n",
                             total_token_used,
-                            CONTEXT_WINDOW_SIZE / 1000
+                            context_window_size / 1000
                         )),
                         style::SetForegroundColor(Color::DarkRed),
                         style::Print("█".repeat(progress_bar_width)),
@@ -2750,7 +3202,7 @@ impl ChatContext {
                         style::Print(" "),
                         style::Print(format!(
                             "{:.2}%",
-                            (total_token_used.value() as f32 / CONTEXT_WINDOW_SIZE as f32) * 100.0
+                            (total_token_used.value() as f32 / context_window_size as f32) * 100.0
                         )),
                     )?;
                 } else {
@@ -2759,7 +3211,7 @@ impl ChatContext {
                         style::Print(format!(
                             "\nCurrent context window ({} of {}k tokens used)\n",
                             total_token_used,
-                            CONTEXT_WINDOW_SIZE / 1000
+                            context_window_size / 1000
                         )),
                         style::SetForegroundColor(Color::DarkCyan),
                         // add a nice visual to mimic "tiny" progress, so the overral progress bar doesn't look too
@@ -2786,7 +3238,7 @@ impl ChatContext {
                         style::SetForegroundColor(Color::Reset),
                         style::Print(format!(
                             "{:.2}%",
-                            (total_token_used.value() as f32 / CONTEXT_WINDOW_SIZE as f32) * 100.0
+                            (total_token_used.value() as f32 / context_window_size as f32) * 100.0
                         )),
                     )?;
                 }
@@ -2802,7 +3254,7 @@ impl ChatContext {
                     style::Print(format!(
                         "~{} tokens ({:.2}%)\n",
                         context_token_count,
-                        (context_token_count.value() as f32 / CONTEXT_WINDOW_SIZE as f32) * 100.0
+                        (context_token_count.value() as f32 / context_window_size as f32) * 100.0
                     )),
                     style::SetForegroundColor(Color::Blue),
                     style::Print("█ Q responses: "),
@@ -2810,7 +3262,7 @@ impl ChatContext {
                     style::Print(format!(
                         "  ~{} tokens ({:.2}%)\n",
                         assistant_token_count,
-                        (assistant_token_count.value() as f32 / CONTEXT_WINDOW_SIZE as f32) * 100.0
+                        (assistant_token_count.value() as f32 / context_window_size as f32) * 100.0
                     )),
                     style::SetForegroundColor(Color::Magenta),
                     style::Print("█ Your prompts: "),
@@ -2818,7 +3270,7 @@ impl ChatContext {
                     style::Print(format!(
                         " ~{} tokens ({:.2}%)\n\n",
                         user_token_count,
-                        (user_token_count.value() as f32 / CONTEXT_WINDOW_SIZE as f32) * 100.0
+                        (user_token_count.value() as f32 / context_window_size as f32) * 100.0
                     )),
                 )?;
 
@@ -2852,6 +3304,148 @@ impl ChatContext {
                     skip_printing_tools: true,
                 }
             },
+            Command::Todos { subcommand } => {
+                if let TodosSubcommand::Help = subcommand {
+                    queue!(self.output, style::Print(TodosSubcommand::help_text()))?;
+                } else {
+                    let mut todos = self
+                        .conversation_state
+                        .todos
+                        .lock()
+                        .map_err(|err| ChatError::Custom(format!("The todo list is unavailable: {err}").into()))?;
+                    let result = match subcommand {
+                        TodosSubcommand::Add { description } => {
+                            let id = todos.add(description);
+                            Ok(Some(format!("Added item {}.", id)))
+                        },
+                        TodosSubcommand::Done { id } => todos.get_mut(id).map(|item| {
+                            item.status = TodoStatus::Completed;
+                            Some(format!("Completed item {}.", id))
+                        }),
+                        TodosSubcommand::Undo { id } => todos.get_mut(id).map(|item| {
+                            item.status = TodoStatus::Pending;
+                            Some(format!("Marked item {} as pending.", id))
+                        }),
+                        TodosSubcommand::Remove { id } => {
+                            todos.remove(id).map(|_| Some(format!("Removed item {}.", id)))
+                        },
+                        TodosSubcommand::Clear => {
+                            todos.clear();
+                            Ok(Some("Cleared the todo list.".to_string()))
+                        },
+                        TodosSubcommand::Show | TodosSubcommand::Help => Ok(None),
+                    };
+
+                    queue!(self.output, style::Print("\n"))?;
+                    match result {
+                        Ok(Some(message)) => queue!(
+                            self.output,
+                            style::SetForegroundColor(Color::Green),
+                            style::Print(format!("{}\n\n", message)),
+                            style::SetForegroundColor(Color::Reset),
+                        )?,
+                        Ok(None) => (),
+                        Err(err) => queue!(
+                            self.output,
+                            style::SetForegroundColor(Color::Red),
+                            style::Print(format!("{}\n\n", err)),
+                            style::SetForegroundColor(Color::Reset),
+                        )?,
+                    }
+                    if todos.is_empty() {
+                        queue!(
+                            self.output,
+                            style::SetForegroundColor(Color::DarkGrey),
+                            style::Print("The todo list is empty.\n"),
+                            style::SetForegroundColor(Color::Reset),
+                        )?;
+                    } else if let Err(err) = todos.queue_checklist(&mut self.output) {
+                        warn!(?err, "Failed to display the todo list");
+                    }
+                }
+                execute!(self.output, style::Print("\n"))?;
+
+                ChatState::PromptUser {
+                    tool_uses: Some(tool_uses),
+                    pending_tool_index,
+                    skip_printing_tools: true,
+                }
+            },
+            Command::Model { subcommand } => {
+                match subcommand {
+                    ModelSubcommand::List => {
+                        let current = self.conversation_state.model().map(|model| model.name.clone());
+                        queue!(self.output, style::Print("\n"))?;
+                        if current.is_none() {
+                            queue!(
+                                self.output,
+                                style::SetForegroundColor(Color::Green),
+                                style::Print("* default\n"),
+                                style::SetForegroundColor(Color::Reset),
+                            )?;
+                        }
+                        for model in self.models.all() {
+                            if current.as_ref() == Some(&model.name) {
+                                queue!(
+                                    self.output,
+                                    style::SetForegroundColor(Color::Green),
+                                    style::Print(format!("* {}", model.name)),
+                                    style::SetForegroundColor(Color::Reset),
+                                )?;
+                            } else {
+                                queue!(self.output, style::Print(format!("  {}", model.name)))?;
+                            }
+                            queue!(
+                                self.output,
+                                style::SetForegroundColor(Color::DarkGrey),
+                                style::Print(format!(
+                                    " ({}k tokens){}\n",
+                                    model.context_window_tokens / 1000,
+                                    model
+                                        .description
+                                        .as_ref()
+                                        .map(|description| format!(" {}", description))
+                                        .unwrap_or_default()
+                                )),
+                                style::SetForegroundColor(Color::Reset),
+                            )?;
+                        }
+                    },
+                    ModelSubcommand::Set { name } => match self.models.find(&name).cloned() {
+                        Some(model) => {
+                            // An explicit choice is kept after the current custom command is answered.
+                            self.model_before_command = None;
+                            queue!(
+                                self.output,
+                                style::SetForegroundColor(Color::Green),
+                                style::Print(format!("\nSwitched to model {}\n", model.name)),
+                                style::SetForegroundColor(Color::Reset),
+                            )?;
+                            self.conversation_state.set_model(Some(model));
+                        },
+                        None => queue!(
+                            self.output,
+                            style::SetForegroundColor(Color::Red),
+                            style::Print(format!(
+                                "\nModel '{}' does not exist. Available models: {}\n",
+                                name,
+                                self.models.names()
+                            )),
+                            style::SetForegroundColor(Color::Reset),
+                        )?,
+                    },
+                    ModelSubcommand::Help => {
+                        queue!(self.output, style::Print(ModelSubcommand::help_text()))?;
+                    },
+                }
+                execute!(self.output, style::Print("\n"))?;
+
+                ChatState::PromptUser {
+                    tool_uses: Some(tool_uses),
+                    pending_tool_index,
+                    skip_printing_tools: true,
+                }
+            },
             Command::Load { path } => {
                 macro_rules! tri {
                     ($v:expr) => {
@@ -2951,9 +3545,159 @@ impl ChatContext {
         })
     }
 
+    /// Asks the user to approve a workspace command before it runs shell commands or trusts tools.
+    /// The approval lasts for the session, or until the command's file changes.
+    fn approve_custom_command(&mut self, command: &CustomCommand) -> Result<bool, ChatError> {
+        if self.approved_commands.get(&command.name) == Some(command) {
+            return Ok(true);
+        }
+
+        if !self.interactive {
+            execute!(
+                self.output,
+                style::SetForegroundColor(Color::Red),
+                style::Print(format!(
+                    "\nError: /{} is a workspace command that has to be approved, which requires interactive mode\n\n",
+                    command.name
+                )),
+                style::SetForegroundColor(Color::Reset)
+            )?;
+            return Ok(false);
+        }
+
+        queue!(
+            self.output,
+            style::SetForegroundColor(Color::Yellow),
+            style::Print(format!(
+                "\n/{} is defined by this workspace in {WORKSPACE_COMMANDS_DIR}.\n",
+                command.name
+            )),
+            style::SetForegroundColor(Color::Reset),
+        )?;
+        for shell_command in command.shell_commands() {
+            queue!(self.output, style::Print(format!("  Runs: {shell_command}\n")))?;
+        }
+        if !command.allowed_tools.is_empty() {
+            queue!(
+                self.output,
+                style::Print(format!("  Trusts: {}\n", command.allowed_tools.join(", ")))
+            )?;
+        }
+        if let Some(model) = &command.model {
+            queue!(self.output, style::Print(format!("  Model: {model}\n")))?;
+        }
+        execute!(
+            self.output,
+            style::SetForegroundColor(Color::DarkGrey),
+            style::Print("\nRun it? "),
+            style::Print("["),
+            style::SetForegroundColor(Color::Green),
+            style::Print("y"),
+            style::SetForegroundColor(Color::DarkGrey),
+            style::Print("/"),
+            style::SetForegroundColor(Color::Green),
+            style::Print("n"),
+            style::SetForegroundColor(Color::DarkGrey),
+            style::Print("]:\n\n"),
+            style::SetForegroundColor(Color::Reset),
+        )?;
+
+        let approved = self
+            .read_user_input("> ".yellow().to_string().as_str(), true)
+            .is_some_and(|input| ["y", "Y"].contains(&input.trim()));
+        if approved {
+            self.approved_commands.insert(command.name.clone(), command.clone());
+        }
+        Ok(approved)
+    }
+
+    /// Asks the user whether to trust the tools listed in the `trusted_tools` of a workspace
+    /// profile and to run its hooks. Approvals are remembered for the rest of the session.
+    fn approve_workspace_profile(&mut self, profile: &str, review: &WorkspaceProfileReview) -> Result<bool, ChatError> {
+        if self.approved_profiles.get(profile) == Some(review) {
+            return Ok(true);
+        }
+
+        if !self.interactive {
+            execute!(
+                self.output,
+                style::SetForegroundColor(Color::Yellow),
+                style::Print(format!(
+                    "\nNot trusting the tools, running the hooks or following the instructions of the workspace profile '{profile}', which requires interactive mode\n\n"
+                )),
+                style::SetForegroundColor(Color::Reset)
+            )?;
+            return Ok(false);
+        }
+
+        execute!(
+            self.output,
+            style::SetForegroundColor(Color::Yellow),
+            style::Print(format!(
+                "\nThe profile '{profile}' is defined by this workspace in {WORKSPACE_PROFILES_DIR}.\n"
+            )),
+            style::SetForegroundColor(Color::Reset),
+        )?;
+        if review.shadows_global {
+            queue!(
+                self.output,
+                style::Print("  Replaces your global profile of the same name\n")
+            )?;
+        }
+        if !review.tools.is_empty() {
+            queue!(
+                self.output,
+                style::Print(format!("  Trusts: {}\n", review.tools.join(", ")))
+            )?;
+        }
+        if !review.hooks.is_empty() {
+            queue!(self.output, style::Print("  Runs hooks:\n"))?;
+            for (name, command) in &review.hooks {
+                queue!(self.output, style::Print(format!("    {name}: {command}\n")))?;
+            }
+        }
+        if !review.paths.is_empty() {
+            queue!(
+                self.output,
+                style::Print("  Includes files outside of the workspace:\n")
+            )?;
+            for path in &review.paths {
+                queue!(self.output, style::Print(format!("    {path}\n")))?;
+            }
+        }
+        if let Some(instructions) = &review.instructions {
+            queue!(self.output, style::Print("  Instructions:\n"))?;
+            for line in instructions.lines() {
+                queue!(self.output, style::Print(format!("    {line}\n")))?;
+            }
+        }
+        execute!(
+            self.output,
+            style::SetForegroundColor(Color::DarkGrey),
+            style::Print("\nApprove this profile? "),
+            style::Print("["),
+            style::SetForegroundColor(Color::Green),
+            style::Print("y"),
+            style::SetForegroundColor(Color::DarkGrey),
+            style::Print("/"),
+            style::SetForegroundColor(Color::Green),
+            style::Print("n"),
+            style::SetForegroundColor(Color::DarkGrey),
+            style::Print("]:\n\n"),
+            style::SetForegroundColor(Color::Reset),
+        )?;
+
+        let approved = self
+            .read_user_input("> ".yellow().to_string().as_str(), true)
+            .is_some_and(|input| ["y", "Y"].contains(&input.trim()));
+        if approved {
+            self.approved_profiles.insert(profile.to_string(), review.clone());
+        }
+        Ok(approved)
+    }
+
     async fn tool_use_execute(
         &mut self,
-        database: &Database,
         telemetry: &TelemetryThread,
         mut tool_uses: Vec<QueuedTool>,
     ) -> Result<ChatState, ChatError> {
@@ -2967,16 +3711,9 @@ impl ChatContext {
             // If there is an override, we will use it. Otherwise fall back to Tool's default.
             let allowed = self.tool_permissions.trust_all
                 || (self.tool_permissions.has(&tool.name) && self.tool_permissions.is_trusted(&tool.name))
+                || self.command_allowed_tools.contains(&tool.name)
                 || !tool.tool.requires_acceptance(&self.ctx);
 
-            if database
-                .settings
-                .get_bool(Setting::ChatEnableNotifications)
-                .unwrap_or(false)
-            {
-                play_notification_bell(!allowed);
-            }
-
             self.print_tool_descriptions(tool, allowed).await?;
 
             if allowed {
@@ -2990,6 +3727,12 @@ impl ChatContext {
                 return Err(ChatError::NonInteractiveToolApproval);
             }
 
+            self.notifier.notify(
+                &mut self.output,
+                NotificationEvent::ToolApproval,
+                &format!("Allow {}?", tool.tool.display_name()),
+            );
+
             return Ok(ChatState::PromptUser {
                 tool_uses: Some(tool_uses),
                 pending_tool_index,
@@ -3027,6 +3770,18 @@ impl ChatContext {
                 });
             }
             let tool_time = format!("{}.{}", tool_time.as_secs(), tool_time.subsec_millis());
+            let event = HookEvent::PostToolUse {
+                tool_name: tool.name.clone(),
+                tool_input: tool.input.clone(),
+                tool_response: match &invoke_result {
+                    Ok(InvokeOutput {
+                        output: OutputKind::Json(json),
+                    }) => json.to_string(),
+                    Ok(result) => result.as_str().to_string(),
+                    Err(err) => err.to_string(),
+                },
+                success: invoke_result.is_ok(),
+            };
             match invoke_result {
                 Ok(result) => {
                     match result.output {
@@ -3098,6 +3853,37 @@ impl ChatContext {
                     }
                 },
             }
+
+            // Post tool use hooks that block send their reason back to the model with the result.
+            for (hook, outcome) in self.run_event_hooks(&event).await {
+                if let (HookOutcome::Block(reason), Some(result)) = (outcome, tool_results.last_mut()) {
+                    execute!(
+                        self.output,
+                        style::SetForegroundColor(Color::Yellow),
+                        style::Print(format!("Hook {} blocked: ", hook.name)),
+                        style::SetForegroundColor(Color::Reset),
+                        style::Print(format!("{reason}\n\n")),
+                    )?;
+                    result.content.push(ToolUseResultBlock::Text(format!(
+                        "Feedback from hook {}: {reason}",
+                        hook.name
+                    )));
+                }
+            }
+
+            // Rules in subdirectories are only loaded once the agent works with files inside them.
+            if let Some(context_manager) = self.conversation_state.context_manager.as_mut() {
+                for path in tool.tool.accessed_paths() {
+                    for dir in context_manager.discover_rules(path).await {
+                        execute!(
+                            self.output,
+                            style::SetForegroundColor(Color::DarkGrey),
+                            style::Print(format!("Loaded rules from {}\n\n", dir.display())),
+                            style::SetForegroundColor(Color::Reset),
+                        )?;
+                    }
+                }
+            }
         }
 
         if !image_blocks.is_empty() {
@@ -3139,6 +3925,7 @@ impl ChatContext {
         let mut ended = false;
         let mut parser = ResponseParser::new(response);
         let mut state = ParseState::new(Some(self.terminal_width()));
+        state.syntax_highlighting = supports_truecolor(&self.ctx);
 
         let mut tool_uses = Vec::new();
         let mut tool_name_being_recvd: Option<String> = None;
@@ -3307,7 +4094,15 @@ impl ChatContext {
 
             // Print the response for normal cases
             loop {
-                let input = Partial::new(&buf[offset..]);
+                let mut input = Partial::new(&buf[offset..]);
+                if ended {
+                    // Nothing else is coming, so patterns that wait for the next line, like table
+                    // headers, have to decide with what's there.
+                    if input.is_empty() {
+                        break;
+                    }
+                    let _ = input.complete();
+                }
                 match interpret_markdown(input, &mut self.output, &mut state) {
                     Ok(parsed) => {
                         offset += parsed.offset_from(&input);
@@ -3343,14 +4138,8 @@ impl ChatContext {
                         .ok();
                 }
 
-                if self.interactive
-                    && database
-                        .settings
-                        .get_bool(Setting::ChatEnableNotifications)
-                        .unwrap_or(false)
-                {
-                    // For final responses (no tools suggested), always play the bell
-                    play_notification_bell(tool_uses.is_empty());
+                if self.interactive && tool_uses.is_empty() {
+                    self.notifier.turn_complete(&mut self.output);
                 }
 
                 if self.interactive {
@@ -3370,6 +4159,11 @@ impl ChatContext {
                     }
                 }
 
+                self.run_event_hooks(&HookEvent::AssistantResponse {
+                    response: buf.trim().to_string(),
+                })
+                .await;
+
                 break;
             }
         }
@@ -3395,13 +4189,45 @@ impl ChatContext {
         let mut queued_tools: Vec<QueuedTool> = Vec::new();
         let mut tool_results: Vec<ToolUseResult> = Vec::new();
 
-        for tool_use in tool_uses {
+        for mut tool_use in tool_uses {
             let tool_use_id = tool_use.id.clone();
             let tool_use_name = tool_use.name.clone();
             let mut tool_telemetry = ToolUseEventBuilder::new(conv_id.clone(), tool_use.id.clone())
                 .set_tool_use_id(tool_use_id.clone())
                 .set_tool_name(tool_use.name.clone())
                 .utterance_id(self.conversation_state.message_id().map(|s| s.to_string()));
+
+            // Pre tool use hooks may allow, block, or rewrite the tool use before it is validated.
+            let mut accepted = false;
+            let mut blocked = None;
+            let event = HookEvent::PreToolUse {
+                tool_name: tool_use.name.clone(),
+                tool_input: tool_use.args.clone(),
+            };
+            for (hook, outcome) in self.run_event_hooks(&event).await {
+                match outcome {
+                    HookOutcome::Continue(_) => (),
+                    // Approving tool uses is opt in, since any hook could otherwise run any tool.
+                    HookOutcome::Allow if self.hooks_allow_tools && !hook.from_workspace => accepted = true,
+                    HookOutcome::Allow => debug!("ignoring allow decision of hook {}", hook.name),
+                    HookOutcome::Block(reason) => blocked = Some(reason),
+                    HookOutcome::Modify(tool_input) => tool_use.args = tool_input,
+                }
+            }
+            if let Some(reason) = blocked {
+                tool_telemetry.is_valid = Some(false);
+                tool_results.push(ToolUseResult {
+                    tool_use_id: tool_use_id.clone(),
+                    content: vec![ToolUseResultBlock::Text(format!(
+                        "Tool use was blocked by a hook: {reason}"
+                    ))],
+                    status: ToolResultStatus::Error,
+                });
+                self.tool_use_telemetry_events.insert(tool_use_id, tool_telemetry);
+                continue;
+            }
+
+            let tool_input = tool_use.args.clone();
             match self.conversation_state.tool_manager.get_tool_from_tool_use(tool_use) {
                 Ok(mut tool) => {
                     // Apply non-Q-generated context to tools
@@ -3414,7 +4240,8 @@ impl ChatContext {
                                 id: tool_use_id.clone(),
                                 name: tool_use_name,
                                 tool,
-                                accepted: false,
+                                accepted,
+                                input: tool_input,
                             });
                         },
                         Err(err) => {
@@ -3491,7 +4318,6 @@ impl ChatContext {
     // output from Amazon Q.
     // TODO: Is there a better way?
     fn contextualize_tool(&self, tool: &mut Tool) {
-        #[allow(clippy::single_match)]
         match tool {
             Tool::GhIssue(gh_issue) => {
                 gh_issue.set_context(GhIssueContext {
@@ -3505,6 +4331,22 @@ impl ChatContext {
                     interactive: self.interactive,
                 });
             },
+            Tool::DelegateTask(delegate_task) => {
+                delegate_task.set_context(DelegateTaskContext {
+                    ctx: Arc::clone(&self.ctx),
+                    client: self.client.clone(),
+                    tool_manager: self.conversation_state.tool_manager.clone(),
+                    tool_permissions: self.tool_permissions.clone(),
+                    profile: self.conversation_state.current_profile().map(str::to_string),
+                    model: self.conversation_state.model().cloned(),
+                    hooks_allow_tools: self.hooks_allow_tools,
+                    web_fetch_domains: self.web_fetch_domains.clone(),
+                    use_aws_native: self.use_aws_native,
+                });
+            },
+            Tool::WebFetch(web_fetch) => web_fetch.set_domain_filter(self.web_fetch_domains.clone()),
+            Tool::TodoList(todo_list) => todo_list.set_todos(Arc::clone(&self.conversation_state.todos)),
+            Tool::UseAws(use_aws) => use_aws.set_prefer_native(self.use_aws_native),
             _ => (),
         };
     }
@@ -3631,13 +4473,21 @@ impl ChatContext {
     }
 }
 
-/// Prints hook configuration grouped by trigger: conversation session start or per user message
+/// Prints hook configuration grouped by trigger. Sections for event triggers are only printed if
+/// they have hooks.
 fn print_hook_section(output: &mut impl Write, hooks: &HashMap<String, Hook>, trigger: HookTrigger) -> Result<()> {
     let section = match trigger {
         HookTrigger::ConversationStart => "On Session Start",
         HookTrigger::PerPrompt => "Per User Message",
+        HookTrigger::PreToolUse => "Before Tool Use",
+        HookTrigger::PostToolUse => "After Tool Use",
+        HookTrigger::AssistantResponse => "After Response",
+        HookTrigger::SessionEnd => "On Session End",
     };
     let hooks: Vec<(&String, &Hook)> = hooks.iter().filter(|(_, h)| h.trigger == trigger).collect();
+    if hooks.is_empty() && !trigger.adds_context() {
+        return Ok(());
+    }
 
     queue!(
         output,
@@ -3655,6 +4505,10 @@ fn print_hook_section(output: &mut impl Write, hooks: &HashMap<String, Hook>, tr
         )?;
     } else {
         for (name, hook) in hooks {
+            let name = match &hook.matcher {
+                Some(matcher) => format!("{name} ({matcher})"),
+                None => name.clone(),
+            };
             if hook.disabled {
                 queue!(
                     output,
@@ -0,0 +1,122 @@
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use tracing::warn;
+
+use super::consts::CONTEXT_WINDOW_SIZE;
+use crate::platform::Context;
+use crate::util::directories::chat_models_path;
+
+/// A model that can be selected with `/model set` or `q chat --model`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModelInfo {
+    /// The short name users select the model by.
+    pub name: String,
+    /// The backend model id sent with each request.
+    pub model_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// In tokens
+    #[serde(default = "default_context_window_tokens")]
+    pub context_window_tokens: usize,
+}
+
+fn default_context_window_tokens() -> usize {
+    CONTEXT_WINDOW_SIZE
+}
+
+/// The models available in chat.
+///
+/// The bundled `models.json` can be extended through `~/.aws/amazonq/models.json`, which uses the
+/// same format. Entries there replace bundled models with the same name.
+#[derive(Debug, Clone, Default)]
+pub struct Models(Vec<ModelInfo>);
+
+impl Models {
+    pub fn bundled() -> Self {
+        Self(serde_json::from_str(include_str!("models.json")).expect("models.json is valid json"))
+    }
+
+    pub async fn load(ctx: &Context) -> Self {
+        let mut models = Self::bundled();
+        let Ok(path) = chat_models_path(ctx) else {
+            return models;
+        };
+        if !ctx.fs().exists(&path) {
+            return models;
+        }
+
+        let user_models = match ctx.fs().read_to_string(&path).await {
+            Ok(contents) => serde_json::from_str::<Vec<ModelInfo>>(&contents).map_err(eyre::Report::from),
+            Err(err) => Err(err.into()),
+        };
+        match user_models {
+            Ok(user_models) => models.extend(user_models),
+            Err(err) => warn!(?err, ?path, "failed to load the models config"),
+        }
+        models
+    }
+
+    fn extend(&mut self, models: Vec<ModelInfo>) {
+        for model in models {
+            match self.0.iter_mut().find(|m| m.name == model.name) {
+                Some(existing) => *existing = model,
+                None => self.0.push(model),
+            }
+        }
+    }
+
+    pub fn all(&self) -> &[ModelInfo] {
+        &self.0
+    }
+
+    /// Finds a model by its name, ignoring case, or by its backend model id.
+    pub fn find(&self, name_or_id: &str) -> Option<&ModelInfo> {
+        self.0
+            .iter()
+            .find(|m| m.name.eq_ignore_ascii_case(name_or_id) || m.model_id == name_or_id)
+    }
+
+    /// The comma separated names of all models, for error messages.
+    pub fn names(&self) -> String {
+        self.0.iter().map(|m| m.name.as_str()).collect::<Vec<_>>().join(", ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_load_models() {
+        let ctx = Context::builder().with_test_home().await.unwrap().build_fake();
+        let bundled = Models::bundled();
+        assert!(!bundled.all().is_empty());
+
+        let path = chat_models_path(&ctx).unwrap();
+        ctx.fs().create_dir_all(path.parent().unwrap()).await.unwrap();
+        ctx.fs()
+            .write(
+                &path,
+                serde_json::json!([
+                    { "name": bundled.all()[0].name, "model_id": "OVERRIDDEN", "context_window_tokens": 100000 },
+                    { "name": "custom", "model_id": "CUSTOM_MODEL_V1" },
+                ])
+                .to_string(),
+            )
+            .await
+            .unwrap();
+
+        let models = Models::load(&ctx).await;
+        assert_eq!(models.all().len(), bundled.all().len() + 1);
+        assert_eq!(models.all()[0].model_id, "OVERRIDDEN");
+        assert_eq!(models.all()[0].context_window_tokens, 100_000);
+        assert_eq!(
+            models.find("CUSTOM").unwrap().context_window_tokens,
+            CONTEXT_WINDOW_SIZE
+        );
+        assert_eq!(models.find("CUSTOM_MODEL_V1").unwrap().name, "custom");
+        assert!(models.find("missing").is_none());
+    }
+}
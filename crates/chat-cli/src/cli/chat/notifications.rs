@@ -0,0 +1,401 @@
+use std::io::Write;
+use std::process::Stdio;
+use std::str::FromStr;
+use std::time::{
+    Duration,
+    Instant,
+};
+
+use crossterm::{
+    execute,
+    style,
+};
+use eyre::{
+    Result,
+    bail,
+};
+use tracing::{
+    debug,
+    warn,
+};
+
+use super::util::should_play_bell;
+use crate::database::settings::{
+    Setting,
+    Settings,
+};
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+const APP_NAME: &str = "Amazon Q";
+
+/// Something that happened in the chat session which the user may want to be told about while
+/// looking at another window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotificationEvent {
+    /// A tool use is waiting for the user to accept it.
+    ToolApproval,
+    /// A response finished after running for at least the configured minimum turn duration.
+    TurnComplete,
+    /// The current turn failed with an error.
+    Error,
+}
+
+impl NotificationEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NotificationEvent::ToolApproval => "tool_approval",
+            NotificationEvent::TurnComplete => "turn_complete",
+            NotificationEvent::Error => "error",
+        }
+    }
+
+    fn title(&self) -> &'static str {
+        match self {
+            NotificationEvent::ToolApproval => "Amazon Q needs your approval",
+            NotificationEvent::TurnComplete => "Amazon Q finished responding",
+            NotificationEvent::Error => "Amazon Q ran into an error",
+        }
+    }
+}
+
+/// The ways a notification can be delivered, configured with `chat.notifications.methods`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotificationMethod {
+    /// The terminal bell.
+    Bell,
+    /// A desktop notification, through the freedesktop notification service on Linux.
+    Desktop,
+    /// An OSC 9 terminal notification, supported by e.g. iTerm2, WezTerm and Windows Terminal.
+    Osc9,
+    /// An OSC 777 terminal notification, supported by e.g. foot, Ghostty and VTE based terminals.
+    Osc777,
+}
+
+impl FromStr for NotificationMethod {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.trim().to_lowercase().as_str() {
+            "bell" => Self::Bell,
+            "desktop" => Self::Desktop,
+            "osc9" => Self::Osc9,
+            "osc777" => Self::Osc777,
+            other => bail!("Unknown notification method '{other}'"),
+        })
+    }
+}
+
+/// Delivers [NotificationEvent]s according to the user's notification settings.
+#[derive(Debug, Clone)]
+pub struct Notifier {
+    enabled: bool,
+    methods: Vec<NotificationMethod>,
+    /// Command run through `bash -c` for every notification.
+    command: Option<String>,
+    min_turn_duration: Duration,
+    turn_started: Option<Instant>,
+    in_tmux: bool,
+}
+
+impl Notifier {
+    pub fn new(settings: &Settings) -> Self {
+        let methods = match settings.get_string_array(Setting::ChatNotificationMethods) {
+            Some(methods) => methods
+                .iter()
+                .filter_map(|method| match method.parse() {
+                    Ok(method) => Some(method),
+                    Err(err) => {
+                        warn!(?err, "ignoring notification method");
+                        None
+                    },
+                })
+                .collect(),
+            None => vec![NotificationMethod::Bell],
+        };
+
+        Self {
+            enabled: settings.get_bool(Setting::ChatEnableNotifications).unwrap_or(false),
+            methods,
+            command: settings
+                .get_string(Setting::ChatNotificationCommand)
+                .filter(|command| !command.trim().is_empty()),
+            min_turn_duration: Duration::from_secs(
+                settings
+                    .get_int(Setting::ChatNotificationMinTurnSeconds)
+                    .and_then(|secs| u64::try_from(secs).ok())
+                    .unwrap_or(0),
+            ),
+            turn_started: None,
+            in_tmux: std::env::var_os("TMUX").is_some(),
+        }
+    }
+
+    /// Marks the start of a turn, i.e. the user sending a prompt or accepting a tool use.
+    pub fn start_turn(&mut self) {
+        self.turn_started = Some(Instant::now());
+    }
+
+    /// Notifies that a turn has completed, if it ran for at least the minimum turn duration.
+    pub fn turn_complete(&mut self, output: &mut impl Write) {
+        let Some(started) = self.turn_started.take() else {
+            return;
+        };
+        let elapsed = started.elapsed();
+        if elapsed >= self.min_turn_duration {
+            self.notify(
+                output,
+                NotificationEvent::TurnComplete,
+                &format!("Finished after {}s", elapsed.as_secs()),
+            );
+        }
+    }
+
+    pub fn notify(&self, output: &mut impl Write, event: NotificationEvent, body: &str) {
+        if !self.enabled {
+            return;
+        }
+        debug!(?event, "sending notification");
+
+        for method in &self.methods {
+            let sequence = match method {
+                NotificationMethod::Bell if should_play_bell() => "\x07".to_string(),
+                NotificationMethod::Bell => continue,
+                NotificationMethod::Desktop => {
+                    send_desktop_notification(event, body);
+                    continue;
+                },
+                NotificationMethod::Osc9 => osc9(event, body),
+                NotificationMethod::Osc777 => osc777(event, body),
+            };
+            let sequence = match self.in_tmux && *method != NotificationMethod::Bell {
+                true => tmux_passthrough(&sequence),
+                false => sequence,
+            };
+            if let Err(err) = execute!(output, style::Print(sequence)) {
+                warn!(?err, "failed to write the terminal notification");
+            }
+        }
+
+        if let Some(command) = &self.command {
+            run_command(command, event, body);
+        }
+    }
+}
+
+/// Strips characters that would terminate or corrupt an escape sequence.
+fn sanitize(text: &str) -> String {
+    text.chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .collect::<String>()
+        .replace(';', ",")
+}
+
+fn osc9(event: NotificationEvent, body: &str) -> String {
+    format!("\x1b]9;{}: {}\x07", event.title(), sanitize(body))
+}
+
+fn osc777(event: NotificationEvent, body: &str) -> String {
+    format!("\x1b]777;notify;{};{}\x07", event.title(), sanitize(body))
+}
+
+/// Wraps an escape sequence so that tmux forwards it to the outer terminal.
+fn tmux_passthrough(sequence: &str) -> String {
+    format!("\x1bPtmux;{}\x1b\\", sequence.replace('\x1b', "\x1b\x1b"))
+}
+
+fn send_desktop_notification(event: NotificationEvent, body: &str) {
+    let body = body.to_string();
+    tokio::spawn(async move {
+        #[cfg(target_os = "linux")]
+        {
+            let urgency = match event {
+                NotificationEvent::Error => freedesktop::URGENCY_CRITICAL,
+                _ => freedesktop::URGENCY_NORMAL,
+            };
+            if let Err(err) = freedesktop::notify(event.title(), &body, urgency).await {
+                warn!(?err, "failed to send the desktop notification");
+            }
+        }
+        #[cfg(target_os = "macos")]
+        {
+            let script = format!(
+                "display notification {} with title {} subtitle {}",
+                applescript_string(&body),
+                applescript_string(APP_NAME),
+                applescript_string(event.title())
+            );
+            if let Err(err) = tokio::process::Command::new("osascript")
+                .args(["-e", &script])
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status()
+                .await
+            {
+                warn!(?err, "failed to send the desktop notification");
+            }
+        }
+        #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+        {
+            let _ = (event, body);
+            debug!("desktop notifications are not supported on this platform");
+        }
+    });
+}
+
+/// Quotes `text` as an AppleScript string literal. Control characters are replaced with spaces
+/// since they can't be escaped.
+#[cfg(any(target_os = "macos", test))]
+fn applescript_string(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '"' | '\\' => {
+                quoted.push('\\');
+                quoted.push(c);
+            },
+            c if c.is_control() => quoted.push(' '),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Desktop notifications through the freedesktop `org.freedesktop.Notifications` service.
+#[cfg(target_os = "linux")]
+mod freedesktop {
+    use std::collections::HashMap;
+
+    use zbus::proxy;
+    use zbus::zvariant::Value;
+
+    use super::APP_NAME;
+
+    pub const URGENCY_NORMAL: u8 = 1;
+    pub const URGENCY_CRITICAL: u8 = 2;
+
+    /// Sends a notification, returning its id.
+    pub async fn notify(summary: &str, body: &str, urgency: u8) -> zbus::Result<u32> {
+        let connection = zbus::Connection::session().await?;
+        let proxy = NotificationsProxy::new(&connection).await?;
+        let hints = HashMap::from([("urgency", Value::U8(urgency))]);
+        proxy.notify(APP_NAME, 0, "", summary, body, &[], hints, -1).await
+    }
+
+    #[proxy(
+        default_service = "org.freedesktop.Notifications",
+        interface = "org.freedesktop.Notifications",
+        default_path = "/org/freedesktop/Notifications"
+    )]
+    trait Notifications {
+        #[allow(clippy::too_many_arguments)]
+        fn notify(
+            &self,
+            app_name: &str,
+            replaces_id: u32,
+            app_icon: &str,
+            summary: &str,
+            body: &str,
+            actions: &[&str],
+            hints: HashMap<&str, Value<'_>>,
+            expire_timeout: i32,
+        ) -> zbus::Result<u32>;
+    }
+}
+
+/// Runs the user's notification command in the background, passing the event through the
+/// environment.
+fn run_command(command: &str, event: NotificationEvent, body: &str) {
+    let child = tokio::process::Command::new("bash")
+        .arg("-c")
+        .arg(command)
+        .env("Q_NOTIFICATION_EVENT", event.as_str())
+        .env("Q_NOTIFICATION_TITLE", event.title())
+        .env("Q_NOTIFICATION_BODY", body)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+
+    match child {
+        Ok(mut child) => {
+            tokio::spawn(async move {
+                match child.wait().await {
+                    Ok(status) if !status.success() => warn!(?status, "notification command failed"),
+                    Err(err) => warn!(?err, "failed to wait for the notification command"),
+                    _ => (),
+                }
+            });
+        },
+        Err(err) => warn!(?err, "failed to run the notification command"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_notification_method_from_str() {
+        assert_eq!("bell".parse::<NotificationMethod>().unwrap(), NotificationMethod::Bell);
+        assert_eq!(
+            " OSC9 ".parse::<NotificationMethod>().unwrap(),
+            NotificationMethod::Osc9
+        );
+        assert_eq!(
+            "osc777".parse::<NotificationMethod>().unwrap(),
+            NotificationMethod::Osc777
+        );
+        assert_eq!(
+            "desktop".parse::<NotificationMethod>().unwrap(),
+            NotificationMethod::Desktop
+        );
+        assert!("popup".parse::<NotificationMethod>().is_err());
+    }
+
+    #[test]
+    fn test_escape_sequences() {
+        assert_eq!(
+            osc9(NotificationEvent::Error, "bad;\x07input"),
+            "\x1b]9;Amazon Q ran into an error: bad, input\x07"
+        );
+        assert_eq!(
+            osc777(NotificationEvent::TurnComplete, "Finished after 42s"),
+            "\x1b]777;notify;Amazon Q finished responding;Finished after 42s\x07"
+        );
+        assert_eq!(tmux_passthrough("\x1b]9;hi\x07"), "\x1bPtmux;\x1b\x1b]9;hi\x07\x1b\\");
+    }
+
+    #[test]
+    fn test_applescript_string() {
+        assert_eq!(applescript_string("Finished after 42s"), "\"Finished after 42s\"");
+        assert_eq!(
+            applescript_string("say \"hi\"\\n\u{1b}[0m\r\nbye"),
+            "\"say \\\"hi\\\"\\\\n [0m  bye\""
+        );
+    }
+
+    #[test]
+    fn test_notifier() {
+        let mut notifier = Notifier::new(&Settings::default());
+        assert!(!notifier.enabled);
+        assert_eq!(notifier.methods, vec![NotificationMethod::Bell]);
+
+        notifier.enabled = true;
+        notifier.in_tmux = false;
+        notifier.methods = vec![NotificationMethod::Osc9];
+        notifier.min_turn_duration = Duration::from_secs(60);
+
+        let mut output = Vec::new();
+        notifier.start_turn();
+        notifier.turn_complete(&mut output);
+        assert!(output.is_empty(), "short turns should not notify");
+
+        notifier.notify(&mut output, NotificationEvent::ToolApproval, "execute_bash");
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "\x1b]9;Amazon Q needs your approval: execute_bash\x07"
+        );
+    }
+}
@@ -9,6 +9,8 @@ use crossterm::{
     Command,
     style,
 };
+use syntect::easy::HighlightLines;
+use syntect::util::as_24_bit_terminal_escaped;
 use unicode_width::{
     UnicodeWidthChar,
     UnicodeWidthStr,
@@ -24,6 +26,7 @@ use winnow::ascii::{
 use winnow::combinator::{
     alt,
     delimited,
+    opt,
     preceded,
     repeat,
     terminated,
@@ -37,21 +40,35 @@ use winnow::prelude::*;
 use winnow::stream::{
     AsChar,
     Stream,
+    StreamIsPartial,
 };
 use winnow::token::{
     any,
+    one_of,
+    take,
     take_till,
     take_until,
     take_while,
 };
 
+use super::tools::fs_write::{
+    SYNTAX_SET,
+    THEME_SET,
+};
+
 const CODE_COLOR: Color = Color::Green;
 const HEADING_COLOR: Color = Color::Magenta;
 const BLOCKQUOTE_COLOR: Color = Color::DarkGrey;
 const URL_TEXT_COLOR: Color = Color::Blue;
 const URL_LINK_COLOR: Color = Color::DarkGrey;
+const TABLE_BORDER_COLOR: Color = Color::DarkGrey;
 
 const DEFAULT_RULE_WIDTH: usize = 40;
+const DEFAULT_TABLE_WIDTH: usize = 80;
+const MAX_TABLE_COLUMN_WIDTH: usize = 40;
+const TABLE_COLUMN_SEPARATOR: &str = " │ ";
+
+const CODE_THEME: &str = "base16-ocean.dark";
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error<'a> {
@@ -90,6 +107,12 @@ pub struct ParseState {
     pub set_newline: bool,
     pub newline: bool,
     pub citations: Vec<(String, String)>,
+    /// Whether fenced code blocks should be syntax highlighted with 24bit color.
+    pub syntax_highlighting: bool,
+    /// Highlighter for the code block currently being printed, if its language is known.
+    pub highlighter: Option<CodeHighlighter>,
+    /// Layout of the table currently being printed, if any.
+    pub table: Option<TableLayout>,
 }
 
 impl ParseState {
@@ -104,6 +127,70 @@ impl ParseState {
             set_newline: false,
             newline: true,
             citations: vec![],
+            syntax_highlighting: false,
+            highlighter: None,
+            table: None,
+        }
+    }
+}
+
+/// Incremental syntax highlighter for a fenced code block. Highlighting state is carried across
+/// lines so that each line only has to be printed once.
+pub struct CodeHighlighter(HighlightLines<'static>);
+
+impl CodeHighlighter {
+    /// Returns a highlighter for the language of a code fence, eg `rust` or `py`, if one is known.
+    pub fn new(language: &str) -> Option<Self> {
+        let syntax = SYNTAX_SET.find_syntax_by_token(language)?;
+        let theme = THEME_SET.themes.get(CODE_THEME)?;
+        Some(Self(HighlightLines::new(syntax, theme)))
+    }
+
+    fn highlight(&mut self, line: &str) -> Option<String> {
+        let ranges = self.0.highlight_line(line, &SYNTAX_SET).ok()?;
+        Some(as_24_bit_terminal_escaped(&ranges[..], false))
+    }
+}
+
+impl std::fmt::Debug for CodeHighlighter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CodeHighlighter").finish_non_exhaustive()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnAlignment {
+    Left,
+    Center,
+    Right,
+}
+
+/// Column layout of a GFM table. Since the response is streamed, the widths are decided from the
+/// header row and the terminal width, and every following row is wrapped to fit them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableLayout {
+    pub widths: Vec<usize>,
+    pub alignments: Vec<ColumnAlignment>,
+    /// Whether the header row started with a `|`, in which case every row is expected to.
+    pub outer_pipes: bool,
+}
+
+impl TableLayout {
+    fn new(headers: &[String], alignments: Vec<ColumnAlignment>, terminal_width: usize, outer_pipes: bool) -> Self {
+        let columns = alignments.len();
+        let separators = TABLE_COLUMN_SEPARATOR.width() * columns.saturating_sub(1);
+        let share = (terminal_width.saturating_sub(separators) / columns).max(1);
+        let widths = (0..columns)
+            .map(|idx| {
+                let header_width = headers.get(idx).map_or(0, |h| visible_width(h));
+                share.min(MAX_TABLE_COLUMN_WIDTH).max(header_width.min(share))
+            })
+            .collect();
+
+        Self {
+            widths,
+            alignments,
+            outer_pipes,
         }
     }
 }
@@ -138,6 +225,10 @@ pub fn interpret_markdown<'a, 'b>(
     match state.in_codeblock {
         false => {
             stateful_alt!(
+                // Must come first so that any line which isn't a row ends the current table
+                table_row,
+                // Rows don't have to start with a `|`, so this has to be tried before plaintext
+                table,
                 // This pattern acts as a short circuit for alphanumeric plaintext
                 // More importantly, it's needed to support manual wordwrapping
                 text,
@@ -167,6 +258,9 @@ pub fn interpret_markdown<'a, 'b>(
                 fallback
             );
         },
+        true if state.highlighter.is_some() => {
+            stateful_alt!(codeblock_end, codeblock_highlighted_line);
+        },
         true => {
             stateful_alt!(
                 codeblock_less_than,
@@ -227,7 +321,10 @@ fn bulleted_item<'a, 'b>(
         }
 
         let ws = (space0, alt(("-", "*")), space1).parse_next(i)?.0;
-        let print = format!("{ws}• ");
+        let print = match task_marker(i)? {
+            Some(marker) => format!("{ws}{marker} "),
+            None => format!("{ws}• "),
+        };
 
         queue_newline_or_advance(&mut o, state, print.width())?;
         queue(&mut o, style::Print(print))
@@ -251,6 +348,12 @@ fn numbered_item<'a, 'b>(
     }
 }
 
+/// Parses the optional `[ ]` or `[x]` checkbox of a task list item.
+fn task_marker<'a>(i: &mut Partial<&'a str>) -> PResult<Option<char>, Error<'a>> {
+    let checked = opt(terminated(delimited("[", one_of([' ', 'x', 'X']), "]"), space1)).parse_next(i)?;
+    Ok(checked.map(|c| if c == ' ' { '☐' } else { '☑' }))
+}
+
 fn horizontal_rule<'a, 'b>(
     mut o: impl Write + 'b,
     state: &'b mut ParseState,
@@ -274,6 +377,284 @@ fn horizontal_rule<'a, 'b>(
     }
 }
 
+fn table<'a, 'b>(
+    mut o: impl Write + 'b,
+    state: &'b mut ParseState,
+) -> impl FnMut(&mut Partial<&'a str>) -> PResult<(), Error<'a>> + 'b {
+    move |i| {
+        if !state.newline || state.table.is_some() {
+            return Err(ErrMode::from_error_kind(i, ErrorKind::Fail));
+        }
+
+        // A header needs at least one `|`. Checking what has been received of the line so far
+        // lets every other line print without waiting for its line ending.
+        let Some(line) = i.lines().next().filter(|line| line.contains('|')) else {
+            return Err(ErrMode::from_error_kind(i, ErrorKind::Fail));
+        };
+
+        // Lines like `a | b` are common in plain text, so without a leading `|` the header is only
+        // held back once the delimiter row has been received as well.
+        if !line.trim_start().starts_with('|')
+            && !i
+                .split_inclusive('\n')
+                .nth(1)
+                .is_some_and(|row| row.ends_with('\n') && parse_table_delimiter(row.trim_end()).is_some())
+        {
+            return Err(ErrMode::from_error_kind(i, ErrorKind::Fail));
+        }
+
+        // A table is only recognized once both the header and the delimiter rows are complete.
+        let header = terminated(till_line_ending, ascii::line_ending).parse_next(i)?;
+        let delimiter = terminated(till_line_ending, ascii::line_ending).parse_next(i)?;
+
+        let headers = split_table_row(header)
+            .iter()
+            .map(|cell| format!("{}{}", style::SetAttribute(Attribute::Bold), render_table_cell(cell)))
+            .collect::<Vec<_>>();
+        let alignments = match parse_table_delimiter(delimiter) {
+            Some(alignments) if alignments.len() == headers.len() => alignments,
+            _ => return Err(ErrMode::from_error_kind(i, ErrorKind::Fail)),
+        };
+
+        let layout = TableLayout::new(
+            &headers,
+            alignments,
+            state.terminal_width.unwrap_or(DEFAULT_TABLE_WIDTH),
+            header.trim_start().starts_with('|'),
+        );
+        let rule = layout
+            .widths
+            .iter()
+            .map(|width| "─".repeat(*width))
+            .collect::<Vec<_>>()
+            .join("─┼─");
+
+        queue_table_row(&mut o, &layout, &headers)?;
+        queue(&mut o, style::SetForegroundColor(TABLE_BORDER_COLOR))?;
+        queue(&mut o, style::Print(format!("{rule}\n")))?;
+        queue(&mut o, style::ResetColor)?;
+
+        state.table = Some(layout);
+        state.column = 0;
+        state.set_newline = true;
+
+        Ok(())
+    }
+}
+
+fn table_row<'a, 'b>(
+    mut o: impl Write + 'b,
+    state: &'b mut ParseState,
+) -> impl FnMut(&mut Partial<&'a str>) -> PResult<(), Error<'a>> + 'b {
+    move |i| {
+        let outer_pipes = match &state.table {
+            Some(layout) if state.newline => layout.outer_pipes,
+            _ => return Err(ErrMode::from_error_kind(i, ErrorKind::Fail)),
+        };
+
+        let row = match table_row_line(i, outer_pipes) {
+            Ok(row) => row,
+            Err(ErrMode::Backtrack(err)) => {
+                // Any line that isn't a row terminates the table.
+                state.table = None;
+                return Err(ErrMode::Backtrack(err));
+            },
+            Err(err) => return Err(err),
+        };
+
+        if let Some(layout) = &state.table {
+            let cells = split_table_row(row)
+                .iter()
+                .map(|cell| render_table_cell(cell))
+                .collect::<Vec<_>>();
+            queue_table_row(&mut o, layout, &cells)?;
+        }
+
+        state.column = 0;
+        state.set_newline = true;
+
+        Ok(())
+    }
+}
+
+/// Parses a complete line that is a row of the current table.
+fn table_row_line<'a>(i: &mut Partial<&'a str>, outer_pipes: bool) -> PResult<&'a str, Error<'a>> {
+    if outer_pipes {
+        // Tables with outer pipes end as soon as a line doesn't start with one, the others have to
+        // wait for the whole line.
+        let start = i.checkpoint();
+        (space0, "|").parse_next(i)?;
+        i.reset(&start);
+    }
+
+    terminated(till_line_ending, ascii::line_ending)
+        .verify(|row: &str| row.contains('|'))
+        .parse_next(i)
+}
+
+/// Splits a table row into its trimmed cells, leaving their inline markdown as is.
+fn split_table_row(row: &str) -> Vec<String> {
+    let row = row.trim();
+    let row = row.strip_prefix('|').unwrap_or(row);
+    let row = match row.strip_suffix('|') {
+        Some(stripped) if !stripped.ends_with('\\') => stripped,
+        _ => row,
+    };
+
+    let mut cells = vec![String::new()];
+    let mut chars = row.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&'|') => {
+                cells.last_mut().unwrap().push('|');
+                chars.next();
+            },
+            '|' => cells.push(String::new()),
+            c => cells.last_mut().unwrap().push(c),
+        }
+    }
+
+    cells.iter().map(|cell| cell.trim().to_string()).collect()
+}
+
+/// Renders the inline markdown of a table cell, eg code, bold and links, with the same parsers as
+/// the rest of the response.
+fn render_table_cell(cell: &str) -> String {
+    // Block level patterns only apply at the start of a line, which a cell never is. The leading
+    // space lets emphasis open at the start of the cell, and is dropped when the cell is wrapped.
+    let mut state = ParseState::new(None);
+    state.newline = false;
+
+    let cell = format!(" {cell}");
+    let mut input = Partial::new(cell.as_str());
+    let _ = input.complete();
+
+    let mut output = vec![];
+    while !input.is_empty() {
+        match interpret_markdown(input, &mut output, &mut state) {
+            Ok(rest) => {
+                input = rest;
+                state.newline = false;
+            },
+            Err(_) => break,
+        }
+    }
+
+    String::from_utf8_lossy(&output).into_owned()
+}
+
+/// Width of `text` in terminal cells, ignoring escape sequences.
+fn visible_width(text: &str) -> usize {
+    strip_ansi_escapes::strip_str(text).width()
+}
+
+/// Parses a delimiter row such as `|:---|:---:|---:|` into the alignment of each column.
+fn parse_table_delimiter(row: &str) -> Option<Vec<ColumnAlignment>> {
+    split_table_row(row)
+        .iter()
+        .map(|cell| {
+            let left = cell.starts_with(':');
+            let right = cell.ends_with(':');
+            let dashes = cell.trim_start_matches(':').trim_end_matches(':');
+            if dashes.is_empty() || !dashes.chars().all(|c| c == '-') {
+                return None;
+            }
+            Some(match (left, right) {
+                (true, true) => ColumnAlignment::Center,
+                (false, true) => ColumnAlignment::Right,
+                _ => ColumnAlignment::Left,
+            })
+        })
+        .collect()
+}
+
+/// Word wraps `text` into lines no wider than `width` terminal cells.
+///
+/// `text` may contain the escape sequences of inline styles. Every line ends with a reset so that
+/// styles don't leak into the padding, and continuation lines replay the escape sequences before
+/// them to restore the style.
+fn wrap_table_cell(text: &str, width: usize) -> Vec<String> {
+    let mut lines = vec![String::new()];
+    let mut line_width = 0;
+    let mut escapes = String::new();
+    for word in text.split_whitespace() {
+        let word_width = visible_width(word);
+        if line_width > 0 && word_width > 0 {
+            if line_width + 1 + word_width <= width {
+                lines.last_mut().unwrap().push(' ');
+                line_width += 1;
+            } else {
+                lines.push(escapes.clone());
+                line_width = 0;
+            }
+        }
+
+        // Words that are wider than the column have to be broken up.
+        let mut rest = word;
+        while let Some(c) = rest.chars().next() {
+            if c == '\x1b' {
+                // Inline styles are CSI sequences, which end with a byte in `@`..=`~`.
+                let len = rest
+                    .get(2..)
+                    .and_then(|params| params.find(|c: char| ('@'..='~').contains(&c)))
+                    .map_or(rest.len(), |end| end + 3);
+                lines.last_mut().unwrap().push_str(&rest[..len]);
+                escapes.push_str(&rest[..len]);
+                rest = &rest[len..];
+                continue;
+            }
+
+            let c_width = c.width().unwrap_or(0);
+            if line_width > 0 && line_width + c_width > width {
+                lines.push(escapes.clone());
+                line_width = 0;
+            }
+            lines.last_mut().unwrap().push(c);
+            line_width += c_width;
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+
+    if !escapes.is_empty() {
+        let reset = style::SetAttribute(Attribute::Reset).to_string();
+        for line in &mut lines {
+            line.push_str(&reset);
+        }
+    }
+    lines
+}
+
+fn queue_table_row<'a>(o: &mut impl Write, layout: &TableLayout, cells: &[String]) -> Result<(), ErrMode<Error<'a>>> {
+    let wrapped = layout
+        .widths
+        .iter()
+        .enumerate()
+        .map(|(idx, width)| wrap_table_cell(cells.get(idx).map_or("", |c| c.as_str()), *width))
+        .collect::<Vec<_>>();
+    let height = wrapped.iter().map(|lines| lines.len()).max().unwrap_or(1);
+
+    for line_idx in 0..height {
+        let mut line = String::new();
+        let columns = wrapped.iter().zip(&layout.widths).zip(&layout.alignments);
+        for (col, ((lines, width), alignment)) in columns.enumerate() {
+            if col > 0 {
+                line.push_str(TABLE_COLUMN_SEPARATOR);
+            }
+            let content = lines.get(line_idx).map_or("", |l| l.as_str());
+            let padding = width.saturating_sub(visible_width(content));
+            let (left, right) = match alignment {
+                ColumnAlignment::Left => (0, padding),
+                ColumnAlignment::Center => (padding / 2, padding - padding / 2),
+                ColumnAlignment::Right => (padding, 0),
+            };
+            line.push_str(&format!("{}{content}{}", " ".repeat(left), " ".repeat(right)));
+        }
+        queue(o, style::Print(format!("{}\n", line.trim_end())))?;
+    }
+
+    Ok(())
+}
+
 fn code<'a, 'b>(
     mut o: impl Write + 'b,
     state: &'b mut ParseState,
@@ -560,7 +941,14 @@ fn codeblock_begin<'a, 'b>(
             queue(&mut o, style::Print(format!("{}\n", language).bold()))?;
         }
 
-        queue(&mut o, style::SetForegroundColor(CODE_COLOR))?;
+        state.highlighter = match (state.syntax_highlighting, language.split_whitespace().next()) {
+            (true, Some(language)) => CodeHighlighter::new(language),
+            _ => None,
+        };
+
+        if state.highlighter.is_none() {
+            queue(&mut o, style::SetForegroundColor(CODE_COLOR))?;
+        }
 
         Ok(())
     }
@@ -573,10 +961,48 @@ fn codeblock_end<'a, 'b>(
     move |i| {
         "```".parse_next(i)?;
         state.in_codeblock = false;
+        state.highlighter = None;
         queue(&mut o, style::ResetColor)
     }
 }
 
+fn codeblock_highlighted_line<'a, 'b>(
+    mut o: impl Write + 'b,
+    state: &'b mut ParseState,
+) -> impl FnMut(&mut Partial<&'a str>) -> PResult<(), Error<'a>> + 'b {
+    move |i| {
+        let Some(highlighter) = state.highlighter.as_mut() else {
+            return Err(ErrMode::from_error_kind(i, ErrorKind::Fail));
+        };
+
+        // Highlighting is line based, so we wait for the whole line. The closing fence may come
+        // at the end of a line, in which case only the code before it is consumed.
+        let start = i.checkpoint();
+        let line = till_line_ending.parse_next(i)?;
+        let (code, line_ending) = match line.find("```") {
+            Some(end) => {
+                i.reset(&start);
+                (take(line[..end].chars().count()).parse_next(i)?, "")
+            },
+            None => (line, ascii::line_ending.parse_next(i)?),
+        };
+
+        let code = format!(
+            "{}{}",
+            code.replace("&lt;", "<")
+                .replace("&gt;", ">")
+                .replace("&quot;", "\"")
+                .replace("&amp;", "&"),
+            if line_ending.is_empty() { "" } else { "\n" }
+        );
+
+        match highlighter.highlight(&code) {
+            Some(highlighted) => queue(&mut o, style::Print(highlighted)),
+            None => queue(&mut o, style::Print(code)),
+        }
+    }
+}
+
 fn codeblock_less_than<'a, 'b>(
     mut o: impl Write + 'b,
     _state: &'b mut ParseState,
@@ -759,4 +1185,167 @@ mod tests {
     validate!(square_bracket_url_like_2, "[text](without url part", [style::Print(
         "[text](without url part"
     )]);
+    validate!(task_item_1, "- [ ] todo", [style::Print("☐ todo")]);
+    validate!(task_item_2, "- [x] done", [style::Print("☑ done")]);
+    validate!(task_item_3, "* [X] done", [style::Print("☑ done")]);
+    validate!(task_item_link, "- [link](url)", [
+        style::Print("• "),
+        style::SetForegroundColor(URL_TEXT_COLOR),
+        style::Print("link "),
+        style::SetForegroundColor(URL_LINK_COLOR),
+        style::Print("url"),
+        style::ResetColor,
+    ]);
+    validate!(table_1, "| a | b |\n|---|:-:|\n| 1 | `2` |\ndone", [
+        style::SetAttribute(Attribute::Bold),
+        style::Print("a"),
+        style::SetAttribute(Attribute::Reset),
+        style::Print(format!("{} │ {}", " ".repeat(37), " ".repeat(18))),
+        style::SetAttribute(Attribute::Bold),
+        style::Print("b"),
+        style::SetAttribute(Attribute::Reset),
+        style::Print("\n"),
+        style::SetForegroundColor(TABLE_BORDER_COLOR),
+        style::Print(format!("{}─┼─{}\n", "─".repeat(38), "─".repeat(38))),
+        style::ResetColor,
+        style::Print(format!("1{} │ {}", " ".repeat(37), " ".repeat(18))),
+        style::SetForegroundColor(CODE_COLOR),
+        style::Print("2"),
+        style::ResetColor,
+        style::SetAttribute(Attribute::Reset),
+        style::Print("\ndone"),
+    ]);
+    validate!(table_without_outer_pipes, "a | b\n--- | ---\n1 | **2**\n\ndone", [
+        style::SetAttribute(Attribute::Bold),
+        style::Print("a"),
+        style::SetAttribute(Attribute::Reset),
+        style::Print(format!("{} │ ", " ".repeat(37))),
+        style::SetAttribute(Attribute::Bold),
+        style::Print("b"),
+        style::SetAttribute(Attribute::Reset),
+        style::Print("\n"),
+        style::SetForegroundColor(TABLE_BORDER_COLOR),
+        style::Print(format!("{}─┼─{}\n", "─".repeat(38), "─".repeat(38))),
+        style::ResetColor,
+        style::Print(format!("1{} │ ", " ".repeat(37))),
+        style::SetAttribute(Attribute::Bold),
+        style::Print("2"),
+        style::SetAttribute(Attribute::NormalIntensity),
+        style::SetAttribute(Attribute::Reset),
+        style::Print("\n"),
+        style::ResetColor,
+        style::SetAttribute(Attribute::Reset),
+        style::Print("\ndone"),
+    ]);
+    validate!(table_not_a_table, "| a | b |\nnot a delimiter\nok", [
+        style::Print("| a | b |"),
+        style::ResetColor,
+        style::SetAttribute(Attribute::Reset),
+        style::Print("\nnot a delimiter"),
+        style::ResetColor,
+        style::SetAttribute(Attribute::Reset),
+        style::Print("\nok"),
+    ]);
+
+    #[test]
+    fn test_table_only_waits_for_likely_tables() {
+        let mut state = ParseState::new(Some(80));
+        let mut parse = |input| table(vec![], &mut state).parse_next(&mut Partial::new(input));
+        assert!(matches!(parse("a | b\n--"), Err(ErrMode::Backtrack(_))));
+        assert!(matches!(parse("| a | b |\n"), Err(ErrMode::Incomplete(_))));
+        assert!(matches!(parse("a | b\n--- | ---\n"), Ok(())));
+    }
+
+    #[test]
+    fn test_table_layout_fits_terminal_width() {
+        let headers = vec!["name".to_string(), "a much longer description".to_string()];
+        let alignments = vec![ColumnAlignment::Left, ColumnAlignment::Right];
+
+        let layout = TableLayout::new(&headers, alignments.clone(), 200, true);
+        assert_eq!(layout.widths, vec![MAX_TABLE_COLUMN_WIDTH, MAX_TABLE_COLUMN_WIDTH]);
+
+        let layout = TableLayout::new(&headers, alignments, 23, true);
+        assert_eq!(layout.widths, vec![10, 10]);
+    }
+
+    #[test]
+    fn test_split_table_row() {
+        assert_eq!(split_table_row("| a | b\\|c |"), vec!["a", "b|c"]);
+        assert_eq!(split_table_row("**a** | &lt;b&gt;"), vec!["**a**", "&lt;b&gt;"]);
+        assert_eq!(split_table_row("| | b |"), vec!["", "b"]);
+        assert_eq!(
+            parse_table_delimiter(":-- | --:"),
+            Some(vec![ColumnAlignment::Left, ColumnAlignment::Right])
+        );
+        assert_eq!(
+            parse_table_delimiter("|:--|:-:|--:|"),
+            Some(vec![
+                ColumnAlignment::Left,
+                ColumnAlignment::Center,
+                ColumnAlignment::Right
+            ])
+        );
+        assert_eq!(parse_table_delimiter("| a | b |"), None);
+    }
+
+    #[test]
+    fn test_wrap_table_cell() {
+        assert_eq!(wrap_table_cell("hello world foo", 11), vec!["hello world", "foo"]);
+        assert_eq!(wrap_table_cell("abcdefgh", 3), vec!["abc", "def", "gh"]);
+        assert_eq!(wrap_table_cell("", 3), vec![""]);
+
+        let bold = style::SetAttribute(Attribute::Bold).to_string();
+        let reset = style::SetAttribute(Attribute::Reset).to_string();
+        assert_eq!(wrap_table_cell(&format!("{bold}hello world"), 5), vec![
+            format!("{bold}hello{reset}"),
+            format!("{bold}world{reset}"),
+        ]);
+    }
+
+    #[test]
+    fn test_render_table_cell() {
+        let plain = |cell: &str| {
+            strip_ansi_escapes::strip_str(render_table_cell(cell))
+                .trim()
+                .to_string()
+        };
+        assert_eq!(plain("**a** `b` [c](d)"), "a b c d");
+        assert_eq!(plain("&lt;b&gt;"), "<b>");
+        assert_eq!(plain("- not a list"), "- not a list");
+        assert!(render_table_cell("`code`").contains(&style::SetForegroundColor(CODE_COLOR).to_string()));
+    }
+
+    #[test]
+    fn test_highlighted_codeblock() {
+        let input = "```rust\nlet x = &lt;1&gt;;\nfn main() {}```\n";
+        let mut state = ParseState::new(Some(80));
+        state.syntax_highlighting = true;
+        let mut output = vec![];
+        let mut offset = 0;
+
+        loop {
+            let partial = Partial::new(&input[offset..]);
+            match interpret_markdown(partial, &mut output, &mut state) {
+                Ok(parsed) => {
+                    offset += parsed.offset_from(&partial);
+                    state.newline = state.set_newline;
+                    state.set_newline = false;
+                },
+                Err(err) => match err.into_inner() {
+                    Some(err) => panic!("{err}"),
+                    None => break,
+                },
+            }
+        }
+
+        assert!(!state.in_codeblock);
+        assert!(state.highlighter.is_none());
+        let output = String::from_utf8(output).unwrap();
+        assert!(
+            output.contains("\x1b[38;2;"),
+            "code should be highlighted with 24bit color"
+        );
+        let plain = strip_ansi_escapes::strip_str(&output);
+        assert_eq!(plain, "rust\nlet x = <1>;\nfn main() {}\n");
+    }
 }
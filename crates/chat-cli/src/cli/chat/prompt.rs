@@ -1,7 +1,18 @@
 use std::borrow::Cow;
+use std::path::{
+    Path,
+    PathBuf,
+};
+use std::sync::{
+    Arc,
+    Mutex,
+};
 
 use crossterm::style::Stylize;
 use eyre::Result;
+use fuzzy_matcher::FuzzyMatcher;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use ignore::WalkBuilder;
 use rustyline::completion::{
     Completer,
     FilenameCompleter,
@@ -22,19 +33,35 @@ use rustyline::{
     Cmd,
     Completer,
     CompletionType,
+    ConditionalEventHandler,
     Config,
     Context,
     EditMode,
     Editor,
+    EventContext,
     EventHandler,
     Helper,
     Hinter,
     KeyCode,
     KeyEvent,
     Modifiers,
+    RepeatCount,
+};
+use tracing::{
+    debug,
+    warn,
 };
 use winnow::stream::AsChar;
 
+use super::consts::MAX_NUMBER_OF_IMAGES_PER_REQUEST;
+#[cfg(unix)]
+use super::skim_integration::SkimHistorySearch;
+use super::util::clipboard::read_clipboard_image;
+use super::util::images::{
+    RichImageBlocks,
+    pasted_image_block,
+    pasted_image_placeholder,
+};
 use crate::database::Database;
 use crate::database::settings::Setting;
 
@@ -76,6 +103,17 @@ pub const COMMANDS: &[&str] = &[
     "/compact",
     "/compact help",
     "/usage",
+    "/todos",
+    "/todos help",
+    "/todos add",
+    "/todos done",
+    "/todos undo",
+    "/todos rm",
+    "/todos clear",
+    "/model",
+    "/model help",
+    "/model list",
+    "/model set",
     "/save",
     "/load",
 ];
@@ -90,18 +128,32 @@ pub fn generate_prompt(current_profile: Option<&str>, warning: bool) -> String {
     format!("{profile_part}{warning_symbol}{}", "> ".magenta())
 }
 
-/// Complete commands that start with a slash
-fn complete_command(word: &str, start: usize) -> (usize, Vec<String>) {
+/// Complete commands that start with a slash, including custom commands
+fn complete_command(word: &str, start: usize, custom_commands: &[String]) -> (usize, Vec<String>) {
     (
         start,
         COMMANDS
             .iter()
+            .copied()
+            .chain(custom_commands.iter().map(String::as_str))
             .filter(|p| p.starts_with(word))
-            .map(|s| (*s).to_owned())
+            .map(|s| s.to_owned())
             .collect(),
     )
 }
 
+/// Number of prompts kept in the history of each directory.
+const MAX_HISTORY_ENTRIES: usize = 1000;
+
+/// Maximum number of paths walked when completing an `@` mention.
+const MAX_MENTION_PATHS: usize = 20_000;
+
+/// Maximum number of completions offered for an `@` mention.
+const MAX_MENTION_COMPLETIONS: usize = 50;
+
+/// Prompts entered in the current directory, oldest first, shared with the Ctrl+R history search.
+pub type PromptHistory = Arc<Mutex<Vec<String>>>;
+
 /// A wrapper around FilenameCompleter that provides enhanced path detection
 /// and completion capabilities for the chat interface.
 pub struct PathCompleter {
@@ -136,6 +188,38 @@ impl PathCompleter {
             Err(err) => Err(err),
         }
     }
+
+    /// Completes an `@` mention by fuzzy matching `query` against the files and directories under
+    /// `root`, skipping ignored files. Directories are completed with a trailing slash.
+    pub fn complete_mention(root: &Path, query: &str) -> Vec<String> {
+        let matcher = SkimMatcherV2::default().ignore_case();
+        let mut matches = WalkBuilder::new(root)
+            .require_git(false)
+            .build()
+            .filter_map(|entry| entry.map_err(|err| debug!(?err, "Skipping path")).ok())
+            .filter(|entry| entry.depth() > 0)
+            .take(MAX_MENTION_PATHS)
+            .filter_map(|entry| {
+                let relative = entry
+                    .path()
+                    .strip_prefix(root)
+                    .ok()?
+                    .to_string_lossy()
+                    .replace('\\', "/");
+                let path = match entry.file_type().is_some_and(|file_type| file_type.is_dir()) {
+                    true => format!("{relative}/"),
+                    false => relative,
+                };
+                matcher.fuzzy_match(&path, query).map(|score| (score, path))
+            })
+            .collect::<Vec<_>>();
+        matches.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+        matches
+            .into_iter()
+            .take(MAX_MENTION_COMPLETIONS)
+            .map(|(_, path)| format!("@{path}"))
+            .collect()
+    }
 }
 
 pub struct PromptCompleter {
@@ -168,6 +252,7 @@ impl PromptCompleter {
 pub struct ChatCompleter {
     path_completer: PathCompleter,
     prompt_completer: PromptCompleter,
+    custom_commands: Vec<String>,
 }
 
 impl ChatCompleter {
@@ -175,6 +260,7 @@ impl ChatCompleter {
         Self {
             path_completer: PathCompleter::new(),
             prompt_completer: PromptCompleter::new(sender, receiver),
+            custom_commands: Vec::new(),
         }
     }
 }
@@ -192,7 +278,7 @@ impl Completer for ChatCompleter {
 
         // Handle command completion
         if word.starts_with('/') {
-            return Ok(complete_command(word, start));
+            return Ok(complete_command(word, start, &self.custom_commands));
         }
 
         if line.starts_with('@') {
@@ -204,6 +290,16 @@ impl Completer for ChatCompleter {
             }
         }
 
+        // Handle `@` file and directory mentions
+        if let Some(query) = word.strip_prefix('@') {
+            if let Ok(cwd) = std::env::current_dir() {
+                let completions = PathCompleter::complete_mention(&cwd, query);
+                if !completions.is_empty() {
+                    return Ok((start, completions));
+                }
+            }
+        }
+
         // Handle file path completion as fallback
         if let Ok((pos, completions)) = self.path_completer.complete_path(line, pos, _ctx) {
             if !completions.is_empty() {
@@ -237,6 +333,45 @@ impl Validator for MultiLineValidator {
     }
 }
 
+/// Images pasted into the prompt that is currently being edited.
+pub type PastedImages = Arc<Mutex<RichImageBlocks>>;
+
+/// Pastes an image from the clipboard into the prompt, shown as an `[image #N]` placeholder.
+///
+/// Falls back to the default binding if the clipboard does not contain an image.
+pub struct PasteImageHandler {
+    pasted_images: PastedImages,
+}
+
+impl ConditionalEventHandler for PasteImageHandler {
+    fn handle(
+        &self,
+        _evt: &rustyline::Event,
+        _n: RepeatCount,
+        _positive: bool,
+        _ctx: &EventContext<'_>,
+    ) -> Option<Cmd> {
+        let bytes = read_clipboard_image()?;
+        let mut pasted_images = self.pasted_images.lock().ok()?;
+        if pasted_images.len() >= MAX_NUMBER_OF_IMAGES_PER_REQUEST {
+            warn!("unable to paste more than {MAX_NUMBER_OF_IMAGES_PER_REQUEST} images");
+            return Some(Cmd::Noop);
+        }
+
+        match pasted_image_block(bytes, pasted_images.len() + 1) {
+            Some(image) => {
+                let placeholder = pasted_image_placeholder(&image.1);
+                pasted_images.push(image);
+                Some(Cmd::Insert(1, placeholder))
+            },
+            None => {
+                warn!("clipboard image is unsupported or too large");
+                Some(Cmd::Noop)
+            },
+        }
+    }
+}
+
 #[derive(Helper, Completer, Hinter)]
 pub struct ChatHelper {
     #[rustyline(Completer)]
@@ -244,6 +379,16 @@ pub struct ChatHelper {
     #[rustyline(Hinter)]
     hinter: (),
     validator: MultiLineValidator,
+    pub pasted_images: PastedImages,
+    /// The file the prompt history of the current directory is persisted to.
+    pub history_path: Option<PathBuf>,
+    pub history: PromptHistory,
+}
+
+impl ChatHelper {
+    pub fn set_custom_commands(&mut self, custom_commands: Vec<String>) {
+        self.completer.custom_commands = custom_commands;
+    }
 }
 
 impl Validator for ChatHelper {
@@ -270,6 +415,7 @@ pub fn rl(
     database: &Database,
     sender: std::sync::mpsc::Sender<Option<String>>,
     receiver: std::sync::mpsc::Receiver<Vec<String>>,
+    history_path: Option<PathBuf>,
 ) -> Result<Editor<ChatHelper, DefaultHistory>> {
     let edit_mode = match database.settings.get_string(Setting::ChatEditMode).as_deref() {
         Some("vi" | "vim") => EditMode::Vi,
@@ -277,15 +423,29 @@ pub fn rl(
     };
     let config = Config::builder()
         .history_ignore_space(true)
+        .history_ignore_dups(true)?
+        .max_history_size(MAX_HISTORY_ENTRIES)?
         .completion_type(CompletionType::List)
         .edit_mode(edit_mode)
         .build();
+    let mut rl = Editor::with_config(config)?;
+
+    if let Some(path) = history_path.as_ref().filter(|path| path.exists()) {
+        if let Err(err) = rl.load_history(path) {
+            warn!(?err, ?path, "failed to load the prompt history");
+        }
+    }
+    let history = PromptHistory::new(Mutex::new(rl.history().iter().cloned().collect()));
+
     let h = ChatHelper {
         completer: ChatCompleter::new(sender, receiver),
         hinter: (),
         validator: MultiLineValidator,
+        pasted_images: PastedImages::default(),
+        history_path,
+        history: Arc::clone(&history),
     };
-    let mut rl = Editor::with_config(config)?;
+    let pasted_images = Arc::clone(&h.pasted_images);
     rl.set_helper(Some(h));
 
     // Add custom keybinding for Alt+Enter to insert a newline
@@ -294,12 +454,31 @@ pub fn rl(
         EventHandler::Simple(Cmd::Insert(1, "\n".to_string())),
     );
 
+    // Add custom keybinding for Shift+Enter to insert a newline, for terminals that report it
+    rl.bind_sequence(
+        KeyEvent(KeyCode::Enter, Modifiers::SHIFT),
+        EventHandler::Simple(Cmd::Insert(1, "\n".to_string())),
+    );
+
     // Add custom keybinding for Ctrl+J to insert a newline
     rl.bind_sequence(
         KeyEvent(KeyCode::Char('j'), Modifiers::CTRL),
         EventHandler::Simple(Cmd::Insert(1, "\n".to_string())),
     );
 
+    // Add custom keybinding for Ctrl+V to paste an image from the clipboard
+    rl.bind_sequence(
+        KeyEvent(KeyCode::Char('v'), Modifiers::CTRL),
+        EventHandler::Conditional(Box::new(PasteImageHandler { pasted_images })),
+    );
+
+    // Add custom keybinding for Ctrl+R to fuzzy search the prompt history
+    #[cfg(unix)]
+    rl.bind_sequence(
+        KeyEvent(KeyCode::Char('r'), Modifiers::CTRL),
+        EventHandler::Conditional(Box::new(SkimHistorySearch::new(history))),
+    );
+
     Ok(rl)
 }
 
@@ -349,6 +528,38 @@ mod tests {
         assert!(completions.contains(&"/help".to_string()));
     }
 
+    #[test]
+    fn test_chat_completer_custom_command_completion() {
+        let (prompt_request_sender, _) = std::sync::mpsc::channel::<Option<String>>();
+        let (_, prompt_response_receiver) = std::sync::mpsc::channel::<Vec<String>>();
+        let mut completer = ChatCompleter::new(prompt_request_sender, prompt_response_receiver);
+        completer.custom_commands = vec!["/review".to_string(), "/deploy".to_string()];
+
+        let empty_history = DefaultHistory::new();
+        let ctx = Context::new(&empty_history);
+        let (start, completions) = completer.complete("/re", 3, &ctx).unwrap();
+
+        assert_eq!(start, 0);
+        assert_eq!(completions, vec!["/review".to_string()]);
+    }
+
+    #[test]
+    fn test_complete_mention() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("src/cli")).unwrap();
+        std::fs::write(dir.path().join("src/cli/mod.rs"), "").unwrap();
+        std::fs::write(dir.path().join("src/main.rs"), "").unwrap();
+        std::fs::write(dir.path().join("README.md"), "").unwrap();
+
+        let completions = PathCompleter::complete_mention(dir.path(), "climod");
+        assert_eq!(completions, vec!["@src/cli/mod.rs".to_string()]);
+
+        let completions = PathCompleter::complete_mention(dir.path(), "src");
+        assert_eq!(completions[0], "@src/");
+        assert!(completions.contains(&"@src/main.rs".to_string()));
+        assert!(!completions.contains(&"@README.md".to_string()));
+    }
+
     #[test]
     fn test_chat_completer_no_completion() {
         let (prompt_request_sender, _) = std::sync::mpsc::channel::<Option<String>>();
@@ -18,12 +18,14 @@ use rustyline::{
     Cmd,
     ConditionalEventHandler,
     EventContext,
+    Movement,
     RepeatCount,
 };
 use skim::prelude::*;
 use tempfile::NamedTempFile;
 
 use super::context::ContextManager;
+use super::prompt::PromptHistory;
 
 pub fn select_profile_with_skim(context_manager: &ContextManager) -> Result<Option<String>> {
     let profiles = context_manager.list_profiles_blocking()?;
@@ -35,14 +37,16 @@ pub fn select_profile_with_skim(context_manager: &ContextManager) -> Result<Opti
 pub struct SkimCommandSelector {
     context_manager: Arc<ContextManager>,
     tool_names: Vec<String>,
+    custom_commands: Vec<String>,
 }
 
 impl SkimCommandSelector {
     /// This allows the ConditionalEventHandler handle function to be bound to a KeyEvent.
-    pub fn new(context_manager: Arc<ContextManager>, tool_names: Vec<String>) -> Self {
+    pub fn new(context_manager: Arc<ContextManager>, tool_names: Vec<String>, custom_commands: Vec<String>) -> Self {
         Self {
             context_manager,
             tool_names,
+            custom_commands,
         }
     }
 }
@@ -56,7 +60,7 @@ impl ConditionalEventHandler for SkimCommandSelector {
         _ctx: &EventContext<'_>,
     ) -> Option<Cmd> {
         // Launch skim command selector with the context manager if available
-        match select_command(self.context_manager.as_ref(), &self.tool_names) {
+        match select_command(self.context_manager.as_ref(), &self.tool_names, &self.custom_commands) {
             Ok(Some(command)) => Some(Cmd::Insert(1, command)),
             _ => {
                 // If cancelled or error, do nothing
@@ -66,7 +70,60 @@ impl ConditionalEventHandler for SkimCommandSelector {
     }
 }
 
-pub fn get_available_commands() -> Vec<String> {
+/// Fuzzy searches the prompt history, replacing the current line with the selected prompt.
+pub struct SkimHistorySearch {
+    history: PromptHistory,
+}
+
+impl SkimHistorySearch {
+    pub fn new(history: PromptHistory) -> Self {
+        Self { history }
+    }
+}
+
+impl ConditionalEventHandler for SkimHistorySearch {
+    fn handle(
+        &self,
+        _evt: &rustyline::Event,
+        _n: RepeatCount,
+        _positive: bool,
+        _ctx: &EventContext<'_>,
+    ) -> Option<Cmd> {
+        let history = self.history.lock().ok()?.clone();
+        match select_history_entry(&history) {
+            Ok(Some(entry)) => Some(Cmd::Replace(Movement::WholeBuffer, Some(entry))),
+            _ => Some(Cmd::Noop),
+        }
+    }
+}
+
+/// Multi-line prompts are shown on a single line, with newlines replaced by this symbol.
+const NEWLINE_SYMBOL: &str = " ⏎ ";
+
+/// Launch skim over `history`, most recent prompts first, and return the selected prompt
+pub fn select_history_entry(history: &[String]) -> Result<Option<String>> {
+    let mut entries = Vec::new();
+    for entry in history.iter().rev() {
+        if !entries.contains(entry) {
+            entries.push(entry.clone());
+        }
+    }
+    if entries.is_empty() {
+        return Ok(None);
+    }
+
+    let lines = entries
+        .iter()
+        .map(|entry| entry.replace('\n', NEWLINE_SYMBOL))
+        .collect::<Vec<_>>();
+    let selected = launch_skim_selector(&lines, "Search history: ", false)?
+        .and_then(|selections| selections.into_iter().next())
+        .and_then(|selected| lines.iter().position(|line| *line == selected))
+        .map(|index| entries[index].clone());
+    Ok(selected)
+}
+
+pub fn get_available_commands(custom_commands: &[String]) -> Vec<String> {
     // Import the COMMANDS array directly from prompt.rs
     // This is the single source of truth for available commands
     let commands_array = super::prompt::COMMANDS;
@@ -75,6 +132,7 @@ pub fn get_available_commands() -> Vec<String> {
     for &cmd in commands_array {
         commands.push(cmd.to_string());
     }
+    commands.extend_from_slice(custom_commands);
 
     commands
 }
@@ -229,8 +287,12 @@ pub fn select_context_paths_with_skim(context_manager: &ContextManager) -> Resul
 }
 
 /// Launch the command selector and handle the selected command
-pub fn select_command(context_manager: &ContextManager, tools: &[String]) -> Result<Option<String>> {
-    let commands = get_available_commands();
+pub fn select_command(
+    context_manager: &ContextManager,
+    tools: &[String],
+    custom_commands: &[String],
+) -> Result<Option<String>> {
+    let commands = get_available_commands(custom_commands);
 
     match launch_skim_selector(&commands, "Select command: ", false)? {
         Some(selections) if !selections.is_empty() => {
@@ -352,7 +414,7 @@ mod tests {
     #[test]
     fn test_hardcoded_commands_in_commands_array() {
         // Get the set of available commands from prompt.rs
-        let available_commands: HashSet<String> = get_available_commands().iter().cloned().collect();
+        let available_commands: HashSet<String> = get_available_commands(&[]).iter().cloned().collect();
 
         // List of hardcoded commands used in select_command
         let hardcoded_commands = vec![
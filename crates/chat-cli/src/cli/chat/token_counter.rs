@@ -119,6 +119,7 @@ impl CharCounter for UserMessage {
     fn char_count(&self) -> CharCount {
         let mut total_chars = 0;
         total_chars += self.additional_context().len();
+        total_chars += self.attachments().len();
         match self.content() {
             UserMessageContent::Prompt { prompt } => {
                 total_chars += prompt.len();
@@ -69,12 +69,15 @@ use crate::cli::chat::tools::custom_tool::{
     CustomToolClient,
     CustomToolConfig,
 };
+use crate::cli::chat::tools::delegate_task::DelegateTask;
 use crate::cli::chat::tools::execute_bash::ExecuteBash;
 use crate::cli::chat::tools::fs_read::FsRead;
 use crate::cli::chat::tools::fs_write::FsWrite;
 use crate::cli::chat::tools::gh_issue::GhIssue;
 use crate::cli::chat::tools::thinking::Thinking;
+use crate::cli::chat::tools::todo_list::TodoList;
 use crate::cli::chat::tools::use_aws::UseAws;
+use crate::cli::chat::tools::web_fetch::WebFetch;
 use crate::cli::chat::tools::{
     Tool,
     ToolOrigin,
@@ -829,6 +832,9 @@ impl ToolManager {
             "use_aws" => Tool::UseAws(serde_json::from_value::<UseAws>(value.args).map_err(map_err)?),
             "report_issue" => Tool::GhIssue(serde_json::from_value::<GhIssue>(value.args).map_err(map_err)?),
             "thinking" => Tool::Thinking(serde_json::from_value::<Thinking>(value.args).map_err(map_err)?),
+            "delegate_task" => Tool::DelegateTask(serde_json::from_value::<DelegateTask>(value.args).map_err(map_err)?),
+            "web_fetch" => Tool::WebFetch(serde_json::from_value::<WebFetch>(value.args).map_err(map_err)?),
+            "todo_list" => Tool::TodoList(serde_json::from_value::<TodoList>(value.args).map_err(map_err)?),
             // Note that this name is namespaced with server_name{DELIMITER}tool_name
             name => {
                 // Note: tn_map also has tools that underwent no transformation. In otherwords, if
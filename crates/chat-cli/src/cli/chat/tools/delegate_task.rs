@@ -0,0 +1,574 @@
+use std::collections::HashMap;
+use std::io::Write;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+
+use crossterm::style::{
+    Color,
+    Stylize,
+};
+use crossterm::{
+    queue,
+    style,
+};
+use eyre::{
+    Result,
+    bail,
+    eyre,
+};
+use rand::distr::{
+    Alphanumeric,
+    SampleString,
+};
+use serde::Deserialize;
+use tracing::{
+    debug,
+    info,
+};
+
+use super::super::consts::DUMMY_TOOL_NAME;
+use super::super::context::ContextManager;
+use super::super::conversation_state::ConversationState;
+use super::super::hooks::{
+    HookEvent,
+    HookOutcome,
+};
+use super::super::message::{
+    AssistantToolUse,
+    ToolUseResult,
+    ToolUseResultBlock,
+};
+use super::super::models::ModelInfo;
+use super::super::parser::{
+    ResponseEvent,
+    ResponseParser,
+};
+use super::super::tool_manager::ToolManager;
+use super::todo_list::SharedTodos;
+use super::web_fetch::DomainFilter;
+use super::{
+    InvokeOutput,
+    OutputKind,
+    Tool,
+    ToolPermissions,
+    ToolSpec,
+};
+use crate::api_client::StreamingClient;
+use crate::api_client::clients::SendMessageOutput;
+use crate::api_client::model::ToolResultStatus;
+use crate::platform::Context;
+
+/// The name of this tool as exposed to the model.
+pub const DELEGATE_TASK_TOOL_NAME: &str = "delegate_task";
+
+/// Number of model responses a sub-agent may take if the model doesn't specify a budget.
+const DEFAULT_MAX_TURNS: usize = 10;
+
+/// Upper bound on the turn budget the model may request.
+const MAX_TURNS_LIMIT: usize = 25;
+
+/// Tools that are never offered to a sub-agent. Delegation can't nest, and reporting an issue
+/// requires the parent conversation's transcript.
+const EXCLUDED_TOOLS: &[&str] = &[DELEGATE_TASK_TOOL_NAME, "report_issue"];
+
+const LAST_TURN_MESSAGE: &str = "This is the last turn for this task. Do not use any more tools; respond with a summary of your progress and findings so far.";
+
+const BUDGET_EXHAUSTED_MESSAGE: &str = "The sub-agent used up its turn budget before finishing the task.";
+
+/// Runs a self-contained task in a fresh child conversation so that large tasks don't overflow
+/// the parent conversation's context window.
+///
+/// The child conversation has its own history and a restricted tool set, is limited to a number
+/// of turns, and only its final response is returned to the parent as the tool result.
+#[derive(Debug, Clone, Deserialize)]
+pub struct DelegateTask {
+    pub task: String,
+    /// Names of the tools the sub-agent may use. Defaults to every tool available to the parent.
+    pub tools: Option<Vec<String>>,
+    pub max_turns: Option<usize>,
+
+    #[serde(skip_deserializing)]
+    pub context: Option<DelegateTaskContext>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DelegateTaskContext {
+    pub ctx: Arc<Context>,
+    pub client: StreamingClient,
+    pub tool_manager: ToolManager,
+    pub tool_permissions: ToolPermissions,
+    pub profile: Option<String>,
+    /// The model of the parent conversation, which the sub-agent responds with as well.
+    pub model: Option<ModelInfo>,
+    /// Whether pre tool use hooks may approve tool uses that would otherwise require approval.
+    pub hooks_allow_tools: bool,
+    /// The parent's `web_fetch` domain filter.
+    pub web_fetch_domains: DomainFilter,
+    /// Whether `use_aws` calls services in-process rather than through the AWS CLI.
+    pub use_aws_native: bool,
+}
+
+impl DelegateTask {
+    pub async fn invoke(&self, ctx: &Context, updates: &mut impl Write) -> Result<InvokeOutput> {
+        let Some(context) = self.context.as_ref() else {
+            return Err(eyre!(
+                "delegate_task: Required tool context (DelegateTaskContext) not set by the program."
+            ));
+        };
+
+        let tool_config = self.tool_config(&context.tool_manager);
+        let tool_names = tool_config.values().map(|spec| spec.name.clone()).collect::<Vec<_>>();
+
+        // The sub-agent shares the parent's MCP clients, but must not pick up tool specs that the
+        // parent conversation has yet to load.
+        let mut tool_manager = context.tool_manager.clone();
+        tool_manager.has_new_stuff = Arc::new(AtomicBool::new(false));
+
+        let conversation_id = Alphanumeric.sample_string(&mut rand::rng(), 9);
+        info!(?conversation_id, "Generated new sub-agent conversation id");
+        let mut conversation = ConversationState::new(
+            Arc::clone(&context.ctx),
+            &conversation_id,
+            tool_config,
+            context.profile.clone(),
+            None,
+            tool_manager,
+        )
+        .await;
+        conversation.set_model(context.model.clone());
+        conversation.set_next_user_message(self.prompt()).await;
+
+        let max_turns = self.max_turns.unwrap_or(DEFAULT_MAX_TURNS).max(1);
+        for turn in 1..=max_turns {
+            queue!(
+                updates,
+                style::SetForegroundColor(Color::DarkGrey),
+                style::Print(format!("\n  ↳ Sub-agent turn {turn}/{max_turns}\n")),
+                style::SetForegroundColor(Color::Reset),
+            )?;
+            updates.flush()?;
+
+            let response = context
+                .client
+                .send_message(conversation.as_sendable_conversation_state(false).await)
+                .await?;
+            let (text, tool_uses) = receive_response(&mut conversation, response).await?;
+            if tool_uses.is_empty() {
+                return Ok(InvokeOutput {
+                    output: OutputKind::Text(text.trim().to_string()),
+                });
+            }
+            if turn == max_turns {
+                // There's no turn left to send the results of these tool uses back on.
+                return Ok(InvokeOutput {
+                    output: OutputKind::Text(match text.trim() {
+                        "" => BUDGET_EXHAUSTED_MESSAGE.to_string(),
+                        text => format!("{text}\n\n{BUDGET_EXHAUSTED_MESSAGE}"),
+                    }),
+                });
+            }
+
+            let mut tool_results = Vec::new();
+            for tool_use in tool_uses {
+                let result = self
+                    .run_tool(
+                        ctx,
+                        context,
+                        &tool_names,
+                        &conversation.todos,
+                        conversation.context_manager.as_mut(),
+                        tool_use,
+                        updates,
+                    )
+                    .await?;
+                tool_results.push(result);
+            }
+            if turn + 1 == max_turns {
+                if let Some(result) = tool_results.last_mut() {
+                    result
+                        .content
+                        .push(ToolUseResultBlock::Text(LAST_TURN_MESSAGE.to_string()));
+                }
+            }
+            conversation.add_tool_results(tool_results);
+        }
+
+        unreachable!("max_turns is at least 1, so the last turn always returns")
+    }
+
+    pub fn set_context(&mut self, context: DelegateTaskContext) {
+        self.context = Some(context);
+    }
+
+    pub fn queue_description(&self, updates: &mut impl Write) -> Result<()> {
+        queue!(
+            updates,
+            style::Print("Delegating a task to a sub-agent"),
+            style::SetForegroundColor(Color::DarkGrey),
+            style::Print(format!(
+                " (up to {} turns)\n",
+                self.max_turns.unwrap_or(DEFAULT_MAX_TURNS)
+            )),
+            style::SetForegroundColor(Color::Reset),
+            style::Print(format!("Task: {}\n", self.task.trim())),
+        )?;
+        if let Some(tools) = &self.tools {
+            queue!(updates, style::Print(format!("Tools: {}\n", tools.join(", "))))?;
+        }
+        Ok(())
+    }
+
+    pub async fn validate(&mut self, _ctx: &Context) -> Result<()> {
+        if self.task.trim().is_empty() {
+            bail!("The task must not be empty");
+        }
+        if let Some(max_turns) = self.max_turns {
+            if !(1..=MAX_TURNS_LIMIT).contains(&max_turns) {
+                bail!("max_turns must be between 1 and {MAX_TURNS_LIMIT}, got {max_turns}");
+            }
+        }
+        if let (Some(tools), Some(context)) = (&self.tools, &self.context) {
+            for tool in tools {
+                if EXCLUDED_TOOLS.contains(&tool.as_str()) {
+                    bail!("The tool \"{tool}\" can't be used by a sub-agent");
+                }
+                if !context.tool_manager.schema.values().any(|spec| &spec.name == tool) {
+                    bail!("The tool \"{tool}\" does not exist");
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the specs of the tools the sub-agent may use.
+    fn tool_config(&self, tool_manager: &ToolManager) -> HashMap<String, ToolSpec> {
+        tool_manager
+            .schema
+            .iter()
+            .filter(|(_, spec)| !EXCLUDED_TOOLS.contains(&spec.name.as_str()))
+            .filter(|(_, spec)| {
+                // The dummy tool is required to keep the history valid if the model uses an
+                // unknown tool.
+                spec.name == DUMMY_TOOL_NAME || self.tools.as_ref().is_none_or(|tools| tools.contains(&spec.name))
+            })
+            .map(|(name, spec)| (name.clone(), spec.clone()))
+            .collect()
+    }
+
+    fn prompt(&self) -> String {
+        format!(
+            "You are a sub-agent that has been delegated the following task. You cannot ask the user for \
+            clarification, and only your final response is returned, so when you are done respond with a \
+            concise but complete summary of your results.\n\nTask: {}",
+            self.task.trim()
+        )
+    }
+
+    /// Validates and runs a tool requested by the sub-agent. Tools that would require the user's
+    /// approval in the parent conversation are rejected, since the sub-agent can't prompt for it.
+    /// Tool use hooks run the same way as for the parent's tool uses.
+    #[allow(clippy::too_many_arguments)]
+    async fn run_tool(
+        &self,
+        ctx: &Context,
+        context: &DelegateTaskContext,
+        tool_names: &[String],
+        todos: &SharedTodos,
+        mut context_manager: Option<&mut ContextManager>,
+        mut tool_use: AssistantToolUse,
+        updates: &mut impl Write,
+    ) -> Result<ToolUseResult> {
+        let tool_use_id = tool_use.id.clone();
+        let error = |message: String| ToolUseResult {
+            tool_use_id: tool_use_id.clone(),
+            content: vec![ToolUseResultBlock::Text(message)],
+            status: ToolResultStatus::Error,
+        };
+
+        if !tool_names.contains(&tool_use.name) {
+            return Ok(error(format!(
+                "The tool \"{}\" is not available to this sub-agent",
+                tool_use.name
+            )));
+        }
+
+        // Pre tool use hooks may allow, block, or rewrite the tool use before it is validated.
+        let mut hook_allowed = false;
+        if let Some(context_manager) = context_manager.as_deref_mut() {
+            let event = HookEvent::PreToolUse {
+                tool_name: tool_use.name.clone(),
+                tool_input: tool_use.args.clone(),
+            };
+            let mut blocked = None;
+            for (hook, outcome) in context_manager.run_event_hooks(&event, Some(&mut *updates)).await {
+                match outcome {
+                    HookOutcome::Continue(_) => (),
+                    HookOutcome::Allow if context.hooks_allow_tools && !hook.from_workspace => hook_allowed = true,
+                    HookOutcome::Allow => debug!("ignoring allow decision of hook {}", hook.name),
+                    HookOutcome::Block(reason) => blocked = Some(reason),
+                    HookOutcome::Modify(tool_input) => tool_use.args = tool_input,
+                }
+            }
+            if let Some(reason) = blocked {
+                return Ok(error(format!("Tool use was blocked by a hook: {reason}")));
+            }
+        }
+
+        let name = tool_use.name.clone();
+        let tool_input = tool_use.args.clone();
+        let mut tool = match context.tool_manager.get_tool_from_tool_use(tool_use) {
+            Ok(tool) => tool,
+            Err(err) => return Ok(err.into()),
+        };
+        // Tools get the same context as in the parent conversation, except that the sub-agent keeps
+        // its own todo list rather than editing the parent's.
+        match &mut tool {
+            Tool::WebFetch(web_fetch) => web_fetch.set_domain_filter(context.web_fetch_domains.clone()),
+            Tool::UseAws(use_aws) => use_aws.set_prefer_native(context.use_aws_native),
+            Tool::TodoList(todo_list) => todo_list.set_todos(Arc::clone(todos)),
+            _ => (),
+        }
+        if let Err(err) = tool.validate(ctx).await {
+            return Ok(error(format!("Failed to validate tool parameters: {err}")));
+        }
+
+        let permissions = &context.tool_permissions;
+        let allowed = hook_allowed
+            || permissions.trust_all
+            || (permissions.has(&name) && permissions.is_trusted(&name))
+            || !tool.requires_acceptance(ctx);
+        if !allowed {
+            queue!(
+                updates,
+                style::Print(format!("  ↳ {} ", tool.display_name())),
+                style::Print("skipped: requires approval\n".dark_yellow()),
+            )?;
+            return Ok(error(format!(
+                "The tool \"{name}\" requires user approval, which is not available to sub-agents. Try to complete the task without it."
+            )));
+        }
+
+        queue!(
+            updates,
+            style::SetForegroundColor(Color::DarkGrey),
+            style::Print(format!("  ↳ Using tool: {}\n", tool.display_name())),
+            style::SetForegroundColor(Color::Reset),
+        )?;
+        updates.flush()?;
+
+        // Boxed since a sub-agent's tools are invoked through [super::Tool::invoke], which is
+        // what invokes this tool.
+        let invoke_result = Box::pin(tool.invoke(ctx, updates)).await;
+        let event = HookEvent::PostToolUse {
+            tool_name: name,
+            tool_input,
+            tool_response: match &invoke_result {
+                Ok(InvokeOutput {
+                    output: OutputKind::Json(json),
+                }) => json.to_string(),
+                Ok(output) => output.as_str().to_string(),
+                Err(err) => err.to_string(),
+            },
+            success: invoke_result.is_ok(),
+        };
+        let mut result = match invoke_result {
+            Ok(output) => ToolUseResult {
+                tool_use_id: tool_use_id.clone(),
+                content: vec![output.into()],
+                status: ToolResultStatus::Success,
+            },
+            Err(err) => {
+                debug!(?err, "sub-agent tool use failed");
+                error(format!("An error occurred processing the tool: \n{err}"))
+            },
+        };
+
+        // Post tool use hooks that block send their reason back to the model with the result.
+        if let Some(context_manager) = context_manager {
+            for (hook, outcome) in context_manager.run_event_hooks(&event, Some(&mut *updates)).await {
+                if let HookOutcome::Block(reason) = outcome {
+                    result.content.push(ToolUseResultBlock::Text(format!(
+                        "Feedback from hook {}: {reason}",
+                        hook.name
+                    )));
+                }
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+/// Consumes a model response, adding it to `conversation`. Returns the response text and any
+/// requested tool uses.
+async fn receive_response(
+    conversation: &mut ConversationState,
+    response: SendMessageOutput,
+) -> Result<(String, Vec<AssistantToolUse>)> {
+    let mut parser = ResponseParser::new(response);
+    let mut text = String::new();
+    let mut tool_uses = Vec::new();
+    loop {
+        match parser.recv().await? {
+            ResponseEvent::ToolUseStart { .. } => (),
+            ResponseEvent::AssistantText(chunk) => text.push_str(&chunk),
+            ResponseEvent::ToolUse(tool_use) => tool_uses.push(tool_use),
+            ResponseEvent::EndStream { message } => {
+                conversation.push_unsaved_assistant_message(message);
+                return Ok((text, tool_uses));
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::chat::create_stream;
+
+    fn tool_manager() -> ToolManager {
+        let mut tool_manager = ToolManager::default();
+        tool_manager.schema = serde_json::from_str(include_str!("tool_index.json")).unwrap();
+        tool_manager
+    }
+
+    fn delegate_task(args: serde_json::Value, client: StreamingClient, ctx: &Arc<Context>) -> DelegateTask {
+        let mut delegate_task = serde_json::from_value::<DelegateTask>(args).unwrap();
+        delegate_task.set_context(DelegateTaskContext {
+            ctx: Arc::clone(ctx),
+            client,
+            tool_manager: tool_manager(),
+            tool_permissions: ToolPermissions::new(0),
+            profile: None,
+            model: None,
+            hooks_allow_tools: false,
+            web_fetch_domains: DomainFilter::default(),
+            use_aws_native: false,
+        });
+        delegate_task
+    }
+
+    #[test]
+    fn test_tool_config() {
+        let ctx = Context::new();
+        let task = delegate_task(
+            serde_json::json!({ "task": "audit", "tools": ["fs_read"] }),
+            create_stream(serde_json::json!([])),
+            &ctx,
+        );
+        let mut names = task
+            .tool_config(&tool_manager())
+            .into_values()
+            .map(|spec| spec.name)
+            .collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, vec![DUMMY_TOOL_NAME, "fs_read"]);
+
+        let task = delegate_task(
+            serde_json::json!({ "task": "audit" }),
+            create_stream(serde_json::json!([])),
+            &ctx,
+        );
+        let config = task.tool_config(&tool_manager());
+        assert!(config.values().any(|spec| spec.name == "execute_bash"));
+        assert!(!config.values().any(|spec| spec.name == DELEGATE_TASK_TOOL_NAME));
+    }
+
+    #[tokio::test]
+    async fn test_validate() {
+        let ctx = Context::new();
+        let client = create_stream(serde_json::json!([]));
+        for args in [
+            serde_json::json!({ "task": " " }),
+            serde_json::json!({ "task": "audit", "max_turns": 0 }),
+            serde_json::json!({ "task": "audit", "max_turns": MAX_TURNS_LIMIT + 1 }),
+            serde_json::json!({ "task": "audit", "tools": ["delegate_task"] }),
+            serde_json::json!({ "task": "audit", "tools": ["not_a_tool"] }),
+        ] {
+            let mut task = delegate_task(args.clone(), client.clone(), &ctx);
+            assert!(task.validate(&ctx).await.is_err(), "{args} should be invalid");
+        }
+
+        let mut task = delegate_task(
+            serde_json::json!({ "task": "audit", "tools": ["fs_read"], "max_turns": 3 }),
+            client,
+            &ctx,
+        );
+        assert!(task.validate(&ctx).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_invoke() {
+        let ctx = Context::builder().with_test_home().await.unwrap().build_fake();
+        ctx.fs().write("/notes.txt", "the handler is fine").await.unwrap();
+        let client = create_stream(serde_json::json!([
+            [
+                "Reading the notes",
+                {
+                    "tool_use_id": "1",
+                    "name": "fs_read",
+                    "args": {
+                        "mode": "Line",
+                        "path": "/notes.txt",
+                    }
+                },
+                {
+                    "tool_use_id": "2",
+                    "name": "fs_write",
+                    "args": {
+                        "command": "create",
+                        "file_text": "oops",
+                        "path": "/other.txt",
+                    }
+                }
+            ],
+            [
+                "The handler is fine.",
+            ],
+        ]));
+
+        let task = delegate_task(serde_json::json!({ "task": "audit the handler" }), client, &ctx);
+        let mut output = Vec::new();
+        let result = task.invoke(&ctx, &mut output).await.unwrap();
+
+        assert_eq!(result.as_str(), "The handler is fine.");
+        assert!(
+            !ctx.fs().exists("/other.txt"),
+            "tools requiring approval should not run"
+        );
+        let output = String::from_utf8_lossy(&output);
+        assert!(output.contains("Using tool: fs_read"));
+        assert!(output.contains("requires approval"));
+    }
+
+    #[tokio::test]
+    async fn test_invoke_exhausts_turns() {
+        let ctx = Context::builder().with_test_home().await.unwrap().build_fake();
+        let tool_use = serde_json::json!({
+            "tool_use_id": "1",
+            "name": "fs_read",
+            "args": {
+                "mode": "Directory",
+                "path": "/",
+            }
+        });
+        let client = create_stream(serde_json::json!([[tool_use], ["Listed the files."], ["Not sent"]]));
+
+        let task = delegate_task(
+            serde_json::json!({ "task": "list files", "max_turns": 2 }),
+            client,
+            &ctx,
+        );
+        let result = task.invoke(&ctx, &mut Vec::new()).await.unwrap();
+        assert_eq!(result.as_str(), "Listed the files.");
+
+        let client = create_stream(serde_json::json!([["Listing", tool_use], ["Not sent"]]));
+        let task = delegate_task(
+            serde_json::json!({ "task": "list files", "max_turns": 1 }),
+            client,
+            &ctx,
+        );
+        let result = task.invoke(&ctx, &mut Vec::new()).await.unwrap();
+        assert_eq!(result.as_str(), format!("Listing\n\n{BUDGET_EXHAUSTED_MESSAGE}"));
+    }
+}
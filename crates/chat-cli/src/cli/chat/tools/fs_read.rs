@@ -1,6 +1,11 @@
 use std::collections::VecDeque;
+use std::fmt::Write as _;
 use std::fs::Metadata;
 use std::io::Write;
+use std::path::{
+    Path,
+    PathBuf,
+};
 
 use crossterm::queue;
 use crossterm::style::{
@@ -10,7 +15,22 @@ use crossterm::style::{
 use eyre::{
     Result,
     bail,
+    eyre,
+};
+use globset::{
+    GlobBuilder,
+    GlobMatcher,
+};
+use ignore::Match;
+use ignore::gitignore::{
+    Gitignore,
+    GitignoreBuilder,
+};
+use ignore::overrides::{
+    Override,
+    OverrideBuilder,
 };
+use regex::Regex;
 use serde::{
     Deserialize,
     Serialize,
@@ -41,6 +61,8 @@ pub enum FsRead {
     Line(FsLine),
     Directory(FsDirectory),
     Search(FsSearch),
+    Grep(FsGrep),
+    Glob(FsGlob),
     Image(FsImage),
 }
 
@@ -50,6 +72,8 @@ impl FsRead {
             FsRead::Line(fs_line) => fs_line.validate(ctx).await,
             FsRead::Directory(fs_directory) => fs_directory.validate(ctx).await,
             FsRead::Search(fs_search) => fs_search.validate(ctx).await,
+            FsRead::Grep(fs_grep) => fs_grep.validate(ctx).await,
+            FsRead::Glob(fs_glob) => fs_glob.validate(ctx).await,
             FsRead::Image(fs_image) => fs_image.validate(ctx).await,
         }
     }
@@ -59,6 +83,8 @@ impl FsRead {
             FsRead::Line(fs_line) => fs_line.queue_description(ctx, updates).await,
             FsRead::Directory(fs_directory) => fs_directory.queue_description(updates),
             FsRead::Search(fs_search) => fs_search.queue_description(updates),
+            FsRead::Grep(fs_grep) => fs_grep.queue_description(updates),
+            FsRead::Glob(fs_glob) => fs_glob.queue_description(updates),
             FsRead::Image(fs_image) => fs_image.queue_description(updates),
         }
     }
@@ -68,9 +94,23 @@ impl FsRead {
             FsRead::Line(fs_line) => fs_line.invoke(ctx, updates).await,
             FsRead::Directory(fs_directory) => fs_directory.invoke(ctx, updates).await,
             FsRead::Search(fs_search) => fs_search.invoke(ctx, updates).await,
+            FsRead::Grep(fs_grep) => fs_grep.invoke(ctx, updates).await,
+            FsRead::Glob(fs_glob) => fs_glob.invoke(ctx, updates).await,
             FsRead::Image(fs_image) => fs_image.invoke(ctx, updates).await,
         }
     }
+
+    /// The paths being read from, as given by the model.
+    pub fn paths(&self) -> Vec<&str> {
+        match self {
+            FsRead::Line(fs_line) => vec![&fs_line.path],
+            FsRead::Directory(fs_directory) => vec![&fs_directory.path],
+            FsRead::Search(fs_search) => vec![&fs_search.path],
+            FsRead::Grep(fs_grep) => vec![&fs_grep.path],
+            FsRead::Glob(fs_glob) => vec![&fs_glob.path],
+            FsRead::Image(fs_image) => fs_image.image_paths.iter().map(String::as_str).collect(),
+        }
+    }
 }
 
 /// Read images from given paths.
@@ -334,6 +374,256 @@ impl FsSearch {
     }
 }
 
+/// Recursively search the files in a directory for a regular expression.
+#[derive(Debug, Clone, Deserialize)]
+pub struct FsGrep {
+    pub path: String,
+    pub pattern: String,
+    /// Globs a file must match one of to be searched.
+    pub include: Option<Vec<String>>,
+    /// Globs of files to skip.
+    pub exclude: Option<Vec<String>>,
+    pub context_lines: Option<usize>,
+    pub max_results: Option<usize>,
+}
+
+impl FsGrep {
+    /// Files with a NUL byte in this many leading bytes are treated as binary and not searched.
+    const BINARY_CHECK_LEN: usize = 8 * 1024;
+    const DEFAULT_CONTEXT_LINES: usize = 0;
+    const DEFAULT_MAX_RESULTS: usize = 100;
+    /// Files larger than this many bytes are not searched.
+    const MAX_FILE_SIZE: u64 = 1024 * 1024;
+
+    pub async fn validate(&mut self, ctx: &Context) -> Result<()> {
+        let path = sanitize_path_tool_arg(ctx, &self.path);
+        let relative_path = format_path(ctx.env().current_dir()?, &path);
+        if !ctx.fs().exists(&path) {
+            bail!("Directory not found: {}", relative_path);
+        }
+        if !ctx.fs().symlink_metadata(&path).await?.is_dir() {
+            bail!("Path is not a directory: {}", relative_path);
+        }
+        if self.pattern.is_empty() {
+            bail!("Search pattern cannot be empty");
+        }
+        Regex::new(&self.pattern).map_err(|err| eyre!("Invalid regular expression '{}': {err}", self.pattern))?;
+        self.overrides(&path)?;
+        Ok(())
+    }
+
+    pub fn queue_description(&self, updates: &mut impl Write) -> Result<()> {
+        queue!(
+            updates,
+            style::Print("Searching recursively: "),
+            style::SetForegroundColor(Color::Green),
+            style::Print(&self.path),
+            style::ResetColor,
+            style::Print(" for regex: "),
+            style::SetForegroundColor(Color::Green),
+            style::Print(&self.pattern),
+            style::ResetColor,
+        )?;
+        if let Some(include) = self.include.as_ref().filter(|globs| !globs.is_empty()) {
+            queue!(updates, style::Print(format!(" in {}", include.join(", "))))?;
+        }
+        if let Some(exclude) = self.exclude.as_ref().filter(|globs| !globs.is_empty()) {
+            queue!(updates, style::Print(format!(" excluding {}", exclude.join(", "))))?;
+        }
+        Ok(())
+    }
+
+    pub async fn invoke(&self, ctx: &Context, updates: &mut impl Write) -> Result<InvokeOutput> {
+        let root = sanitize_path_tool_arg(ctx, &self.path);
+        let regex = Regex::new(&self.pattern)?;
+        let context_lines = self.context_lines.unwrap_or(Self::DEFAULT_CONTEXT_LINES);
+        let max_results = self.max_results.unwrap_or(Self::DEFAULT_MAX_RESULTS);
+
+        let mut matches = Vec::new();
+        let mut truncated = false;
+        let mut files_searched = 0;
+        let files = walk(ctx, &root, &self.overrides(&root)?)
+            .await?
+            .into_iter()
+            .filter(|(_, metadata)| metadata.is_file());
+        'files: for (file, metadata) in files {
+            if metadata.len() > Self::MAX_FILE_SIZE {
+                debug!(?file, size = metadata.len(), "Skipping large file");
+                continue;
+            }
+            // Binary and otherwise unreadable files are skipped.
+            let Ok(bytes) = ctx.fs().read(&file).await else {
+                continue;
+            };
+            if bytes[..bytes.len().min(Self::BINARY_CHECK_LEN)].contains(&0) {
+                continue;
+            }
+            let Ok(content) = String::from_utf8(bytes) else {
+                continue;
+            };
+            files_searched += 1;
+            let lines: Vec<&str> = content.lines().collect();
+            for (line_num, line) in lines.iter().enumerate() {
+                if !regex.is_match(line) {
+                    continue;
+                }
+                if matches.len() == max_results {
+                    truncated = true;
+                    break 'files;
+                }
+                let start = line_num.saturating_sub(context_lines);
+                let end = lines.len().min(line_num + context_lines + 1);
+                let mut context = String::new();
+                for (i, line) in lines.iter().enumerate().take(end).skip(start) {
+                    let prefix = if i == line_num {
+                        FsSearch::MATCHING_LINE_PREFIX
+                    } else {
+                        FsSearch::CONTEXT_LINE_PREFIX
+                    };
+                    let _ = writeln!(context, "{}{}: {}", prefix, i + 1, line);
+                }
+                matches.push(GrepMatch {
+                    path: display_path(&self.path, &root, &file),
+                    line_number: line_num + 1,
+                    context,
+                });
+            }
+        }
+
+        queue!(
+            updates,
+            style::Print(format!(
+                "Found {}{} matches for regex '{}' in {} files\n",
+                matches.len(),
+                if truncated { "+" } else { "" },
+                self.pattern,
+                files_searched
+            )),
+            style::Print("\n"),
+        )?;
+
+        let result = serde_json::to_string(&GrepResults { matches, truncated })?;
+        let byte_count = result.len();
+        if byte_count > MAX_TOOL_RESPONSE_SIZE {
+            bail!(
+                "This tool only supports reading up to {MAX_TOOL_RESPONSE_SIZE} bytes at a time. The matches are {byte_count} bytes. Try a more specific pattern, fewer context lines, or a lower max_results."
+            );
+        }
+
+        Ok(InvokeOutput {
+            output: OutputKind::Text(result),
+        })
+    }
+
+    /// Builds the include and exclude globs into overrides for the directory walk.
+    fn overrides(&self, root: &Path) -> Result<Override> {
+        let mut builder = OverrideBuilder::new(root);
+        for glob in self.include.iter().flatten() {
+            builder.add(glob)?;
+        }
+        for glob in self.exclude.iter().flatten() {
+            builder.add(&format!("!{glob}"))?;
+        }
+        Ok(builder.build()?)
+    }
+}
+
+/// Find the paths in a directory matching a glob.
+#[derive(Debug, Clone, Deserialize)]
+pub struct FsGlob {
+    pub path: String,
+    pub pattern: String,
+    pub max_results: Option<usize>,
+}
+
+impl FsGlob {
+    const DEFAULT_MAX_RESULTS: usize = 100;
+
+    pub async fn validate(&mut self, ctx: &Context) -> Result<()> {
+        let path = sanitize_path_tool_arg(ctx, &self.path);
+        let relative_path = format_path(ctx.env().current_dir()?, &path);
+        if !ctx.fs().exists(&path) {
+            bail!("Directory not found: {}", relative_path);
+        }
+        if !ctx.fs().symlink_metadata(&path).await?.is_dir() {
+            bail!("Path is not a directory: {}", relative_path);
+        }
+        if self.pattern.is_empty() {
+            bail!("Glob pattern cannot be empty");
+        }
+        self.matcher()?;
+        Ok(())
+    }
+
+    pub fn queue_description(&self, updates: &mut impl Write) -> Result<()> {
+        queue!(
+            updates,
+            style::Print("Finding paths in: "),
+            style::SetForegroundColor(Color::Green),
+            style::Print(&self.path),
+            style::ResetColor,
+            style::Print(" matching: "),
+            style::SetForegroundColor(Color::Green),
+            style::Print(&self.pattern),
+            style::ResetColor,
+        )?;
+        Ok(())
+    }
+
+    pub async fn invoke(&self, ctx: &Context, updates: &mut impl Write) -> Result<InvokeOutput> {
+        let root = sanitize_path_tool_arg(ctx, &self.path);
+        let matcher = self.matcher()?;
+        let max_results = self.max_results.unwrap_or(Self::DEFAULT_MAX_RESULTS);
+
+        let mut paths = walk(ctx, &root, &Override::empty())
+            .await?
+            .into_iter()
+            .filter(|(path, _)| {
+                path.strip_prefix(&root)
+                    .is_ok_and(|relative| matcher.is_match(relative))
+            })
+            .map(|(path, _)| display_path(&self.path, &root, &path))
+            .collect::<Vec<_>>();
+        paths.sort();
+
+        queue!(
+            updates,
+            style::Print(format!("Found {} paths matching '{}'\n", paths.len(), self.pattern)),
+            style::Print("\n"),
+        )?;
+
+        let total = paths.len();
+        paths.truncate(max_results);
+        let mut result = paths.join("\n");
+        if total > max_results {
+            result.push_str(&format!(
+                "\n... {} more paths not shown. Use a more specific pattern or a higher max_results.",
+                total - max_results
+            ));
+        }
+
+        let byte_count = result.len();
+        if byte_count > MAX_TOOL_RESPONSE_SIZE {
+            bail!(
+                "This tool only supports reading up to {MAX_TOOL_RESPONSE_SIZE} bytes at a time. The paths are {byte_count} bytes. Try a more specific pattern or a lower max_results."
+            );
+        }
+
+        Ok(InvokeOutput {
+            output: OutputKind::Text(result),
+        })
+    }
+
+    fn matcher(&self) -> Result<GlobMatcher> {
+        let pattern = self.pattern.strip_prefix("./").unwrap_or(&self.pattern);
+        Ok(GlobBuilder::new(pattern)
+            .literal_separator(true)
+            .build()
+            .map_err(|err| eyre!("Invalid glob '{}': {err}", self.pattern))?
+            .compile_matcher())
+    }
+}
+
 /// List directory contents.
 #[derive(Debug, Clone, Deserialize)]
 pub struct FsDirectory {
@@ -495,6 +785,104 @@ struct SearchMatch {
     context: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct GrepMatch {
+    path: String,
+    line_number: usize,
+    context: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct GrepResults {
+    matches: Vec<GrepMatch>,
+    /// Whether the search stopped early after reaching `max_results`.
+    truncated: bool,
+}
+
+/// Returns the paths below `root` in path order along with their metadata, skipping hidden files,
+/// those ignored by `.gitignore` files in `root` or its parents, and those ignored by `overrides`.
+///
+/// The walk goes through [Context::fs] so that it sees the same files as the other tools.
+async fn walk(ctx: &Context, root: &Path, overrides: &Override) -> Result<Vec<(PathBuf, Metadata)>> {
+    let mut parent_ignores = Vec::new();
+    for dir in root.ancestors().skip(1).collect::<Vec<_>>().into_iter().rev() {
+        if let Some(gitignore) = load_gitignore(ctx, dir).await {
+            parent_ignores.push(gitignore);
+        }
+    }
+
+    let mut paths = Vec::new();
+    let mut dirs = vec![(root.to_path_buf(), parent_ignores)];
+    while let Some((dir, mut ignores)) = dirs.pop() {
+        if let Some(gitignore) = load_gitignore(ctx, &dir).await {
+            ignores.push(gitignore);
+        }
+        let mut read_dir = match ctx.fs().read_dir(&dir).await {
+            Ok(read_dir) => read_dir,
+            Err(err) => {
+                debug!(?err, ?dir, "Skipping directory");
+                continue;
+            },
+        };
+        while let Some(entry) = read_dir.next_entry().await? {
+            let path = entry.path();
+            let Ok(metadata) = entry.metadata().await else {
+                continue;
+            };
+            let is_dir = metadata.is_dir();
+            match overrides.matched(&path, is_dir) {
+                Match::Ignore(_) => continue,
+                Match::Whitelist(_) => (),
+                Match::None => {
+                    if entry.file_name().to_string_lossy().starts_with('.') {
+                        continue;
+                    }
+                },
+            }
+            // Deeper `.gitignore` files take precedence.
+            let ignored = ignores
+                .iter()
+                .rev()
+                .map(|gitignore| gitignore.matched(&path, is_dir))
+                .find(|m| !m.is_none())
+                .is_some_and(|m| m.is_ignore());
+            if ignored {
+                continue;
+            }
+            if is_dir {
+                dirs.push((path.clone(), ignores.clone()));
+            }
+            paths.push((path, metadata));
+        }
+    }
+    paths.sort_by(|(a, _), (b, _)| a.cmp(b));
+    Ok(paths)
+}
+
+/// Reads the `.gitignore` file in `dir`, if there is one.
+async fn load_gitignore(ctx: &Context, dir: &Path) -> Option<Gitignore> {
+    let path = dir.join(".gitignore");
+    let content = ctx.fs().read_to_string(&path).await.ok()?;
+    let mut builder = GitignoreBuilder::new(dir);
+    for line in content.lines() {
+        if let Err(err) = builder.add_line(Some(path.clone()), line) {
+            debug!(?err, ?path, "Skipping invalid gitignore line");
+        }
+    }
+    builder
+        .build()
+        .map_err(|err| debug!(?err, ?path, "Skipping gitignore"))
+        .ok()
+}
+
+/// Formats `path`, found by walking `root`, relative to the path the model passed as `root_arg`.
+fn display_path(root_arg: &str, root: &Path, path: &Path) -> String {
+    match path.strip_prefix(root) {
+        Ok(relative) => Path::new(root_arg).join(relative).to_string_lossy().to_string(),
+        Err(_) => path.to_string_lossy().to_string(),
+    }
+}
+
 fn format_ftype(md: &Metadata) -> char {
     if md.is_symlink() {
         'l'
@@ -597,6 +985,12 @@ mod tests {
             serde_json::json!({ "path": "/test_file.txt", "mode": "Search", "pattern": "hello" }),
         )
         .unwrap();
+        serde_json::from_value::<FsRead>(
+            serde_json::json!({ "path": "/", "mode": "Grep", "pattern": "hel+o", "include": ["*.txt"], "max_results": 5 }),
+        )
+        .unwrap();
+        serde_json::from_value::<FsRead>(serde_json::json!({ "path": "/", "mode": "Glob", "pattern": "**/*.rs" }))
+            .unwrap();
     }
 
     #[tokio::test]
@@ -753,4 +1147,131 @@ mod tests {
             )
         );
     }
+
+    #[tokio::test]
+    async fn test_fs_read_grep_invoke() {
+        let ctx = setup_test_directory().await;
+        let fs = ctx.fs();
+        fs.write("/aaaa1/bbbb1/main.rs", "fn main() {\n    hello();\n}\n")
+            .await
+            .unwrap();
+        fs.write("/aaaa1/ignored.txt", "Hello world!\n").await.unwrap();
+        fs.write("/.gitignore", "ignored.txt\n").await.unwrap();
+        fs.write("/aaaa1/binary.bin", b"hello\0world\n").await.unwrap();
+        fs.write("/aaaa2/large.txt", "hello\n".repeat(FsGrep::MAX_FILE_SIZE as usize))
+            .await
+            .unwrap();
+        let mut stdout = std::io::stdout();
+
+        macro_rules! invoke_grep {
+            ($value:tt) => {{
+                let v = serde_json::json!($value);
+                let mut fs_read = serde_json::from_value::<FsRead>(v).unwrap();
+                fs_read.validate(&ctx).await.unwrap();
+                let output = fs_read.invoke(&ctx, &mut stdout).await.unwrap();
+
+                if let OutputKind::Text(value) = output.output {
+                    serde_json::from_str::<GrepResults>(&value).unwrap()
+                } else {
+                    panic!("expected Text output")
+                }
+            }};
+        }
+
+        let results = invoke_grep!({
+            "mode": "Grep",
+            "path": "/",
+            "pattern": "(?i)hel+o",
+        });
+        assert!(!results.truncated);
+        let found = results
+            .matches
+            .iter()
+            .map(|m| (m.path.as_str(), m.line_number))
+            .collect::<Vec<_>>();
+        assert_eq!(found, vec![
+            ("/aaaa1/bbbb1/main.rs", 2),
+            ("/test_file.txt", 1),
+            ("/test_file.txt", 4)
+        ]);
+
+        let results = invoke_grep!({
+            "mode": "Grep",
+            "path": "/",
+            "pattern": "hello",
+            "include": ["*.rs"],
+            "context_lines": 1,
+        });
+        assert_eq!(results.matches.len(), 1);
+        assert_eq!(
+            results.matches[0].context,
+            format!(
+                "{}1: fn main() {{\n{}2:     hello();\n{}3: }}\n",
+                FsSearch::CONTEXT_LINE_PREFIX,
+                FsSearch::MATCHING_LINE_PREFIX,
+                FsSearch::CONTEXT_LINE_PREFIX
+            )
+        );
+
+        let results = invoke_grep!({
+            "mode": "Grep",
+            "path": "/",
+            "pattern": "(?i)hello",
+            "exclude": ["*.rs"],
+            "max_results": 1,
+        });
+        assert!(results.truncated);
+        assert_eq!(results.matches.len(), 1);
+        assert_eq!(results.matches[0].path, "/test_file.txt");
+
+        let invalid = serde_json::json!({ "mode": "Grep", "path": "/", "pattern": "(unclosed" });
+        assert!(
+            serde_json::from_value::<FsRead>(invalid)
+                .unwrap()
+                .validate(&ctx)
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fs_read_glob_invoke() {
+        let ctx = setup_test_directory().await;
+        let fs = ctx.fs();
+        fs.write("/aaaa1/bbbb1/main.rs", "").await.unwrap();
+        fs.write("/aaaa1/lib.rs", "").await.unwrap();
+        fs.write("/aaaa1/target.rs", "").await.unwrap();
+        fs.write("/.gitignore", "target.rs\n").await.unwrap();
+        let mut stdout = std::io::stdout();
+
+        macro_rules! invoke_glob {
+            ($value:tt) => {{
+                let v = serde_json::json!($value);
+                let output = serde_json::from_value::<FsRead>(v)
+                    .unwrap()
+                    .invoke(&ctx, &mut stdout)
+                    .await
+                    .unwrap();
+
+                if let OutputKind::Text(value) = output.output {
+                    value
+                } else {
+                    panic!("expected Text output")
+                }
+            }};
+        }
+
+        assert_eq!(
+            invoke_glob!({ "mode": "Glob", "path": "/", "pattern": "**/*.rs" }),
+            "/aaaa1/bbbb1/main.rs\n/aaaa1/lib.rs"
+        );
+        assert_eq!(
+            invoke_glob!({ "mode": "Glob", "path": "/aaaa1", "pattern": "*.rs" }),
+            "/aaaa1/lib.rs"
+        );
+        assert!(
+            invoke_glob!({ "mode": "Glob", "path": "/", "pattern": "**/*.rs", "max_results": 1 })
+                .starts_with("/aaaa1/bbbb1/main.rs\n... 1 more paths not shown")
+        );
+    }
 }
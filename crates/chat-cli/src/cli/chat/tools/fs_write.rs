@@ -35,8 +35,8 @@ use super::{
 };
 use crate::platform::Context;
 
-static SYNTAX_SET: LazyLock<SyntaxSet> = LazyLock::new(SyntaxSet::load_defaults_newlines);
-static THEME_SET: LazyLock<ThemeSet> = LazyLock::new(ThemeSet::load_defaults);
+pub static SYNTAX_SET: LazyLock<SyntaxSet> = LazyLock::new(SyntaxSet::load_defaults_newlines);
+pub static THEME_SET: LazyLock<ThemeSet> = LazyLock::new(ThemeSet::load_defaults);
 
 #[derive(Debug, Clone, Deserialize)]
 #[serde(tag = "command")]
@@ -272,6 +272,16 @@ impl FsWrite {
         Ok(())
     }
 
+    /// The path of the file being written to, as given by the model.
+    pub fn path(&self) -> &str {
+        match self {
+            FsWrite::Create { path, .. }
+            | FsWrite::StrReplace { path, .. }
+            | FsWrite::Insert { path, .. }
+            | FsWrite::Append { path, .. } => path,
+        }
+    }
+
     /// Returns the text to use for the [FsWrite::Create] command. This is required since we can't
     /// rely on the model always providing `file_text`.
     fn canonical_create_command_text(&self) -> String {
@@ -1,10 +1,13 @@
 pub mod custom_tool;
+pub mod delegate_task;
 pub mod execute_bash;
 pub mod fs_read;
 pub mod fs_write;
 pub mod gh_issue;
 pub mod thinking;
+pub mod todo_list;
 pub mod use_aws;
+pub mod web_fetch;
 
 use std::collections::HashMap;
 use std::io::Write;
@@ -15,6 +18,7 @@ use std::path::{
 
 use crossterm::style::Stylize;
 use custom_tool::CustomTool;
+use delegate_task::DelegateTask;
 use execute_bash::ExecuteBash;
 use eyre::Result;
 use fs_read::FsRead;
@@ -25,7 +29,9 @@ use serde::{
     Serialize,
 };
 use thinking::Thinking;
+use todo_list::TodoList;
 use use_aws::UseAws;
+use web_fetch::WebFetch;
 
 use super::consts::MAX_TOOL_RESPONSE_SIZE;
 use super::util::images::RichImageBlocks;
@@ -42,6 +48,9 @@ pub enum Tool {
     Custom(CustomTool),
     GhIssue(GhIssue),
     Thinking(Thinking),
+    DelegateTask(DelegateTask),
+    WebFetch(WebFetch),
+    TodoList(TodoList),
 }
 
 impl Tool {
@@ -55,6 +64,9 @@ impl Tool {
             Tool::Custom(custom_tool) => &custom_tool.name,
             Tool::GhIssue(_) => "gh_issue",
             Tool::Thinking(_) => "thinking (prerelease)",
+            Tool::DelegateTask(_) => "delegate_task",
+            Tool::WebFetch(_) => "web_fetch",
+            Tool::TodoList(_) => "todo_list",
         }
         .to_owned()
     }
@@ -69,6 +81,9 @@ impl Tool {
             Tool::Custom(_) => true,
             Tool::GhIssue(_) => false,
             Tool::Thinking(_) => false,
+            Tool::DelegateTask(_) => false,
+            Tool::WebFetch(web_fetch) => web_fetch.requires_acceptance(),
+            Tool::TodoList(_) => false,
         }
     }
 
@@ -82,6 +97,9 @@ impl Tool {
             Tool::Custom(custom_tool) => custom_tool.invoke(context, updates).await,
             Tool::GhIssue(gh_issue) => gh_issue.invoke(updates).await,
             Tool::Thinking(think) => think.invoke(updates).await,
+            Tool::DelegateTask(delegate_task) => delegate_task.invoke(context, updates).await,
+            Tool::WebFetch(web_fetch) => web_fetch.invoke(context, updates).await,
+            Tool::TodoList(todo_list) => todo_list.invoke(updates).await,
         }
     }
 
@@ -95,6 +113,18 @@ impl Tool {
             Tool::Custom(custom_tool) => custom_tool.queue_description(updates),
             Tool::GhIssue(gh_issue) => gh_issue.queue_description(updates),
             Tool::Thinking(thinking) => thinking.queue_description(updates),
+            Tool::DelegateTask(delegate_task) => delegate_task.queue_description(updates),
+            Tool::WebFetch(web_fetch) => web_fetch.queue_description(updates),
+            Tool::TodoList(todo_list) => todo_list.queue_description(updates),
+        }
+    }
+
+    /// The file system paths the tool reads from or writes to
+    pub fn accessed_paths(&self) -> Vec<&str> {
+        match self {
+            Tool::FsRead(fs_read) => fs_read.paths(),
+            Tool::FsWrite(fs_write) => vec![fs_write.path()],
+            _ => vec![],
         }
     }
 
@@ -108,6 +138,9 @@ impl Tool {
             Tool::Custom(custom_tool) => custom_tool.validate(ctx).await,
             Tool::GhIssue(gh_issue) => gh_issue.validate(ctx).await,
             Tool::Thinking(think) => think.validate(ctx).await,
+            Tool::DelegateTask(delegate_task) => delegate_task.validate(ctx).await,
+            Tool::WebFetch(web_fetch) => web_fetch.validate(ctx).await,
+            Tool::TodoList(todo_list) => todo_list.validate(ctx).await,
         }
     }
 }
@@ -187,6 +220,9 @@ impl ToolPermissions {
             "use_aws" => "trust read-only commands".dark_grey(),
             "report_issue" => "trusted".dark_green().bold(),
             "thinking" => "trusted (prerelease)".dark_green().bold(),
+            "delegate_task" => "trusted".dark_green().bold(),
+            "web_fetch" => "trust allowed domains".dark_grey(),
+            "todo_list" => "trusted".dark_green().bold(),
             _ if self.trust_all => "trusted".dark_grey().bold(),
             _ => "not trusted".dark_grey(),
         };
@@ -258,6 +294,10 @@ pub struct QueuedTool {
     pub name: String,
     pub accepted: bool,
     pub tool: Tool,
+    /// The input the tool was created from, passed to [`HookTrigger::PostToolUse`] hooks.
+    ///
+    /// [`HookTrigger::PostToolUse`]: super::hooks::HookTrigger::PostToolUse
+    pub input: serde_json::Value,
 }
 
 /// The schema specification describing a tool's fields.
@@ -362,7 +402,7 @@ fn format_path(cwd: impl AsRef<Path>, path: impl AsRef<Path>) -> String {
         .unwrap_or(path.as_ref().to_string_lossy().to_string())
 }
 
-fn supports_truecolor(ctx: &Context) -> bool {
+pub fn supports_truecolor(ctx: &Context) -> bool {
     // Simple override to disable truecolor since shell_color doesn't use Context.
     !ctx.env().get("Q_DISABLE_TRUECOLOR").is_ok_and(|s| !s.is_empty())
         && shell_color::get_color_support().contains(shell_color::ColorSupport::TERM24BIT)
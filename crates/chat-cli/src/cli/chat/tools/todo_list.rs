@@ -0,0 +1,346 @@
+use std::io::Write;
+use std::sync::{
+    Arc,
+    Mutex,
+};
+
+use crossterm::queue;
+use crossterm::style::{
+    self,
+    Color,
+    Stylize,
+};
+use eyre::{
+    Result,
+    bail,
+    eyre,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use super::{
+    InvokeOutput,
+    OutputKind,
+};
+
+/// The todo list of a conversation, shared between the conversation state and the `todo_list` tool.
+pub type SharedTodos = Arc<Mutex<Todos>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TodoStatus {
+    Pending,
+    InProgress,
+    Completed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TodoItem {
+    pub id: usize,
+    pub description: String,
+    pub status: TodoStatus,
+}
+
+/// A checklist of steps the model is working through, persisted as part of the conversation.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Todos {
+    items: Vec<TodoItem>,
+    next_id: usize,
+}
+
+impl Todos {
+    pub fn items(&self) -> &[TodoItem] {
+        &self.items
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Whether any item is not yet completed.
+    pub fn has_open_items(&self) -> bool {
+        self.items.iter().any(|item| item.status != TodoStatus::Completed)
+    }
+
+    pub fn add(&mut self, description: impl Into<String>) -> usize {
+        self.next_id += 1;
+        self.items.push(TodoItem {
+            id: self.next_id,
+            description: description.into(),
+            status: TodoStatus::Pending,
+        });
+        self.next_id
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear();
+        self.next_id = 0;
+    }
+
+    pub fn get_mut(&mut self, id: usize) -> Result<&mut TodoItem> {
+        self.items
+            .iter_mut()
+            .find(|item| item.id == id)
+            .ok_or_else(|| eyre!("No todo item with id {}", id))
+    }
+
+    pub fn remove(&mut self, id: usize) -> Result<TodoItem> {
+        let index = self
+            .items
+            .iter()
+            .position(|item| item.id == id)
+            .ok_or_else(|| eyre!("No todo item with id {}", id))?;
+        Ok(self.items.remove(index))
+    }
+
+    /// Renders the list as a plain text checklist, e.g. for the model.
+    pub fn to_checklist(&self) -> String {
+        self.items
+            .iter()
+            .map(|item| {
+                let mark = match item.status {
+                    TodoStatus::Pending => "[ ]",
+                    TodoStatus::InProgress => "[~]",
+                    TodoStatus::Completed => "[x]",
+                };
+                format!("{} {}. {}", mark, item.id, item.description)
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Queues the list as a colored checklist for the user.
+    pub fn queue_checklist(&self, output: &mut impl Write) -> Result<()> {
+        let completed = self
+            .items
+            .iter()
+            .filter(|item| item.status == TodoStatus::Completed)
+            .count();
+        queue!(
+            output,
+            style::SetForegroundColor(Color::Magenta),
+            style::Print(format!("Todos ({}/{} completed)\n", completed, self.items.len())),
+            style::ResetColor,
+        )?;
+        for item in &self.items {
+            let line = format!("{}. {}", item.id, item.description);
+            match item.status {
+                TodoStatus::Pending => queue!(output, style::Print(format!("  ☐ {line}\n")))?,
+                TodoStatus::InProgress => queue!(
+                    output,
+                    style::Print("  ☐ ".yellow()),
+                    style::Print(line.yellow()),
+                    style::Print(" (in progress)\n".dark_grey()),
+                )?,
+                TodoStatus::Completed => queue!(
+                    output,
+                    style::Print("  ☑ ".green()),
+                    style::Print(line.dark_grey().crossed_out()),
+                    style::Print("\n"),
+                )?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "command")]
+pub enum TodoCommand {
+    /// Replaces the list with new items.
+    #[serde(rename = "create")]
+    Create { items: Vec<String> },
+    #[serde(rename = "add")]
+    Add { items: Vec<String> },
+    #[serde(rename = "update")]
+    Update {
+        id: usize,
+        description: Option<String>,
+        status: Option<TodoStatus>,
+    },
+    #[serde(rename = "complete")]
+    Complete { ids: Vec<usize> },
+    #[serde(rename = "remove")]
+    Remove { ids: Vec<usize> },
+}
+
+/// Lets the model keep track of the steps of a multi-step task across turns.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TodoList {
+    #[serde(flatten)]
+    pub command: TodoCommand,
+
+    #[serde(skip)]
+    pub todos: SharedTodos,
+}
+
+impl TodoList {
+    pub fn set_todos(&mut self, todos: SharedTodos) {
+        self.todos = todos;
+    }
+
+    pub fn queue_description(&self, updates: &mut impl Write) -> Result<()> {
+        let description = match &self.command {
+            TodoCommand::Create { items } => format!("Creating a todo list with {} items", items.len()),
+            TodoCommand::Add { items } => format!("Adding {} items to the todo list", items.len()),
+            TodoCommand::Update { id, .. } => format!("Updating todo item {}", id),
+            TodoCommand::Complete { ids } => format!("Completing todo items {}", join_ids(ids)),
+            TodoCommand::Remove { ids } => format!("Removing todo items {}", join_ids(ids)),
+        };
+        queue!(updates, style::Print(description), style::Print("\n"))?;
+        Ok(())
+    }
+
+    pub async fn validate(&mut self, _ctx: &crate::platform::Context) -> Result<()> {
+        let todos = self
+            .todos
+            .lock()
+            .map_err(|err| eyre!("The todo list is unavailable: {err}"))?;
+        match &self.command {
+            TodoCommand::Create { items } | TodoCommand::Add { items } => {
+                if items.is_empty() || items.iter().any(|item| item.trim().is_empty()) {
+                    bail!("Todo items must not be empty");
+                }
+            },
+            TodoCommand::Update { id, .. } => {
+                if !todos.items.iter().any(|item| item.id == *id) {
+                    bail!("No todo item with id {}", id);
+                }
+            },
+            TodoCommand::Complete { ids } | TodoCommand::Remove { ids } => {
+                if let Some(id) = ids.iter().find(|id| !todos.items.iter().any(|item| item.id == **id)) {
+                    bail!("No todo item with id {}", id);
+                }
+            },
+        }
+        Ok(())
+    }
+
+    pub async fn invoke(&self, updates: &mut impl Write) -> Result<InvokeOutput> {
+        let mut todos = self
+            .todos
+            .lock()
+            .map_err(|err| eyre!("The todo list is unavailable: {err}"))?;
+        match &self.command {
+            TodoCommand::Create { items } => {
+                todos.clear();
+                for item in items {
+                    todos.add(item.trim());
+                }
+            },
+            TodoCommand::Add { items } => {
+                for item in items {
+                    todos.add(item.trim());
+                }
+            },
+            TodoCommand::Update {
+                id,
+                description,
+                status,
+            } => {
+                let item = todos.get_mut(*id)?;
+                if let Some(description) = description {
+                    item.description = description.trim().to_string();
+                }
+                if let Some(status) = status {
+                    item.status = *status;
+                }
+            },
+            TodoCommand::Complete { ids } => {
+                for id in ids {
+                    todos.get_mut(*id)?.status = TodoStatus::Completed;
+                }
+            },
+            TodoCommand::Remove { ids } => {
+                for id in ids {
+                    todos.remove(*id)?;
+                }
+            },
+        }
+
+        todos.queue_checklist(updates)?;
+        Ok(InvokeOutput {
+            output: OutputKind::Text(if todos.is_empty() {
+                "The todo list is empty.".to_string()
+            } else {
+                todos.to_checklist()
+            }),
+        })
+    }
+}
+
+fn join_ids(ids: &[usize]) -> String {
+    ids.iter().map(|id| id.to_string()).collect::<Vec<_>>().join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn todo_list(value: serde_json::Value, todos: &SharedTodos) -> TodoList {
+        let mut tool = serde_json::from_value::<TodoList>(value).unwrap();
+        tool.set_todos(Arc::clone(todos));
+        tool
+    }
+
+    #[tokio::test]
+    async fn test_todo_list() {
+        let ctx = crate::platform::Context::builder()
+            .with_test_home()
+            .await
+            .unwrap()
+            .build_fake();
+        let todos = SharedTodos::default();
+        let mut output = Vec::new();
+
+        let mut tool = todo_list(
+            serde_json::json!({ "command": "create", "items": ["Read the code", "Write the fix", "Run the tests"] }),
+            &todos,
+        );
+        tool.validate(&ctx).await.unwrap();
+        tool.invoke(&mut output).await.unwrap();
+
+        let mut tool = todo_list(
+            serde_json::json!({ "command": "update", "id": 2, "status": "in_progress" }),
+            &todos,
+        );
+        tool.validate(&ctx).await.unwrap();
+        tool.invoke(&mut output).await.unwrap();
+
+        let tool = todo_list(serde_json::json!({ "command": "complete", "ids": [1] }), &todos);
+        let result = tool.invoke(&mut output).await.unwrap();
+        assert_eq!(
+            result.as_str(),
+            "[x] 1. Read the code\n[~] 2. Write the fix\n[ ] 3. Run the tests"
+        );
+
+        let tool = todo_list(serde_json::json!({ "command": "remove", "ids": [3] }), &todos);
+        tool.invoke(&mut output).await.unwrap();
+        let mut tool = todo_list(serde_json::json!({ "command": "add", "items": ["Open a PR"] }), &todos);
+        tool.validate(&ctx).await.unwrap();
+        let result = tool.invoke(&mut output).await.unwrap();
+        assert_eq!(
+            result.as_str(),
+            "[x] 1. Read the code\n[~] 2. Write the fix\n[ ] 4. Open a PR"
+        );
+        assert!(todos.lock().unwrap().has_open_items());
+
+        let mut tool = todo_list(serde_json::json!({ "command": "complete", "ids": [2, 3] }), &todos);
+        assert!(tool.validate(&ctx).await.is_err());
+        let mut tool = todo_list(serde_json::json!({ "command": "add", "items": [" "] }), &todos);
+        assert!(tool.validate(&ctx).await.is_err());
+    }
+
+    #[test]
+    fn test_todos_serialization() {
+        let mut todos = Todos::default();
+        todos.add("First");
+        todos.get_mut(1).unwrap().status = TodoStatus::Completed;
+
+        let todos: Todos = serde_json::from_str(&serde_json::to_string(&todos).unwrap()).unwrap();
+        assert_eq!(todos.items()[0].status, TodoStatus::Completed);
+        assert_eq!(todos.to_checklist(), "[x] 1. First");
+    }
+}
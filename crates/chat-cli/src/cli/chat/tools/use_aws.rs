@@ -1,3 +1,5 @@
+mod native;
+
 use std::collections::HashMap;
 use std::io::Write;
 use std::process::Stdio;
@@ -14,9 +16,16 @@ use crossterm::{
 use eyre::{
     Result,
     WrapErr,
+    bail,
+    eyre,
+};
+use native::{
+    NativeRequest,
+    ServiceModel,
 };
 use serde::Deserialize;
 
+use super::super::util::truncate_safe;
 use super::{
     InvokeOutput,
     MAX_TOOL_RESPONSE_SIZE,
@@ -24,6 +33,8 @@ use super::{
 };
 use crate::platform::Context;
 
+/// Operation name prefixes considered read-only for services whose model in
+/// `service_models.json` doesn't mark read-only operations, or that have no model.
 const READONLY_OPS: [&str; 6] = ["get", "describe", "list", "ls", "search", "batch_get"];
 
 /// The environment variable name where we set additional metadata for the AWS CLI user agent.
@@ -42,14 +53,76 @@ pub struct UseAws {
     pub region: String,
     pub profile_name: Option<String>,
     pub label: Option<String>,
+    /// Maximum number of results to collect across pages.
+    pub max_results: Option<usize>,
+    /// Checks whether the call would succeed without making any changes.
+    pub dry_run: Option<bool>,
+    /// JMESPath expression the output is projected with.
+    pub query: Option<String>,
+
+    /// Whether to call the service in-process rather than through the AWS CLI, configured with the
+    /// `chat.useAws.native` setting.
+    #[serde(skip_deserializing)]
+    pub prefer_native: bool,
 }
 
 impl UseAws {
+    pub fn set_prefer_native(&mut self, prefer_native: bool) {
+        self.prefer_native = prefer_native;
+    }
+
     pub fn requires_acceptance(&self) -> bool {
-        !READONLY_OPS.iter().any(|op| self.operation_name.starts_with(op))
+        let operation = native::operation_name(&self.operation_name);
+        match native::service_model(&self.service_name) {
+            // A dry run only checks permissions for operations with a `DryRun` parameter.
+            Some(model) if self.is_dry_run() && model.supports_dry_run(&operation) => false,
+            Some(model) => match model.is_read_only(&operation) {
+                Some(read_only) => !read_only,
+                None => model.is_sensitive(&operation) || !self.has_read_only_name(),
+            },
+            None => !self.has_read_only_name(),
+        }
+    }
+
+    fn has_read_only_name(&self) -> bool {
+        READONLY_OPS.iter().any(|op| self.operation_name.starts_with(op))
+    }
+
+    /// Whether the operation takes a `DryRun` parameter, which the AWS CLI's `--dry-run` sets.
+    fn supports_dry_run(&self) -> bool {
+        native::service_model(&self.service_name)
+            .is_some_and(|model| model.supports_dry_run(&native::operation_name(&self.operation_name)))
+    }
+
+    /// Whether the AWS CLI can limit the results of the operation with `--max-items`.
+    fn is_paginated(&self) -> bool {
+        native::service_model(&self.service_name)
+            .is_some_and(|model| model.is_paginated(&native::operation_name(&self.operation_name)))
+    }
+
+    fn is_dry_run(&self) -> bool {
+        self.dry_run.unwrap_or(false)
     }
 
-    pub async fn invoke(&self, _ctx: &Context, _updates: impl Write) -> Result<InvokeOutput> {
+    /// Returns the model of the service if it should be called in-process, which is the case if
+    /// the user prefers it or the AWS CLI isn't installed.
+    fn native_model(&self, ctx: &Context) -> Option<&'static ServiceModel> {
+        native::service_model(&self.service_name)
+            .filter(|model| model.supports_native() && (self.prefer_native || !aws_cli_installed(ctx)))
+    }
+
+    pub async fn invoke(&self, ctx: &Context, _updates: impl Write) -> Result<InvokeOutput> {
+        if let Some(model) = self.native_model(ctx) {
+            return self.invoke_native(ctx, model).await;
+        }
+        if self.is_dry_run() && !self.supports_dry_run() {
+            bail!(
+                "{} {} has no DryRun parameter, so it can't be checked without making the call",
+                self.service_name,
+                self.operation_name
+            );
+        }
+
         let mut command = tokio::process::Command::new("aws");
 
         // Set up environment variables
@@ -88,6 +161,15 @@ impl UseAws {
                 }
             }
         }
+        if let Some(max_results) = self.max_results.filter(|_| self.is_paginated()) {
+            command.arg("--max-items").arg(max_results.to_string());
+        }
+        if self.is_dry_run() {
+            command.arg("--dry-run");
+        }
+        if let Some(query) = &self.query {
+            command.arg("--query").arg(query);
+        }
         let output = command
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
@@ -120,7 +202,8 @@ impl UseAws {
             }
         );
 
-        if status.eq("0") {
+        // A successful dry run is reported by the CLI as a DryRunOperation error.
+        if status.eq("0") || (self.is_dry_run() && stderr.contains("(DryRunOperation)")) {
             Ok(InvokeOutput {
                 output: OutputKind::Json(serde_json::json!({
                     "exit_status": status,
@@ -133,6 +216,42 @@ impl UseAws {
         }
     }
 
+    async fn invoke_native(&self, ctx: &Context, model: &ServiceModel) -> Result<InvokeOutput> {
+        let operation = native::operation_name(&self.operation_name);
+        let body = native::request_body(model, &operation, self.parameters.as_ref())?;
+        let request = NativeRequest {
+            model,
+            operation,
+            body,
+            region: &self.region,
+            profile_name: self.profile_name.as_deref(),
+            max_results: self.max_results,
+        };
+        if self.is_dry_run() {
+            return Ok(InvokeOutput {
+                output: OutputKind::Json(request.preview(ctx)),
+            });
+        }
+
+        let mut output = request.send(ctx).await?;
+        if let Some(query) = &self.query {
+            output = search(query, &output)?;
+        }
+
+        let serialized = serde_json::to_string(&output)?;
+        if serialized.len() > MAX_TOOL_RESPONSE_SIZE {
+            return Ok(InvokeOutput {
+                output: OutputKind::Text(format!(
+                    "{} ... truncated, use max_results or query to reduce the output",
+                    truncate_safe(&serialized, MAX_TOOL_RESPONSE_SIZE)
+                )),
+            });
+        }
+        Ok(InvokeOutput {
+            output: OutputKind::Json(output),
+        })
+    }
+
     pub fn queue_description(&self, updates: &mut impl Write) -> Result<()> {
         queue!(
             updates,
@@ -162,6 +281,16 @@ impl UseAws {
 
         queue!(updates, style::Print(format!("Region: {}", self.region)))?;
 
+        if let Some(max_results) = self.max_results {
+            queue!(updates, style::Print(format!("\nMax results: {}", max_results)))?;
+        }
+        if let Some(ref query) = self.query {
+            queue!(updates, style::Print(format!("\nQuery: {}", query)))?;
+        }
+        if self.is_dry_run() {
+            queue!(updates, style::Print("\nDry run: no changes will be made"))?;
+        }
+
         if let Some(ref label) = self.label {
             queue!(updates, style::Print(format!("\nLabel: {}", label)))?;
         }
@@ -189,6 +318,27 @@ impl UseAws {
     }
 }
 
+fn aws_cli_installed(ctx: &Context) -> bool {
+    let Some(path) = ctx.env().get_os("PATH") else {
+        return false;
+    };
+    let names: &[&str] = if cfg!(windows) {
+        &["aws.exe", "aws.cmd"]
+    } else {
+        &["aws"]
+    };
+    std::env::split_paths(&path).any(|dir| names.iter().any(|name| dir.join(name).is_file()))
+}
+
+/// Projects `data` with the JMESPath expression `query`, like the AWS CLI's `--query`.
+fn search(query: &str, data: &serde_json::Value) -> Result<serde_json::Value> {
+    let expression = jmespath::compile(query).map_err(|err| eyre!("Invalid query '{}': {}", query, err))?;
+    let result = expression
+        .search(data)
+        .map_err(|err| eyre!("Unable to evaluate query '{}': {}", query, err))?;
+    Ok(serde_json::to_value(&*result)?)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -227,6 +377,114 @@ mod tests {
         assert!(cmd.requires_acceptance());
     }
 
+    #[test]
+    fn test_requires_acceptance_from_service_model() {
+        let cmd = use_aws! {{
+            "service_name": "dynamodb",
+            "operation_name": "query",
+            "region": "us-west-2",
+            "label": ""
+        }};
+        assert!(!cmd.requires_acceptance());
+        // Starts with a read-only prefix, but reveals the secret.
+        let cmd = use_aws! {{
+            "service_name": "secretsmanager",
+            "operation_name": "get-secret-value",
+            "region": "us-west-2",
+            "label": ""
+        }};
+        assert!(cmd.requires_acceptance());
+        let cmd = use_aws! {{
+            "service_name": "ec2",
+            "operation_name": "terminate-instances",
+            "region": "us-west-2",
+            "label": ""
+        }};
+        assert!(cmd.requires_acceptance());
+        let cmd = use_aws! {{
+            "service_name": "ec2",
+            "operation_name": "terminate-instances",
+            "dry_run": true,
+            "region": "us-west-2",
+            "label": ""
+        }};
+        assert!(!cmd.requires_acceptance());
+        // Services without a `DryRun` parameter can't check a call without making it.
+        let cmd = use_aws! {{
+            "service_name": "dynamodb",
+            "operation_name": "delete-table",
+            "dry_run": true,
+            "region": "us-west-2",
+            "label": ""
+        }};
+        assert!(cmd.requires_acceptance());
+        let cmd = use_aws! {{
+            "service_name": "s3",
+            "operation_name": "delete-bucket",
+            "dry_run": true,
+            "region": "us-west-2",
+            "label": ""
+        }};
+        assert!(cmd.requires_acceptance());
+    }
+
+    #[tokio::test]
+    async fn test_invoke_dry_run_without_parameter() {
+        let ctx = Context::builder().with_test_home().await.unwrap().build_fake();
+        let cmd = use_aws! {{
+            "service_name": "s3",
+            "operation_name": "delete-bucket",
+            "parameters": { "bucket": "logs" },
+            "dry_run": true,
+            "region": "us-west-2",
+            "label": ""
+        }};
+        let err = cmd.invoke(&ctx, &mut std::io::stdout()).await.unwrap_err();
+        assert!(err.to_string().contains("no DryRun parameter"));
+    }
+
+    #[tokio::test]
+    async fn test_invoke_native() {
+        let mut server = mockito::Server::new_async().await;
+        let ctx = Context::builder()
+            .with_test_home()
+            .await
+            .unwrap()
+            .with_env_var("AWS_ENDPOINT_URL", &server.url())
+            .with_env_var("AWS_ACCESS_KEY_ID", "AKIDEXAMPLE")
+            .with_env_var("AWS_SECRET_ACCESS_KEY", "secret")
+            .build_fake();
+        let mock = server
+            .mock("POST", "/")
+            .match_header("x-amz-target", "Logs_20140328.DescribeLogGroups")
+            .match_header("content-type", "application/x-amz-json-1.1")
+            .match_body(mockito::Matcher::Json(serde_json::json!({ "LogGroupNamePrefix": "/aws/lambda" })))
+            .with_body(r#"{"logGroups": [{"logGroupName": "/aws/lambda/a", "storedBytes": 10}, {"logGroupName": "/aws/lambda/b", "storedBytes": 0}]}"#)
+            .create_async()
+            .await;
+
+        let mut cmd = use_aws! {{
+            "service_name": "logs",
+            "operation_name": "describe-log-groups",
+            "parameters": { "log-group-name-prefix": "/aws/lambda" },
+            "query": "logGroups[?storedBytes > `0`].logGroupName",
+            "region": "us-west-2",
+            "label": ""
+        }};
+        cmd.set_prefer_native(true);
+        let output = cmd.invoke(&ctx, &mut std::io::stdout()).await.unwrap();
+        assert!(matches!(output.output, OutputKind::Json(json) if json == serde_json::json!(["/aws/lambda/a"])));
+        mock.assert_async().await;
+
+        // Dry runs only describe the request.
+        cmd.dry_run = Some(true);
+        let output = cmd.invoke(&ctx, &mut std::io::stdout()).await.unwrap();
+        assert!(
+            matches!(output.output, OutputKind::Json(json) if json["X-Amz-Target"] == "Logs_20140328.DescribeLogGroups")
+        );
+        mock.assert_async().await;
+    }
+
     #[test]
     fn test_use_aws_deser() {
         let cmd = use_aws! {{
@@ -0,0 +1,752 @@
+//! In-process execution of `use_aws` calls for services using the AWS JSON protocol, without
+//! requiring the AWS CLI to be installed.
+
+use std::collections::HashMap;
+use std::iter::Peekable;
+use std::str::Chars;
+use std::sync::LazyLock;
+use std::time::{
+    Duration,
+    SystemTime,
+};
+
+use aws_config::profile::ProfileFileCredentialsProvider;
+use aws_credential_types::Credentials;
+use aws_credential_types::provider::ProvideCredentials;
+use aws_sigv4::http_request::{
+    SignableBody,
+    SignableRequest,
+    SigningSettings,
+    sign,
+};
+use aws_sigv4::sign::v4;
+use aws_smithy_runtime_api::client::identity::Identity;
+use convert_case::{
+    Case,
+    Casing,
+};
+use eyre::{
+    Result,
+    bail,
+    eyre,
+};
+use serde::Deserialize;
+use serde_json::{
+    Map,
+    Number,
+    Value,
+};
+use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
+use tracing::debug;
+
+use crate::api_client::credentials::CredentialsChain;
+use crate::platform::Context;
+
+/// Results collected across pages when `max_results` isn't given.
+pub const DEFAULT_MAX_RESULTS: usize = 1000;
+
+/// Upper bound on the number of pages requested for a single call, in case a service keeps
+/// returning pagination tokens for empty pages.
+const MAX_PAGES: usize = 100;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Generated from the botocore service models by `build-scripts/use_aws_models.py`.
+static SERVICE_MODELS: LazyLock<HashMap<String, ServiceModel>> = LazyLock::new(|| {
+    serde_json::from_str(include_str!("service_models.json")).expect("service_models.json is valid json")
+});
+
+/// The subset of a service's model needed to classify and call its operations.
+#[derive(Debug, Deserialize)]
+pub struct ServiceModel {
+    /// The wire protocol of the service. Only `json` services can be called natively.
+    pub protocol: String,
+    pub endpoint_prefix: Option<String>,
+    pub signing_name: Option<String>,
+    pub json_version: Option<String>,
+    pub target_prefix: Option<String>,
+    /// Operations that don't modify any resources or reveal secrets, as marked by the `readonly`
+    /// trait or HTTP method of the operation. Unset if the model doesn't mark them.
+    #[serde(default)]
+    pub read_only_operations: Option<Vec<String>>,
+    /// Operations that return secrets or credentials.
+    #[serde(default)]
+    pub sensitive_operations: Vec<String>,
+    /// Operations the AWS CLI can limit with `--max-items`.
+    #[serde(default)]
+    pub paginated_operations: Vec<String>,
+    /// Operations taking a `DryRun` parameter that checks permissions without making changes.
+    #[serde(default)]
+    pub dry_run_operations: Vec<String>,
+    /// Paginators simple enough to be followed in-process.
+    #[serde(default)]
+    pub paginators: HashMap<String, Paginator>,
+    /// The name of the input shape of each operation.
+    #[serde(default)]
+    pub input_shapes: HashMap<String, String>,
+    /// The structures, lists and maps the operation inputs are made of. Their members reference
+    /// scalar shapes by type, e.g. `integer`, and the others by name.
+    #[serde(default)]
+    pub shapes: HashMap<String, Shape>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Paginator {
+    pub input_token: String,
+    pub output_token: String,
+    pub result_key: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Shape {
+    Structure { members: HashMap<String, String> },
+    List { member: String },
+    Map { value: String },
+}
+
+impl ServiceModel {
+    /// Whether the operation is read-only, or `None` if the model doesn't say.
+    pub fn is_read_only(&self, operation: &str) -> Option<bool> {
+        self.read_only_operations
+            .as_ref()
+            .map(|operations| operations.iter().any(|op| *op == operation))
+    }
+
+    pub fn is_sensitive(&self, operation: &str) -> bool {
+        self.sensitive_operations.iter().any(|op| *op == operation)
+    }
+
+    pub fn is_paginated(&self, operation: &str) -> bool {
+        self.paginated_operations.iter().any(|op| *op == operation)
+    }
+
+    pub fn supports_dry_run(&self, operation: &str) -> bool {
+        self.dry_run_operations.iter().any(|op| *op == operation)
+    }
+
+    pub fn supports_native(&self) -> bool {
+        self.protocol == "json"
+            && self.endpoint_prefix.is_some()
+            && self.signing_name.is_some()
+            && self.json_version.is_some()
+            && self.target_prefix.is_some()
+    }
+
+    /// Coerces the members of `object` to the shapes in `members`. Unknown members are left as is.
+    fn coerce_members(
+        &self,
+        members: &HashMap<String, String>,
+        object: Map<String, Value>,
+    ) -> Result<Map<String, Value>> {
+        object
+            .into_iter()
+            .map(|(name, value)| {
+                let value = match members.get(&name) {
+                    Some(shape) => self.coerce(shape, &name, value)?,
+                    None => value,
+                };
+                Ok((name, value))
+            })
+            .collect()
+    }
+
+    /// Coerces `value`, given for the member `name`, to `shape`. Strings are parsed the way the AWS
+    /// CLI parses its arguments, e.g. `10` for an integer or shorthand syntax like `Key=a,Size=1`
+    /// for a structure.
+    fn coerce(&self, shape: &str, name: &str, value: Value) -> Result<Value> {
+        match (shape, value) {
+            ("integer" | "long", Value::String(s)) => s
+                .trim()
+                .parse::<i64>()
+                .map(Value::from)
+                .map_err(|err| eyre!("Invalid value '{s}' for {name}, expected an integer: {err}")),
+            ("double" | "float", Value::String(s)) => s
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .and_then(Number::from_f64)
+                .map(Value::Number)
+                .ok_or_else(|| eyre!("Invalid value '{s}' for {name}, expected a number")),
+            ("boolean", Value::String(s)) => match s.trim().to_ascii_lowercase().as_str() {
+                "" | "true" => Ok(Value::Bool(true)),
+                "false" => Ok(Value::Bool(false)),
+                _ => bail!("Invalid value '{s}' for {name}, expected true or false"),
+            },
+            // Timestamps are sent as seconds since the epoch.
+            ("timestamp", Value::String(s)) => match s.trim().parse::<f64>() {
+                Ok(seconds) => Ok(Number::from_f64(seconds).map_or(Value::String(s), Value::Number)),
+                Err(_) => OffsetDateTime::parse(s.trim(), &Rfc3339)
+                    .map(|time| Value::from(time.unix_timestamp()))
+                    .map_err(|err| eyre!("Invalid value '{s}' for {name}, expected a timestamp: {err}")),
+            },
+            ("string", Value::Number(n)) => Ok(Value::String(n.to_string())),
+            ("string", Value::Bool(b)) => Ok(Value::String(b.to_string())),
+            (shape, value) => match (self.shapes.get(shape), value) {
+                (Some(Shape::Structure { members }), Value::Object(object)) => {
+                    Ok(Value::Object(self.coerce_members(members, object)?))
+                },
+                (Some(Shape::Map { value: value_shape }), Value::Object(object)) => object
+                    .into_iter()
+                    .map(|(key, value)| Ok((key, self.coerce(value_shape, name, value)?)))
+                    .collect::<Result<_>>()
+                    .map(Value::Object),
+                (Some(Shape::List { member }), Value::Array(items)) => items
+                    .into_iter()
+                    .map(|item| self.coerce(member, name, item))
+                    .collect::<Result<_>>()
+                    .map(Value::Array),
+                // Like on the command line, items are separated by spaces. Lists of scalars may
+                // also separate them by commas.
+                (Some(Shape::List { member }), Value::String(s)) => {
+                    let items = if self.shapes.contains_key(member) {
+                        split_shorthand_items(&s)
+                    } else {
+                        s.split(|c: char| c == ',' || c.is_whitespace())
+                            .filter(|item| !item.is_empty())
+                            .collect()
+                    };
+                    items
+                        .into_iter()
+                        .map(|item| self.coerce(member, name, Value::String(item.to_string())))
+                        .collect::<Result<_>>()
+                        .map(Value::Array)
+                },
+                (Some(Shape::Structure { .. } | Shape::Map { .. }), Value::String(s)) => {
+                    let value = parse_shorthand(&s).map_err(|err| eyre!("Invalid value '{s}' for {name}: {err}"))?;
+                    self.coerce(shape, name, value)
+                },
+                (_, value) => Ok(value),
+            },
+        }
+    }
+}
+
+/// Returns the model for the service with the given CLI name, e.g. `dynamodb` or `logs`.
+pub fn service_model(service_name: &str) -> Option<&'static ServiceModel> {
+    SERVICE_MODELS.get(service_name)
+}
+
+/// Converts a CLI operation name like `list-tables` to its API name, `ListTables`.
+pub fn operation_name(cli_name: &str) -> String {
+    cli_name.to_case(Case::Pascal)
+}
+
+/// Converts CLI style parameters to the JSON request body of the operation.
+///
+/// Keys are converted to PascalCase, flags with an empty value become `true`, and string values
+/// holding JSON objects or arrays are parsed. The values are then coerced to the types of the
+/// operation's input shape.
+pub fn request_body(
+    model: &ServiceModel,
+    operation: &str,
+    parameters: Option<&HashMap<String, Value>>,
+) -> Result<Map<String, Value>> {
+    let mut body = Map::new();
+    for (name, value) in parameters.into_iter().flatten() {
+        let name = name.trim_start_matches("--");
+        let (name, value) = match value {
+            Value::String(s) if s.is_empty() => (name, Value::Bool(true)),
+            Value::String(s) if s.trim_start().starts_with(['{', '[']) => {
+                (name, serde_json::from_str(s).unwrap_or_else(|_| value.clone()))
+            },
+            _ => (name, value.clone()),
+        };
+        body.insert(name.to_case(Case::Pascal), value);
+    }
+
+    match model
+        .input_shapes
+        .get(operation)
+        .and_then(|input| model.shapes.get(input))
+    {
+        Some(Shape::Structure { members }) => model.coerce_members(members, body),
+        _ => Ok(body),
+    }
+}
+
+/// Splits a list given in shorthand syntax into its items, which are separated by spaces outside
+/// of brackets and quotes, e.g. `Name=a,Values=[1,2] Name=b`.
+fn split_shorthand_items(s: &str) -> Vec<&str> {
+    let mut items = Vec::new();
+    let (mut depth, mut quote, mut start) = (0_usize, None, 0);
+    for (i, c) in s.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => (),
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '[' | '{') => depth += 1,
+            (None, ']' | '}') => depth = depth.saturating_sub(1),
+            (None, c) if c.is_whitespace() && depth == 0 => {
+                items.push(&s[start..i]);
+                start = i + c.len_utf8();
+            },
+            _ => (),
+        }
+    }
+    items.push(&s[start..]);
+    items.retain(|item| !item.trim().is_empty());
+    items
+}
+
+/// Parses a structure or map in the AWS CLI shorthand syntax, e.g.
+/// `Name=a,Values=[1,2],Config={Size=1}`. Values are parsed as strings, which are coerced to their
+/// shapes afterwards.
+fn parse_shorthand(s: &str) -> Result<Value> {
+    let mut chars = s.trim().chars().peekable();
+    shorthand_pairs(&mut chars, None)
+}
+
+fn shorthand_pairs(chars: &mut Peekable<Chars<'_>>, end: Option<char>) -> Result<Value> {
+    let mut object = Map::new();
+    if end.is_some() && chars.peek().copied() == end {
+        chars.next();
+        return Ok(Value::Object(object));
+    }
+    loop {
+        let key = shorthand_scalar(chars, &['=', ',']);
+        if chars.next() != Some('=') {
+            bail!("expected '=' after '{}'", key.trim());
+        }
+        let value = shorthand_value(chars)?;
+        object.insert(key.trim().to_string(), value);
+        match chars.next() {
+            Some(',') => (),
+            next if next == end => return Ok(Value::Object(object)),
+            Some(c) => bail!("unexpected '{c}'"),
+            None => bail!("missing '{}'", end.unwrap_or_default()),
+        }
+    }
+}
+
+fn shorthand_value(chars: &mut Peekable<Chars<'_>>) -> Result<Value> {
+    while chars.next_if(|c| c.is_whitespace()).is_some() {}
+    match chars.peek() {
+        Some('[') => {
+            chars.next();
+            let mut items = Vec::new();
+            if chars.next_if_eq(&']').is_some() {
+                return Ok(Value::Array(items));
+            }
+            loop {
+                items.push(shorthand_value(chars)?);
+                match chars.next() {
+                    Some(',') => (),
+                    Some(']') => return Ok(Value::Array(items)),
+                    _ => bail!("missing ']'"),
+                }
+            }
+        },
+        Some('{') => {
+            chars.next();
+            shorthand_pairs(chars, Some('}'))
+        },
+        Some(&quote @ ('"' | '\'')) => {
+            chars.next();
+            let value = chars.by_ref().take_while(|c| *c != quote).collect();
+            Ok(Value::String(value))
+        },
+        _ => Ok(Value::String(
+            shorthand_scalar(chars, &[',', ']', '}']).trim().to_string(),
+        )),
+    }
+}
+
+/// Takes characters up to, but not including, any of `stops`.
+fn shorthand_scalar(chars: &mut Peekable<Chars<'_>>, stops: &[char]) -> String {
+    let mut scalar = String::new();
+    while let Some(c) = chars.next_if(|c| !stops.contains(c)) {
+        scalar.push(c);
+    }
+    scalar
+}
+
+/// A call to an operation of a JSON protocol service.
+#[derive(Debug)]
+pub struct NativeRequest<'a> {
+    pub model: &'a ServiceModel,
+    pub operation: String,
+    pub body: Map<String, Value>,
+    pub region: &'a str,
+    pub profile_name: Option<&'a str>,
+    pub max_results: Option<usize>,
+}
+
+impl NativeRequest<'_> {
+    fn endpoint(&self, ctx: &Context) -> String {
+        if let Ok(endpoint) = ctx.env().get("AWS_ENDPOINT_URL") {
+            return endpoint;
+        }
+        let suffix = if self.region.starts_with("cn-") {
+            "amazonaws.com.cn"
+        } else {
+            "amazonaws.com"
+        };
+        format!(
+            "https://{}.{}.{}/",
+            self.model.endpoint_prefix.as_deref().unwrap_or_default(),
+            self.region,
+            suffix
+        )
+    }
+
+    fn target(&self) -> String {
+        format!(
+            "{}.{}",
+            self.model.target_prefix.as_deref().unwrap_or_default(),
+            self.operation
+        )
+    }
+
+    fn content_type(&self) -> String {
+        format!(
+            "application/x-amz-json-{}",
+            self.model.json_version.as_deref().unwrap_or("1.1")
+        )
+    }
+
+    /// Describes the request that would be sent, without sending it.
+    pub fn preview(&self, ctx: &Context) -> Value {
+        serde_json::json!({
+            "DryRun": true,
+            "Method": "POST",
+            "Endpoint": self.endpoint(ctx),
+            "X-Amz-Target": self.target(),
+            "Body": self.body,
+        })
+    }
+
+    /// Sends the request, following pagination tokens until `max_results` results are collected
+    /// for operations that have a paginator.
+    pub async fn send(&self, ctx: &Context) -> Result<Value> {
+        if !self.model.supports_native() {
+            bail!(
+                "The {} protocol is not supported without the AWS CLI",
+                self.model.protocol
+            );
+        }
+
+        let credentials = credentials(ctx, self.profile_name).await?;
+        let Some(paginator) = self.model.paginators.get(&self.operation) else {
+            return self.send_page(ctx, &credentials, &self.body).await;
+        };
+
+        let max_results = self.max_results.unwrap_or(DEFAULT_MAX_RESULTS);
+        let mut body = self.body.clone();
+        let mut output = Map::new();
+        let mut results = Vec::new();
+        let mut next_token = None;
+        for _ in 0..MAX_PAGES {
+            let Value::Object(mut page) = self.send_page(ctx, &credentials, &body).await? else {
+                bail!("Expected a JSON object in the response");
+            };
+            if let Some(Value::Array(page_results)) = page.remove(&paginator.result_key) {
+                results.extend(page_results);
+            }
+            next_token = page.remove(&paginator.output_token).filter(|token| !token.is_null());
+            if output.is_empty() {
+                output = page;
+            }
+
+            match &next_token {
+                Some(token) if results.len() < max_results => {
+                    body.insert(paginator.input_token.clone(), token.clone());
+                },
+                _ => break,
+            }
+        }
+
+        // The token can only be used to resume if no results of the last page are dropped.
+        if results.len() > max_results {
+            results.truncate(max_results);
+            next_token = None;
+            output.insert("Truncated".to_string(), Value::Bool(true));
+        }
+        output.insert(paginator.result_key.clone(), Value::Array(results));
+        if let Some(token) = next_token {
+            output.insert(paginator.output_token.clone(), token);
+        }
+        Ok(Value::Object(output))
+    }
+
+    async fn send_page(&self, ctx: &Context, credentials: &Credentials, body: &Map<String, Value>) -> Result<Value> {
+        let url = self.endpoint(ctx);
+        let body = serde_json::to_vec(body)?;
+        let headers = [("content-type", self.content_type()), ("x-amz-target", self.target())];
+
+        let identity: Identity = credentials.clone().into();
+        let signing_params = v4::SigningParams::builder()
+            .identity(&identity)
+            .region(self.region)
+            .name(self.model.signing_name.as_deref().unwrap_or_default())
+            .time(SystemTime::now())
+            .settings(SigningSettings::default())
+            .build()?
+            .into();
+        let signable_request = SignableRequest::new(
+            "POST",
+            &url,
+            headers.iter().map(|(name, value)| (*name, value.as_str())),
+            SignableBody::Bytes(&body),
+        )?;
+        let (signing_instructions, _) = sign(signable_request, &signing_params)?.into_parts();
+
+        let client = crate::request::new_client()?;
+        let mut request = client.post(&url).timeout(REQUEST_TIMEOUT).body(body);
+        for (name, value) in headers.iter().map(|(name, value)| (*name, value.as_str())) {
+            request = request.header(name, value);
+        }
+        for (name, value) in signing_instructions.headers() {
+            request = request.header(name, value);
+        }
+
+        debug!(target = %self.target(), %url, "sending native use_aws request");
+        let response = request.send().await?;
+        let status = response.status();
+        let text = response.text().await?;
+        let output = if text.trim().is_empty() {
+            Value::Object(Map::new())
+        } else {
+            serde_json::from_str(&text).map_err(|err| eyre!("Invalid JSON in response: {}", err))?
+        };
+
+        if !status.is_success() {
+            bail!("{}", service_error(status.as_u16(), &output));
+        }
+        Ok(output)
+    }
+}
+
+/// Formats an error response like the AWS CLI does, e.g.
+/// `An error occurred (ResourceNotFoundException): Requested resource not found`.
+fn service_error(status: u16, output: &Value) -> String {
+    let code = output
+        .get("__type")
+        .and_then(Value::as_str)
+        .map_or_else(
+            || format!("HTTP {status}"),
+            |code| code.rsplit('#').next().unwrap_or(code).to_string(),
+        );
+    let message = output
+        .get("message")
+        .or_else(|| output.get("Message"))
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    format!("An error occurred ({code}): {message}")
+}
+
+async fn credentials(ctx: &Context, profile_name: Option<&str>) -> Result<Credentials> {
+    if profile_name.is_none() {
+        if let (Ok(access_key_id), Ok(secret_access_key)) = (
+            ctx.env().get("AWS_ACCESS_KEY_ID"),
+            ctx.env().get("AWS_SECRET_ACCESS_KEY"),
+        ) {
+            return Ok(Credentials::new(
+                access_key_id,
+                secret_access_key,
+                ctx.env().get("AWS_SESSION_TOKEN").ok(),
+                None,
+                "Environment",
+            ));
+        }
+    }
+
+    let credentials = match profile_name {
+        Some(profile_name) => {
+            ProfileFileCredentialsProvider::builder()
+                .profile_name(profile_name)
+                .build()
+                .provide_credentials()
+                .await
+        },
+        None => CredentialsChain::new().await.provide_credentials().await,
+    };
+    credentials.map_err(|err| eyre!("Unable to load AWS credentials: {}", err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_service_models() {
+        let dynamodb = service_model("dynamodb").unwrap();
+        assert!(dynamodb.supports_native());
+        assert_eq!(dynamodb.is_read_only(&operation_name("batch-get-item")), None);
+        assert!(dynamodb.paginators.contains_key("ListTables"));
+        assert!(dynamodb.is_paginated("Scan"));
+        assert!(!dynamodb.supports_dry_run("PutItem"));
+
+        let ec2 = service_model("ec2").unwrap();
+        assert!(!ec2.supports_native());
+        assert!(ec2.supports_dry_run("TerminateInstances"));
+        assert!(!ec2.is_paginated("TerminateInstances"));
+        assert!(service_model("secretsmanager").unwrap().is_sensitive("GetSecretValue"));
+        let lambda = service_model("lambda").unwrap();
+        assert_eq!(lambda.is_read_only(&operation_name("get-function")), Some(true));
+        assert_eq!(lambda.is_read_only(&operation_name("delete-function")), Some(false));
+        assert!(service_model("not-a-service").is_none());
+
+        for (name, model) in SERVICE_MODELS.iter().filter(|(_, model)| model.supports_native()) {
+            for (operation, input) in &model.input_shapes {
+                assert!(
+                    model.shapes.contains_key(input),
+                    "{name} {operation} has no input shape"
+                );
+            }
+        }
+
+        for (name, model) in SERVICE_MODELS.iter() {
+            for operation in model.paginators.keys() {
+                assert!(
+                    model.is_read_only(operation) != Some(false) && !model.is_sensitive(operation),
+                    "{name} {operation} is paginated but not read only"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_request_body() {
+        let parameters = serde_json::from_value::<HashMap<String, Value>>(serde_json::json!({
+            "table-name": "orders",
+            "--key-condition-expression": "pk = :pk",
+            "expression-attribute-values": "{\":pk\": {\"S\": \"1\"}}",
+            "consistent-read": "",
+            "Limit": "10"
+        }))
+        .unwrap();
+        let dynamodb = service_model("dynamodb").unwrap();
+
+        assert_eq!(
+            Value::Object(request_body(dynamodb, "Query", Some(&parameters)).unwrap()),
+            serde_json::json!({
+                "TableName": "orders",
+                "KeyConditionExpression": "pk = :pk",
+                "ExpressionAttributeValues": { ":pk": { "S": "1" } },
+                "ConsistentRead": true,
+                "Limit": 10
+            })
+        );
+
+        // Shorthand syntax and space separated lists, as on the command line.
+        let parameters = serde_json::from_value::<HashMap<String, Value>>(serde_json::json!({
+            "table-name": "orders",
+            "exclusive-start-key": "pk={S=1},sk={N=2}",
+            "attributes-to-get": "pk sk",
+            "total-segments": 2,
+            "scan-filter": "status={ComparisonOperator=IN,AttributeValueList=[{S=open},{S='on hold'}]}"
+        }))
+        .unwrap();
+        assert_eq!(
+            Value::Object(request_body(dynamodb, "Scan", Some(&parameters)).unwrap()),
+            serde_json::json!({
+                "TableName": "orders",
+                "ExclusiveStartKey": { "pk": { "S": "1" }, "sk": { "N": "2" } },
+                "AttributesToGet": ["pk", "sk"],
+                "TotalSegments": 2,
+                "ScanFilter": {
+                    "status": {
+                        "ComparisonOperator": "IN",
+                        "AttributeValueList": [{ "S": "open" }, { "S": "on hold" }]
+                    }
+                }
+            })
+        );
+
+        let parameters =
+            serde_json::from_value::<HashMap<String, Value>>(serde_json::json!({ "limit": "ten" })).unwrap();
+        assert!(request_body(dynamodb, "Scan", Some(&parameters)).is_err());
+        let parameters =
+            serde_json::from_value::<HashMap<String, Value>>(serde_json::json!({ "exclusive-start-key": "pk" }))
+                .unwrap();
+        assert!(request_body(dynamodb, "Scan", Some(&parameters)).is_err());
+    }
+
+    #[test]
+    fn test_split_shorthand_items() {
+        assert_eq!(split_shorthand_items("Name=a,Values=[1, 2]  Name='b c'"), vec![
+            "Name=a,Values=[1, 2]",
+            "Name='b c'"
+        ]);
+        assert!(split_shorthand_items(" ").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_send_paginates() {
+        let mut server = mockito::Server::new_async().await;
+        let ctx = Context::builder()
+            .with_test_home()
+            .await
+            .unwrap()
+            .with_env_var("AWS_ENDPOINT_URL", &server.url())
+            .with_env_var("AWS_ACCESS_KEY_ID", "AKIDEXAMPLE")
+            .with_env_var("AWS_SECRET_ACCESS_KEY", "secret")
+            .build_fake();
+
+        let first = server
+            .mock("POST", "/")
+            .match_header("x-amz-target", "DynamoDB_20120810.ListTables")
+            .match_header(
+                "authorization",
+                mockito::Matcher::Regex("^AWS4-HMAC-SHA256 ".to_string()),
+            )
+            .match_body(mockito::Matcher::Json(serde_json::json!({})))
+            .with_body(r#"{"TableNames": ["a", "b"], "LastEvaluatedTableName": "b"}"#)
+            .expect(2)
+            .create_async()
+            .await;
+        let second = server
+            .mock("POST", "/")
+            .match_body(mockito::Matcher::Json(
+                serde_json::json!({ "ExclusiveStartTableName": "b" }),
+            ))
+            .with_body(r#"{"TableNames": ["c", "d"], "LastEvaluatedTableName": "d"}"#)
+            .expect(2)
+            .create_async()
+            .await;
+
+        let model = service_model("dynamodb").unwrap();
+        let mut request = NativeRequest {
+            model,
+            operation: "ListTables".to_string(),
+            body: Map::new(),
+            region: "us-east-1",
+            profile_name: None,
+            max_results: Some(3),
+        };
+        assert_eq!(
+            request.send(&ctx).await.unwrap(),
+            serde_json::json!({ "TableNames": ["a", "b", "c"], "Truncated": true })
+        );
+
+        request.max_results = Some(4);
+        assert_eq!(
+            request.send(&ctx).await.unwrap(),
+            serde_json::json!({ "TableNames": ["a", "b", "c", "d"], "LastEvaluatedTableName": "d" })
+        );
+        first.assert_async().await;
+        second.assert_async().await;
+
+        server
+            .mock("POST", "/")
+            .match_header("x-amz-target", "DynamoDB_20120810.DescribeTable")
+            .with_status(400)
+            .with_body(r#"{"__type": "com.amazonaws.dynamodb.v20120810#ResourceNotFoundException", "message": "Requested resource not found"}"#)
+            .create_async()
+            .await;
+        let request = NativeRequest {
+            model,
+            operation: "DescribeTable".to_string(),
+            body: Map::new(),
+            region: "us-east-1",
+            profile_name: None,
+            max_results: None,
+        };
+        assert_eq!(
+            request.send(&ctx).await.unwrap_err().to_string(),
+            "An error occurred (ResourceNotFoundException): Requested resource not found"
+        );
+    }
+}
@@ -0,0 +1,437 @@
+use std::io::Write;
+use std::path::{
+    Path,
+    PathBuf,
+};
+use std::time::{
+    Duration,
+    SystemTime,
+};
+
+use crossterm::queue;
+use crossterm::style::{
+    self,
+    Color,
+};
+use eyre::{
+    Result,
+    bail,
+    eyre,
+};
+use serde::Deserialize;
+use sha2::{
+    Digest,
+    Sha256,
+};
+use tracing::{
+    debug,
+    warn,
+};
+use url::Url;
+
+use super::super::util::html::html_to_markdown;
+use super::super::util::truncate_safe;
+use super::{
+    InvokeOutput,
+    MAX_TOOL_RESPONSE_SIZE,
+    OutputKind,
+};
+use crate::platform::Context;
+use crate::util::directories;
+
+/// How long a fetched page is served from the on-disk cache.
+const CACHE_TTL: Duration = Duration::from_secs(15 * 60);
+
+/// How many pages are kept in the on-disk cache. The oldest are removed first.
+const MAX_CACHE_ENTRIES: usize = 100;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+const MAX_REDIRECTS: usize = 10;
+
+/// Response bodies are read up to this many bytes, the rest of the page is dropped.
+const MAX_BODY_SIZE: usize = 5 * 1024 * 1024;
+
+/// Domains the user allows or denies fetching from, configured with the
+/// `chat.webFetch.allowedDomains` and `chat.webFetch.deniedDomains` settings.
+///
+/// A domain matches its subdomains as well, e.g. `rust-lang.org` matches `doc.rust-lang.org`.
+#[derive(Debug, Clone, Default)]
+pub struct DomainFilter {
+    /// Domains that are fetched without asking the user first.
+    pub allowed: Vec<String>,
+    /// Domains that are never fetched. Takes precedence over `allowed`.
+    pub denied: Vec<String>,
+}
+
+impl DomainFilter {
+    pub fn is_allowed(&self, host: &str) -> bool {
+        !self.is_denied(host) && self.allowed.iter().any(|domain| domain_matches(domain, host))
+    }
+
+    pub fn is_denied(&self, host: &str) -> bool {
+        self.denied.iter().any(|domain| domain_matches(domain, host))
+    }
+}
+
+fn domain_matches(domain: &str, host: &str) -> bool {
+    let domain = domain.trim_start_matches("*.").trim_end_matches('.');
+    let host = host.trim_end_matches('.');
+    host.eq_ignore_ascii_case(domain)
+        || host
+            .len()
+            .checked_sub(domain.len() + 1)
+            .is_some_and(|i| host.as_bytes()[i] == b'.' && host[i + 1..].eq_ignore_ascii_case(domain))
+}
+
+/// Downloads a web page and returns its content as markdown.
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebFetch {
+    pub url: String,
+
+    #[serde(skip_deserializing)]
+    pub domains: DomainFilter,
+}
+
+impl WebFetch {
+    pub fn set_domain_filter(&mut self, domains: DomainFilter) {
+        self.domains = domains;
+    }
+
+    pub fn requires_acceptance(&self) -> bool {
+        Url::parse(&self.url)
+            .ok()
+            .and_then(|url| url.host_str().map(|host| !self.domains.is_allowed(host)))
+            .unwrap_or(true)
+    }
+
+    pub async fn validate(&mut self, _ctx: &Context) -> Result<()> {
+        let url = Url::parse(&self.url).map_err(|err| eyre!("Invalid URL '{}': {}", self.url, err))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            bail!("Only http and https URLs can be fetched");
+        }
+        let Some(host) = url.host_str() else {
+            bail!("The URL '{}' has no host", self.url);
+        };
+        if self.domains.is_denied(host) {
+            bail!(
+                "Fetching from {} is denied by the user's chat.webFetch.deniedDomains setting",
+                host
+            );
+        }
+        Ok(())
+    }
+
+    pub fn queue_description(&self, updates: &mut impl Write) -> Result<()> {
+        queue!(
+            updates,
+            style::Print("Fetching: "),
+            style::SetForegroundColor(Color::Green),
+            style::Print(&self.url),
+            style::ResetColor,
+            style::Print("\n"),
+        )?;
+        Ok(())
+    }
+
+    pub async fn invoke(&self, ctx: &Context, _updates: &mut impl Write) -> Result<InvokeOutput> {
+        let cache_path = cache_path(ctx, &self.url)?;
+        let content = match read_cache(ctx, &cache_path).await {
+            Some(content) => {
+                debug!(url = %self.url, "serving web page from cache");
+                content
+            },
+            None => {
+                // Redirects from a domain fetched without asking must stay within allowed domains.
+                let content = fetch(&self.url, &self.domains, !self.requires_acceptance()).await?;
+                if let Err(err) = write_cache(ctx, &cache_path, &content).await {
+                    warn!(?err, "failed to cache web page");
+                }
+                content
+            },
+        };
+
+        let output = if content.len() > MAX_TOOL_RESPONSE_SIZE {
+            format!(
+                "{}\n\n... page truncated to {} bytes",
+                truncate_safe(&content, MAX_TOOL_RESPONSE_SIZE),
+                MAX_TOOL_RESPONSE_SIZE
+            )
+        } else {
+            content
+        };
+
+        Ok(InvokeOutput {
+            output: OutputKind::Text(output),
+        })
+    }
+}
+
+/// Downloads `url`, converting HTML responses to markdown. Other text responses are returned as is.
+///
+/// Every redirect is checked against `domains` the same way the original URL is.
+async fn fetch(url: &str, domains: &DomainFilter, allowed_only: bool) -> Result<String> {
+    let domains = domains.clone();
+    let redirect_policy = reqwest::redirect::Policy::custom(move |attempt| {
+        if attempt.previous().len() >= MAX_REDIRECTS {
+            return attempt.error("too many redirects");
+        }
+        match check_redirect(attempt.url(), &domains, allowed_only) {
+            Ok(()) => attempt.follow(),
+            Err(err) => attempt.error(err),
+        }
+    });
+    // Uses the same client configuration as the AWS SDK so that proxy settings are honoured.
+    let client = crate::request::client_builder().redirect(redirect_policy).build()?;
+    let mut response = client
+        .get(url)
+        .timeout(REQUEST_TIMEOUT)
+        .send()
+        .await?
+        .error_for_status()?;
+
+    let final_url = response.url().clone();
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("text/html")
+        .to_ascii_lowercase();
+    if !(content_type.contains("html")
+        || content_type.starts_with("text/")
+        || content_type.contains("json")
+        || content_type.contains("xml"))
+    {
+        bail!("Unsupported content type '{}'", content_type);
+    }
+
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        let remaining = MAX_BODY_SIZE - body.len();
+        if chunk.len() >= remaining {
+            body.extend_from_slice(&chunk[..remaining]);
+            debug!(%url, "web page truncated to {MAX_BODY_SIZE} bytes");
+            break;
+        }
+        body.extend_from_slice(&chunk);
+    }
+    let body = String::from_utf8_lossy(&body);
+
+    if content_type.contains("html") {
+        Ok(html_to_markdown(&body, Some(&final_url)))
+    } else {
+        Ok(body.into_owned())
+    }
+}
+
+/// Checks the target of a redirect against the domain filter.
+fn check_redirect(url: &Url, domains: &DomainFilter, allowed_only: bool) -> Result<(), String> {
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(format!("redirect to unsupported URL '{url}'"));
+    }
+    let Some(host) = url.host_str() else {
+        return Err(format!("redirect to '{url}' without a host"));
+    };
+    if domains.is_denied(host) {
+        return Err(format!(
+            "redirect to {host}, which is denied by the user's chat.webFetch.deniedDomains setting"
+        ));
+    }
+    if allowed_only && !domains.is_allowed(host) {
+        return Err(format!(
+            "redirect to {host}, which is not in the user's chat.webFetch.allowedDomains setting"
+        ));
+    }
+    Ok(())
+}
+
+fn cache_path(ctx: &Context, url: &str) -> Result<PathBuf> {
+    let key = hex::encode(Sha256::digest(url.as_bytes()));
+    Ok(directories::chat_web_cache_dir(ctx)?.join(format!("{key}.md")))
+}
+
+async fn read_cache(ctx: &Context, path: &PathBuf) -> Option<String> {
+    let modified = ctx.fs().symlink_metadata(path).await.ok()?.modified().ok()?;
+    if SystemTime::now().duration_since(modified).ok()? > CACHE_TTL {
+        return None;
+    }
+    ctx.fs().read_to_string(path).await.ok()
+}
+
+async fn write_cache(ctx: &Context, path: &PathBuf, content: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        ctx.fs().create_dir_all(parent).await?;
+    }
+    ctx.fs().write(path, content).await?;
+    if let Some(parent) = path.parent() {
+        prune_cache(ctx, parent).await?;
+    }
+    Ok(())
+}
+
+/// Removes the expired pages from the cache in `dir`, and the oldest ones if there are more than
+/// [MAX_CACHE_ENTRIES].
+async fn prune_cache(ctx: &Context, dir: &Path) -> Result<()> {
+    let mut entries = Vec::new();
+    let mut read_dir = ctx.fs().read_dir(dir).await?;
+    while let Some(entry) = read_dir.next_entry().await? {
+        let modified = entry.metadata().await?.modified()?;
+        entries.push((modified, dir.join(entry.file_name())));
+    }
+
+    // Newest first, so that everything past the limit is the oldest.
+    entries.sort_by(|a, b| b.0.cmp(&a.0));
+    let now = SystemTime::now();
+    for (i, (modified, path)) in entries.into_iter().enumerate() {
+        let expired = now.duration_since(modified).is_ok_and(|age| age > CACHE_TTL);
+        if expired || i >= MAX_CACHE_ENTRIES {
+            ctx.fs().remove_file(&path).await?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn web_fetch(url: &str, domains: DomainFilter) -> WebFetch {
+        let mut tool = serde_json::from_value::<WebFetch>(serde_json::json!({ "url": url })).unwrap();
+        tool.set_domain_filter(domains);
+        tool
+    }
+
+    #[test]
+    fn test_domain_filter() {
+        let domains = DomainFilter {
+            allowed: vec!["rust-lang.org".to_string(), "*.example.com".to_string()],
+            denied: vec!["internal.example.com".to_string()],
+        };
+
+        assert!(domains.is_allowed("rust-lang.org"));
+        assert!(domains.is_allowed("doc.rust-lang.org"));
+        assert!(!domains.is_allowed("notrust-lang.org"));
+        assert!(domains.is_allowed("docs.example.com"));
+        assert!(!domains.is_allowed("internal.example.com"));
+        assert!(domains.is_denied("wiki.internal.example.com"));
+        assert!(!domains.is_denied("example.org"));
+    }
+
+    #[tokio::test]
+    async fn test_web_fetch_validate() {
+        let ctx = Context::builder().with_test_home().await.unwrap().build_fake();
+        let domains = DomainFilter {
+            allowed: vec!["docs.aws.amazon.com".to_string()],
+            denied: vec!["internal.example.com".to_string()],
+        };
+
+        let mut tool = web_fetch("https://docs.aws.amazon.com/cli/", domains.clone());
+        assert!(tool.validate(&ctx).await.is_ok());
+        assert!(!tool.requires_acceptance());
+
+        let mut tool = web_fetch("https://github.com/aws", domains.clone());
+        assert!(tool.validate(&ctx).await.is_ok());
+        assert!(tool.requires_acceptance());
+
+        assert!(
+            web_fetch("https://internal.example.com/wiki", domains.clone())
+                .validate(&ctx)
+                .await
+                .is_err()
+        );
+        assert!(
+            web_fetch("file:///etc/passwd", domains.clone())
+                .validate(&ctx)
+                .await
+                .is_err()
+        );
+        assert!(web_fetch("not a url", domains).validate(&ctx).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_web_fetch_invoke() {
+        let ctx = Context::builder().with_test_home().await.unwrap().build_fake();
+        let mut server = mockito::Server::new_async().await;
+        let page = server
+            .mock("GET", "/guide")
+            .with_status(200)
+            .with_header("content-type", "text/html; charset=utf-8")
+            .with_body("<html><head><title>Guide</title></head><body><h1>Guide</h1><p>See <a href=\"/faq\">the FAQ</a>.</p></body></html>")
+            .expect(1)
+            .create_async()
+            .await;
+        let missing = server.mock("GET", "/missing").with_status(404).create_async().await;
+        let url = format!("{}/guide", server.url());
+
+        let tool = web_fetch(&url, DomainFilter::default());
+        let mut stdout = std::io::stdout();
+        let expected = format!("# Guide\n\nSee [the FAQ]({}/faq).", server.url());
+        for _ in 0..2 {
+            // The second invocation is served from the cache.
+            let output = tool.invoke(&ctx, &mut stdout).await.unwrap();
+            assert_eq!(output.as_str(), expected);
+        }
+        page.assert_async().await;
+
+        let tool = web_fetch(&format!("{}/missing", server.url()), DomainFilter::default());
+        assert!(tool.invoke(&ctx, &mut stdout).await.is_err());
+        missing.assert_async().await;
+
+        let redirect = server
+            .mock("GET", "/moved")
+            .with_status(302)
+            .with_header("location", "http://internal.example.com/wiki")
+            .create_async()
+            .await;
+        let domains = DomainFilter {
+            allowed: vec![],
+            denied: vec!["internal.example.com".to_string()],
+        };
+        let tool = web_fetch(&format!("{}/moved", server.url()), domains);
+        assert!(tool.invoke(&ctx, &mut stdout).await.is_err());
+        redirect.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_prune_cache() {
+        let ctx = Context::builder().with_test_home().await.unwrap().build_fake();
+        let dir = directories::chat_web_cache_dir(&ctx).unwrap();
+        ctx.fs().create_dir_all(&dir).await.unwrap();
+
+        let stale = dir.join("stale.md");
+        ctx.fs().write(&stale, "old").await.unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open(ctx.fs().chroot_path(&stale))
+            .unwrap()
+            .set_modified(SystemTime::now() - CACHE_TTL * 2)
+            .unwrap();
+        for i in 0..=MAX_CACHE_ENTRIES {
+            ctx.fs().write(dir.join(format!("{i}.md")), "new").await.unwrap();
+        }
+
+        prune_cache(&ctx, &dir).await.unwrap();
+        assert!(!ctx.fs().exists(&stale));
+        let mut read_dir = ctx.fs().read_dir(&dir).await.unwrap();
+        let mut count = 0;
+        while read_dir.next_entry().await.unwrap().is_some() {
+            count += 1;
+        }
+        assert_eq!(count, MAX_CACHE_ENTRIES);
+    }
+
+    #[test]
+    fn test_check_redirect() {
+        let domains = DomainFilter {
+            allowed: vec!["rust-lang.org".to_string()],
+            denied: vec!["internal.example.com".to_string()],
+        };
+        let url = |url: &str| Url::parse(url).unwrap();
+
+        assert!(check_redirect(&url("https://doc.rust-lang.org/std"), &domains, true).is_ok());
+        assert!(check_redirect(&url("https://github.com/rust-lang"), &domains, false).is_ok());
+        assert!(check_redirect(&url("https://github.com/rust-lang"), &domains, true).is_err());
+        assert!(check_redirect(&url("https://internal.example.com/wiki"), &domains, false).is_err());
+        assert!(check_redirect(&url("file:///etc/passwd"), &domains, false).is_err());
+    }
+}
@@ -0,0 +1,177 @@
+use std::process::{
+    Command,
+    Stdio,
+};
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use tracing::debug;
+
+use super::images::{
+    RichImageBlocks,
+    image_format_from_bytes,
+    pasted_image_block,
+    pasted_image_placeholder,
+};
+
+/// Image mime types we are able to send, in order of preference.
+const IMAGE_MIME_TYPES: &[&str] = &["image/png", "image/jpeg", "image/webp", "image/gif"];
+
+/// Returns the raw bytes of the image currently in the clipboard, if any.
+///
+/// This uses the native clipboard tools, `wl-paste` or `xclip` on Linux and `osascript` on macOS.
+pub fn read_clipboard_image() -> Option<Vec<u8>> {
+    read_native_clipboard_image().filter(|bytes| image_format_from_bytes(bytes).is_some())
+}
+
+#[cfg(target_os = "linux")]
+fn read_native_clipboard_image() -> Option<Vec<u8>> {
+    if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        let types = command_output("wl-paste", &["--list-types"])?;
+        let mime_type = find_image_mime_type(&String::from_utf8_lossy(&types))?;
+        return command_output("wl-paste", &["--no-newline", "--type", mime_type]);
+    }
+
+    let targets = command_output("xclip", &["-selection", "clipboard", "-t", "TARGETS", "-o"])?;
+    let mime_type = find_image_mime_type(&String::from_utf8_lossy(&targets))?;
+    command_output("xclip", &["-selection", "clipboard", "-t", mime_type, "-o"])
+}
+
+#[cfg(target_os = "macos")]
+fn read_native_clipboard_image() -> Option<Vec<u8>> {
+    let file = tempfile::NamedTempFile::new().ok()?;
+    let script = format!(
+        "set f to open for access POSIX file \"{}\" with write permission\n\
+         write (the clipboard as «class PNGf») to f\n\
+         close access f",
+        file.path().display()
+    );
+    command_output("osascript", &["-e", &script])?;
+    std::fs::read(file.path()).ok().filter(|bytes| !bytes.is_empty())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn read_native_clipboard_image() -> Option<Vec<u8>> {
+    None
+}
+
+/// Runs a clipboard command, returning its stdout only if it succeeded with some output.
+#[cfg_attr(not(any(target_os = "linux", target_os = "macos")), allow(dead_code))]
+fn command_output(program: &str, args: &[&str]) -> Option<Vec<u8>> {
+    let output = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .map_err(|err| debug!(?err, "failed to run {program}"))
+        .ok()?;
+    (output.status.success() && !output.stdout.is_empty()).then_some(output.stdout)
+}
+
+/// Picks the preferred image mime type from a newline separated list of clipboard targets.
+fn find_image_mime_type(targets: &str) -> Option<&'static str> {
+    IMAGE_MIME_TYPES
+        .iter()
+        .find(|mime_type| targets.lines().any(|target| target.trim() == **mime_type))
+        .copied()
+}
+
+/// Terminals that support the iTerm2 or kitty inline image protocols may paste images as escape
+/// sequences. This replaces each of them in `line` with an `[image #N]` placeholder, numbered
+/// from `first_number`, and returns the decoded images.
+pub fn extract_inline_images(line: &str, first_number: usize) -> (String, RichImageBlocks) {
+    let mut images = RichImageBlocks::new();
+    let mut output = String::with_capacity(line.len());
+    let mut rest = line;
+
+    while let Some((start, payload, end)) = find_inline_image(rest) {
+        output.push_str(&rest[..start]);
+        match BASE64
+            .decode(payload)
+            .ok()
+            .and_then(|bytes| pasted_image_block(bytes, first_number + images.len()))
+        {
+            Some(image) => {
+                output.push_str(&pasted_image_placeholder(&image.1));
+                images.push(image);
+            },
+            None => debug!("dropping unsupported inline image"),
+        }
+        rest = &rest[end..];
+    }
+    output.push_str(rest);
+
+    (output, images)
+}
+
+/// Finds the first inline image escape sequence in `s`, returning its start offset, the base64
+/// payload, and the end offset.
+fn find_inline_image(s: &str) -> Option<(usize, String, usize)> {
+    let iterm = s.find("\x1b]1337;File=");
+    let kitty = s.find("\x1b_G");
+
+    match (iterm, kitty) {
+        (Some(start), kitty) if kitty.is_none_or(|k| start < k) => {
+            // ESC ] 1337 ; File = [args] : <base64> BEL
+            let body = &s[start..];
+            let end = body
+                .find('\x07')
+                .map(|i| (i, 1))
+                .or(body.find("\x1b\\").map(|i| (i, 2)))?;
+            let payload = body[..end.0].split_once(':')?.1.to_string();
+            Some((start, payload, start + end.0 + end.1))
+        },
+        (_, Some(start)) => {
+            // ESC _ G <keys> ; <base64> ESC \, possibly split into chunks with `m=1`.
+            let mut payload = String::new();
+            let mut offset = start;
+            loop {
+                let body = s[offset..].strip_prefix("\x1b_G")?;
+                let end = body.find("\x1b\\")?;
+                let (keys, data) = body[..end].split_once(';').unwrap_or((&body[..end], ""));
+                payload.push_str(data);
+                offset += 3 + end + 2;
+                if !keys.split(',').any(|key| key == "m=1") {
+                    return Some((start, payload, offset));
+                }
+            }
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PNG: &[u8] = b"\x89PNG\r\n\x1a\nrest of the image";
+
+    #[test]
+    fn test_find_image_mime_type() {
+        assert_eq!(
+            find_image_mime_type("TARGETS\ntext/plain\nimage/jpeg\nimage/png\n"),
+            Some("image/png")
+        );
+        assert_eq!(find_image_mime_type("image/gif"), Some("image/gif"));
+        assert_eq!(find_image_mime_type("text/plain\nUTF8_STRING"), None);
+    }
+
+    #[test]
+    fn test_extract_inline_images() {
+        let encoded = BASE64.encode(PNG);
+        let line = format!(
+            "what is \x1b]1337;File=inline=1:{encoded}\x07 and \x1b_Gf=100,m=1;{}\x1b\\\x1b_Gm=0;{}\x1b\\?",
+            &encoded[..8],
+            &encoded[8..]
+        );
+
+        let (line, images) = extract_inline_images(&line, 2);
+        assert_eq!(line, "what is [image #2] and [image #3]?");
+        assert_eq!(images.len(), 2);
+        assert_eq!(images[1].1.filename, "image #3");
+
+        let (line, images) = extract_inline_images("no images here", 1);
+        assert_eq!(line, "no images here");
+        assert!(images.is_empty());
+    }
+}
@@ -0,0 +1,309 @@
+use kuchikiki::traits::TendrilSink;
+use kuchikiki::{
+    ElementData,
+    NodeData,
+    NodeRef,
+};
+use url::Url;
+
+/// Elements whose content is never part of the readable page.
+const SKIPPED_ELEMENTS: &[&str] = &[
+    "head", "script", "style", "noscript", "svg", "template", "iframe", "canvas", "button", "form",
+];
+
+/// Elements that are rendered on their own lines.
+const BLOCK_ELEMENTS: &[&str] = &[
+    "p",
+    "div",
+    "section",
+    "article",
+    "main",
+    "header",
+    "footer",
+    "nav",
+    "aside",
+    "table",
+    "tr",
+    "ul",
+    "ol",
+    "dl",
+    "dt",
+    "dd",
+    "figure",
+    "figcaption",
+    "details",
+    "summary",
+];
+
+/// How deeply nested elements are converted. Anything nested deeper is dropped, so that
+/// adversarial pages can't overflow the stack.
+const MAX_DEPTH: usize = 256;
+
+/// Converts an HTML document to markdown, dropping scripts, styles, and other elements that
+/// aren't part of the readable content. Relative links are resolved against `base` if given.
+pub fn html_to_markdown(html: &str, base: Option<&Url>) -> String {
+    let document = kuchikiki::parse_html().one(html);
+    let mut converter = Converter {
+        base,
+        ..Default::default()
+    };
+    converter.children(&document);
+
+    normalize_blank_lines(&converter.out)
+}
+
+#[derive(Default)]
+struct Converter<'a> {
+    base: Option<&'a Url>,
+    out: String,
+    /// How many elements deep the conversion currently is.
+    depth: usize,
+    pre_depth: usize,
+    /// The open lists, with the next item number for ordered lists.
+    lists: Vec<Option<usize>>,
+}
+
+impl Converter<'_> {
+    fn children(&mut self, node: &NodeRef) {
+        if self.depth >= MAX_DEPTH {
+            return;
+        }
+        self.depth += 1;
+        for child in node.children() {
+            match child.data() {
+                NodeData::Text(text) => self.text(&text.borrow()),
+                NodeData::Element(element) => self.element(&child, element),
+                NodeData::Document(_) | NodeData::DocumentFragment => self.children(&child),
+                _ => (),
+            }
+        }
+        self.depth -= 1;
+    }
+
+    fn text(&mut self, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+
+        if self.pre_depth > 0 {
+            self.out.push_str(text);
+            return;
+        }
+
+        let mut words = text.split_whitespace().peekable();
+        if words.peek().is_none() {
+            if !self.out.ends_with(char::is_whitespace) && !self.out.is_empty() {
+                self.out.push(' ');
+            }
+            return;
+        }
+        if text.starts_with(char::is_whitespace) && !self.out.ends_with(char::is_whitespace) && !self.out.is_empty() {
+            self.out.push(' ');
+        }
+        for (i, word) in words.enumerate() {
+            if i > 0 {
+                self.out.push(' ');
+            }
+            self.out.push_str(word);
+        }
+        if text.ends_with(char::is_whitespace) {
+            self.out.push(' ');
+        }
+    }
+
+    fn element(&mut self, node: &NodeRef, element: &ElementData) {
+        let name = &*element.name.local;
+        if SKIPPED_ELEMENTS.contains(&name) {
+            return;
+        }
+        let attribute = |name: &str| element.attributes.borrow().get(name).map(str::to_string);
+
+        match name {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                self.blank_line();
+                let level = name[1..].parse().unwrap_or(1);
+                self.out.push_str(&"#".repeat(level));
+                self.out.push(' ');
+                self.children(node);
+                self.blank_line();
+            },
+            "br" => self.out.push('\n'),
+            "hr" => {
+                self.blank_line();
+                self.out.push_str("---");
+                self.blank_line();
+            },
+            "pre" => {
+                self.blank_line();
+                self.out.push_str("```\n");
+                self.pre_depth += 1;
+                self.children(node);
+                self.pre_depth -= 1;
+                self.newline();
+                self.out.push_str("```");
+                self.blank_line();
+            },
+            "code" | "kbd" | "samp" if self.pre_depth == 0 => self.wrap(node, "`"),
+            "strong" | "b" => self.wrap(node, "**"),
+            "em" | "i" => self.wrap(node, "_"),
+            "blockquote" => {
+                self.blank_line();
+                self.out.push_str("> ");
+                self.children(node);
+                self.blank_line();
+            },
+            "ul" | "ol" => {
+                self.newline();
+                self.lists.push((name == "ol").then_some(1));
+                self.children(node);
+                self.lists.pop();
+                if self.lists.is_empty() {
+                    self.blank_line();
+                }
+            },
+            "li" => {
+                self.newline();
+                let depth = self.lists.len().saturating_sub(1);
+                self.out.push_str(&"  ".repeat(depth));
+                match self.lists.last_mut() {
+                    Some(Some(number)) => {
+                        self.out.push_str(&format!("{number}. "));
+                        *number += 1;
+                    },
+                    _ => self.out.push_str("- "),
+                }
+                self.children(node);
+                self.newline();
+            },
+            "td" | "th" => {
+                self.out.push_str(" | ");
+                self.children(node);
+            },
+            "a" => {
+                let href = attribute("href")
+                    .filter(|href| !href.starts_with('#') && !href.starts_with("javascript:"))
+                    .map(|href| self.resolve(&href));
+                match href {
+                    Some(href) => {
+                        self.out.push('[');
+                        self.children(node);
+                        self.out.push_str(&format!("]({href})"));
+                    },
+                    None => self.children(node),
+                }
+            },
+            "img" => {
+                if let Some(src) = attribute("src") {
+                    let alt = attribute("alt").unwrap_or_default();
+                    let src = self.resolve(&src);
+                    self.out.push_str(&format!("![{alt}]({src})"));
+                }
+            },
+            name if BLOCK_ELEMENTS.contains(&name) => {
+                self.block_break();
+                self.children(node);
+                self.block_break();
+            },
+            _ => self.children(node),
+        }
+    }
+
+    /// Renders the children of `node` between two `marker`s.
+    fn wrap(&mut self, node: &NodeRef, marker: &str) {
+        self.out.push_str(marker);
+        self.children(node);
+        self.out.push_str(marker);
+    }
+
+    fn resolve(&self, link: &str) -> String {
+        self.base
+            .and_then(|base| base.join(link).ok())
+            .map_or_else(|| link.to_string(), |url| url.to_string())
+    }
+
+    /// Separates block elements by a blank line, or by a newline inside lists.
+    fn block_break(&mut self) {
+        if self.lists.is_empty() {
+            self.blank_line();
+        } else {
+            self.newline();
+        }
+    }
+
+    fn newline(&mut self) {
+        let trimmed = self.out.trim_end_matches(' ').len();
+        self.out.truncate(trimmed);
+        if !self.out.is_empty() && !self.out.ends_with('\n') {
+            self.out.push('\n');
+        }
+    }
+
+    fn blank_line(&mut self) {
+        self.newline();
+        if !self.out.is_empty() && !self.out.ends_with("\n\n") {
+            self.out.push('\n');
+        }
+    }
+}
+
+/// Trims trailing whitespace from each line and collapses runs of blank lines into one.
+fn normalize_blank_lines(markdown: &str) -> String {
+    let mut normalized = String::with_capacity(markdown.len());
+    let mut blank_lines = 0;
+    for line in markdown.lines().map(str::trim_end) {
+        if line.is_empty() {
+            blank_lines += 1;
+            continue;
+        }
+        if !normalized.is_empty() {
+            normalized.push_str(if blank_lines > 0 { "\n\n" } else { "\n" });
+        }
+        blank_lines = 0;
+        normalized.push_str(line);
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_html_to_markdown() {
+        let html = r#"<!DOCTYPE html>
+<html>
+<head><title>Docs</title><style>body { color: red; }</style></head>
+<body>
+  <nav><a href="/">Home</a></nav>
+  <h1>Getting &amp; started</h1>
+  <!-- a comment -->
+  <p>Install the <code>q</code> CLI with <strong>one</strong> command.
+     See the <a href="guide/install.html">install guide</a>.</p>
+  <script>console.log("<p>not content</p>");</script>
+  <ul><li>First</li><li>Second<ol><li>Nested</li></ol></li></ul>
+  <pre><code>q chat
+  --trust-all-tools</code></pre>
+</body>
+</html>"#;
+        let base = Url::parse("https://example.com/docs/index.html").unwrap();
+
+        assert_eq!(
+            html_to_markdown(html, Some(&base)),
+            "[Home](https://example.com/)\n\n# Getting & started\n\nInstall the `q` CLI with **one** command. See the [install guide](https://example.com/docs/guide/install.html).\n\n- First\n- Second\n  1. Nested\n\n```\nq chat\n  --trust-all-tools\n```"
+        );
+    }
+
+    #[test]
+    fn test_html_to_markdown_entities() {
+        assert_eq!(
+            html_to_markdown("<p>a &lt;b&gt; &#39;c&#x27; &copy; &unknown; & d</p>", None),
+            "a <b> 'c' © &unknown; & d"
+        );
+    }
+
+    #[test]
+    fn test_html_to_markdown_deep_nesting() {
+        let html = format!("<p>top</p>{}deep", "<div>".repeat(1_000));
+        assert_eq!(html_to_markdown(&html, None), "top");
+    }
+}
@@ -186,6 +186,46 @@ pub fn get_image_block_from_file_path(maybe_file_path: &str) -> Option<ImageBloc
     Some(image_block)
 }
 
+/// Detects the format of raw image data from its magic bytes.
+pub fn image_format_from_bytes(bytes: &[u8]) -> Option<ImageFormat> {
+    match bytes {
+        [0x89, b'P', b'N', b'G', ..] => Some(ImageFormat::Png),
+        [0xff, 0xd8, 0xff, ..] => Some(ImageFormat::Jpeg),
+        [b'G', b'I', b'F', b'8', ..] => Some(ImageFormat::Gif),
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => Some(ImageFormat::Webp),
+        _ => None,
+    }
+}
+
+/// Creates a [RichImageBlock] for an image that was pasted into the prompt rather than read from
+/// a file. The image is referred to as `image #{number}` in the prompt.
+///
+/// Returns [None] if the data is not a supported image, or if it exceeds [MAX_IMAGE_SIZE].
+pub fn pasted_image_block(bytes: Vec<u8>, number: usize) -> Option<RichImageBlock> {
+    let format = image_format_from_bytes(&bytes)?;
+    if bytes.len() > MAX_IMAGE_SIZE {
+        return None;
+    }
+
+    let size = bytes.len() as u64;
+    Some((
+        ImageBlock {
+            format,
+            source: ImageSource::Bytes(bytes),
+        },
+        ImageMetadata {
+            filepath: "clipboard".to_string(),
+            size,
+            filename: format!("image #{number}"),
+        },
+    ))
+}
+
+/// The placeholder shown in the prompt for a pasted image.
+pub fn pasted_image_placeholder(metadata: &ImageMetadata) -> String {
+    format!("[{}]", metadata.filename)
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -281,6 +321,31 @@ mod tests {
         assert!(images.is_empty());
     }
 
+    #[test]
+    fn test_image_format_from_bytes() {
+        assert_eq!(image_format_from_bytes(b"\x89PNG\r\n\x1a\n"), Some(ImageFormat::Png));
+        assert_eq!(image_format_from_bytes(b"\xFF\xD8\xFF\xE0"), Some(ImageFormat::Jpeg));
+        assert_eq!(image_format_from_bytes(b"GIF89a"), Some(ImageFormat::Gif));
+        assert_eq!(
+            image_format_from_bytes(b"RIFF\0\0\0\0WEBPVP8 "),
+            Some(ImageFormat::Webp)
+        );
+        assert_eq!(image_format_from_bytes(b"hello world"), None);
+    }
+
+    #[test]
+    fn test_pasted_image_block() {
+        let (block, metadata) = pasted_image_block(b"\x89PNG\r\n\x1a\n".to_vec(), 2).unwrap();
+        assert_eq!(block.format, ImageFormat::Png);
+        assert_eq!(metadata.size, 8);
+        assert_eq!(pasted_image_placeholder(&metadata), "[image #2]");
+
+        assert!(pasted_image_block(b"not an image".to_vec(), 1).is_none());
+        let mut too_large = b"\x89PNG".to_vec();
+        too_large.resize(MAX_IMAGE_SIZE + 1, 0);
+        assert!(pasted_image_block(too_large, 1).is_none());
+    }
+
     #[test]
     fn test_handle_images_number_exceeded() {
         let temp_dir = tempfile::tempdir().unwrap();
@@ -1,3 +1,5 @@
+pub mod clipboard;
+pub mod html;
 pub mod images;
 pub mod issue;
 pub mod shared_writer;
@@ -56,22 +58,8 @@ pub fn animate_output(output: &mut impl Write, bytes: &[u8]) -> Result<(), ChatE
     Ok(())
 }
 
-/// Play the terminal bell notification sound
-pub fn play_notification_bell(requires_confirmation: bool) {
-    // Don't play bell for tools that don't require confirmation
-    if !requires_confirmation {
-        return;
-    }
-
-    // Check if we should play the bell based on terminal type
-    if should_play_bell() {
-        print!("\x07"); // ASCII bell character
-        std::io::stdout().flush().unwrap();
-    }
-}
-
 /// Determine if we should play the bell based on terminal type
-fn should_play_bell() -> bool {
+pub fn should_play_bell() -> bool {
     // Get the TERM environment variable
     if let Ok(term) = std::env::var("TERM") {
         // List of terminals known to handle bell character well
@@ -35,8 +35,14 @@ use tracing::{
 };
 use user::UserSubcommand;
 
-use crate::cli::chat::cli::Mcp;
-use crate::cli::chat::mcp;
+use crate::cli::chat::cli::{
+    Config,
+    Mcp,
+};
+use crate::cli::chat::{
+    config,
+    mcp,
+};
 use crate::logging::{
     LogArgs,
     initialize_logging,
@@ -109,6 +115,9 @@ pub enum CliRootCommands {
     /// Model Context Protocol (MCP)
     #[command(subcommand)]
     Mcp(Mcp),
+    /// Export, diff and apply configuration bundles
+    #[command(subcommand)]
+    Config(Config),
 }
 
 impl CliRootCommands {
@@ -124,6 +133,7 @@ impl CliRootCommands {
             CliRootCommands::Version { .. } => "version",
             CliRootCommands::Chat { .. } => "chat",
             CliRootCommands::Mcp(_) => "mcp",
+            CliRootCommands::Config(_) => "config",
         }
     }
 }
@@ -213,6 +223,7 @@ impl Cli {
                 CliRootCommands::Version { changelog } => Self::print_version(changelog),
                 CliRootCommands::Chat(args) => chat::launch_chat(&mut database, &telemetry, args).await,
                 CliRootCommands::Mcp(args) => mcp::execute_mcp(args).await,
+                CliRootCommands::Config(args) => config::execute_config(&mut database, args).await,
             },
             // Root command
             None => chat::launch_chat(&mut database, &telemetry, chat::cli::Chat::default()).await,
@@ -317,6 +328,8 @@ impl Cli {
 mod test {
     use super::*;
     use crate::cli::chat::cli::{
+        ConfigApply,
+        ConfigExport,
         McpAdd,
         McpImport,
         McpList,
@@ -372,6 +385,7 @@ mod test {
                 resume: false,
                 input: None,
                 profile: None,
+                model: None,
                 trust_all_tools: false,
                 trust_tools: None,
             })),
@@ -411,6 +425,24 @@ mod test {
                 resume: false,
                 input: None,
                 profile: Some("my-profile".to_string()),
+                model: None,
+                trust_all_tools: false,
+                trust_tools: None,
+            })
+        );
+    }
+
+    #[test]
+    fn test_chat_with_model() {
+        assert_parse!(
+            ["chat", "--model", "claude-4-sonnet"],
+            CliRootCommands::Chat(Chat {
+                accept_all: false,
+                no_interactive: false,
+                resume: false,
+                input: None,
+                profile: None,
+                model: Some("claude-4-sonnet".to_string()),
                 trust_all_tools: false,
                 trust_tools: None,
             })
@@ -427,6 +459,7 @@ mod test {
                 resume: false,
                 input: Some("Hello".to_string()),
                 profile: Some("my-profile".to_string()),
+                model: None,
                 trust_all_tools: false,
                 trust_tools: None,
             })
@@ -443,6 +476,7 @@ mod test {
                 resume: false,
                 input: None,
                 profile: Some("my-profile".to_string()),
+                model: None,
                 trust_all_tools: false,
                 trust_tools: None,
             })
@@ -459,6 +493,7 @@ mod test {
                 resume: true,
                 input: None,
                 profile: None,
+                model: None,
                 trust_all_tools: false,
                 trust_tools: None,
             })
@@ -471,6 +506,7 @@ mod test {
                 resume: true,
                 input: None,
                 profile: None,
+                model: None,
                 trust_all_tools: false,
                 trust_tools: None,
             })
@@ -487,6 +523,7 @@ mod test {
                 resume: false,
                 input: None,
                 profile: None,
+                model: None,
                 trust_all_tools: true,
                 trust_tools: None,
             })
@@ -503,6 +540,7 @@ mod test {
                 resume: false,
                 input: None,
                 profile: None,
+                model: None,
                 trust_all_tools: false,
                 trust_tools: Some(vec!["".to_string()]),
             })
@@ -519,6 +557,7 @@ mod test {
                 resume: false,
                 input: None,
                 profile: None,
+                model: None,
                 trust_all_tools: false,
                 trust_tools: Some(vec!["fs_read".to_string(), "fs_write".to_string()]),
             })
@@ -595,4 +634,24 @@ mod test {
             }))
         );
     }
+
+    #[test]
+    fn test_config_subcommands() {
+        assert_parse!(
+            ["config", "export", "-o", "team.toml"],
+            CliRootCommands::Config(Config::Export(ConfigExport {
+                output: Some("team.toml".into()),
+                format: None,
+            }))
+        );
+        assert_parse!(
+            ["config", "apply", "team.toml", "--dry-run"],
+            CliRootCommands::Config(Config::Apply(ConfigApply {
+                file: "team.toml".into(),
+                dry_run: true,
+                force: false,
+                yes: false,
+            }))
+        );
+    }
 }
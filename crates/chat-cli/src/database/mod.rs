@@ -57,6 +57,7 @@ const IDC_REGION_KEY: &str = "auth.idc.region";
 // We include this key to remove for backwards compatibility
 const CUSTOMIZATION_STATE_KEY: &str = "api.selectedCustomization";
 const ROTATING_TIP_KEY: &str = "chat.greeting.rotating_tips_current_index";
+const APPLIED_CONFIG_BUNDLE_KEY: &str = "config.appliedBundle";
 
 const MIGRATIONS: &[Migration] = migrations![
     "000_migration_table",
@@ -304,6 +305,16 @@ impl Database {
         Ok(tip)
     }
 
+    /// Get the entries of the last config bundle that was applied.
+    pub fn get_applied_config_bundle(&self) -> Result<Option<Map<String, Value>>, DatabaseError> {
+        self.get_json_entry(Table::State, APPLIED_CONFIG_BUNDLE_KEY)
+    }
+
+    /// Set the entries of the last config bundle that was applied.
+    pub fn set_applied_config_bundle(&mut self, entries: &Map<String, Value>) -> Result<usize, DatabaseError> {
+        self.set_json_entry(Table::State, APPLIED_CONFIG_BUNDLE_KEY, entries)
+    }
+
     /// Get a chat conversation given a path to the conversation.
     pub fn get_conversation_by_path(
         &mut self,
@@ -26,11 +26,19 @@ pub enum Setting {
     ApiTimeout,
     ChatEditMode,
     ChatEnableNotifications,
+    ChatDefaultModel,
+    ChatNotificationMethods,
+    ChatNotificationCommand,
+    ChatNotificationMinTurnSeconds,
     ApiCodeWhispererService,
     ApiQService,
     McpInitTimeout,
     McpNoInteractiveTimeout,
     McpLoadedBefore,
+    WebFetchAllowedDomains,
+    WebFetchDeniedDomains,
+    UseAwsNative,
+    ChatHooksAllowTools,
 }
 
 impl AsRef<str> for Setting {
@@ -45,11 +53,19 @@ impl AsRef<str> for Setting {
             Self::ApiTimeout => "api.timeout",
             Self::ChatEditMode => "chat.editMode",
             Self::ChatEnableNotifications => "chat.enableNotifications",
+            Self::ChatDefaultModel => "chat.defaultModel",
+            Self::ChatNotificationMethods => "chat.notifications.methods",
+            Self::ChatNotificationCommand => "chat.notifications.command",
+            Self::ChatNotificationMinTurnSeconds => "chat.notifications.minTurnSeconds",
             Self::ApiCodeWhispererService => "api.codewhisperer.service",
             Self::ApiQService => "api.q.service",
             Self::McpInitTimeout => "mcp.initTimeout",
             Self::McpNoInteractiveTimeout => "mcp.noInteractiveTimeout",
             Self::McpLoadedBefore => "mcp.loadedBefore",
+            Self::WebFetchAllowedDomains => "chat.webFetch.allowedDomains",
+            Self::WebFetchDeniedDomains => "chat.webFetch.deniedDomains",
+            Self::UseAwsNative => "chat.useAws.native",
+            Self::ChatHooksAllowTools => "chat.hooks.allowTools",
         }
     }
 }
@@ -74,11 +90,19 @@ impl TryFrom<&str> for Setting {
             "api.timeout" => Ok(Self::ApiTimeout),
             "chat.editMode" => Ok(Self::ChatEditMode),
             "chat.enableNotifications" => Ok(Self::ChatEnableNotifications),
+            "chat.defaultModel" => Ok(Self::ChatDefaultModel),
+            "chat.notifications.methods" => Ok(Self::ChatNotificationMethods),
+            "chat.notifications.command" => Ok(Self::ChatNotificationCommand),
+            "chat.notifications.minTurnSeconds" => Ok(Self::ChatNotificationMinTurnSeconds),
             "api.codewhisperer.service" => Ok(Self::ApiCodeWhispererService),
             "api.q.service" => Ok(Self::ApiQService),
             "mcp.initTimeout" => Ok(Self::McpInitTimeout),
             "mcp.noInteractiveTimeout" => Ok(Self::McpNoInteractiveTimeout),
             "mcp.loadedBefore" => Ok(Self::McpLoadedBefore),
+            "chat.webFetch.allowedDomains" => Ok(Self::WebFetchAllowedDomains),
+            "chat.webFetch.deniedDomains" => Ok(Self::WebFetchDeniedDomains),
+            "chat.useAws.native" => Ok(Self::UseAwsNative),
+            "chat.hooks.allowTools" => Ok(Self::ChatHooksAllowTools),
             _ => Err(DatabaseError::InvalidSetting(value.to_string())),
         }
     }
@@ -136,6 +160,18 @@ impl Settings {
         Ok(key)
     }
 
+    /// Sets a key that may not be a known [Setting], e.g. one written by another version.
+    pub async fn set_key(&mut self, key: &str, value: impl Into<serde_json::Value>) -> Result<(), DatabaseError> {
+        self.0.insert(key.to_owned(), value.into());
+        self.save_to_file().await
+    }
+
+    pub async fn remove_key(&mut self, key: &str) -> Result<Option<Value>, DatabaseError> {
+        let value = self.0.remove(key);
+        self.save_to_file().await?;
+        Ok(value)
+    }
+
     pub fn get_bool(&self, key: Setting) -> Option<bool> {
         self.get(key).and_then(|value| value.as_bool())
     }
@@ -148,6 +184,15 @@ impl Settings {
         self.get(key).and_then(|value| value.as_i64())
     }
 
+    pub fn get_string_array(&self, key: Setting) -> Option<Vec<String>> {
+        self.get(key).and_then(|value| value.as_array()).map(|values| {
+            values
+                .iter()
+                .filter_map(|value| value.as_str().map(|s| s.into()))
+                .collect()
+        })
+    }
+
     async fn save_to_file(&self) -> Result<(), DatabaseError> {
         if cfg!(test) {
             return Ok(());
@@ -1,5 +1,12 @@
-use std::path::PathBuf;
-
+use std::path::{
+    Path,
+    PathBuf,
+};
+
+use sha2::{
+    Digest,
+    Sha256,
+};
 use thiserror::Error;
 
 use crate::platform::Context;
@@ -123,6 +130,31 @@ pub fn chat_profiles_dir(ctx: &Context) -> Result<PathBuf> {
     Ok(home_dir(ctx)?.join(".aws").join("amazonq").join("profiles"))
 }
 
+/// The directory containing global custom slash commands for `q chat`.
+pub fn chat_global_commands_dir(ctx: &Context) -> Result<PathBuf> {
+    Ok(home_dir(ctx)?.join(".aws").join("amazonq").join("commands"))
+}
+
+/// The path to the config extending the models available in `q chat`.
+pub fn chat_models_path(ctx: &Context) -> Result<PathBuf> {
+    Ok(home_dir(ctx)?.join(".aws").join("amazonq").join("models.json"))
+}
+
+/// The file the `q chat` prompt history entered in `cwd` is saved to.
+pub fn chat_history_path(ctx: &Context, cwd: &Path) -> Result<PathBuf> {
+    let key = hex::encode(Sha256::digest(cwd.as_os_str().as_encoded_bytes()));
+    Ok(home_dir(ctx)?
+        .join(".aws")
+        .join("amazonq")
+        .join("history")
+        .join(format!("{}.txt", &key[..16])))
+}
+
+/// The directory caching web pages fetched by the `web_fetch` tool in `q chat`.
+pub fn chat_web_cache_dir(ctx: &Context) -> Result<PathBuf> {
+    Ok(home_dir(ctx)?.join(".aws").join("amazonq").join("cache").join("web"))
+}
+
 /// The path to the fig settings file
 pub fn settings_path() -> Result<PathBuf> {
     Ok(fig_data_dir()?.join("settings.json"))
@@ -52,7 +52,7 @@ pub async fn uninstall(components: InstallComponents, ctx: Arc<Context>) -> Resu
     };
 
     let shell_integration_result = {
-        for shell in [Shell::Bash, Shell::Zsh, Shell::Fish] {
+        for shell in Shell::all() {
             for integration in shell.get_shell_integrations(ctx.env())? {
                 integration.uninstall().await?;
             }
@@ -107,7 +107,9 @@ impl ShellExt for Shell {
         for file in match self {
             Shell::Bash => [".bashrc", ".bash_profile", ".bash_login", ".profile"].iter(),
             Shell::Zsh => [".zshrc", ".zprofile"].iter(),
-            Shell::Fish | Shell::Nu => [].iter(),
+            Shell::Elvish => ["rc.elv"].iter(),
+            Shell::PowerShell => ["Microsoft.PowerShell_profile.ps1"].iter(),
+            Shell::Fish | Shell::Nu | Shell::Xonsh => [].iter(),
         } {
             for when in &When::all() {
                 let path = directories::fig_data_dir()?
@@ -181,6 +183,37 @@ impl ShellExt for Shell {
                 ]
             },
             Shell::Nu => vec![],
+            // Only touch the config of these shells when they are installed
+            Shell::Xonsh | Shell::Elvish | Shell::PowerShell if !self.is_installed(env) => vec![],
+            Shell::Xonsh => {
+                let xonsh_config_dir = config_dir.join("rc.d");
+                vec![
+                    Box::new(ShellScriptShellIntegration {
+                        when: When::Pre,
+                        shell: *self,
+                        path: xonsh_config_dir.join("00_fig_pre.xsh"),
+                    }),
+                    Box::new(ShellScriptShellIntegration {
+                        when: When::Post,
+                        shell: *self,
+                        path: xonsh_config_dir.join("99_fig_post.xsh"),
+                    }),
+                ]
+            },
+            Shell::Elvish => vec![Box::new(DotfileShellIntegration {
+                pre: true,
+                post: true,
+                shell: *self,
+                dotfile_directory: config_dir,
+                dotfile_name: "rc.elv",
+            })],
+            Shell::PowerShell => vec![Box::new(DotfileShellIntegration {
+                pre: true,
+                post: true,
+                shell: *self,
+                dotfile_directory: config_dir,
+                dotfile_name: "Microsoft.PowerShell_profile.ps1",
+            })],
         };
 
         Ok(integrations)
@@ -214,6 +247,12 @@ impl ShellExt for Shell {
             },
             (Shell::Nu, When::Pre) => include_str!("scripts/pre.nu"),
             (Shell::Nu, When::Post) => include_str!("scripts/post.nu"),
+            (Shell::Xonsh, When::Pre) => include_str!("scripts/pre.xsh"),
+            (Shell::Xonsh, When::Post) => include_str!("scripts/post.xsh"),
+            (Shell::Elvish, When::Pre) => include_str!("scripts/pre.elv"),
+            (Shell::Elvish, When::Post) => include_str!("scripts/post.elv"),
+            (Shell::PowerShell, When::Pre) => include_str!("scripts/pre.ps1"),
+            (Shell::PowerShell, When::Post) => include_str!("scripts/post.ps1"),
         }
     }
 }
@@ -283,8 +322,10 @@ impl ShellScriptShellIntegration {
     fn get_contents(&self) -> String {
         let Self { shell, when, path } = self;
         let rcfile = match path.file_name().and_then(|x| x.to_str()) {
-            Some(name) => format!(" --rcfile {}", get_prefix(name)),
-            None => "".into(),
+            Some(name) if matches!(shell, Shell::Bash | Shell::Zsh | Shell::Fish) => {
+                format!(" --rcfile {}", get_prefix(name))
+            },
+            _ => "".into(),
         };
         cfg_if!(
             if #[cfg(target_os = "macos")] {
@@ -293,6 +334,9 @@ impl ShellScriptShellIntegration {
                     Shell::Bash | Shell::Zsh => format!("[ -x ~/.local/bin/{CLI_BINARY_NAME} ] && eval \"$(~/.local/bin/{CLI_BINARY_NAME} init {shell} {when}{rcfile})\""),
                     Shell::Fish => format!("test -x ~/.local/bin/{CLI_BINARY_NAME}; and eval (~/.local/bin/{CLI_BINARY_NAME} init {shell} {when}{rcfile} | string split0)"),
                     Shell::Nu => "".into(),
+                    Shell::Xonsh => format!("if !(test -x ~/.local/bin/{CLI_BINARY_NAME}):\n    execx($(~/.local/bin/{CLI_BINARY_NAME} init {shell} {when}))"),
+                    Shell::Elvish => format!("if ?(test -x ~/.local/bin/{CLI_BINARY_NAME}) {{ eval (~/.local/bin/{CLI_BINARY_NAME} init {shell} {when} | slurp) }}"),
+                    Shell::PowerShell => format!("if (Test-Path ~/.local/bin/{CLI_BINARY_NAME}) {{ ~/.local/bin/{CLI_BINARY_NAME} init {shell} {when} | Out-String | Invoke-Expression }}"),
                 }
             } else {
                 let add_to_path_line = match self.shell {
@@ -303,6 +347,16 @@ impl ShellScriptShellIntegration {
                     "#},
                     Shell::Fish => "contains $HOME/.local/bin $PATH; or set -a PATH $HOME/.local/bin",
                     Shell::Nu => "",
+                    Shell::Xonsh => indoc::indoc! {r#"
+                        if $HOME + '/.local/bin' not in $PATH:
+                            $PATH.append($HOME + '/.local/bin')
+                    "#},
+                    Shell::Elvish => "if (not (has-value $paths $E:HOME/.local/bin)) { set paths = [$@paths $E:HOME/.local/bin] }",
+                    Shell::PowerShell => indoc::indoc! {r#"
+                        if (($env:PATH -split [IO.Path]::PathSeparator) -notcontains "$HOME/.local/bin") {
+                          $env:PATH = $env:PATH + [IO.Path]::PathSeparator + "$HOME/.local/bin"
+                        }
+                    "#},
                 };
 
                 let source_line = match self.shell {
@@ -313,6 +367,9 @@ impl ShellScriptShellIntegration {
                         format!("{bash_pre}command -v {CLI_BINARY_NAME} >/dev/null 2>&1 && eval \"$({CLI_BINARY_NAME} init {shell} {when}{rcfile})\"")
                     }
                     Shell::Nu => "".into(),
+                    Shell::Xonsh => format!("if !(which {CLI_BINARY_NAME}):\n    execx($({CLI_BINARY_NAME} init {shell} {when}))"),
+                    Shell::Elvish => format!("if (has-external {CLI_BINARY_NAME}) {{ eval ({CLI_BINARY_NAME} init {shell} {when} | slurp) }}"),
+                    Shell::PowerShell => format!("if (Get-Command {CLI_BINARY_NAME} -CommandType Application -ErrorAction SilentlyContinue) {{ {CLI_BINARY_NAME} init {shell} {when} | Out-String | Invoke-Expression }}"),
                 };
 
                 return format!("{add_to_path_line}\n{source_line}\n");
@@ -502,11 +559,27 @@ impl DotfileShellIntegration {
     fn source_text(&self, when: When) -> Result<String> {
         let home = directories::home_dir()?;
         let integration_path = self.script_integration(when)?.path;
-        let path = format!("\"${{HOME}}/{}\"", integration_path.strip_prefix(home)?.display());
+        let relative_path = integration_path.strip_prefix(home)?.display();
 
         match self.shell {
-            Shell::Fish => Ok(format!("test -f {path}; and builtin source {path}")),
-            _ => Ok(format!("[[ -f {path} ]] && builtin source {path}")),
+            Shell::Fish => {
+                let path = format!("\"${{HOME}}/{relative_path}\"");
+                Ok(format!("test -f {path}; and builtin source {path}"))
+            },
+            Shell::Elvish => {
+                let path = format!("$E:HOME'/{relative_path}'");
+                Ok(format!("if ?(test -f {path}) {{ eval (slurp < {path}) }}"))
+            },
+            Shell::PowerShell => {
+                let path = format!("\"$HOME/{relative_path}\"");
+                Ok(format!(
+                    "if (Test-Path {path}) {{ Get-Content -Raw {path} | Invoke-Expression }}"
+                ))
+            },
+            _ => {
+                let path = format!("\"${{HOME}}/{relative_path}\"");
+                Ok(format!("[[ -f {path} ]] && builtin source {path}"))
+            },
         }
     }
 
@@ -612,6 +685,10 @@ impl DotfileShellIntegration {
         }
 
         if contents.ne(&original_contents) {
+            // The config directories of elvish and PowerShell don't exist until they are first used
+            if let Some(parent) = dotfile.parent() {
+                std::fs::create_dir_all(parent).with_path(parent)?;
+            }
             let mut file = File::create(&dotfile).with_path(self.path())?;
             file.write_all(contents.as_bytes())?;
         }
@@ -890,6 +967,38 @@ mod test {
         assert_eq!(replaced, "");
     }
 
+    #[test]
+    fn test_elvish_and_pwsh_source_text_matches() {
+        for (shell, dotfile_name) in [
+            (Shell::Elvish, "rc.elv"),
+            (Shell::PowerShell, "Microsoft.PowerShell_profile.ps1"),
+        ] {
+            let integration = DotfileShellIntegration {
+                pre: true,
+                post: true,
+                shell,
+                dotfile_directory: "".into(),
+                dotfile_name,
+            };
+            let text = format!(
+                "{}\n{}\necho hello\n{}\n{}",
+                integration.description(When::Pre),
+                integration.source_text(When::Pre).unwrap(),
+                integration.description(When::Post),
+                integration.source_text(When::Post).unwrap(),
+            );
+            integration.matches_text(&text, When::Pre).unwrap();
+            integration.matches_text(&text, When::Post).unwrap();
+            assert_eq!(
+                integration
+                    .remove_from_text(integration.remove_from_text(&text, When::Pre).unwrap(), When::Post)
+                    .unwrap()
+                    .trim(),
+                "echo hello"
+            );
+        }
+    }
+
     #[test]
     fn test_split_shebang() {
         let shebang = "#!/usr/bin/env sh";
@@ -936,7 +1045,15 @@ mod test {
     #[cfg(target_os = "linux")]
     fn dotfile_shell_integrations_shellcheck() {
         for integration in all_dotfile_shell_integrations() {
-            run_shellcheck(integration.get_contents());
+            let contents = integration.get_contents();
+            match integration.shell {
+                // shellcheck can't parse these, so just make sure they still load the integration
+                Shell::Elvish | Shell::PowerShell => {
+                    let init = format!("init {} {}", integration.shell, integration.when);
+                    assert!(contents.contains(&init), "{contents}");
+                },
+                _ => run_shellcheck(contents),
+            }
         }
     }
 }
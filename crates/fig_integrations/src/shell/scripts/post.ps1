@@ -0,0 +1,67 @@
+if (-not $env:TTY) {
+  $env:TTY = tty
+}
+
+$env:SHELL_PID = $PID
+
+if (-not $env:Q_SHELL) {
+  $env:Q_SHELL = q _ get-shell
+}
+
+function global:Invoke-FigOsc([string] $Value) {
+  "$([char]0x1b)]697;$Value$([char]0x07)"
+}
+
+$global:FigUserPrompt = $function:prompt
+
+function global:prompt {
+  # $? is reset by every statement, so it has to be read first
+  $figSuccess = $?
+  $figLastExitCode = $global:LASTEXITCODE
+  $figLastStatus = if ($figSuccess) { 0 } elseif ($figLastExitCode) { $figLastExitCode } else { 1 }
+
+  $marks = @(
+    "OSCUnlock=$env:QTERM_SESSION_ID"
+    "Dir=$($executionContext.SessionState.Path.CurrentFileSystemLocation.Path)"
+    'Shell=pwsh'
+    "ShellPath=$env:Q_SHELL"
+    "PID=$PID"
+    "ExitCode=$figLastStatus"
+    "TTY=$env:TTY"
+    "Log=$env:Q_LOG_LEVEL"
+    "User=$(if ($env:USER) { $env:USER } else { 'root' })"
+  )
+  if ($env:WSL_DISTRO_NAME) {
+    $marks += "WSLDistro=$env:WSL_DISTRO_NAME"
+  }
+
+  if (Get-Command q -CommandType Application -ErrorAction SilentlyContinue) {
+    # Nothing reads the output, so it is not redirected and the handle is released right away
+    $startInfo = [Diagnostics.ProcessStartInfo]::new('q', '_ pre-cmd')
+    $startInfo.UseShellExecute = $false
+    $startInfo.CreateNoWindow = $true
+    $preCmd = [Diagnostics.Process]::Start($startInfo)
+    if ($preCmd) { $preCmd.Dispose() }
+  }
+
+  $userPrompt = & $global:FigUserPrompt
+  $global:LASTEXITCODE = $figLastExitCode
+  (($marks | ForEach-Object { Invoke-FigOsc $_ }) -join '') +
+    (Invoke-FigOsc StartPrompt) + $userPrompt + (Invoke-FigOsc EndPrompt) +
+    (Invoke-FigOsc "NewCmd=$env:QTERM_SESSION_ID")
+}
+
+# PSReadLine calls this to read each command line, so the command starts once it returns
+if (Test-Path Function:PSConsoleHostReadLine) {
+  $global:FigUserReadLine = $function:PSConsoleHostReadLine
+
+  function global:PSConsoleHostReadLine {
+    $line = & $global:FigUserReadLine
+    [Console]::Write((Invoke-FigOsc "OSCLock=$env:QTERM_SESSION_ID") + (Invoke-FigOsc PreExec))
+    $line
+  }
+}
+
+if ($env:PROCESS_LAUNCHED_BY_Q) {
+  [Console]::Write((Invoke-FigOsc DoneSourcing))
+}
@@ -0,0 +1,52 @@
+$null = New-Item -ItemType Directory -Force -Path "$HOME/.local/bin"
+
+if (($env:PATH -split [IO.Path]::PathSeparator) -notcontains "$HOME/.local/bin") {
+  $env:PATH = $env:PATH + [IO.Path]::PathSeparator + "$HOME/.local/bin"
+}
+
+if ($env:Q_NEW_SESSION) {
+  Remove-Item Env:QTERM_SESSION_ID -ErrorAction SilentlyContinue
+  Remove-Item Env:Q_TERM -ErrorAction SilentlyContinue
+  Remove-Item Env:Q_NEW_SESSION -ErrorAction SilentlyContinue
+}
+
+if (-not $env:Q_SET_PARENT_CHECK) {
+  if (-not $env:Q_PARENT -and $env:Q_SET_PARENT) {
+    $env:Q_PARENT = $env:Q_SET_PARENT
+    Remove-Item Env:Q_SET_PARENT
+  }
+  $env:Q_SET_PARENT_CHECK = 1
+}
+
+if (-not $env:SHOULD_QTERM_LAUNCH) {
+  # 0 = Yes, 1 = No, 2 = Fallback to Q_TERM
+  q _ should-figterm-launch *> $null
+  $env:SHOULD_QTERM_LAUNCH = $LASTEXITCODE
+}
+
+if (
+  -not $env:PROCESS_LAUNCHED_BY_Q -and
+  -not [Console]::IsOutputRedirected -and
+  [Environment]::UserInteractive -and
+  (Get-Command qterm -CommandType Application -ErrorAction SilentlyContinue) -and
+  ($env:SHOULD_QTERM_LAUNCH -eq 0 -or
+    ($env:SHOULD_QTERM_LAUNCH -eq 2 -and (-not $env:Q_TERM -or (-not $env:Q_TERM_TMUX -and $env:TMUX))))
+) {
+  if (-not $env:Q_SHELL) {
+    $env:Q_SHELL = q _ get-shell
+  }
+  $env:Q_IS_LOGIN_SHELL = 0
+
+  $figTermName = "$(Split-Path -Leaf $env:Q_SHELL) (qterm)"
+  if (-not $env:Q_TERM_PATH) {
+    if (Test-Path "$HOME/.local/bin/$figTermName") {
+      $env:Q_TERM_PATH = "$HOME/.local/bin/$figTermName"
+    } else {
+      $env:Q_TERM_PATH = (Get-Command qterm -CommandType Application | Select-Object -First 1).Source
+    }
+  }
+
+  # PowerShell has no exec, so run qterm in the foreground and exit once it does
+  & $env:Q_TERM_PATH
+  exit $LASTEXITCODE
+}
@@ -188,7 +188,7 @@ impl History {
         Ok(rows_mapped)
     }
 
-    /// The Where expression is not escaped, so be careful!
+    /// Values in the Where expression are escaped, but nothing else is, so be careful!
     ///
     /// Ugh i should like use sqlx or something
     pub fn rows(
@@ -227,6 +227,49 @@ impl History {
         Ok(rows_mapped)
     }
 
+    /// Aggregates the commands matching `where_expr` into per command statistics.
+    ///
+    /// The Where expression is escaped by its [Display](std::fmt::Display) implementation.
+    pub fn command_stats(
+        &self,
+        where_expr: Option<WhereExpression>,
+        order: StatsOrder,
+        limit: usize,
+    ) -> Result<Vec<CommandStats>> {
+        let where_expr = match where_expr {
+            Some(where_expr) => format!("WHERE {where_expr}"),
+            None => "".to_owned(),
+        };
+
+        let order = match order {
+            StatsOrder::MostUsed => "ORDER BY count DESC",
+            StatsOrder::Slowest => "HAVING avg_duration IS NOT NULL ORDER BY avg_duration DESC",
+            StatsOrder::MostFailed => "HAVING failures > 0 ORDER BY failures DESC, count DESC",
+        };
+
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT command, COUNT(*) AS count,
+                SUM(CASE WHEN exit_code IS NOT NULL AND exit_code != 0 THEN 1 ELSE 0 END) AS failures,
+                CAST(AVG(duration) AS INTEGER) AS avg_duration,
+                MAX(duration) AS max_duration
+            FROM history {where_expr} GROUP BY command {order} LIMIT ?",
+        ))?;
+
+        let millis = |d: Option<i64>| d.and_then(|d| Some(std::time::Duration::from_millis(u64::try_from(d).ok()?)));
+        let rows = stmt.query_map(params![limit], |row| {
+            Ok(CommandStats {
+                command: row.get::<_, Option<String>>(0)?.unwrap_or_default(),
+                count: row.get(1)?,
+                failures: row.get(2)?,
+                average_duration: millis(row.get(3)?),
+                max_duration: millis(row.get(4)?),
+            })
+        })?;
+
+        Ok(rows.collect::<rusqlite::Result<Vec<CommandStats>>>()?)
+    }
+
     /// A raw sql query that returns a json array of objects
     pub fn query<P: rusqlite::Params>(
         &self,
@@ -284,6 +327,29 @@ fn map_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<CommandInfo> {
     })
 }
 
+/// Statistics of all runs of a command, see [History::command_stats].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandStats {
+    pub command: String,
+    /// The number of times the command was run.
+    pub count: u64,
+    /// The number of runs that exited with a non zero exit code.
+    pub failures: u64,
+    pub average_duration: Option<std::time::Duration>,
+    pub max_duration: Option<std::time::Duration>,
+}
+
+/// How [History::command_stats] orders and filters the commands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatsOrder {
+    MostUsed,
+    /// Commands with a known duration, slowest on average first.
+    Slowest,
+    /// Commands that failed at least once, most failures first.
+    MostFailed,
+}
+
+#[derive(Debug, Clone)]
 pub enum HistoryColumn {
     Id,
     Command,
@@ -345,6 +411,7 @@ impl std::fmt::Display for OrderBy {
     }
 }
 
+#[derive(Debug, Clone)]
 pub enum WhereExpression {
     Eq(HistoryColumn, String),
     Ne(HistoryColumn, String),
@@ -362,17 +429,39 @@ pub enum WhereExpression {
     Or(Box<WhereExpression>, Box<WhereExpression>),
 }
 
+impl WhereExpression {
+    /// Combines `exprs` with `AND`, returning [None] if there are none.
+    pub fn all(exprs: impl IntoIterator<Item = WhereExpression>) -> Option<Self> {
+        exprs
+            .into_iter()
+            .reduce(|left, right| WhereExpression::And(Box::new(left), Box::new(right)))
+    }
+}
+
+/// Escapes a value for use in a single quoted sql string.
+fn escape_sql(value: &str) -> String {
+    value.replace('\'', "''")
+}
+
+/// Escapes the `LIKE` wildcards in `value` so that it only matches itself, e.g. for building a
+/// pattern from user input.
+pub fn escape_like(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
+
 impl std::fmt::Display for WhereExpression {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            WhereExpression::Eq(column, value) => write!(f, "{column} = '{value}'"),
-            WhereExpression::Ne(column, value) => write!(f, "{column} != '{value}'"),
-            WhereExpression::Gt(column, value) => write!(f, "{column} > '{value}'"),
-            WhereExpression::Lt(column, value) => write!(f, "{column} < '{value}'"),
-            WhereExpression::Ge(column, value) => write!(f, "{column} >= '{value}'"),
-            WhereExpression::Le(column, value) => write!(f, "{column} <= '{value}'"),
-            WhereExpression::Like(column, value) => write!(f, "{column} LIKE '{value}'"),
-            WhereExpression::NotLike(column, value) => write!(f, "{column} NOT LIKE '{value}'"),
+            WhereExpression::Eq(column, value) => write!(f, "{column} = '{}'", escape_sql(value)),
+            WhereExpression::Ne(column, value) => write!(f, "{column} != '{}'", escape_sql(value)),
+            WhereExpression::Gt(column, value) => write!(f, "{column} > '{}'", escape_sql(value)),
+            WhereExpression::Lt(column, value) => write!(f, "{column} < '{}'", escape_sql(value)),
+            WhereExpression::Ge(column, value) => write!(f, "{column} >= '{}'", escape_sql(value)),
+            WhereExpression::Le(column, value) => write!(f, "{column} <= '{}'", escape_sql(value)),
+            WhereExpression::Like(column, value) => write!(f, "{column} LIKE '{}' ESCAPE '\\'", escape_sql(value)),
+            WhereExpression::NotLike(column, value) => {
+                write!(f, "{column} NOT LIKE '{}' ESCAPE '\\'", escape_sql(value))
+            },
             WhereExpression::IsNull(column) => write!(f, "{column} IS NULL"),
             WhereExpression::NotNull(column) => write!(f, "{column} IS NOT NULL"),
             WhereExpression::In(column, values) => write!(
@@ -381,7 +470,7 @@ impl std::fmt::Display for WhereExpression {
                 column,
                 values
                     .iter()
-                    .map(|v| format!("'{v}'"))
+                    .map(|v| format!("'{}'", escape_sql(v)))
                     .collect::<Vec<String>>()
                     .join(", ")
             ),
@@ -391,7 +480,7 @@ impl std::fmt::Display for WhereExpression {
                 column,
                 values
                     .iter()
-                    .map(|v| format!("'{v}'"))
+                    .map(|v| format!("'{}'", escape_sql(v)))
                     .collect::<Vec<String>>()
                     .join(", ")
             ),
@@ -595,4 +684,73 @@ mod tests {
             .unwrap()
         );
     }
+
+    #[test]
+    fn command_stats_and_filters() {
+        let history = History::mock();
+        let insert = |command: &str, cwd: &str, secs: u64, duration: u64, exit_code: i32| {
+            let start_time = std::time::UNIX_EPOCH + std::time::Duration::from_secs(secs);
+            history
+                .insert_command_history(
+                    &CommandInfo {
+                        command: Some(command.into()),
+                        cwd: Some(cwd.into()),
+                        start_time: Some(start_time),
+                        end_time: Some(start_time + std::time::Duration::from_secs(duration)),
+                        exit_code: Some(exit_code),
+                        ..Default::default()
+                    },
+                    false,
+                )
+                .unwrap();
+        };
+        insert("cargo build", "/src/it's", 100, 30, 0);
+        insert("cargo build", "/src/it's", 200, 10, 101);
+        insert("ls", "/home", 300, 0, 0);
+        insert("false", "/home", 400, 0, 1);
+
+        let rows = history
+            .rows(
+                WhereExpression::all([
+                    WhereExpression::Eq(HistoryColumn::Cwd, "/src/it's".into()),
+                    WhereExpression::Ne(HistoryColumn::ExitCode, "0".into()),
+                ]),
+                vec![],
+                10,
+                0,
+            )
+            .unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].exit_code, Some(101));
+        assert!(WhereExpression::all([]).is_none());
+
+        let like = |pattern: String| {
+            history
+                .rows(Some(WhereExpression::Like(HistoryColumn::Cwd, pattern)), vec![], 10, 0)
+                .unwrap()
+                .len()
+        };
+        assert_eq!(like("/h_me".into()), 2);
+        assert_eq!(like(escape_like("/h_me")), 0);
+        assert_eq!(like(format!("%{}", escape_like("it's"))), 2);
+
+        let slowest = history.command_stats(None, StatsOrder::Slowest, 1).unwrap();
+        assert_eq!(slowest, vec![CommandStats {
+            command: "cargo build".into(),
+            count: 2,
+            failures: 1,
+            average_duration: Some(std::time::Duration::from_secs(20)),
+            max_duration: Some(std::time::Duration::from_secs(30)),
+        }]);
+
+        let failed = history
+            .command_stats(
+                Some(WhereExpression::Ge(HistoryColumn::StartTime, "300".into())),
+                StatsOrder::MostFailed,
+                10,
+            )
+            .unwrap();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].command, "false");
+    }
 }
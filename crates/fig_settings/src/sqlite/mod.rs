@@ -233,6 +233,22 @@ impl Db {
         self.all_values(STATE_TABLE_NAME)
     }
 
+    /// Runs sqlite's `PRAGMA integrity_check`, returning the problems it found
+    pub fn integrity_check(&self) -> Result<Vec<String>> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare("PRAGMA integrity_check")?;
+        let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+
+        let mut problems = vec![];
+        for row in rows {
+            let row = row?;
+            if row != "ok" {
+                problems.push(row);
+            }
+        }
+        Ok(problems)
+    }
+
     // atomic style operations
 
     fn atomic_op<T: FromSql + ToSql>(
@@ -424,6 +440,13 @@ mod tests {
         assert!(!db.is_auth_value_set("test2").unwrap());
     }
 
+    #[test]
+    fn integrity_check() {
+        let db = mock();
+        db.set_state_value("test", "test").unwrap();
+        assert_eq!(db.integrity_check().unwrap(), Vec::<String>::new());
+    }
+
     #[test]
     fn db_open_time() {
         let tempdir = tempfile::tempdir().unwrap();
@@ -2,9 +2,11 @@ pub mod directories;
 pub mod manifest;
 mod open;
 pub mod process_info;
+pub mod redact;
 mod shell;
 pub mod system_info;
 pub mod terminal;
+pub mod validate;
 
 pub mod consts;
 #[cfg(target_os = "macos")]
@@ -34,6 +34,14 @@ pub enum Shell {
     Fish,
     /// Nu shell
     Nu,
+    /// Xonsh shell
+    Xonsh,
+    /// Elvish shell
+    Elvish,
+    /// PowerShell
+    #[serde(rename = "pwsh")]
+    #[value(name = "pwsh")]
+    PowerShell,
 }
 
 impl Display for Shell {
@@ -51,6 +59,9 @@ impl FromStr for Shell {
             "zsh" => Ok(Shell::Zsh),
             "fish" => Ok(Shell::Fish),
             "nu" => Ok(Shell::Nu),
+            "xonsh" => Ok(Shell::Xonsh),
+            "elvish" => Ok(Shell::Elvish),
+            "pwsh" | "powershell" => Ok(Shell::PowerShell),
             _ => Err(()),
         }
     }
@@ -58,7 +69,15 @@ impl FromStr for Shell {
 
 impl Shell {
     pub fn all() -> &'static [Self] {
-        &[Shell::Bash, Shell::Zsh, Shell::Fish, Shell::Nu]
+        &[
+            Shell::Bash,
+            Shell::Zsh,
+            Shell::Fish,
+            Shell::Nu,
+            Shell::Xonsh,
+            Shell::Elvish,
+            Shell::PowerShell,
+        ]
     }
 
     /// All shells to run unit / integration tests with
@@ -81,6 +100,12 @@ impl Shell {
             Some(Shell::Fish)
         } else if input == "nu" || input == "nushell" {
             Some(Shell::Nu)
+        } else if input.contains("xonsh") {
+            Some(Shell::Xonsh)
+        } else if input.contains("elvish") {
+            Some(Shell::Elvish)
+        } else if input.contains("pwsh") || input.contains("powershell") {
+            Some(Shell::PowerShell)
         } else {
             None
         }
@@ -119,9 +144,23 @@ impl Shell {
                 None => Ok(directories::home_dir()?.join(".config").join("fish")),
             },
             Shell::Nu => Ok(directories::config_dir()?.join("nushell")),
+            Shell::Xonsh => match env.get_os("XONSH_CONFIG_DIR").map(PathBuf::from) {
+                Some(dir) => Ok(dir),
+                None => Ok(xdg_config_dir(env)?.join("xonsh")),
+            },
+            Shell::Elvish => Ok(xdg_config_dir(env)?.join("elvish")),
+            Shell::PowerShell => Ok(xdg_config_dir(env)?.join("powershell")),
         }
     }
 
+    /// Whether the shell's executable is on the `PATH`
+    pub fn is_installed(&self, env: &Env) -> bool {
+        let Some(path) = env.get_os("PATH") else {
+            return false;
+        };
+        std::env::split_paths(&path).any(|dir| dir.join(self.as_str()).is_file())
+    }
+
     pub fn get_data_path(&self) -> Result<PathBuf, directories::DirectoryError> {
         Ok(directories::fig_data_dir()?.join("shell").join(format!("{self}.json")))
     }
@@ -132,6 +171,9 @@ impl Shell {
             Shell::Zsh => "zsh",
             Shell::Fish => "fish",
             Shell::Nu => "nu",
+            Shell::Xonsh => "xonsh",
+            Shell::Elvish => "elvish",
+            Shell::PowerShell => "pwsh",
         }
     }
 
@@ -152,6 +194,14 @@ impl Shell {
     }
 }
 
+/// `$XDG_CONFIG_HOME`, which xonsh, elvish and PowerShell use on macOS too
+fn xdg_config_dir(env: &Env) -> Result<PathBuf, directories::DirectoryError> {
+    match env.get_os("XDG_CONFIG_HOME").map(PathBuf::from) {
+        Some(dir) if dir.is_absolute() => Ok(dir),
+        _ => Ok(directories::home_dir()?.join(".config")),
+    }
+}
+
 const BASH_RE: &str = r"GNU bash, version (\d+\.\d+\.\d+)";
 const ZSH_RE: &str = r"(\d+\.\d+)";
 const FISH_RE: &str = r"(\d+\.\d+\.\d+)";
@@ -171,13 +221,13 @@ async fn shell_version(shell: &Shell, exe_path: &Path) -> Result<String, Error>
             let version_capture = re.captures(std::str::from_utf8(&version_output.stdout)?);
             Ok(version_capture.ok_or_else(err)?.get(1).ok_or_else(err)?.as_str().into())
         },
-        Shell::Fish => {
+        Shell::Fish | Shell::Xonsh | Shell::PowerShell => {
             let re = Regex::new(FISH_RE).unwrap();
             let version_output = Command::new(exe_path).arg("--version").output().await?;
             let version_capture = re.captures(std::str::from_utf8(&version_output.stdout)?);
             Ok(version_capture.ok_or_else(err)?.get(1).ok_or_else(err)?.as_str().into())
         },
-        Shell::Nu => {
+        Shell::Nu | Shell::Elvish => {
             let version_output = Command::new(exe_path).arg("--version").output().await?;
             Ok(std::str::from_utf8(&version_output.stdout)?.trim().into())
         },
@@ -240,6 +290,19 @@ mod tests {
         assert_eq!(re.captures(zsh_version).unwrap().get(1).unwrap().as_str(), "5.9");
     }
 
+    #[test]
+    fn test_try_find_shell() {
+        assert_eq!(Shell::try_find_shell("/usr/bin/xonsh"), Some(Shell::Xonsh));
+        assert_eq!(Shell::try_find_shell("/usr/local/bin/elvish"), Some(Shell::Elvish));
+        assert_eq!(
+            Shell::try_find_shell("/opt/microsoft/powershell/7/pwsh"),
+            Some(Shell::PowerShell)
+        );
+        assert_eq!("powershell".parse(), Ok(Shell::PowerShell));
+        assert_eq!(Shell::PowerShell.to_string(), "pwsh");
+        assert_eq!(Shell::try_find_shell("/usr/bin/python3"), None);
+    }
+
     #[test]
     fn test_fish_re() {
         let re = Regex::new(FISH_RE).unwrap();
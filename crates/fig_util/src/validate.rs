@@ -1,6 +1,10 @@
+//! Checks on generated shell commands before they are suggested to the user.
+
 use std::path::Path;
 
-pub(super) fn validate(command: &str) -> bool {
+/// Whether `command` is worth suggesting, e.g. it has no redactions and any directory it `cd`s
+/// into exists.
+pub fn validate(command: &str) -> bool {
     validate_with_context(
         command,
         || {
@@ -8,7 +12,7 @@ pub(super) fn validate(command: &str) -> bool {
                 .ok()
                 .and_then(|s| s.to_str().map(ToOwned::to_owned))
         },
-        || fig_util::directories::home_dir_utf8().ok(),
+        || crate::directories::home_dir_utf8().ok(),
         |key| std::env::var(key).map(Some),
     )
 }
@@ -77,11 +81,11 @@ mod tests {
         let tempdir_path = tempdir.path().to_str().unwrap();
         let cwd = || -> Option<&str> { Some(tempdir_path) };
         let home_dir = || -> Option<&str> { Some(tempdir_path) };
-        let context = |key: &str| -> anyhow::Result<Option<&str>> {
+        let context = |key: &str| -> Result<Option<&str>, std::env::VarError> {
             match key {
-                "HOME" => anyhow::Ok(Some(tempdir_path)),
-                "A" => anyhow::Ok(Some("a")),
-                "B" => anyhow::Ok(Some("b")),
+                "HOME" => Ok(Some(tempdir_path)),
+                "A" => Ok(Some("a")),
+                "B" => Ok(Some("b")),
                 _ => Ok(None),
             }
         };
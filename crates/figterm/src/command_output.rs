@@ -0,0 +1,118 @@
+//! Keeps the output of the last commands run in the terminal, so that it can be attached to chat
+//! prompts and used to explain failed commands.
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use alacritty_terminal::term::CommandInfo;
+use fig_proto::figterm::CommandOutput;
+use fig_util::redact::redact;
+
+pub const OUTPUT_CAPTURE_SETTINGS_KEY: &str = "qterm.output-capture.enabled";
+
+/// The number of commands whose output is kept.
+const MAX_COMMANDS: usize = 10;
+
+/// The maximum number of lines kept of each output, counting from the end.
+const MAX_OUTPUT_LINES: usize = 500;
+
+/// The maximum number of bytes kept of each output, counting from the end.
+const MAX_OUTPUT_BYTES: usize = 32 * 1024;
+
+static OUTPUTS: Mutex<VecDeque<CommandOutput>> = Mutex::new(VecDeque::new());
+
+/// Records the output of a command that finished, dropping the oldest recorded output.
+pub fn record(command_info: &CommandInfo) {
+    let Some(output) = &command_info.output else {
+        return;
+    };
+    if !fig_settings::settings::get_bool_or(OUTPUT_CAPTURE_SETTINGS_KEY, true) {
+        return;
+    }
+
+    let (output, truncated) = truncate(&redact(output));
+    let mut outputs = OUTPUTS.lock().unwrap();
+    outputs.push_front(CommandOutput {
+        command: command_info.command.clone(),
+        exit_code: command_info.exit_code,
+        cwd: command_info.cwd.clone(),
+        output,
+        truncated,
+    });
+    outputs.truncate(MAX_COMMANDS);
+}
+
+/// The recorded output `index` commands back, or all recorded outputs, newest first.
+pub fn get(index: Option<u32>) -> Vec<CommandOutput> {
+    let outputs = OUTPUTS.lock().unwrap();
+    match index {
+        Some(index) => outputs.get(index as usize).cloned().into_iter().collect(),
+        None => outputs.iter().cloned().collect(),
+    }
+}
+
+/// Keeps the end of `output`, where errors usually are, within the size limits. Returns whether
+/// anything was dropped.
+fn truncate(output: &str) -> (String, bool) {
+    let lines = output.trim_end().lines().map(str::trim_end).collect::<Vec<_>>();
+    let mut truncated = lines.len() > MAX_OUTPUT_LINES;
+    let mut output = lines[lines.len().saturating_sub(MAX_OUTPUT_LINES)..].join("\n");
+
+    if output.len() > MAX_OUTPUT_BYTES {
+        let mut start = output.len() - MAX_OUTPUT_BYTES;
+        while !output.is_char_boundary(start) {
+            start += 1;
+        }
+        output = output[start..].to_owned();
+        truncated = true;
+    }
+
+    (output, truncated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate() {
+        assert_eq!(truncate("a  \nb\n\n\n"), ("a\nb".into(), false));
+
+        let lines = (0..MAX_OUTPUT_LINES + 5).map(|i| i.to_string()).collect::<Vec<_>>();
+        let (output, truncated) = truncate(&lines.join("\n"));
+        assert!(truncated);
+        assert!(output.starts_with("5\n"));
+        assert!(output.ends_with(&format!("\n{}", MAX_OUTPUT_LINES + 4)));
+
+        let (output, truncated) = truncate(&"é".repeat(MAX_OUTPUT_BYTES));
+        assert!(truncated);
+        assert_eq!(output.len(), MAX_OUTPUT_BYTES);
+    }
+
+    #[test]
+    fn test_record() {
+        for i in 0..MAX_COMMANDS + 2 {
+            record(&CommandInfo {
+                command: Some(format!("echo {i}")),
+                shell: None,
+                pid: None,
+                session_id: None,
+                cwd: None,
+                start_time: None,
+                end_time: None,
+                username: None,
+                exit_code: Some(0),
+                output: Some(format!("{i}\n")),
+            });
+        }
+
+        let outputs = get(None);
+        assert_eq!(outputs.len(), MAX_COMMANDS);
+        assert_eq!(outputs[0].output, (MAX_COMMANDS + 1).to_string());
+        assert_eq!(
+            get(Some(1))[0].command.as_deref(),
+            Some(&*format!("echo {MAX_COMMANDS}"))
+        );
+        assert!(get(Some(MAX_COMMANDS as u32)).is_empty());
+    }
+}
@@ -18,6 +18,7 @@ use tracing::{
     error,
 };
 
+use crate::command_output;
 use crate::history::{
     HistoryCommand,
     HistorySender,
@@ -115,6 +116,7 @@ impl EventListener for EventHandler {
             },
             Event::CommandInfo(command_info) => {
                 tokio::spawn(on_prompt());
+                command_output::record(command_info);
 
                 let context = shell_state_to_context(shell_state);
                 let hook = new_postexec_hook(context, command_info.command.clone(), command_info.exit_code);
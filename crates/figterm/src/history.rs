@@ -1,4 +1,7 @@
+use std::sync::Arc;
+
 use fig_settings::history::{
+    CommandInfo,
     HistoryColumn,
     Order,
     OrderBy,
@@ -19,10 +22,7 @@ pub struct HistoryQueryParams {
 
 pub enum HistoryCommand {
     Insert(alacritty_terminal::term::CommandInfo),
-    Query(
-        HistoryQueryParams,
-        Sender<Option<Vec<fig_settings::history::CommandInfo>>>,
-    ),
+    Query(HistoryQueryParams, Sender<Option<Arc<Vec<CommandInfo>>>>),
 }
 
 pub type HistorySender = Sender<HistoryCommand>;
@@ -34,11 +34,14 @@ pub async fn spawn_history_task() -> HistorySender {
 
     tokio::task::spawn(async move {
         let history = fig_settings::history::History::new();
+        // Inline completions query the history on every keystroke, so the rows are kept until the
+        // next command is inserted, along with the limit they were queried with.
+        let mut cached_rows: Option<(usize, Arc<Vec<CommandInfo>>)> = None;
 
         while let Ok(command) = receiver.recv_async().await {
             match command {
                 HistoryCommand::Insert(command) => {
-                    let command_info = fig_settings::history::CommandInfo {
+                    let command_info = CommandInfo {
                         command: command.command,
                         shell: command.shell,
                         pid: command.pid,
@@ -56,8 +59,16 @@ pub async fn spawn_history_task() -> HistorySender {
                     if let Err(err) = history.insert_command_history(&command_info, true) {
                         error!(%err, "Failed to insert command into history");
                     }
+                    cached_rows = None;
                 },
                 HistoryCommand::Query(query, sender) => {
+                    if let Some((_, rows)) = cached_rows.as_ref().filter(|(limit, _)| *limit == query.limit) {
+                        if let Err(err) = sender.send(Some(Arc::clone(rows))) {
+                            error!(%err, "Failed to send history query result");
+                        }
+                        continue;
+                    }
+
                     match history.rows(
                         Some(WhereExpression::NotNull(HistoryColumn::ExitCode)),
                         vec![OrderBy::new(HistoryColumn::Id, Order::Desc)],
@@ -65,6 +76,8 @@ pub async fn spawn_history_task() -> HistorySender {
                         0,
                     ) {
                         Ok(rows) => {
+                            let rows = Arc::new(rows);
+                            cached_rows = Some((query.limit, Arc::clone(&rows)));
                             if let Err(err) = sender.send(Some(rows)) {
                                 error!(%err, "Failed to send history query result");
                             }
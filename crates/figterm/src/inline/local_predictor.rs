@@ -0,0 +1,190 @@
+//! Predicts the command being typed from the shell history, without any network requests.
+
+use std::collections::HashMap;
+
+use fig_settings::history::CommandInfo;
+
+/// The number of history entries the local predictor ranks.
+pub const HISTORY_COUNT: usize = 2000;
+
+/// Predictions scoring at least this are served without waiting for remote completions, roughly
+/// a command run a few times recently.
+pub const CONFIDENT_SCORE: f64 = 3.0;
+
+/// How much more a command run in the current directory counts.
+const CWD_WEIGHT: f64 = 2.0;
+/// How much a failed run of a command counts, relative to a successful one.
+const FAILED_WEIGHT: f64 = 0.25;
+/// How much more a run of a command counts when it followed the command that was run last.
+const NGRAM_WEIGHT: f64 = 3.0;
+/// The number of newer history entries after which a run of a command counts half as much.
+const RECENCY_HALF_LIFE: f64 = 100.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Prediction {
+    /// The full command, including the buffer it completes.
+    pub command: String,
+    pub score: f64,
+}
+
+/// Ranks the commands in `history`, which is ordered newest first, that complete `buffer`.
+///
+/// Every run of a command adds to its score, weighted by how recent it is, whether it was run in
+/// `cwd`, whether it succeeded, and whether it followed the same command as the one that was run
+/// last.
+pub fn predict(history: &[CommandInfo], buffer: &str, cwd: Option<&str>) -> Vec<Prediction> {
+    if buffer.trim().is_empty() {
+        return vec![];
+    }
+
+    let last_command = history.first().and_then(|info| info.command.as_deref());
+    let mut scores: HashMap<&str, f64> = HashMap::new();
+    for (i, info) in history.iter().enumerate() {
+        let Some(command) = info.command.as_deref() else {
+            continue;
+        };
+        if command.len() <= buffer.len() || !command.starts_with(buffer) || command.contains('\n') {
+            continue;
+        }
+
+        let mut weight = 0.5_f64.powf(i as f64 / RECENCY_HALF_LIFE);
+        if cwd.is_some() && info.cwd.as_deref() == cwd {
+            weight *= CWD_WEIGHT;
+        }
+        if info.exit_code.is_some_and(|code| code != 0) {
+            weight *= FAILED_WEIGHT;
+        }
+        let previous = history.get(i + 1).and_then(|info| info.command.as_deref());
+        if last_command.is_some() && previous == last_command {
+            weight *= NGRAM_WEIGHT;
+        }
+
+        *scores.entry(command).or_default() += weight;
+    }
+
+    let mut predictions = scores
+        .into_iter()
+        .map(|(command, score)| Prediction {
+            command: command.to_owned(),
+            score,
+        })
+        .collect::<Vec<_>>();
+    predictions.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.command.cmp(&b.command)));
+    predictions
+}
+
+/// Merges the full text of remote completions, best first, with local predictions into
+/// `(full text, priority)` pairs for the completion cache, where lower priorities are better.
+///
+/// Remote completions keep their order, except that the ones also predicted from the history rank
+/// above the rest. Local predictions that weren't suggested remotely rank below remote
+/// completions.
+pub fn merge(remote: &[String], local: &[Prediction]) -> Vec<(String, f64)> {
+    let max_score = local.first().map_or(1.0, |prediction| prediction.score);
+    let local_score = |text: &str| {
+        local
+            .iter()
+            .find(|prediction| prediction.command == text)
+            .map(|prediction| prediction.score / max_score)
+    };
+
+    let mut merged = remote
+        .iter()
+        .enumerate()
+        .map(|(i, text)| {
+            let priority = if i == 0 { 0.0 } else { 1.0 };
+            let boost = local_score(text).map_or(0.0, |score| 1.0 + score);
+            (text.clone(), priority - boost)
+        })
+        .collect::<Vec<_>>();
+
+    merged.extend(
+        local
+            .iter()
+            .filter(|prediction| !remote.contains(&prediction.command))
+            .map(|prediction| (prediction.command.clone(), 2.0 - prediction.score / max_score)),
+    );
+
+    merged.sort_by(|(_, a), (_, b)| a.total_cmp(b));
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command(command: &str, cwd: &str, exit_code: i32) -> CommandInfo {
+        CommandInfo {
+            command: Some(command.into()),
+            cwd: Some(cwd.into()),
+            exit_code: Some(exit_code),
+            ..Default::default()
+        }
+    }
+
+    fn commands(predictions: &[Prediction]) -> Vec<&str> {
+        predictions.iter().map(|p| p.command.as_str()).collect()
+    }
+
+    #[test]
+    fn test_predict() {
+        // Newest first
+        let history = vec![
+            command("ls", "/home", 0),
+            command("git push --force", "/repo", 1),
+            command("git pull", "/other", 0),
+            command("git push", "/repo", 0),
+        ];
+
+        assert_eq!(commands(&predict(&history, "git p", Some("/other"))), vec![
+            "git pull",
+            "git push",
+            "git push --force"
+        ]);
+        assert_eq!(commands(&predict(&history, "git p", Some("/repo"))), vec![
+            "git push",
+            "git pull",
+            "git push --force"
+        ]);
+        assert!(predict(&history, "git pull", None).is_empty());
+        assert!(predict(&history, " ", None).is_empty());
+    }
+
+    #[test]
+    fn test_predict_ngram() {
+        let history = vec![
+            command("cargo build", "/repo", 0),
+            command("cargo clippy", "/repo", 0),
+            command("cargo test", "/repo", 0),
+            command("cargo build", "/repo", 0),
+        ];
+
+        // `cargo test` followed the last command, `cargo build`, before
+        assert_eq!(predict(&history, "cargo ", None)[0].command, "cargo test");
+    }
+
+    #[test]
+    fn test_merge() {
+        let local = vec![
+            Prediction {
+                command: "git status".into(),
+                score: 4.0,
+            },
+            Prediction {
+                command: "git stash".into(),
+                score: 1.0,
+            },
+        ];
+
+        let merged = merge(&["git stash pop".into(), "git stash".into()], &local);
+        assert_eq!(merged.iter().map(|(text, _)| text.as_str()).collect::<Vec<_>>(), vec![
+            "git stash",
+            "git stash pop",
+            "git status"
+        ]);
+
+        let merged = merge(&[], &local);
+        assert_eq!(merged, vec![("git status".into(), 1.0), ("git stash".into(), 1.75)]);
+        assert!(merge(&[], &[]).is_empty());
+    }
+}
@@ -1,8 +1,14 @@
 mod completion_cache;
-mod validate;
+mod local_predictor;
+mod project;
 
+use std::cmp::Reverse;
 use std::fmt::Write;
-use std::sync::LazyLock;
+use std::path::Path;
+use std::sync::{
+    Arc,
+    LazyLock,
+};
 use std::time::{
     Duration,
     Instant,
@@ -35,6 +41,7 @@ use fig_util::terminal::{
     current_terminal,
     current_terminal_version,
 };
+use fig_util::validate::validate;
 use flume::Sender;
 use regex::Regex;
 use tokio::sync::Mutex;
@@ -43,9 +50,13 @@ use tracing::{
     info,
     warn,
 };
-use validate::validate;
 
 use self::completion_cache::CompletionCache;
+use self::local_predictor::{
+    CONFIDENT_SCORE,
+    Prediction,
+};
+use self::project::ProjectContext;
 use crate::history::{
     self,
     HistoryQueryParams,
@@ -53,6 +64,9 @@ use crate::history::{
 };
 
 const HISTORY_COUNT_DEFAULT: usize = 49;
+/// Only predict completions from the local history, never sending requests, e.g. for air gapped
+/// machines.
+const INLINE_LOCAL_ONLY_SETTINGS_KEY: &str = "inline.local-only";
 const DEBOUNCE_DURATION_DEFAULT: Duration = Duration::from_millis(300);
 
 static INLINE_ENABLED: Mutex<bool> = Mutex::const_new(true);
@@ -161,7 +175,8 @@ struct TelemetryQueueItem {
 
 pub async fn handle_request(
     figterm_request: InlineShellCompletionRequest,
-    _session_id: String,
+    session_id: String,
+    cwd: Option<String>,
     response_tx: Sender<FigtermResponseMessage>,
     history_sender: HistorySender,
 ) {
@@ -190,14 +205,36 @@ pub async fn handle_request(
         }
     }
 
+    let history = query_history(&history_sender).await;
+    let predictions = local_predictor::predict(&history, buffer, cwd.as_deref());
+
+    // Serve predictions from the history instantly if they are likely enough, or if remote
+    // completions are disabled
+    let local_only = fig_settings::settings::get_bool_or(INLINE_LOCAL_ONLY_SETTINGS_KEY, false);
+    if local_only || predictions.first().is_some_and(|p| p.score >= CONFIDENT_SCORE) {
+        send_local_prediction(buffer, &predictions, &response_tx).await;
+        return;
+    }
+
     // debounce requests
     let now = SystemTime::now();
     LAST_RECEIVED.lock().await.replace(now);
 
     let Ok(client) = Client::new().await else {
+        send_local_prediction(buffer, &predictions, &response_tx).await;
         return;
     };
 
+    let prompt_context = PromptContext {
+        project: cwd
+            .as_deref()
+            .map(Path::new)
+            .map(ProjectContext::detect)
+            .unwrap_or_default(),
+        cwd,
+        session_id: Some(session_id),
+    };
+
     for _ in 0..3 {
         tokio::time::sleep(*DEBOUNCE_DURATION).await;
         if *LAST_RECEIVED.lock().await == Some(now) {
@@ -221,26 +258,7 @@ pub async fn handle_request(
 
         info!("Sending inline_shell_completion completion request");
 
-        let (history_query_tx, history_query_rx) = flume::bounded(1);
-        if let Err(err) = history_sender
-            .send_async(history::HistoryCommand::Query(
-                HistoryQueryParams { limit: *HISTORY_COUNT },
-                history_query_tx,
-            ))
-            .await
-        {
-            error!(%err, "Failed to send history query");
-        }
-
-        let history = match history_query_rx.recv_async().await {
-            Ok(Some(history)) => history,
-            err => {
-                error!(?err, "Failed to get history");
-                vec![]
-            },
-        };
-
-        let Some(prompt) = prompt(&history, buffer) else {
+        let Some(prompt) = prompt(&history, buffer, &prompt_context) else {
             return;
         };
 
@@ -281,23 +299,29 @@ pub async fn handle_request(
                     .map(|choice| clean_completion(&choice.content).clone())
                     .collect::<Vec<_>>();
 
-                // skips the first one which we will recommend, we only cache the rest
-                for completion in completions.iter().skip(1) {
-                    let full_text = format!("{buffer}{completion}");
-                    if !completion.is_empty() && validate(&full_text) {
-                        completion_cache.insert(full_text, 1.0);
-                    }
+                // merge the remote completions with the predictions from the history, which
+                // re-ranks the remote completions that were also predicted locally
+                let remote = completions
+                    .iter()
+                    .filter(|completion| !completion.is_empty())
+                    .map(|completion| format!("{buffer}{completion}"))
+                    .filter(|full_text| validate(full_text))
+                    .collect::<Vec<_>>();
+                let merged = local_predictor::merge(&remote, &predictions)
+                    .into_iter()
+                    .filter(|(full_text, _)| validate(full_text))
+                    .collect::<Vec<_>>();
+                for (full_text, priority) in &merged {
+                    completion_cache.insert(full_text.clone(), *priority);
                 }
+                let best = merged
+                    .first()
+                    .map(|(full_text, _)| full_text.strip_prefix(buffer).unwrap_or(full_text).to_owned());
 
                 // now deals with the first recommendation
                 if let Some(completion) = completions.first_mut() {
                     let full_text = format!("{buffer}{completion}");
                     let valid = validate(&full_text);
-                    let is_empty = completion.is_empty();
-
-                    if valid && !is_empty {
-                        completion_cache.insert(full_text, 0.0);
-                    }
 
                     let suggestion_state = match (valid, completion.is_empty()) {
                         (true, true) => SuggestionState::Empty,
@@ -328,14 +352,18 @@ pub async fn handle_request(
                         }
                     });
 
-                    if valid { Some(std::mem::take(completion)) } else { None }
+                    if valid && best.is_none() {
+                        Some(std::mem::take(completion))
+                    } else {
+                        best
+                    }
                 } else {
-                    None
+                    best
                 }
             },
             Err(err) => {
-                error!(%err, "Failed to get inline_shell_completion completion");
-                None
+                error!(%err, "Failed to get inline_shell_completion completion, falling back to the history");
+                best_local_prediction(buffer, &predictions)
             },
         };
 
@@ -358,7 +386,68 @@ pub async fn handle_request(
             },
         }
 
-        break;
+        return;
+    }
+
+    // every attempt was throttled
+    send_local_prediction(buffer, &predictions, &response_tx).await;
+}
+
+async fn query_history(history_sender: &HistorySender) -> Arc<Vec<CommandInfo>> {
+    let (history_query_tx, history_query_rx) = flume::bounded(1);
+    if let Err(err) = history_sender
+        .send_async(history::HistoryCommand::Query(
+            HistoryQueryParams {
+                limit: local_predictor::HISTORY_COUNT.max(*HISTORY_COUNT),
+            },
+            history_query_tx,
+        ))
+        .await
+    {
+        error!(%err, "Failed to send history query");
+    }
+
+    match history_query_rx.recv_async().await {
+        Ok(Some(history)) => history,
+        err => {
+            error!(?err, "Failed to get history");
+            Arc::default()
+        },
+    }
+}
+
+/// The text to insert for the best valid prediction from the history.
+fn best_local_prediction(buffer: &str, predictions: &[Prediction]) -> Option<String> {
+    predictions
+        .iter()
+        .map(|prediction| prediction.command.as_str())
+        .find(|full_text| validate(full_text))
+        .map(|full_text| full_text.strip_prefix(buffer).unwrap_or(full_text).to_owned())
+}
+
+/// Responds with the best prediction from the history, caching the rest, for when remote
+/// completions are disabled or unavailable.
+async fn send_local_prediction(buffer: &str, predictions: &[Prediction], response_tx: &Sender<FigtermResponseMessage>) {
+    let merged = local_predictor::merge(&[], predictions);
+
+    let mut completion_cache = COMPLETION_CACHE.lock().await;
+    for (full_text, priority) in merged.iter().filter(|(full_text, _)| validate(full_text)) {
+        completion_cache.insert(full_text.clone(), *priority);
+    }
+    drop(completion_cache);
+
+    let insert_text = best_local_prediction(buffer, predictions);
+    info!(?insert_text, "Got inline_shell_completion completion from the history");
+
+    if let Err(err) = response_tx
+        .send_async(FigtermResponseMessage {
+            response: Some(FigtermResponse::InlineShellCompletion(InlineShellCompletionResponse {
+                insert_text,
+            })),
+        })
+        .await
+    {
+        error!(%err, "Failed to send inline_shell_completion completion");
     }
 }
 
@@ -376,16 +465,64 @@ pub async fn handle_set_enabled(figterm_request: InlineShellCompletionSetEnabled
     *INLINE_ENABLED.lock().await = figterm_request.enabled;
 }
 
-fn prompt(history: &[CommandInfo], buffer: &str) -> Option<String> {
-    for i in (0..history.len()).rev() {
-        let formatted_prompt = history
+/// What an inline completion prompt is built for, used to scope the history and add context.
+#[derive(Debug, Default)]
+struct PromptContext {
+    cwd: Option<String>,
+    session_id: Option<String>,
+    project: ProjectContext,
+}
+
+impl PromptContext {
+    /// How relevant a command from the history is, commands from the current session are the
+    /// most relevant followed by the ones run in the current directory.
+    fn relevance(&self, command: &CommandInfo) -> u8 {
+        let same_session = self.session_id.is_some() && command.session_id == self.session_id;
+        let same_cwd = self.cwd.is_some() && command.cwd == self.cwd;
+        (u8::from(same_session) << 1) | u8::from(same_cwd)
+    }
+
+    fn header(&self) -> String {
+        let mut header = String::new();
+        if let Some(cwd) = &self.cwd {
+            let _ = writeln!(header, "# cwd: {cwd}");
+        }
+        for comment in self.project.comments() {
+            let _ = writeln!(header, "{comment}");
+        }
+        header
+    }
+}
+
+/// Picks up to `HISTORY_COUNT` commands for the prompt from `history`, which is ordered newest
+/// first. Failed commands are left out and the most relevant commands are preferred over more
+/// recent ones. Returns the commands oldest first.
+fn prompt_commands<'a>(history: &'a [CommandInfo], context: &PromptContext) -> Vec<&'a str> {
+    let mut commands = history
+        .iter()
+        .enumerate()
+        .filter(|(_, info)| info.exit_code.is_none_or(|code| code == 0))
+        .filter_map(|(i, info)| Some((i, info.command.as_deref()?, context.relevance(info))))
+        .collect::<Vec<_>>();
+
+    commands.sort_by_key(|(i, _, relevance)| (Reverse(*relevance), *i));
+    commands.truncate(*HISTORY_COUNT);
+    commands.sort_by_key(|(i, ..)| Reverse(*i));
+    commands.into_iter().map(|(_, command, _)| command).collect()
+}
+
+fn prompt(history: &[CommandInfo], buffer: &str, context: &PromptContext) -> Option<String> {
+    let commands = prompt_commands(history, context);
+    let header = context.header();
+
+    // drop the oldest commands until the prompt fits
+    for i in (0..commands.len()).rev() {
+        let formatted_prompt = commands[commands.len() - (i + 1)..]
             .iter()
-            .rev()
-            .take(i + 1)
-            .filter_map(|c| c.command.clone())
-            .chain([buffer.into()])
+            .copied()
+            .chain([buffer])
             .enumerate()
-            .fold(String::new(), |mut acc, (i, c)| {
+            .fold(header.clone(), |mut acc, (i, c)| {
                 if i > 0 {
                     acc.push('\n');
                 }
@@ -442,12 +579,84 @@ mod tests {
             },
         ];
 
-        let prompt = prompt(&history, "echo ").unwrap();
+        let prompt = prompt(&history, "echo ", &PromptContext::default()).unwrap();
         println!("{prompt}");
 
         assert_eq!(prompt, "    1  echo hello\n    2  echo world\n    3  echo ");
     }
 
+    #[test]
+    fn test_prompt_excludes_failed_commands() {
+        let history = vec![
+            CommandInfo {
+                command: Some("gti status".into()),
+                exit_code: Some(127),
+                ..Default::default()
+            },
+            CommandInfo {
+                command: Some("git add .".into()),
+                exit_code: Some(0),
+                ..Default::default()
+            },
+        ];
+
+        let prompt = prompt(&history, "git ", &PromptContext::default()).unwrap();
+        assert_eq!(prompt, "    1  git add .\n    2  git ");
+    }
+
+    #[test]
+    fn test_prompt_prefers_session_and_cwd() {
+        let context = PromptContext {
+            cwd: Some("/repo".into()),
+            session_id: Some("session".into()),
+            ..Default::default()
+        };
+        let command = |command: &str, cwd: &str, session_id: &str| CommandInfo {
+            command: Some(command.into()),
+            cwd: Some(cwd.into()),
+            session_id: Some(session_id.into()),
+            exit_code: Some(0),
+            ..Default::default()
+        };
+
+        // newest first, with more unrelated commands than fit in the prompt
+        let mut history = (0..*HISTORY_COUNT)
+            .map(|i| command(&format!("echo {i}"), "/other", "other"))
+            .collect::<Vec<_>>();
+        history.push(command("cargo build", "/repo", "other"));
+        history.push(command("cargo test", "/elsewhere", "session"));
+
+        let commands = prompt_commands(&history, &context);
+        assert_eq!(commands.len(), *HISTORY_COUNT);
+        // the relevant commands are kept in chronological order, the oldest unrelated ones dropped
+        assert_eq!(&commands[..3], ["cargo test", "cargo build", "echo 46"]);
+        assert_eq!(commands.last(), Some(&"echo 0"));
+    }
+
+    #[test]
+    fn test_prompt_project_context() {
+        let context = PromptContext {
+            cwd: Some("/repo".into()),
+            project: ProjectContext {
+                git_branch: Some("main".into()),
+                project_files: vec!["Cargo.toml", "Makefile"],
+                make_targets: vec!["build".into(), "test".into()],
+                npm_scripts: vec![],
+            },
+            ..Default::default()
+        };
+        let history = vec![CommandInfo {
+            command: Some("make build".into()),
+            ..Default::default()
+        }];
+
+        assert_eq!(
+            prompt(&history, "make ", &context).unwrap(),
+            "# cwd: /repo\n# git branch: main\n# project files: Cargo.toml, Makefile\n# make targets: build, \
+             test\n    1  make build\n    2  make "
+        );
+    }
+
     #[test]
     fn test_clean_completion() {
         assert_eq!(clean_completion("echo hello"), "echo hello");
@@ -475,7 +684,7 @@ mod tests {
             ..Default::default()
         }];
 
-        assert!(prompt(&history, "echo ").is_none());
+        assert!(prompt(&history, "echo ", &PromptContext::default()).is_none());
     }
 
     #[ignore = "not in CI"]
@@ -490,7 +699,7 @@ mod tests {
                 0,
             )
             .unwrap();
-        let prompt = prompt(&commands, "cd ").unwrap();
+        let prompt = prompt(&commands, "cd ", &PromptContext::default()).unwrap();
 
         let client = fig_api_client::Client::new().await.unwrap();
         let out = client
@@ -0,0 +1,209 @@
+//! Lightweight signals about the project in the current directory, added as context to inline
+//! completion prompts.
+
+use std::path::{
+    Path,
+    PathBuf,
+};
+
+/// Files whose presence in the current directory says what kind of project it is.
+const PROJECT_FILES: &[&str] = &[
+    "Cargo.toml",
+    "package.json",
+    "Makefile",
+    "pyproject.toml",
+    "go.mod",
+    "pom.xml",
+    "build.gradle",
+    "docker-compose.yml",
+    "Dockerfile",
+];
+
+/// The maximum number of make targets or npm scripts listed.
+const MAX_TARGETS: usize = 10;
+
+/// Makefiles or package.json files larger than this are not read.
+const MAX_FILE_SIZE: u64 = 256 * 1024;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProjectContext {
+    pub git_branch: Option<String>,
+    pub project_files: Vec<&'static str>,
+    pub make_targets: Vec<String>,
+    pub npm_scripts: Vec<String>,
+}
+
+impl ProjectContext {
+    pub fn detect(cwd: &Path) -> Self {
+        let project_files = PROJECT_FILES
+            .iter()
+            .copied()
+            .filter(|file| cwd.join(file).is_file())
+            .collect::<Vec<_>>();
+
+        let make_targets = match project_files.contains(&"Makefile") {
+            true => read_small_file(&cwd.join("Makefile"))
+                .map(|makefile| make_targets(&makefile))
+                .unwrap_or_default(),
+            false => vec![],
+        };
+
+        let npm_scripts = match project_files.contains(&"package.json") {
+            true => read_small_file(&cwd.join("package.json"))
+                .map(|package_json| npm_scripts(&package_json))
+                .unwrap_or_default(),
+            false => vec![],
+        };
+
+        Self {
+            git_branch: git_branch(cwd),
+            project_files,
+            make_targets,
+            npm_scripts,
+        }
+    }
+
+    /// Shell comments describing the project, one per line.
+    pub fn comments(&self) -> Vec<String> {
+        let mut comments = vec![];
+        if let Some(branch) = &self.git_branch {
+            comments.push(format!("# git branch: {branch}"));
+        }
+        if !self.project_files.is_empty() {
+            comments.push(format!("# project files: {}", self.project_files.join(", ")));
+        }
+        if !self.make_targets.is_empty() {
+            comments.push(format!("# make targets: {}", self.make_targets.join(", ")));
+        }
+        if !self.npm_scripts.is_empty() {
+            comments.push(format!("# npm scripts: {}", self.npm_scripts.join(", ")));
+        }
+        comments
+    }
+}
+
+fn read_small_file(path: &Path) -> Option<String> {
+    if path.metadata().ok()?.len() > MAX_FILE_SIZE {
+        return None;
+    }
+    std::fs::read_to_string(path).ok()
+}
+
+/// The current branch of the git repository containing `cwd`, or the short commit hash if the
+/// HEAD is detached.
+fn git_branch(cwd: &Path) -> Option<String> {
+    let git_dir = cwd.ancestors().map(|dir| dir.join(".git")).find_map(|git| {
+        if git.is_dir() {
+            return Some(git);
+        }
+        // worktrees and submodules have a `.git` file pointing to the git directory
+        let contents = std::fs::read_to_string(&git).ok()?;
+        let git_dir = PathBuf::from(contents.strip_prefix("gitdir:")?.trim());
+        Some(match git_dir.is_absolute() {
+            true => git_dir,
+            false => git.parent()?.join(git_dir),
+        })
+    })?;
+
+    let head = std::fs::read_to_string(git_dir.join("HEAD")).ok()?;
+    let head = head.trim();
+    match head.strip_prefix("ref: refs/heads/") {
+        Some(branch) => Some(branch.to_owned()),
+        None => head.get(..7).map(str::to_owned),
+    }
+}
+
+/// The explicit targets of a Makefile, skipping special and pattern targets.
+fn make_targets(makefile: &str) -> Vec<String> {
+    let mut targets = vec![];
+    for line in makefile.lines() {
+        if line.starts_with(['\t', ' ', '#', '.']) {
+            continue;
+        }
+        let Some((names, rest)) = line.split_once(':') else {
+            continue;
+        };
+        // variable assignments such as `FOO := bar`
+        if rest.starts_with('=') || names.contains('=') {
+            continue;
+        }
+        for name in names.split_whitespace() {
+            if !name.contains(['%', '$']) && !targets.iter().any(|target| target == name) {
+                targets.push(name.to_owned());
+            }
+        }
+        if targets.len() >= MAX_TARGETS {
+            break;
+        }
+    }
+    targets.truncate(MAX_TARGETS);
+    targets
+}
+
+fn npm_scripts(package_json: &str) -> Vec<String> {
+    let Ok(package) = serde_json::from_str::<serde_json::Value>(package_json) else {
+        return vec![];
+    };
+    package
+        .get("scripts")
+        .and_then(|scripts| scripts.as_object())
+        .map(|scripts| scripts.keys().take(MAX_TARGETS).cloned().collect())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_make_targets() {
+        let makefile = "\
+CARGO := cargo
+.PHONY: build test
+build: deps
+\t$(CARGO) build
+test lint: build
+\t$(CARGO) test
+%.o: %.c
+\tcc $<
+# comment: not a target
+";
+        assert_eq!(make_targets(makefile), vec!["build", "test", "lint"]);
+    }
+
+    #[test]
+    fn test_npm_scripts() {
+        assert_eq!(
+            npm_scripts(r#"{ "name": "app", "scripts": { "build": "tsc", "test": "jest" } }"#),
+            vec!["build", "test"]
+        );
+        assert!(npm_scripts("not json").is_empty());
+    }
+
+    #[test]
+    fn test_detect() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path().join("project");
+        std::fs::create_dir_all(dir.path().join(".git")).unwrap();
+        std::fs::create_dir_all(&project).unwrap();
+        std::fs::write(dir.path().join(".git/HEAD"), "ref: refs/heads/feature/inline\n").unwrap();
+        std::fs::write(project.join("Cargo.toml"), "[package]").unwrap();
+        std::fs::write(project.join("Makefile"), "release:\n\tcargo build --release\n").unwrap();
+
+        let context = ProjectContext::detect(&project);
+        assert_eq!(context, ProjectContext {
+            git_branch: Some("feature/inline".into()),
+            project_files: vec!["Cargo.toml", "Makefile"],
+            make_targets: vec!["release".into()],
+            npm_scripts: vec![],
+        });
+        assert_eq!(context.comments(), vec![
+            "# git branch: feature/inline",
+            "# project files: Cargo.toml, Makefile",
+            "# make targets: release",
+        ]);
+
+        std::fs::write(dir.path().join(".git/HEAD"), "0123456789abcdef\n").unwrap();
+        assert_eq!(git_branch(&project), Some("0123456".into()));
+    }
+}
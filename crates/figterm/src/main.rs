@@ -1,6 +1,7 @@
 #[cfg(target_os = "linux")]
 mod cleanup;
 pub mod cli;
+mod command_output;
 mod event_handler;
 pub mod history;
 pub mod inline;
@@ -303,7 +304,7 @@ fn can_send_edit_buffer<T>(term: &Term<T>) -> bool
 where
     T: EventListener,
 {
-    let shell_enabled = ["bash", "zsh", "fish", "nu", "dash"]
+    let shell_enabled = ["bash", "zsh", "fish", "nu", "dash", "xonsh", "elvish", "pwsh"]
         .into_iter()
         .chain(USER_ENABLED_SHELLS.iter().map(|s| s.as_str()))
         .any(|s| {
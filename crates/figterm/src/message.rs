@@ -24,6 +24,7 @@ use fig_proto::figterm::intercept_request::{
 };
 use fig_proto::figterm::{
     self,
+    CommandOutputResponse,
     FigtermRequestMessage,
     FigtermResponseMessage,
     TelemetryRequest,
@@ -55,6 +56,7 @@ use crate::{
     MainLoopEvent,
     SHELL_ALIAS,
     SHELL_ENVIRONMENT_VARIABLES,
+    command_output,
     inline,
     shell_state_to_context,
 };
@@ -294,6 +296,7 @@ pub async fn process_figterm_request(
             anyhow::bail!("InlineShellCompletionSetEnabled is not supported over remote")
         },
         FigtermRequest::Telemtety(_) => anyhow::bail!("Telemetry is not supported over remote"),
+        FigtermRequest::CommandOutput(_) => anyhow::bail!("CommandOutput is not supported over remote"),
     }
 }
 
@@ -313,8 +316,20 @@ pub async fn process_figterm_message(
         Some(FigtermRequest::InlineShellCompletion(request)) => {
             let history_sender = history_sender.clone();
             let session_id = session_id.to_owned();
-
-            tokio::spawn(inline::handle_request(request, session_id, response_tx, history_sender));
+            let cwd = term
+                .shell_state()
+                .get_context()
+                .current_working_directory
+                .as_ref()
+                .map(|cwd| cwd.to_string_lossy().into_owned());
+
+            tokio::spawn(inline::handle_request(
+                request,
+                session_id,
+                cwd,
+                response_tx,
+                history_sender,
+            ));
         },
         Some(FigtermRequest::InlineShellCompletionAccept(request)) => {
             tokio::spawn(inline::handle_accept(request, session_id.to_owned()));
@@ -322,6 +337,16 @@ pub async fn process_figterm_message(
         Some(FigtermRequest::InlineShellCompletionSetEnabled(request)) => {
             tokio::spawn(inline::handle_set_enabled(request, session_id.to_owned()));
         },
+        Some(FigtermRequest::CommandOutput(request)) => {
+            let response_message = FigtermResponseMessage {
+                response: Some(FigtermResponse::CommandOutput(CommandOutputResponse {
+                    outputs: command_output::get(request.index),
+                })),
+            };
+            if let Err(err) = response_tx.send_async(response_message).await {
+                error!(%err, "Failed sending request response");
+            }
+        },
         Some(FigtermRequest::Telemtety(TelemetryRequest { event_blob })) => {
             match fig_telemetry::AppTelemetryEvent::from_json(&event_blob) {
                 Ok(event) => {
@@ -362,7 +387,7 @@ async fn send_figterm_response_hostbound(
                 nonce,
                 response: Some(match response {
                     FigtermResponse::Diagnostics(diagnostics) => Response::Diagnostics(diagnostics),
-                    FigtermResponse::InlineShellCompletion(_) => unreachable!(),
+                    FigtermResponse::InlineShellCompletion(_) | FigtermResponse::CommandOutput(_) => unreachable!(),
                 }),
             })),
         };
@@ -839,7 +839,9 @@ impl DebugSubcommand {
                                 ]);
                                 command
                             },
-                            Shell::Nu => eyre::bail!("Unsupported shell for debug"),
+                            Shell::Nu | Shell::Xonsh | Shell::Elvish | Shell::PowerShell => {
+                                eyre::bail!("Unsupported shell for debug")
+                            },
                         };
 
                         println!("Starting {PRODUCT_NAME} debug shell");
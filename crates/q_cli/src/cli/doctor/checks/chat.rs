@@ -0,0 +1,411 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::path::{
+    Path,
+    PathBuf,
+};
+use std::process::Stdio;
+use std::time::Duration;
+
+use async_trait::async_trait;
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+use fig_util::consts::CHAT_BINARY_NAME;
+use fig_util::consts::CLI_BINARY_NAME;
+use owo_colors::OwoColorize;
+use serde::Deserialize;
+use tokio::io::AsyncReadExt;
+use tokio::process::Command;
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+use crate::cli::doctor::doctor_error;
+use crate::cli::doctor::{
+    DoctorCheck,
+    DoctorCheckType,
+    DoctorError,
+    Platform,
+};
+
+/// How long an MCP server has to stay up for the launch to count as successful
+const MCP_LAUNCH_TIMEOUT: Duration = Duration::from_secs(3);
+
+const PROXY_ENV_VARS: &[&str] = &[
+    "HTTPS_PROXY",
+    "https_proxy",
+    "HTTP_PROXY",
+    "http_proxy",
+    "ALL_PROXY",
+    "all_proxy",
+];
+
+pub struct AuthTokenCheck;
+
+#[async_trait]
+impl DoctorCheck for AuthTokenCheck {
+    fn name(&self) -> Cow<'static, str> {
+        "Login token is valid".into()
+    }
+
+    async fn get_type(&self, _: &(), _: Platform) -> DoctorCheckType {
+        // Not being logged in at all is reported by the auth check
+        if fig_util::system_info::in_cloudshell() || !fig_auth::is_logged_in().await {
+            DoctorCheckType::NoCheck
+        } else {
+            DoctorCheckType::NormalCheck
+        }
+    }
+
+    async fn check(&self, _: &()) -> Result<(), DoctorError> {
+        let login_info = format!("Run {} to log in again", format!("{CLI_BINARY_NAME} login").magenta()).into();
+        // Doctor only reads the token, forcing a refresh would write to the secret store
+        match fig_auth::builder_id_token().await {
+            Ok(Some(token)) if !token.is_expired() => Ok(()),
+            Ok(Some(_)) => Err(DoctorError::Error {
+                reason: "Login token has expired".into(),
+                info: vec![login_info],
+                fix: None,
+                error: None,
+            }),
+            Ok(None) => Err(DoctorError::Error {
+                reason: "Login token is no longer valid".into(),
+                info: vec![login_info],
+                fix: None,
+                error: None,
+            }),
+            Err(err) => Err(DoctorError::Error {
+                reason: "Failed to read the login token".into(),
+                info: vec![login_info],
+                fix: None,
+                error: Some(err.into()),
+            }),
+        }
+    }
+}
+
+pub struct ChatDatabaseCheck;
+
+#[async_trait]
+impl DoctorCheck for ChatDatabaseCheck {
+    fn name(&self) -> Cow<'static, str> {
+        "Chat database is intact".into()
+    }
+
+    async fn check(&self, _: &()) -> Result<(), DoctorError> {
+        let db = fig_settings::sqlite::database().map_err(|err| DoctorError::Error {
+            reason: "Failed to open the database".into(),
+            info: vec![],
+            fix: None,
+            error: Some(err.into()),
+        })?;
+
+        let problems = db.integrity_check().map_err(|err| DoctorError::Error {
+            reason: "Failed to check the database".into(),
+            info: vec![],
+            fix: None,
+            error: Some(err.into()),
+        })?;
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(DoctorError::Error {
+                reason: "The database is corrupted".into(),
+                info: problems.into_iter().map(Into::into).collect(),
+                fix: None,
+                error: None,
+            })
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct McpConfig {
+    #[serde(default)]
+    mcp_servers: HashMap<String, McpServer>,
+}
+
+#[derive(Debug, Deserialize)]
+struct McpServer {
+    command: String,
+    #[serde(default)]
+    args: Vec<String>,
+    #[serde(default)]
+    env: Option<HashMap<String, String>>,
+}
+
+/// The MCP config files, paired with whether they come from the current workspace
+fn mcp_config_paths() -> Vec<(PathBuf, bool)> {
+    let mut paths = vec![];
+    if let Ok(home) = fig_util::directories::home_dir() {
+        paths.push((home.join(".aws").join("amazonq").join("mcp.json"), false));
+    }
+    if let Ok(cwd) = std::env::current_dir() {
+        paths.push((cwd.join(".amazonq").join("mcp.json"), true));
+    }
+    paths
+}
+
+/// Starts the server and waits a moment to see whether it exits right away
+async fn launch_mcp_server(server: &McpServer) -> Result<(), String> {
+    let mut child = Command::new(&server.command)
+        .args(&server.args)
+        .envs(server.env.iter().flatten())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|err| format!("failed to start `{}`: {err}", server.command))?;
+
+    match tokio::time::timeout(MCP_LAUNCH_TIMEOUT, child.wait()).await {
+        Ok(Ok(status)) => {
+            let mut stderr = String::new();
+            if let Some(mut pipe) = child.stderr.take() {
+                pipe.read_to_string(&mut stderr).await.ok();
+            }
+            match stderr.lines().rev().find(|line| !line.trim().is_empty()) {
+                Some(line) => Err(format!("exited with {status}: {}", line.trim())),
+                None => Err(format!("exited with {status}")),
+            }
+        },
+        Ok(Err(err)) => Err(err.to_string()),
+        Err(_) => {
+            child.kill().await.ok();
+            Ok(())
+        },
+    }
+}
+
+pub struct McpConfigCheck;
+
+#[async_trait]
+impl DoctorCheck for McpConfigCheck {
+    fn name(&self) -> Cow<'static, str> {
+        "MCP servers are configured correctly".into()
+    }
+
+    async fn get_type(&self, _: &(), _: Platform) -> DoctorCheckType {
+        if mcp_config_paths().iter().any(|(path, _)| path.exists()) {
+            DoctorCheckType::NormalCheck
+        } else {
+            DoctorCheckType::NoCheck
+        }
+    }
+
+    async fn check(&self, _: &()) -> Result<(), DoctorError> {
+        let mut problems: Vec<Cow<'static, str>> = vec![];
+
+        let mut launches = vec![];
+
+        for (path, from_workspace) in mcp_config_paths() {
+            let Ok(contents) = tokio::fs::read_to_string(&path).await else {
+                continue;
+            };
+
+            let config: McpConfig = match serde_json::from_str(&contents) {
+                Ok(config) => config,
+                Err(err) => {
+                    problems.push(format!("{}: {err}", path.display()).into());
+                    continue;
+                },
+            };
+
+            // Workspace configs come from whatever directory doctor runs in, so only check that they parse
+            if from_workspace {
+                continue;
+            }
+
+            let mut servers = config.mcp_servers.into_iter().collect::<Vec<_>>();
+            servers.sort_by(|(a, _), (b, _)| a.cmp(b));
+            for (name, server) in servers {
+                let path = path.clone();
+                launches.push(async move {
+                    launch_mcp_server(&server)
+                        .await
+                        .map_err(|err| format!("{name} ({}): {err}", path.display()))
+                });
+            }
+        }
+
+        for result in futures::future::join_all(launches).await {
+            if let Err(err) = result {
+                problems.push(err.into());
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(DoctorError::Error {
+                reason: "Some MCP servers could not be loaded".into(),
+                info: problems,
+                fix: None,
+                error: None,
+            })
+        }
+    }
+}
+
+/// Checks that a proxy URL can be used by the http client
+fn validate_proxy(value: &str) -> Result<(), String> {
+    let url = url::Url::parse(value).map_err(|err| err.to_string())?;
+    match url.scheme() {
+        "http" | "https" | "socks5" | "socks5h" => {},
+        scheme => return Err(format!("unsupported scheme `{scheme}`")),
+    }
+    if url.host_str().is_none_or(str::is_empty) {
+        return Err("missing host".into());
+    }
+    Ok(())
+}
+
+fn validate_cert_file(path: &Path) -> Result<(), String> {
+    let contents = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+    if contents.contains("-----BEGIN CERTIFICATE-----") {
+        Ok(())
+    } else {
+        Err("no PEM certificates found".into())
+    }
+}
+
+pub struct ProxyCheck;
+
+#[async_trait]
+impl DoctorCheck for ProxyCheck {
+    fn name(&self) -> Cow<'static, str> {
+        "Proxy and certificate settings are valid".into()
+    }
+
+    async fn check(&self, _: &()) -> Result<(), DoctorError> {
+        let mut problems: Vec<Cow<'static, str>> = vec![];
+
+        for var in PROXY_ENV_VARS {
+            if let Ok(value) = std::env::var(var) {
+                if let Err(err) = validate_proxy(&value) {
+                    problems.push(format!("{var}={value}: {err}").into());
+                }
+            }
+        }
+
+        let custom_cert = std::env::var("Q_CUSTOM_CERT")
+            .ok()
+            .or_else(|| fig_settings::state::get_string("Q_CUSTOM_CERT").ok().flatten());
+        for (name, path) in [
+            ("Q_CUSTOM_CERT", custom_cert),
+            ("AWS_CA_BUNDLE", std::env::var("AWS_CA_BUNDLE").ok()),
+            ("SSL_CERT_FILE", std::env::var("SSL_CERT_FILE").ok()),
+        ] {
+            if let Some(path) = path {
+                if let Err(err) = validate_cert_file(Path::new(&path)) {
+                    problems.push(format!("{name}={path}: {err}").into());
+                }
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(DoctorError::Error {
+                reason: "Invalid proxy or certificate settings".into(),
+                info: problems,
+                fix: None,
+                error: None,
+            })
+        }
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+pub struct ChatBinaryCheck;
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+#[async_trait]
+impl DoctorCheck for ChatBinaryCheck {
+    fn name(&self) -> Cow<'static, str> {
+        format!("{CHAT_BINARY_NAME} is installed and up to date").into()
+    }
+
+    async fn check(&self, _: &()) -> Result<(), DoctorError> {
+        let path = crate::cli::qchat_path()?;
+        if !path.exists() {
+            return Err(doctor_error!("{CHAT_BINARY_NAME} is missing from {}", path.display()));
+        }
+
+        let output = Command::new(&path)
+            .arg("--version")
+            .output()
+            .await
+            .map_err(|err| DoctorError::Error {
+                reason: format!("Failed to run {}", path.display()).into(),
+                info: vec![],
+                fix: None,
+                error: Some(err.into()),
+            })?;
+
+        let expected = env!("CARGO_PKG_VERSION");
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        match stdout.split_whitespace().last() {
+            Some(version) if version == expected => Ok(()),
+            Some(version) => Err(DoctorError::Error {
+                reason: format!("{CHAT_BINARY_NAME} is version {version}, expected {expected}").into(),
+                info: vec![
+                    format!(
+                        "Run {} to reinstall matching versions",
+                        format!("{CLI_BINARY_NAME} update").magenta()
+                    )
+                    .into(),
+                ],
+                fix: None,
+                error: None,
+            }),
+            None => Err(doctor_error!("Failed to read the version of {}", path.display())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_mcp_config() {
+        let config: McpConfig = serde_json::from_str(
+            r#"{ "mcpServers": { "git": { "command": "uvx", "args": ["mcp-server-git"], "env": { "A": "1" } } } }"#,
+        )
+        .unwrap();
+        let git = &config.mcp_servers["git"];
+        assert_eq!(git.command, "uvx");
+        assert_eq!(git.args, vec!["mcp-server-git"]);
+        assert_eq!(git.env.as_ref().unwrap()["A"], "1");
+
+        assert!(serde_json::from_str::<McpConfig>(r#"{ "mcpServers": { "git": { "args": [] } } }"#).is_err());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_launch_mcp_server() {
+        let exits = McpServer {
+            command: "sh".into(),
+            args: vec!["-c".into(), "echo boom >&2; exit 3".into()],
+            env: None,
+        };
+        let err = launch_mcp_server(&exits).await.unwrap_err();
+        assert!(err.contains("boom"), "{err}");
+
+        let missing = McpServer {
+            command: "definitely-not-a-real-mcp-server".into(),
+            args: vec![],
+            env: None,
+        };
+        assert!(launch_mcp_server(&missing).await.is_err());
+    }
+
+    #[test]
+    fn test_validate_proxy() {
+        assert!(validate_proxy("http://proxy.example.com:8080").is_ok());
+        assert!(validate_proxy("socks5://127.0.0.1:1080").is_ok());
+        assert!(validate_proxy("proxy.example.com:8080").is_err());
+        assert!(validate_proxy("ftp://proxy.example.com").is_err());
+        assert!(validate_proxy("not a url").is_err());
+    }
+}
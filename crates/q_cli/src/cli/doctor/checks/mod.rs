@@ -1,4 +1,5 @@
 mod bash_version;
+mod chat;
 mod fish_version;
 #[cfg(target_os = "linux")]
 pub mod linux;
@@ -6,6 +7,14 @@ mod midway;
 mod sshd_config;
 
 pub use bash_version::BashVersionCheck;
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+pub use chat::ChatBinaryCheck;
+pub use chat::{
+    AuthTokenCheck,
+    ChatDatabaseCheck,
+    McpConfigCheck,
+    ProxyCheck,
+};
 pub use fish_version::FishVersionCheck;
 pub use midway::MidwayCheck;
 pub use sshd_config::SshdConfigCheck;
@@ -23,10 +23,16 @@ use anstream::{
     println,
 };
 use async_trait::async_trait;
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+use checks::ChatBinaryCheck;
 use checks::{
+    AuthTokenCheck,
     BashVersionCheck,
+    ChatDatabaseCheck,
     FishVersionCheck,
+    McpConfigCheck,
     MidwayCheck,
+    ProxyCheck,
     SshdConfigCheck,
 };
 use clap::Args;
@@ -99,12 +105,14 @@ use futures::future::BoxFuture;
 use owo_colors::OwoColorize;
 use regex::Regex;
 use semver::Version;
+use serde::Serialize;
 use spinners::{
     Spinner,
     Spinners,
 };
 use tokio::io::AsyncBufReadExt;
 
+use super::OutputFormat;
 use super::app::restart_fig;
 use super::diagnostics::verify_integration;
 use crate::util::desktop::{
@@ -127,11 +135,14 @@ pub struct DoctorArgs {
     /// Error on warnings
     #[arg(long, short = 's')]
     pub strict: bool,
+    /// Format of the output, anything but plain runs all checks with no fixes
+    #[arg(long, short, value_enum, default_value_t)]
+    pub format: OutputFormat,
 }
 
 impl DoctorArgs {
     pub async fn execute(self) -> Result<ExitCode> {
-        doctor_cli(self.all, self.strict).await
+        doctor_cli(self.all, self.strict, self.format).await
     }
 }
 
@@ -345,6 +356,57 @@ fn print_status_result(name: impl Display, status: &Result<(), DoctorError>, ver
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum CheckStatus {
+    Ok,
+    Warning,
+    Error,
+}
+
+/// The result of one check in the `--format json` report
+#[derive(Debug, Serialize)]
+struct CheckReport {
+    section: String,
+    name: String,
+    status: CheckStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    info: Vec<String>,
+}
+
+impl CheckReport {
+    fn new(section: &str, name: &str, result: &Result<(), DoctorError>) -> Self {
+        let strip = |text: &str| strip_ansi(text).trim().to_owned();
+        let (status, reason, info) = match result {
+            Ok(()) => (CheckStatus::Ok, None, vec![]),
+            Err(DoctorError::Warning(msg)) => (CheckStatus::Warning, Some(strip(msg)), vec![]),
+            Err(DoctorError::Error { reason, info, .. }) => (
+                CheckStatus::Error,
+                Some(strip(reason)),
+                info.iter()
+                    .map(|line| strip(line))
+                    .filter(|line| !line.is_empty())
+                    .collect(),
+            ),
+        };
+        Self {
+            section: strip(section.trim_end_matches('.')),
+            name: strip(name),
+            status,
+            reason,
+            info,
+        }
+    }
+}
+
+fn strip_ansi(text: &str) -> Cow<'_, str> {
+    static ANSI_REGEX: std::sync::LazyLock<Regex> =
+        std::sync::LazyLock::new(|| Regex::new(r"\x1b\[[0-9;]*[A-Za-z]").unwrap());
+    ANSI_REGEX.replace_all(text, "")
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[allow(clippy::enum_variant_names)]
 enum DoctorCheckType {
@@ -1071,7 +1133,7 @@ impl DoctorCheck<DiagnosticsResponse> for ShellCompatibilityCheck {
     }
 
     async fn check(&self, _: &DiagnosticsResponse) -> Result<(), DoctorError> {
-        let shell_regex = Regex::new(r"(bash|fish|zsh|nu)").unwrap();
+        let shell_regex = Regex::new(r"(bash|fish|zsh|nu|xonsh|elvish|pwsh)").unwrap();
 
         let current_shell = fig_util::get_parent_process_exe();
         let current_shell_valid = current_shell
@@ -1946,18 +2008,32 @@ async fn run_checks_with_context<T, Fut>(
     get_context: impl Fn() -> Fut,
     config: CheckConfiguration,
     spinner: &mut Option<Spinner>,
+    reports: &mut Vec<CheckReport>,
 ) -> Result<()>
 where
     T: Sync + Send,
     Fut: Future<Output = Result<T>>,
 {
-    if config.all {
+    let plain = config.format == OutputFormat::Plain;
+    if config.all && plain {
         println!("{}", header.as_ref().dark_grey());
     }
     let mut context = match get_context().await {
         Ok(c) => c,
         Err(e) => {
-            println!("Failed to get context: {e:?}");
+            if plain {
+                println!("Failed to get context: {e:?}");
+            }
+            reports.push(CheckReport::new(
+                header.as_ref(),
+                "Load check context",
+                &Err(DoctorError::Error {
+                    reason: format!("Failed to get context: {e}").into(),
+                    info: vec![],
+                    fix: None,
+                    error: None,
+                }),
+            ));
             eyre::bail!(e);
         },
     };
@@ -1977,7 +2053,9 @@ where
             }
         }
 
-        if config.all || result.is_err() {
+        reports.push(CheckReport::new(header.as_ref(), &name, &result));
+
+        if plain && (config.all || result.is_err()) {
             stop_spinner(spinner.take())?;
             print_status_result(&name, &result, config.all);
         }
@@ -2023,7 +2101,7 @@ where
         }
     }
 
-    if config.all {
+    if config.all && plain {
         println!();
     }
 
@@ -2054,8 +2132,9 @@ async fn run_checks(
     checks: Vec<&dyn DoctorCheck>,
     config: CheckConfiguration,
     spinner: &mut Option<Spinner>,
+    reports: &mut Vec<CheckReport>,
 ) -> Result<()> {
-    run_checks_with_context(header, checks, get_null_context, config, spinner).await
+    run_checks_with_context(header, checks, get_null_context, config, spinner, reports).await
 }
 
 fn stop_spinner(spinner: Option<Spinner>) -> Result<()> {
@@ -2072,10 +2151,14 @@ fn stop_spinner(spinner: Option<Spinner>) -> Result<()> {
 struct CheckConfiguration {
     all: bool,
     strict: bool,
+    format: OutputFormat,
 }
 
 // Doctor
-pub async fn doctor_cli(all: bool, strict: bool) -> Result<ExitCode> {
+pub async fn doctor_cli(all: bool, strict: bool, format: OutputFormat) -> Result<ExitCode> {
+    // Reports for other tools are read-only and must list every check
+    let all = all || format != OutputFormat::Plain;
+
     #[cfg(unix)]
     {
         use nix::unistd::geteuid;
@@ -2091,7 +2174,8 @@ pub async fn doctor_cli(all: bool, strict: bool) -> Result<ExitCode> {
         }
     }
 
-    let config = CheckConfiguration { all, strict };
+    let config = CheckConfiguration { all, strict, format };
+    let mut reports = vec![];
 
     let mut spinner: Option<Spinner> = None;
     if !config.all {
@@ -2117,20 +2201,23 @@ pub async fn doctor_cli(all: bool, strict: bool) -> Result<ExitCode> {
         vec![&LoginStatusCheck {}],
         config,
         &mut spinner,
+        &mut reports,
     )
     .await?;
 
     // If user is logged in, try to launch fig
-    launch_fig_desktop(LaunchArgs {
-        wait_for_socket: true,
-        open_dashboard: false,
-        immediate_update: true,
-        verbose: false,
-    })
-    .ok();
+    if format == OutputFormat::Plain {
+        launch_fig_desktop(LaunchArgs {
+            wait_for_socket: true,
+            open_dashboard: false,
+            immediate_update: true,
+            verbose: false,
+        })
+        .ok();
+    }
 
-    let shell_integrations: Vec<_> = [Shell::Bash, Shell::Zsh, Shell::Fish]
-        .into_iter()
+    let shell_integrations: Vec<_> = Shell::all()
+        .iter()
         .map(|shell| shell.get_shell_integrations(&Env::new()))
         .collect::<Result<Vec<_>, fig_integrations::Error>>()?
         .into_iter()
@@ -2148,6 +2235,7 @@ pub async fn doctor_cli(all: bool, strict: bool) -> Result<ExitCode> {
             get_shell_context,
             config,
             &mut spinner,
+            &mut reports,
         )
         .await?;
 
@@ -2166,6 +2254,23 @@ pub async fn doctor_cli(all: bool, strict: bool) -> Result<ExitCode> {
             ],
             config,
             &mut spinner,
+            &mut reports,
+        )
+        .await?;
+
+        run_checks(
+            format!("Let's make sure {PRODUCT_NAME} chat is set up correctly..."),
+            vec![
+                &AuthTokenCheck,
+                &ChatDatabaseCheck,
+                &McpConfigCheck,
+                &ProxyCheck,
+                #[cfg(any(target_os = "linux", target_os = "macos"))]
+                &ChatBinaryCheck,
+            ],
+            config,
+            &mut spinner,
+            &mut reports,
         )
         .await?;
 
@@ -2175,6 +2280,7 @@ pub async fn doctor_cli(all: bool, strict: bool) -> Result<ExitCode> {
                 vec![&AppRunningCheck, &DesktopSocketCheck],
                 config,
                 &mut spinner,
+                &mut reports,
             )
             .await?;
         }
@@ -2193,6 +2299,7 @@ pub async fn doctor_cli(all: bool, strict: bool) -> Result<ExitCode> {
             ],
             config,
             &mut spinner,
+            &mut reports,
         )
         .await?;
 
@@ -2207,6 +2314,7 @@ pub async fn doctor_cli(all: bool, strict: bool) -> Result<ExitCode> {
             ],
             config,
             &mut spinner,
+            &mut reports,
         )
         .await
         .ok();
@@ -2230,6 +2338,7 @@ pub async fn doctor_cli(all: bool, strict: bool) -> Result<ExitCode> {
                 super::diagnostics::get_diagnostics,
                 config,
                 &mut spinner,
+                &mut reports,
             )
             .await?;
         }
@@ -2261,6 +2370,7 @@ pub async fn doctor_cli(all: bool, strict: bool) -> Result<ExitCode> {
                     get_linux_context,
                     config,
                     &mut spinner,
+                    &mut reports,
                 )
                 .await?;
             }
@@ -2275,6 +2385,7 @@ pub async fn doctor_cli(all: bool, strict: bool) -> Result<ExitCode> {
                     super::diagnostics::get_diagnostics,
                     config,
                     &mut spinner,
+                    &mut reports,
                 )
                 .await?;
             }
@@ -2297,6 +2408,7 @@ pub async fn doctor_cli(all: bool, strict: bool) -> Result<ExitCode> {
             get_terminal_context,
             config,
             &mut spinner,
+            &mut reports,
         )
         .await?;
 
@@ -2308,6 +2420,26 @@ pub async fn doctor_cli(all: bool, strict: bool) -> Result<ExitCode> {
 
     stop_spinner(spinner)?;
 
+    if format != OutputFormat::Plain {
+        let has_errors = is_error
+            || reports.iter().any(|report| report.status == CheckStatus::Error)
+            || (strict && reports.iter().any(|report| report.status == CheckStatus::Warning));
+        format.print(
+            || "",
+            || {
+                serde_json::json!({
+                    "ok": !has_errors,
+                    "checks": reports,
+                })
+            },
+        );
+        return Ok(if has_errors {
+            ExitCode::FAILURE
+        } else {
+            ExitCode::SUCCESS
+        });
+    }
+
     if is_error {
         println!();
         println!("{} Doctor found errors. Please fix them and try again.", CROSS.red());
@@ -2342,3 +2474,43 @@ pub async fn doctor_cli(all: bool, strict: bool) -> Result<ExitCode> {
 
     Ok(ExitCode::SUCCESS)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_report() {
+        let ok = CheckReport::new(
+            "Let's check your dotfiles...",
+            "zsh ~/.zshrc integration check",
+            &Ok(()),
+        );
+        assert_eq!(
+            serde_json::to_value(&ok).unwrap(),
+            serde_json::json!({
+                "section": "Let's check your dotfiles",
+                "name": "zsh ~/.zshrc integration check",
+                "status": "ok",
+            })
+        );
+
+        let error = CheckReport::new(
+            "Let's check if you're logged in...",
+            "Auth",
+            &Err(DoctorError::Error {
+                reason: format!("Not authenticated. Please run {}", "q login".bold()).into(),
+                info: vec!["".into(), format!("Run {}", "q login".magenta()).into()],
+                fix: None,
+                error: None,
+            }),
+        );
+        assert_eq!(error.status, CheckStatus::Error);
+        assert_eq!(error.reason.as_deref(), Some("Not authenticated. Please run q login"));
+        assert_eq!(error.info, vec!["Run q login"]);
+
+        let warning = CheckReport::new("Checks", "Dashboard", &Err(doctor_warning!("host is localhost")));
+        assert_eq!(warning.status, CheckStatus::Warning);
+        assert_eq!(warning.reason.as_deref(), Some("host is localhost"));
+    }
+}
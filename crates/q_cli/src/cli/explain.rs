@@ -0,0 +1,652 @@
+use std::io::{
+    IsTerminal,
+    stdout,
+};
+use std::process::{
+    ExitCode,
+    Stdio,
+};
+use std::time::Duration;
+
+use anstream::{
+    eprintln,
+    println,
+};
+use clap::Args;
+use crossterm::style::Stylize;
+use eyre::{
+    Result,
+    eyre,
+};
+use fig_api_client::Client;
+use fig_api_client::model::{
+    FileContext,
+    LanguageName,
+    ProgrammingLanguage,
+    RecommendationsInput,
+};
+use fig_settings::history::{
+    CommandInfo,
+    History,
+    HistoryColumn,
+    Order,
+    OrderBy,
+    WhereExpression,
+};
+use fig_util::CLI_BINARY_NAME;
+use fig_util::env_var::QTERM_SESSION_ID;
+use fig_util::validate::validate;
+use serde_json::json;
+
+use super::OutputFormat;
+use super::internal::command_output::command_outputs;
+use super::translate::{
+    CommandPart,
+    PartKind,
+    breakdown,
+    format_parts,
+    send_figterm,
+};
+use crate::util::choose;
+
+/// How long reading the man page or `--help` text of a program may take.
+const HELP_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Programs without a man page whose `--help` is known to only print their help, so it's safe to
+/// run to explain them.
+const HELP_PROGRAMS: &[&str] = &[
+    "aws",
+    "brew",
+    "cargo",
+    "docker",
+    "gh",
+    "git",
+    "go",
+    "kubectl",
+    "npm",
+    "pip",
+    "pip3",
+    "rustup",
+    "terraform",
+    "yarn",
+];
+
+/// The number of lines of the failed command's output added to the fix prompt, counting from the
+/// end.
+const MAX_ERROR_LINES: usize = 20;
+
+#[derive(Debug, Clone, PartialEq, Eq, Args)]
+pub struct ExplainArgs {
+    /// The command to explain
+    #[arg(
+        trailing_var_arg = true,
+        allow_hyphen_values = true,
+        required_unless_present = "last",
+        conflicts_with = "last"
+    )]
+    pub command: Vec<String>,
+    /// Explain the last failed command in this session and suggest a fix
+    #[arg(long)]
+    pub last: bool,
+    #[arg(long, short, value_enum, default_value_t)]
+    pub format: OutputFormat,
+}
+
+impl ExplainArgs {
+    pub async fn execute(self) -> Result<ExitCode> {
+        let last_failed = match self.last {
+            true => Some(last_failed_command()?),
+            false => None,
+        };
+        let command = match &last_failed {
+            Some(info) => info.command.clone().unwrap_or_default(),
+            None => self.command.join(" "),
+        };
+        if command.trim().is_empty() {
+            eyre::bail!("no command to explain");
+        }
+        let output = match &last_failed {
+            Some(info) => failed_command_output(info).await,
+            None => None,
+        };
+
+        let parts = explain_parts(&command).await;
+
+        let use_model = fig_util::system_info::in_cloudshell() || fig_auth::is_logged_in().await;
+        let summary = match use_model {
+            true => complete(summary_prompt(&command)).await.ok().flatten(),
+            false => None,
+        };
+        let fix = match (&last_failed, use_model) {
+            (Some(info), true) => complete(fix_prompt(info, output.as_deref()))
+                .await
+                .ok()
+                .flatten()
+                .filter(|fix| *fix != command && !fix.starts_with('#') && validate(fix)),
+            _ => None,
+        };
+
+        let exit_code = last_failed.as_ref().and_then(|info| info.exit_code);
+        self.format.print(
+            || {
+                let mut text = String::new();
+                if let Some(exit_code) = exit_code {
+                    text.push_str(&format!(
+                        "{}\n",
+                        format!("Last failed command (exit code {exit_code}):").dim()
+                    ));
+                }
+                text.push_str(&format!("{}\n", command.as_str().bold()));
+                if let Some(output) = &output {
+                    text.push_str(&format!("{}\n", error_lines(output).join("\n").red()));
+                }
+                if let Some(summary) = &summary {
+                    text.push_str(&format!("{summary}\n"));
+                }
+                text.push('\n');
+                text.push_str(&format_parts(&parts));
+                if let Some(fix) = &fix {
+                    text.push_str(&format!("\n\n{} {}", "Suggested fix:".bold(), fix.as_str().magenta()));
+                }
+                if !use_model {
+                    text.push_str(&format!(
+                        "\n\n{}",
+                        format!("Log in with {CLI_BINARY_NAME} login to also explain the command with AI").dim()
+                    ));
+                }
+                text
+            },
+            || {
+                json!({
+                    "command": command,
+                    "exit_code": exit_code,
+                    "output": output,
+                    "summary": summary,
+                    "parts": parts.iter().map(|(part, description)| json!({
+                        "text": part.text,
+                        "kind": part.kind,
+                        "description": description,
+                    })).collect::<Vec<_>>(),
+                    "fix": fix,
+                })
+            },
+        );
+
+        // The fix is inserted on the next prompt, since text inserted while this command runs
+        // would be read by it instead of the shell
+        if let Some(fix) = fix {
+            if self.format == OutputFormat::Plain && std::io::stdin().is_terminal() && stdout().is_terminal() {
+                println!();
+                if choose("Insert the fix into your next prompt?", &["Yes", "No"])? == Some(0) {
+                    if let Err(err) = send_figterm(fix, false).await {
+                        eprintln!("{} {err}", "Failed to insert command:".red().bold());
+                    }
+                }
+            }
+        }
+
+        Ok(ExitCode::SUCCESS)
+    }
+}
+
+/// The most recent command that failed, in the current terminal session if there is one.
+fn last_failed_command() -> Result<CommandInfo> {
+    let mut exprs = vec![
+        WhereExpression::NotNull(HistoryColumn::ExitCode),
+        WhereExpression::Ne(HistoryColumn::ExitCode, "0".into()),
+        WhereExpression::NotLike(HistoryColumn::Command, format!("{CLI_BINARY_NAME} explain%")),
+    ];
+    if let Ok(session_id) = std::env::var(QTERM_SESSION_ID) {
+        exprs.push(WhereExpression::Eq(HistoryColumn::SessionId, session_id));
+    }
+
+    let order_by = vec![OrderBy::new(HistoryColumn::Id, Order::Desc)];
+    History::new()
+        .rows(WhereExpression::all(exprs), order_by, 1, 0)?
+        .into_iter()
+        .find(|info| info.command.is_some())
+        .ok_or_else(|| eyre!("No failed commands found in your history"))
+}
+
+/// The output figterm captured of the failed command, if it is one of the last commands run in
+/// this terminal session.
+async fn failed_command_output(info: &CommandInfo) -> Option<String> {
+    let outputs = command_outputs(None).await.ok()?;
+    outputs
+        .into_iter()
+        .find(|output| output.command == info.command && output.exit_code == info.exit_code)
+        .map(|output| output.output)
+        .filter(|output| !output.trim().is_empty())
+}
+
+/// The last lines of `output`, where the error usually is.
+fn error_lines(output: &str) -> Vec<&str> {
+    let lines = output.lines().collect::<Vec<_>>();
+    lines[lines.len().saturating_sub(MAX_ERROR_LINES)..].to_vec()
+}
+
+/// Describes every part of `command`, looking up programs and their options in their man page or
+/// `--help` text.
+async fn explain_parts(command: &str) -> Vec<(CommandPart, String)> {
+    let parts = breakdown(command);
+    let mut explained = Vec::with_capacity(parts.len());
+    let mut docs = None;
+    let mut subcommand = None;
+
+    for (i, part) in parts.iter().enumerate() {
+        let description = match part.kind {
+            PartKind::Program => {
+                let next_word = parts
+                    .get(i + 1)
+                    .filter(|next| next.kind == PartKind::Argument && is_word(&next.text))
+                    .map(|next| next.text.as_str());
+                docs = HelpDocs::load(&part.text, next_word).await;
+                subcommand = docs.as_ref().filter(|docs| docs.subcommand.is_some()).map(|_| i + 1);
+                docs.as_ref().and_then(|docs| docs.summary.clone())
+            },
+            PartKind::Argument if subcommand == Some(i) => Some("subcommand".into()),
+            PartKind::Flag => docs.as_ref().and_then(|docs| docs.describe_flag(&part.text)),
+            _ => None,
+        };
+        explained.push((
+            part.clone(),
+            description.unwrap_or_else(|| part.description().to_owned()),
+        ));
+    }
+    explained
+}
+
+fn is_word(text: &str) -> bool {
+    !text.is_empty() && text.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// A documented option of a program.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct OptionDoc {
+    /// The names of the option, e.g. `-a` and `--all`.
+    names: Vec<String>,
+    /// The first sentence of the description.
+    description: String,
+}
+
+/// What the man page or `--help` text of a program says about it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct HelpDocs {
+    /// The subcommand the docs are for, if a page specific to it was found.
+    subcommand: Option<String>,
+    summary: Option<String>,
+    options: Vec<OptionDoc>,
+}
+
+impl HelpDocs {
+    /// Loads the docs of `program`, preferring the docs of `subcommand` if it has any.
+    ///
+    /// Explaining a command never runs it: docs come from man pages, or from `program --help` for
+    /// the programs in [HELP_PROGRAMS] that are on the `PATH`.
+    async fn load(program: &str, subcommand: Option<&str>) -> Option<Self> {
+        if !is_word(program) {
+            return None;
+        }
+
+        if let Some(subcommand) = subcommand {
+            if let Some(text) = man_page(&format!("{program}-{subcommand}")).await {
+                return Some(Self::parse(&text).for_subcommand(subcommand));
+            }
+        }
+        if let Some(text) = man_page(program).await {
+            return Some(Self::parse(&text));
+        }
+        if !HELP_PROGRAMS.contains(&program) || which::which(program).is_err() {
+            return None;
+        }
+        run_help(program).await.map(|text| Self::parse(&text))
+    }
+
+    fn parse(text: &str) -> Self {
+        let text = strip_overstrike(text);
+        Self {
+            subcommand: None,
+            summary: parse_summary(&text),
+            options: parse_options(&text),
+        }
+    }
+
+    fn for_subcommand(self, subcommand: &str) -> Self {
+        Self {
+            subcommand: Some(subcommand.to_owned()),
+            ..self
+        }
+    }
+
+    fn find(&self, name: &str) -> Option<&OptionDoc> {
+        self.options
+            .iter()
+            .find(|option| option.names.iter().any(|n| n == name))
+    }
+
+    /// Describes `flag`, which may have a value like `--color=auto` or combine short options like
+    /// `-la`.
+    fn describe_flag(&self, flag: &str) -> Option<String> {
+        let name = flag.split('=').next()?;
+        if let Some(option) = self.find(name) {
+            return Some(option.description.clone());
+        }
+
+        let short = name.strip_prefix('-')?;
+        if short.starts_with('-') || short.len() < 2 || !short.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return None;
+        }
+        short
+            .chars()
+            .map(|c| {
+                self.find(&format!("-{c}"))
+                    .map(|option| format!("-{c}: {}", option.description))
+            })
+            .collect::<Option<Vec<_>>>()
+            .map(|descriptions| descriptions.join("; "))
+    }
+}
+
+async fn man_page(page: &str) -> Option<String> {
+    let mut command = tokio::process::Command::new("man");
+    command
+        .args(["-P", "cat", "--", page])
+        .env("MANWIDTH", "120")
+        .env("GROFF_NO_SGR", "1")
+        .env_remove("MAN_KEEP_FORMATTING");
+    run_for_output(command).await
+}
+
+async fn run_help(program: &str) -> Option<String> {
+    let mut command = tokio::process::Command::new(program);
+    command.arg("--help").env("PAGER", "cat").env("GIT_PAGER", "cat");
+    run_for_output(command).await
+}
+
+/// The stdout of `command`, or its stderr if stdout is empty, if it succeeds within
+/// [HELP_TIMEOUT].
+async fn run_for_output(mut command: tokio::process::Command) -> Option<String> {
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    let output = tokio::time::timeout(HELP_TIMEOUT, command.output()).await.ok()?.ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let text = match output.stdout.is_empty() {
+        true => output.stderr,
+        false => output.stdout,
+    };
+    let text = String::from_utf8_lossy(&text).into_owned();
+    (!text.trim().is_empty()).then_some(text)
+}
+
+/// Removes the backspace overstriking man pages use for bold and underlined text.
+fn strip_overstrike(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\x08' => {
+                stripped.pop();
+            },
+            c => stripped.push(c),
+        }
+    }
+    stripped
+}
+
+/// The one line description from the NAME section of a man page, e.g. `list directory contents`.
+fn parse_summary(text: &str) -> Option<String> {
+    let mut lines = text.lines().skip_while(|line| line.trim() != "NAME").skip(1);
+    let line = lines.find(|line| !line.trim().is_empty())?;
+    let (_, summary) = line
+        .split_once(" - ")
+        .or_else(|| line.split_once(" -- "))
+        .or_else(|| line.split_once(" – "))?;
+    Some(summary.trim().to_owned())
+}
+
+/// Parses the options documented in a man page or `--help` text, which are either followed by
+/// their description on the same line after at least two spaces, or on the next, more indented,
+/// lines.
+fn parse_options(text: &str) -> Vec<OptionDoc> {
+    let lines = text.lines().collect::<Vec<_>>();
+    let indent = |line: &str| line.len() - line.trim_start().len();
+
+    let mut options = vec![];
+    for (i, line) in lines.iter().enumerate() {
+        let trimmed = line.trim_start();
+        if !trimmed.starts_with('-') {
+            continue;
+        }
+
+        let (spec, description) = match [trimmed.find("  "), trimmed.find('\t')].into_iter().flatten().min() {
+            Some(split) => (&trimmed[..split], trimmed[split..].trim()),
+            None => (trimmed, ""),
+        };
+        let names = option_names(spec);
+        if names.is_empty() {
+            continue;
+        }
+
+        let mut description = description.to_owned();
+        for next in &lines[i + 1..] {
+            let next_trimmed = next.trim();
+            if next_trimmed.is_empty() || next_trimmed.starts_with('-') || indent(next) <= indent(line) {
+                break;
+            }
+            if !description.is_empty() {
+                description.push(' ');
+            }
+            description.push_str(next_trimmed);
+        }
+
+        let description = first_sentence(&description);
+        if !description.is_empty() {
+            options.push(OptionDoc { names, description });
+        }
+    }
+    options
+}
+
+/// The names in an option spec like `-a, --all`, `--color[=WHEN]` or `-m <msg>`.
+fn option_names(spec: &str) -> Vec<String> {
+    spec.split([',', ' ', '|'])
+        .filter(|word| word.starts_with('-'))
+        .filter_map(|word| word.split(['=', '[', '<']).next())
+        .filter(|name| !name.ends_with('-'))
+        .map(str::to_owned)
+        .collect()
+}
+
+fn first_sentence(text: &str) -> String {
+    let sentence = match text.find(". ") {
+        Some(end) => &text[..end],
+        // Keep periods that are part of the description, like in `entries starting with .`
+        None => text
+            .strip_suffix('.')
+            .filter(|text| !text.ends_with(' '))
+            .unwrap_or(text),
+    };
+    sentence.trim().to_owned()
+}
+
+fn summary_prompt(command: &str) -> String {
+    let command = command.replace('\n', " ");
+    format!(
+        "# Explain each shell command in one sentence\n\
+         # Command: tar -xzf archive.tar.gz\n\
+         # Explanation: Extracts the gzip compressed archive archive.tar.gz into the current directory\n\
+         # Command: {command}\n\
+         # Explanation:"
+    )
+}
+
+fn fix_prompt(info: &CommandInfo, output: Option<&str>) -> String {
+    let command = info.command.as_deref().unwrap_or_default().replace('\n', " ");
+    let exit_code = info.exit_code.unwrap_or(1);
+    let shell = info
+        .shell
+        .as_deref()
+        .map(|shell| format!("# shell: {shell}\n"))
+        .unwrap_or_default();
+    let output = output
+        .map(|output| {
+            error_lines(output)
+                .into_iter()
+                .map(|line| format!("# > {line}\n"))
+                .collect::<String>()
+        })
+        .unwrap_or_default();
+    format!(
+        "# Shell commands that failed, each followed by the command that fixes it\n\
+         {shell}\
+         # Failed with exit code 1: git comit -m \"message\"\n\
+         git commit -m \"message\"\n\
+         # Failed with exit code 127: pyhton3 script.py\n\
+         python3 script.py\n\
+         # Failed with exit code {exit_code}: {command}\n\
+         {output}"
+    )
+}
+
+/// The first line of the model's completion of `prompt`.
+async fn complete(prompt: String) -> Result<Option<String>> {
+    let client = Client::new().await?;
+    let output = client
+        .generate_recommendations(RecommendationsInput {
+            file_context: FileContext {
+                left_file_content: prompt,
+                right_file_content: "".into(),
+                filename: "commands.sh".into(),
+                programming_language: ProgrammingLanguage {
+                    language_name: LanguageName::Shell,
+                },
+            },
+            max_results: 1,
+            next_token: None,
+        })
+        .await?;
+
+    Ok(output.recommendations.into_iter().find_map(|recommendation| {
+        recommendation
+            .content
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty())
+            .map(str::to_owned)
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAN_PAGE: &str = "\
+LS(1)                            User Commands                           LS(1)
+
+NAME
+       ls - list directory contents
+
+OPTIONS
+       -a, --all
+              do not ignore entries starting with .
+
+       --color[=WHEN]
+              color the output WHEN; more info below
+
+       -l     use a long listing format
+
+       -\x08-s\x08si\x08iz\x08ze\x08e
+              print the size of each file.  See --block-size.
+";
+
+    const HELP_TEXT: &str = "\
+Usage: grep [OPTION]... PATTERNS [FILE]...
+  -i, --ignore-case         ignore case distinctions in patterns and data
+  -r, --recursive           like --directories=recurse
+  -e, --regexp=PATTERNS     use PATTERNS for matching
+      --exclude-dir=GLOB    skip directories that match GLOB;
+                            can be given more than once
+";
+
+    #[test]
+    fn test_parse_man_page() {
+        let docs = HelpDocs::parse(MAN_PAGE);
+        assert_eq!(docs.summary.as_deref(), Some("list directory contents"));
+        assert_eq!(docs.options, vec![
+            OptionDoc {
+                names: vec!["-a".into(), "--all".into()],
+                description: "do not ignore entries starting with .".into(),
+            },
+            OptionDoc {
+                names: vec!["--color".into()],
+                description: "color the output WHEN; more info below".into(),
+            },
+            OptionDoc {
+                names: vec!["-l".into()],
+                description: "use a long listing format".into(),
+            },
+            OptionDoc {
+                names: vec!["--size".into()],
+                description: "print the size of each file".into(),
+            },
+        ]);
+    }
+
+    #[test]
+    fn test_parse_help_text() {
+        let docs = HelpDocs::parse(HELP_TEXT);
+        assert_eq!(docs.summary, None);
+        assert_eq!(
+            docs.describe_flag("--exclude-dir=target").as_deref(),
+            Some("skip directories that match GLOB; can be given more than once")
+        );
+        assert_eq!(
+            docs.describe_flag("-ri").as_deref(),
+            Some("-r: like --directories=recurse; -i: ignore case distinctions in patterns and data")
+        );
+        assert_eq!(docs.describe_flag("-e").as_deref(), Some("use PATTERNS for matching"));
+        assert_eq!(docs.describe_flag("-rx"), None);
+        assert_eq!(docs.describe_flag("--missing"), None);
+    }
+
+    #[test]
+    fn test_option_names() {
+        assert_eq!(option_names("-m <msg>, --message=<msg>"), vec!["-m", "--message"]);
+        assert_eq!(option_names("-name pattern"), vec!["-name"]);
+        assert!(option_names("--").is_empty());
+    }
+
+    #[test]
+    fn test_fix_prompt() {
+        let prompt = fix_prompt(
+            &CommandInfo {
+                command: Some("cargo tset".into()),
+                shell: Some("zsh".into()),
+                exit_code: Some(101),
+                ..Default::default()
+            },
+            None,
+        );
+        assert!(prompt.contains("# shell: zsh\n"));
+        assert!(prompt.ends_with("# Failed with exit code 101: cargo tset\n"));
+
+        let info = CommandInfo {
+            command: Some("cargo tset".into()),
+            exit_code: Some(101),
+            ..Default::default()
+        };
+        let output = "error: no such command: `tset`\n\n\tDid you mean `test`?";
+        assert!(fix_prompt(&info, Some(output)).ends_with(
+            "# Failed with exit code 101: cargo tset\n\
+             # > error: no such command: `tset`\n\
+             # > \n\
+             # > \tDid you mean `test`?\n"
+        ));
+        assert!(summary_prompt("ls\n-la").ends_with("# Command: ls -la\n# Explanation:"));
+    }
+}
@@ -0,0 +1,464 @@
+use std::collections::HashSet;
+use std::io::Write as _;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::{
+    Duration,
+    SystemTime,
+};
+
+use anstream::println;
+use clap::{
+    Args,
+    Subcommand,
+};
+use crossterm::style::Stylize;
+use dialoguer::FuzzySelect;
+use dialoguer::console::Term;
+use eyre::{
+    Result,
+    bail,
+    eyre,
+};
+use fig_settings::history::{
+    CommandInfo,
+    CommandStats,
+    History,
+    HistoryColumn,
+    Order,
+    OrderBy,
+    StatsOrder,
+    WhereExpression,
+    escape_like,
+};
+use fig_util::env_var::QTERM_SESSION_ID;
+use fig_util::{
+    CLI_BINARY_NAME,
+    Shell,
+};
+use fuzzy_matcher::FuzzyMatcher;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use indoc::formatdoc;
+use serde_json::json;
+use time::OffsetDateTime;
+use time::macros::format_description;
+
+use super::OutputFormat;
+use crate::util::dialoguer_theme;
+
+/// The maximum number of recent commands that are fuzzy searched.
+const MAX_SEARCH_CANDIDATES: usize = 10_000;
+
+#[derive(Debug, Clone, PartialEq, Eq, Subcommand)]
+pub enum HistorySubcommand {
+    /// Search the commands recorded by the shell integrations
+    Search {
+        /// Fuzzy search for commands matching this query
+        query: Option<String>,
+        #[command(flatten)]
+        filters: HistoryFilters,
+        /// Pick a command interactively and print it, used by the shell widget
+        #[arg(long, short)]
+        interactive: bool,
+        /// The maximum number of commands to show
+        #[arg(long, short, default_value_t = 50)]
+        limit: usize,
+        #[arg(long, short, value_enum, default_value_t)]
+        format: OutputFormat,
+    },
+    /// Show the most used, slowest and most failed commands
+    Stats {
+        #[command(flatten)]
+        filters: HistoryFilters,
+        /// The maximum number of commands to show per statistic
+        #[arg(long, short, default_value_t = 10)]
+        limit: usize,
+        #[arg(long, short, value_enum, default_value_t)]
+        format: OutputFormat,
+    },
+    /// Print a shell widget that replaces Ctrl+R with `q history search --interactive`
+    ///
+    /// Add `eval "$(q history widget zsh)"` to your .zshrc, or the equivalent for your shell.
+    Widget {
+        /// The shell to print the widget for
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Args)]
+pub struct HistoryFilters {
+    /// Only include commands run in this directory, or the current directory if no value is given
+    #[arg(long, num_args = 0..=1, default_missing_value = ".")]
+    pub cwd: Option<PathBuf>,
+    /// Only include commands that exited with a non zero exit code
+    #[arg(long)]
+    pub failed: bool,
+    /// Only include commands run within a duration, e.g. 30m, 12h, 7d or 2w, or since a date such
+    /// as 2025-01-31
+    #[arg(long)]
+    pub since: Option<String>,
+    /// Only include commands from this terminal session, or the current session if no value is
+    /// given
+    #[arg(long, num_args = 0..=1, default_missing_value = "")]
+    pub session: Option<String>,
+    /// Only include commands run on a host whose name contains this value
+    #[arg(long)]
+    pub host: Option<String>,
+}
+
+impl HistoryFilters {
+    fn where_expression(&self) -> Result<Option<WhereExpression>> {
+        let mut exprs = vec![];
+
+        if let Some(cwd) = &self.cwd {
+            let cwd = cwd.canonicalize().unwrap_or_else(|_| cwd.clone());
+            exprs.push(WhereExpression::Eq(HistoryColumn::Cwd, cwd.to_string_lossy().into()));
+        }
+
+        if self.failed {
+            exprs.push(WhereExpression::NotNull(HistoryColumn::ExitCode));
+            exprs.push(WhereExpression::Ne(HistoryColumn::ExitCode, "0".into()));
+        }
+
+        if let Some(since) = &self.since {
+            let since = parse_since(since, SystemTime::now())?;
+            let secs = since
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs();
+            exprs.push(WhereExpression::Ge(HistoryColumn::StartTime, secs.to_string()));
+        }
+
+        match self.session.as_deref() {
+            Some("") => match std::env::var(QTERM_SESSION_ID) {
+                Ok(session_id) => exprs.push(WhereExpression::Eq(HistoryColumn::SessionId, session_id)),
+                Err(_) => {
+                    bail!("Not in a terminal session with the shell integrations, pass a session id to --session")
+                },
+            },
+            Some(session_id) => exprs.push(WhereExpression::Eq(HistoryColumn::SessionId, session_id.into())),
+            None => {},
+        }
+
+        if let Some(host) = &self.host {
+            exprs.push(WhereExpression::Like(
+                HistoryColumn::Hostname,
+                format!("%{}%", escape_like(host)),
+            ));
+        }
+
+        Ok(WhereExpression::all(exprs))
+    }
+}
+
+impl HistorySubcommand {
+    pub async fn execute(self) -> Result<ExitCode> {
+        match self {
+            HistorySubcommand::Search {
+                query,
+                filters,
+                interactive,
+                limit,
+                format,
+            } => {
+                let history = History::new();
+                let where_expr = filters.where_expression()?;
+                let order_by = vec![OrderBy::new(HistoryColumn::Id, Order::Desc)];
+
+                if interactive {
+                    let rows = history.rows(where_expr, order_by, MAX_SEARCH_CANDIDATES, 0)?;
+                    return pick_command(&rows, query.as_deref().unwrap_or_default());
+                }
+
+                let rows = match query.as_deref() {
+                    Some(query) if !query.is_empty() => {
+                        let rows = history.rows(where_expr, order_by, MAX_SEARCH_CANDIDATES, 0)?;
+                        let matcher = SkimMatcherV2::default().ignore_case();
+                        let mut matches = rows
+                            .into_iter()
+                            .filter_map(|row| Some((matcher.fuzzy_match(row.command.as_deref()?, query)?, row)))
+                            .collect::<Vec<_>>();
+                        // Stable, so equally good matches stay in recency order
+                        matches.sort_by(|(a, _), (b, _)| b.cmp(a));
+                        matches.into_iter().take(limit).map(|(_, row)| row).collect()
+                    },
+                    _ => history.rows(where_expr, order_by, limit, 0)?,
+                };
+
+                format.print(
+                    || rows.iter().map(format_row).collect::<Vec<_>>().join("\n"),
+                    || rows.iter().map(row_json).collect::<Vec<_>>(),
+                );
+                Ok(ExitCode::SUCCESS)
+            },
+            HistorySubcommand::Stats { filters, limit, format } => {
+                let history = History::new();
+                let where_expr = filters.where_expression()?;
+                let most_used = history.command_stats(where_expr.clone(), StatsOrder::MostUsed, limit)?;
+                let slowest = history.command_stats(where_expr.clone(), StatsOrder::Slowest, limit)?;
+                let most_failed = history.command_stats(where_expr, StatsOrder::MostFailed, limit)?;
+
+                format.print(
+                    || {
+                        [
+                            ("Most used", &most_used),
+                            ("Slowest", &slowest),
+                            ("Most failed", &most_failed),
+                        ]
+                        .into_iter()
+                        .map(|(title, stats)| {
+                            let mut section = format!("{}", title.bold().magenta());
+                            if stats.is_empty() {
+                                section.push_str(&format!("\n  {}", "No commands".dark_grey()));
+                            }
+                            for stat in stats {
+                                section.push_str(&format!("\n  {}", format_stats(stat)));
+                            }
+                            section
+                        })
+                        .collect::<Vec<_>>()
+                        .join("\n\n")
+                    },
+                    || {
+                        json!({
+                            "most_used": most_used.iter().map(stats_json).collect::<Vec<_>>(),
+                            "slowest": slowest.iter().map(stats_json).collect::<Vec<_>>(),
+                            "most_failed": most_failed.iter().map(stats_json).collect::<Vec<_>>(),
+                        })
+                    },
+                );
+                Ok(ExitCode::SUCCESS)
+            },
+            HistorySubcommand::Widget { shell } => {
+                println!("{}", widget(&shell)?);
+                Ok(ExitCode::SUCCESS)
+            },
+        }
+    }
+}
+
+/// Lets the user fuzzy pick one of the unique commands in `rows` and prints it to stdout, while
+/// the picker is drawn on stderr so the output can be captured by a shell widget.
+fn pick_command(rows: &[CommandInfo], query: &str) -> Result<ExitCode> {
+    let mut seen = HashSet::new();
+    let commands = rows
+        .iter()
+        .filter_map(|row| row.command.as_deref())
+        .filter(|command| seen.insert(*command))
+        .collect::<Vec<_>>();
+
+    if commands.is_empty() {
+        bail!("No commands found in the history");
+    }
+
+    let items = commands.iter().map(|c| c.replace('\n', " ⏎ ")).collect::<Vec<_>>();
+    let selection = FuzzySelect::with_theme(&dialoguer_theme())
+        .with_prompt("History")
+        .items(&items)
+        .with_initial_text(query)
+        .default(0)
+        .interact_on_opt(&Term::stderr())?;
+
+    match selection {
+        Some(index) => {
+            let mut stdout = std::io::stdout();
+            stdout.write_all(commands[index].as_bytes())?;
+            stdout.flush()?;
+            Ok(ExitCode::SUCCESS)
+        },
+        None => Ok(ExitCode::FAILURE),
+    }
+}
+
+/// Parses a relative duration such as `30m`, `12h`, `7d` or `2w`, or a date such as `2025-01-31`,
+/// into the time it refers to.
+fn parse_since(since: &str, now: SystemTime) -> Result<SystemTime> {
+    let since = since.trim();
+    if let Ok(date) = time::Date::parse(since, format_description!("[year]-[month]-[day]")) {
+        return Ok(date.midnight().assume_utc().into());
+    }
+
+    let split = since.find(|c: char| !c.is_ascii_digit()).unwrap_or(since.len());
+    let (amount, unit) = since.split_at(split);
+    let amount: u64 = amount
+        .parse()
+        .map_err(|_| eyre!("Invalid --since value '{since}', expected e.g. 30m, 12h, 7d, 2w or 2025-01-31"))?;
+    let secs = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 60 * 60 * 24,
+        "w" => 60 * 60 * 24 * 7,
+        _ => bail!("Invalid --since unit '{unit}', expected one of s, m, h, d or w"),
+    };
+
+    now.checked_sub(Duration::from_secs(amount.saturating_mul(secs)))
+        .filter(|time| *time >= SystemTime::UNIX_EPOCH)
+        .ok_or_else(|| eyre!("--since value '{since}' is too large"))
+}
+
+fn format_duration(duration: Duration) -> String {
+    let millis = duration.as_millis();
+    match millis {
+        0..1000 => format!("{millis}ms"),
+        1000..60_000 => format!("{:.1}s", duration.as_secs_f64()),
+        _ => format!("{}m {}s", duration.as_secs() / 60, duration.as_secs() % 60),
+    }
+}
+
+fn format_time(time: SystemTime) -> String {
+    let time = OffsetDateTime::from(time);
+    let time = match time::UtcOffset::current_local_offset() {
+        Ok(offset) => time.to_offset(offset),
+        Err(_) => time,
+    };
+    time.format(format_description!("[year]-[month]-[day] [hour]:[minute]:[second]"))
+        .unwrap_or_default()
+}
+
+fn format_row(row: &CommandInfo) -> String {
+    let time = row.start_time.map(format_time).unwrap_or_else(|| "-".repeat(19));
+    let status = match row.exit_code {
+        Some(0) => format!("{:>4}", "✓").green().to_string(),
+        Some(code) => format!("{code:>4}").red().to_string(),
+        None => format!("{:>4}", "?").dark_grey().to_string(),
+    };
+    let duration = row
+        .start_time
+        .zip(row.end_time)
+        .and_then(|(start, end)| end.duration_since(start).ok())
+        .map(format_duration)
+        .unwrap_or_default();
+    format!(
+        "{}  {status}  {:>8}  {}",
+        time.dark_grey(),
+        duration.dark_grey(),
+        row.command.as_deref().unwrap_or_default()
+    )
+}
+
+fn format_stats(stats: &CommandStats) -> String {
+    let mut details = vec![format!("{} runs", stats.count)];
+    if stats.failures > 0 {
+        details.push(format!("{} failed", stats.failures));
+    }
+    if let Some(average) = stats.average_duration {
+        details.push(format!("avg {}", format_duration(average)));
+    }
+    if let Some(max) = stats.max_duration {
+        details.push(format!("max {}", format_duration(max)));
+    }
+    format!("{}  {}", stats.command, details.join(", ").dark_grey())
+}
+
+fn unix_secs(time: SystemTime) -> Option<u64> {
+    time.duration_since(SystemTime::UNIX_EPOCH).ok().map(|d| d.as_secs())
+}
+
+fn row_json(row: &CommandInfo) -> serde_json::Value {
+    json!({
+        "command": row.command,
+        "cwd": row.cwd,
+        "shell": row.shell,
+        "exit_code": row.exit_code,
+        "start_time": row.start_time.and_then(unix_secs),
+        "end_time": row.end_time.and_then(unix_secs),
+        "session_id": row.session_id,
+        "hostname": row.hostname,
+    })
+}
+
+fn stats_json(stats: &CommandStats) -> serde_json::Value {
+    json!({
+        "command": stats.command,
+        "count": stats.count,
+        "failures": stats.failures,
+        "average_duration_ms": stats.average_duration.map(|d| d.as_millis() as u64),
+        "max_duration_ms": stats.max_duration.map(|d| d.as_millis() as u64),
+    })
+}
+
+/// Shell code that binds Ctrl+R to `q history search --interactive`, starting from the current
+/// command line.
+fn widget(shell: &Shell) -> Result<String> {
+    Ok(match shell {
+        Shell::Zsh => formatdoc! {r#"
+            _q_history_widget() {{
+              local selected
+              if selected="$({CLI_BINARY_NAME} history search --interactive -- "$LBUFFER" </dev/tty)"; then
+                BUFFER="$selected"
+                CURSOR=${{#BUFFER}}
+              fi
+              zle reset-prompt
+            }}
+            zle -N _q_history_widget
+            bindkey '^R' _q_history_widget
+        "#},
+        Shell::Bash => formatdoc! {r#"
+            _q_history_widget() {{
+              local selected
+              if selected="$({CLI_BINARY_NAME} history search --interactive -- "$READLINE_LINE" </dev/tty)"; then
+                READLINE_LINE="$selected"
+                READLINE_POINT=${{#READLINE_LINE}}
+              fi
+            }}
+            bind -x '"\C-r": _q_history_widget'
+        "#},
+        Shell::Fish => formatdoc! {r#"
+            function _q_history_widget
+              set -l selected ({CLI_BINARY_NAME} history search --interactive -- (commandline) </dev/tty | string collect)
+              and commandline -r -- $selected
+              commandline -f repaint
+            end
+            bind \cr _q_history_widget
+        "#},
+        Shell::Nu | Shell::Xonsh | Shell::Elvish | Shell::PowerShell => {
+            bail!("The history widget is not supported for {shell}")
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_since() {
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+        assert_eq!(parse_since("30m", now).unwrap(), now - Duration::from_secs(30 * 60));
+        assert_eq!(
+            parse_since("2w", now).unwrap(),
+            now - Duration::from_secs(2 * 7 * 24 * 60 * 60)
+        );
+        assert_eq!(
+            parse_since("1970-01-02", now).unwrap(),
+            SystemTime::UNIX_EPOCH + Duration::from_secs(24 * 60 * 60)
+        );
+        assert!(parse_since("3y", now).is_err());
+        assert!(parse_since("soon", now).is_err());
+        assert!(parse_since("100w", now).is_err());
+    }
+
+    #[test]
+    fn test_filters_where_expression() {
+        let filters = HistoryFilters {
+            failed: true,
+            host: Some("my_laptop".into()),
+            session: Some("abc".into()),
+            ..Default::default()
+        };
+        assert_eq!(
+            filters.where_expression().unwrap().unwrap().to_string(),
+            r"(((exit_code IS NOT NULL AND exit_code != '0') AND session_id = 'abc') AND hostname LIKE '%my\_laptop%' ESCAPE '\')"
+        );
+        assert!(HistoryFilters::default().where_expression().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::from_millis(340)), "340ms");
+        assert_eq!(format_duration(Duration::from_millis(1500)), "1.5s");
+        assert_eq!(format_duration(Duration::from_secs(125)), "2m 5s");
+    }
+}
@@ -72,6 +72,17 @@ fn assign_shell_variable(shell: &Shell, name: impl Display, value: impl Display,
         (Shell::Fish, false) => format!("set -g {name} \"{value}\""),
         (Shell::Fish, true) => format!("set -gx {name} \"{value}\""),
         (Shell::Nu, _) => format!("let-env {name} = \"{value}\";"),
+        (Shell::Xonsh, _) => format!("${name} = \"{value}\""),
+        (Shell::Elvish, _) => format!("set-env {name} \"{value}\""),
+        (Shell::PowerShell, _) => format!("$env:{name} = \"{value}\""),
+    }
+}
+
+/// Elvish has a `printf` builtin that doesn't interpret backslash escapes, so use the external one
+fn printf_command(shell: Shell) -> &'static str {
+    match shell {
+        Shell::Elvish => "e:printf",
+        _ => "printf",
     }
 }
 
@@ -87,6 +98,9 @@ fn guard_source(
         Shell::Bash | Shell::Zsh => format!("if [ -z \"${{{guard_var}}}\" ]; then").into(),
         Shell::Fish => format!("if test -z \"${guard_var}\"").into(),
         Shell::Nu => format!("if env | any name == '{guard_var}' {{").into(),
+        Shell::Xonsh => format!("if not ${{...}}.get('{guard_var}'):").into(),
+        Shell::Elvish => format!("if (not (has-env {guard_var})) {{").into(),
+        Shell::PowerShell => format!("if (-not $env:{guard_var}) {{").into(),
     });
 
     let shell_var = assign_shell_variable(shell, guard_var, "1", export);
@@ -111,6 +125,8 @@ fn guard_source(
             Shell::Bash | Shell::Zsh => "fi\n",
             Shell::Fish => "end\n",
             Shell::Nu => "}",
+            Shell::Elvish | Shell::PowerShell => "}\n",
+            Shell::Xonsh => "\n",
         }
         .into(),
     );
@@ -130,7 +146,8 @@ async fn shell_init(shell: &Shell, when: &When, rcfile: &Option<String>) -> Resu
 
     let mut to_source = Vec::new();
 
-    if let Some(parent_process) = get_parent_process_exe() {
+    // xonsh runs on python, so its parent process is not the shell
+    if let Some(parent_process) = get_parent_process_exe().filter(|_| shell != &Shell::Xonsh) {
         to_source.push(assign_shell_variable(
             shell,
             Q_SHELL,
@@ -231,7 +248,7 @@ async fn shell_init(shell: &Shell, when: &When, rcfile: &Option<String>) -> Resu
                         [ -f '{bundle}/Contents/plugins/terminal/fish/config.fish' ] && source '{bundle}/Contents/plugins/terminal/fish/config.fish'
                         [ -f '{bundle}/Contents/plugins/terminal/fish/init.fish' ] && source '{bundle}/Contents/plugins/terminal/fish/init.fish'
                     "}),
-                    Shell::Nu => None,
+                    Shell::Nu | Shell::Xonsh | Shell::Elvish | Shell::PowerShell => None,
                 }
             } else {
                 None
@@ -301,7 +318,8 @@ fn shell_integrations_disabled_code(shell: Shell) -> String {
         "Q_SHELL_INTEGRATION_DISABLED",
         GuardAssignment::AfterSourcing,
         format!(
-            "printf '{PRODUCT_NAME} shell integration is disabled.\\nRe-enable by running: {}\\n'",
+            "{} '{PRODUCT_NAME} shell integration is disabled.\\nRe-enable by running: {}\\n'",
+            printf_command(shell),
             format!("{CLI_BINARY_NAME} _ local-state -d {SHELL_INTEGRATIONS_ENABLED_STATE_KEY}").magenta()
         ),
     )
@@ -315,8 +333,9 @@ fn input_method_prompt_code(shell: Shell, terminal: &Terminal) -> String {
         "Q_INPUT_METHOD_PROMPT",
         GuardAssignment::AfterSourcing,
         format!(
-            "printf '\\n🚀 {PRODUCT_NAME} supports {terminal}!\\n\\nEnable integrations with {terminal} by \
+            "{} '\\n🚀 {PRODUCT_NAME} supports {terminal}!\\n\\nEnable integrations with {terminal} by \
              running:\\n  {}\\n\\n'\n",
+            printf_command(shell),
             format!("{CLI_BINARY_NAME} integrations install input-method").magenta()
         ),
     )
@@ -329,7 +348,8 @@ fn inline_prompt_code(shell: Shell) -> String {
         "Q_INLINE_PROMPT",
         GuardAssignment::AfterSourcing,
         format!(
-            "printf '\\n{PRODUCT_NAME} now supports AI-powered inline completions!\\n\\nTo disable run: {}\\n\\n'\n",
+            "{} '\\n{PRODUCT_NAME} now supports AI-powered inline completions!\\n\\nTo disable run: {}\\n\\n'\n",
+            printf_command(shell),
             format!("{CLI_BINARY_NAME} inline disable").magenta()
         ),
     )
@@ -343,7 +363,8 @@ fn login_prompt_code(shell: Shell) -> String {
         "Q_LOGIN_PROMPT",
         GuardAssignment::AfterSourcing,
         format!(
-            "printf '\\nRun {} to log back into {PRODUCT_NAME}. Logging back in allows you to use AI features such as inline completions, {}, and {}\\n\\n'\n",
+            "{} '\\nRun {} to log back into {PRODUCT_NAME}. Logging back in allows you to use AI features such as inline completions, {}, and {}\\n\\n'\n",
+            printf_command(shell),
             format!("{CLI_BINARY_NAME} login").magenta(),
             format!("{CLI_BINARY_NAME} translate").magenta(),
             format!("{CLI_BINARY_NAME} chat").magenta()
@@ -28,6 +28,7 @@ use super::OutputFormat;
 use crate::util::CliContext;
 
 const INLINE_ENABLED_SETTINGS_KEY: &str = "inline.enabled";
+const INLINE_LOCAL_ONLY_SETTINGS_KEY: &str = "inline.local-only";
 
 #[derive(Debug, Clone, PartialEq, Subcommand)]
 pub enum InlineSubcommand {
@@ -72,6 +73,9 @@ impl InlineSubcommand {
             InlineSubcommand::Status => {
                 let enabled = settings.get_bool(INLINE_ENABLED_SETTINGS_KEY)?.unwrap_or(true);
                 println!("Inline is {}", if enabled { "enabled" } else { "disabled" }.bold());
+                if enabled && settings.get_bool(INLINE_LOCAL_ONLY_SETTINGS_KEY)?.unwrap_or(false) {
+                    println!("Suggestions only come from your shell history ({INLINE_LOCAL_ONLY_SETTINGS_KEY})");
+                }
             },
             InlineSubcommand::SetCustomization { arn } => {
                 let customizations = Client::new().await?.list_customizations().await?;
@@ -0,0 +1,64 @@
+use std::process::ExitCode;
+use std::time::Duration;
+
+use eyre::{
+    Result,
+    bail,
+};
+use fig_ipc::{
+    BufferedUnixStream,
+    SendRecvMessage,
+};
+use fig_proto::figterm::figterm_request_message::Request;
+use fig_proto::figterm::figterm_response_message::Response;
+use fig_proto::figterm::{
+    CommandOutput,
+    CommandOutputRequest,
+    CommandOutputResponse,
+    FigtermRequestMessage,
+    FigtermResponseMessage,
+};
+use fig_util::env_var::QTERM_SESSION_ID;
+use serde_json::json;
+
+/// The output of the command `index` commands back in the current terminal session, or of all
+/// the commands figterm kept, newest first.
+pub async fn command_outputs(index: Option<u32>) -> Result<Vec<CommandOutput>> {
+    let session_id = std::env::var(QTERM_SESSION_ID)?;
+    let figterm_socket_path = fig_util::directories::figterm_socket_path(&session_id)?;
+    let mut conn = BufferedUnixStream::connect(figterm_socket_path).await?;
+
+    match conn
+        .send_recv_message_timeout(
+            FigtermRequestMessage {
+                request: Some(Request::CommandOutput(CommandOutputRequest { index })),
+            },
+            Duration::from_secs(1),
+        )
+        .await?
+    {
+        Some(FigtermResponseMessage {
+            response: Some(Response::CommandOutput(CommandOutputResponse { outputs })),
+        }) => Ok(outputs),
+        res => bail!("Unexpected response from figterm: {res:?}"),
+    }
+}
+
+pub fn to_json(output: &CommandOutput) -> serde_json::Value {
+    json!({
+        "command": output.command,
+        "exit_code": output.exit_code,
+        "cwd": output.cwd,
+        "output": output.output,
+        "truncated": output.truncated,
+    })
+}
+
+pub(super) async fn command_output(index: Option<u32>) -> Result<ExitCode> {
+    let outputs = command_outputs(index).await?;
+    println!(
+        "{}",
+        serde_json::to_string(&outputs.iter().map(to_json).collect::<Vec<_>>())?
+    );
+    Ok(ExitCode::SUCCESS)
+}
@@ -1,3 +1,4 @@
+pub mod command_output;
 mod generate_ssh;
 mod inline_shell_completion;
 pub mod local_state;
@@ -276,6 +277,12 @@ pub enum InternalSubcommand {
     /// This lets us bypass a bug in Include and vdollar_expand that causes environment variables to
     /// be expanded, even in files that are only referenced in match blocks that resolve to false
     GenerateSsh(generate_ssh::GenerateSshArgs),
+    /// Prints the output of the last commands run in this terminal session as JSON, newest first
+    CommandOutput {
+        /// Only print the output of the command this many commands back, where 0 is the last one
+        #[arg(long)]
+        index: Option<u32>,
+    },
     InlineShellCompletion {
         #[arg(long, allow_hyphen_values = true)]
         buffer: String,
@@ -848,6 +855,7 @@ impl InternalSubcommand {
                 Ok(ExitCode::SUCCESS)
             },
             InternalSubcommand::GenerateSsh(args) => args.execute().await,
+            InternalSubcommand::CommandOutput { index } => command_output::command_output(index).await,
             InternalSubcommand::InlineShellCompletion { buffer } => Ok(inline_shell_completion(buffer).await),
             InternalSubcommand::InlineShellCompletionAccept { buffer, suggestion } => {
                 Ok(inline_shell_completion_accept(buffer, suggestion).await)
@@ -75,7 +75,10 @@ fn parent_status(ctx: &Context, current_pid: fig_os_shim::process_info::Pid) ->
         None => return Status::DontLaunch("No parent name".into()),
     };
 
-    let valid_parent = ["zsh", "bash", "fish", "nu"].contains(&parent_name);
+    let valid_parent = ["zsh", "bash", "fish", "nu", "elvish", "pwsh"].contains(&parent_name)
+        // xonsh runs on python, so look for it in the command line instead
+        || (parent_name.starts_with("python")
+            && parent_pid.cmdline().is_some_and(|cmdline| cmdline.contains("xonsh")));
 
     if env.in_ssh() && env.get_os(Q_TERM).is_none() {
         return Status::Launch(format!("In SSH and {Q_TERM} is not set").into());
@@ -387,6 +390,13 @@ mod tests {
             self
         }
 
+        fn parent_cmdline(mut self, cmdline: impl Into<String>) -> Self {
+            if let Some(parent) = self.parent.as_mut() {
+                parent.cmdline = Some(cmdline.into());
+            }
+            self
+        }
+
         fn grandparent_exe(mut self, exe: impl Into<PathBuf>) -> Self {
             self.grandparent_exe = Some(exe.into());
             self
@@ -475,6 +485,10 @@ mod tests {
         let tests = [
             test("no parent id").expect(1),
             test("invalid parent").parent_exe("/usr/bin/invalid").expect(1),
+            test("python parent that isn't xonsh")
+                .parent_exe("/usr/bin/python3")
+                .parent_cmdline("/usr/bin/python3 script.py")
+                .expect(1),
             test(format!("In Codespaces with {Q_TERM}"))
                 .parent_exe("/usr/bin/zsh")
                 .env(&[("CODESPACES", "1")])
@@ -517,6 +531,17 @@ mod tests {
                 .parent_exe("/usr/bin/zsh")
                 .grandparent_exe("/usr/bin/wezterm")
                 .expect(0),
+            test("on linux with elvish parent and valid grandparent exe")
+                .os(Os::Linux)
+                .parent_exe("/usr/bin/elvish")
+                .grandparent_exe("/usr/bin/wezterm")
+                .expect(0),
+            test("on linux with xonsh parent and valid grandparent exe")
+                .os(Os::Linux)
+                .parent_exe("/usr/bin/python3.12")
+                .parent_cmdline("/usr/bin/python3 /usr/bin/xonsh --login")
+                .grandparent_exe("/usr/bin/wezterm")
+                .expect(0),
             test("on linux with valid parent, invalid grandparent exe, and valid grandparent cmdline")
                 .os(Os::Linux)
                 .parent_exe("/usr/bin/zsh")
@@ -5,7 +5,9 @@ mod completion;
 mod debug;
 mod diagnostics;
 mod doctor;
+mod explain;
 mod feed;
+mod history;
 mod hook;
 mod init;
 mod inline;
@@ -182,6 +184,8 @@ pub enum CliRootCommands {
     /// Natural Language to Shell translation
     #[command(alias("ai"))]
     Translate(translate::TranslateArgs),
+    /// Explain a shell command, or the last failed command
+    Explain(explain::ExplainArgs),
     /// Enable/disable telemetry
     #[command(subcommand, hide = true)]
     Telemetry(telemetry::TelemetrySubcommand),
@@ -209,9 +213,19 @@ pub enum CliRootCommands {
         #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
         args: Vec<String>,
     },
+    /// Export, diff and apply configuration bundles
+    #[command(disable_help_flag = true)]
+    Config {
+        /// Args for the config subcommand
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
     /// Inline shell completions
     #[command(subcommand)]
     Inline(inline::InlineSubcommand),
+    /// Search and analyze your shell history
+    #[command(subcommand)]
+    History(history::HistorySubcommand),
 }
 
 impl CliRootCommands {
@@ -240,12 +254,15 @@ impl CliRootCommands {
             CliRootCommands::Restart { .. } => "restart",
             CliRootCommands::Integrations(_) => "integrations",
             CliRootCommands::Translate(_) => "translate",
+            CliRootCommands::Explain(_) => "explain",
             CliRootCommands::Telemetry(_) => "telemetry",
             CliRootCommands::Version { .. } => "version",
             CliRootCommands::Dashboard => "dashboard",
             CliRootCommands::Chat { .. } => "chat",
             CliRootCommands::Mcp { .. } => "mcp",
+            CliRootCommands::Config { .. } => "config",
             CliRootCommands::Inline(_) => "inline",
+            CliRootCommands::History(_) => "history",
         }
     }
 }
@@ -351,6 +368,7 @@ impl Cli {
                 },
                 CliRootCommands::Integrations(subcommand) => subcommand.execute().await,
                 CliRootCommands::Translate(args) => args.execute().await,
+                CliRootCommands::Explain(args) => args.execute().await,
                 CliRootCommands::Telemetry(subcommand) => subcommand.execute().await,
                 CliRootCommands::Version { changelog } => Self::print_version(changelog),
                 CliRootCommands::Dashboard => launch_dashboard(false).await,
@@ -368,7 +386,15 @@ impl Cli {
 
                     Self::execute_chat("mcp", Some(args), true).await
                 },
+                CliRootCommands::Config { args } => {
+                    if args.iter().any(|arg| ["--help", "-h"].contains(&arg.as_str())) {
+                        return Self::execute_chat("config", Some(vec!["--help".to_owned()]), false).await;
+                    }
+
+                    Self::execute_chat("config", Some(args), false).await
+                },
                 CliRootCommands::Inline(subcommand) => subcommand.execute(&cli_context).await,
+                CliRootCommands::History(subcommand) => subcommand.execute().await,
             },
             // Root command
             None => Self::execute_chat("chat", None, true).await,
@@ -553,7 +579,7 @@ async fn launch_dashboard(help_fallback: bool) -> Result<ExitCode> {
 }
 
 #[cfg(target_os = "linux")]
-fn qchat_path() -> Result<PathBuf> {
+pub(crate) fn qchat_path() -> Result<PathBuf> {
     use fig_os_shim::Context;
     use fig_util::consts::CHAT_BINARY_NAME;
 
@@ -568,7 +594,7 @@ fn qchat_path() -> Result<PathBuf> {
 }
 
 #[cfg(target_os = "macos")]
-fn qchat_path() -> Result<PathBuf> {
+pub(crate) fn qchat_path() -> Result<PathBuf> {
     use fig_util::consts::CHAT_BINARY_NAME;
     use macos_utils::bundle::get_bundle_path_for_executable;
 
@@ -713,6 +739,7 @@ mod test {
             CliRootCommands::Doctor(doctor::DoctorArgs {
                 all: false,
                 strict: false,
+                format: OutputFormat::Plain,
             })
         );
         assert_parse!(
@@ -720,6 +747,7 @@ mod test {
             CliRootCommands::Doctor(doctor::DoctorArgs {
                 all: true,
                 strict: false,
+                format: OutputFormat::Plain,
             })
         );
         assert_parse!(
@@ -727,6 +755,7 @@ mod test {
             CliRootCommands::Doctor(doctor::DoctorArgs {
                 all: false,
                 strict: true,
+                format: OutputFormat::Plain,
             })
         );
         assert_parse!(
@@ -734,8 +763,81 @@ mod test {
             CliRootCommands::Doctor(doctor::DoctorArgs {
                 all: true,
                 strict: true,
+                format: OutputFormat::Plain,
+            })
+        );
+        assert_parse!(
+            ["doctor", "--format", "json"],
+            CliRootCommands::Doctor(doctor::DoctorArgs {
+                all: false,
+                strict: false,
+                format: OutputFormat::Json,
+            })
+        );
+    }
+
+    #[test]
+    fn test_history() {
+        assert_parse!(
+            ["history", "search", "cargo", "--cwd", "--failed", "--since", "7d"],
+            CliRootCommands::History(history::HistorySubcommand::Search {
+                query: Some("cargo".into()),
+                filters: history::HistoryFilters {
+                    cwd: Some(PathBuf::from(".")),
+                    failed: true,
+                    since: Some("7d".into()),
+                    ..Default::default()
+                },
+                interactive: false,
+                limit: 50,
+                format: OutputFormat::Plain,
+            })
+        );
+        assert_parse!(
+            ["history", "search", "-i", "--session", "--", "git co"],
+            CliRootCommands::History(history::HistorySubcommand::Search {
+                query: Some("git co".into()),
+                filters: history::HistoryFilters {
+                    session: Some("".into()),
+                    ..Default::default()
+                },
+                interactive: true,
+                limit: 50,
+                format: OutputFormat::Plain,
+            })
+        );
+        assert_parse!(
+            ["history", "stats", "--host", "laptop", "-f", "json"],
+            CliRootCommands::History(history::HistorySubcommand::Stats {
+                filters: history::HistoryFilters {
+                    host: Some("laptop".into()),
+                    ..Default::default()
+                },
+                limit: 10,
+                format: OutputFormat::Json,
+            })
+        );
+    }
+
+    #[test]
+    fn test_explain() {
+        assert_parse!(
+            ["explain", "ls", "-la"],
+            CliRootCommands::Explain(explain::ExplainArgs {
+                command: vec!["ls".into(), "-la".into()],
+                last: false,
+                format: OutputFormat::Plain,
+            })
+        );
+        assert_parse!(
+            ["explain", "--last", "-f", "json"],
+            CliRootCommands::Explain(explain::ExplainArgs {
+                command: vec![],
+                last: true,
+                format: OutputFormat::Json,
             })
         );
+        assert!(Cli::try_parse_from([CLI_BINARY_NAME, "explain"]).is_err());
     }
 
     #[test]
@@ -3,6 +3,7 @@ use std::io::{
     IsTerminal,
     stdout,
 };
+use std::path::Path;
 use std::process::ExitCode;
 use std::sync::LazyLock;
 use std::time::Instant;
@@ -31,9 +32,21 @@ use fig_ipc::{
     BufferedUnixStream,
     SendMessage,
 };
+use fig_settings::history::{
+    History,
+    HistoryColumn,
+    Order,
+    OrderBy,
+    WhereExpression,
+};
 use fig_telemetry::SuggestionState;
-use fig_util::CLI_BINARY_NAME;
 use fig_util::env_var::QTERM_SESSION_ID;
+use fig_util::redact::redact;
+use fig_util::validate::validate;
+use fig_util::{
+    CLI_BINARY_NAME,
+    Shell,
+};
 use regex::{
     Captures,
     Regex,
@@ -51,12 +64,56 @@ use crate::util::spinner::{
 
 const SEEN_ONBOARDING_KEY: &str = "ai.seen-onboarding";
 
+/// Whether the current directory, its files and recent commands are added to the prompt.
+const LOCAL_CONTEXT_KEY: &str = "ai.translate-local-context";
+
+/// Tools whose availability is added to the prompt, so that generated commands use the ones that
+/// are installed.
+const COMMON_TOOLS: &[&str] = &[
+    "apt",
+    "aws",
+    "brew",
+    "cargo",
+    "curl",
+    "dnf",
+    "docker",
+    "fd",
+    "fzf",
+    "gh",
+    "git",
+    "go",
+    "jq",
+    "kubectl",
+    "node",
+    "npm",
+    "pacman",
+    "pip3",
+    "pnpm",
+    "python3",
+    "rg",
+    "terraform",
+    "wget",
+    "yarn",
+];
+
+/// The maximum number of entries of the current directory listed in the prompt.
+const MAX_CWD_ENTRIES: usize = 20;
+
+/// The number of recent commands from the history added to the prompt.
+const HISTORY_COUNT: usize = 10;
+
+/// Longer commands from the history are left out of the prompt.
+const MAX_HISTORY_COMMAND_LEN: usize = 200;
+
 #[derive(Debug, Args, Default, PartialEq, Eq)]
 pub struct TranslateArgs {
     input: Vec<String>,
     /// Number of completions to generate (must be <=5)
     #[arg(short, long, hide = true)]
     n: Option<i32>,
+    /// Break the generated command down into its parts
+    #[arg(long)]
+    explain: bool,
 }
 
 impl TranslateArgs {
@@ -139,7 +196,7 @@ fn theme() -> ColorfulTheme {
     }
 }
 
-async fn send_figterm(text: String, execute: bool) -> Result<()> {
+pub(super) async fn send_figterm(text: String, execute: bool) -> Result<()> {
     let session_id = std::env::var(QTERM_SESSION_ID)?;
     let mut conn = BufferedUnixStream::connect(fig_util::directories::figterm_socket_path(&session_id)?).await?;
     conn.send_message(fig_proto::figterm::FigtermRequestMessage {
@@ -159,21 +216,114 @@ struct CwResponse {
     completions: Vec<String>,
 }
 
-async fn generate_response(question: &str, n: i32) -> Result<CwResponse> {
-    let os = match std::env::consts::OS {
-        "macos" => "macOS",
-        "linux" => fig_util::system_info::linux::get_os_release()
-            .and_then(|a| a.name.as_deref())
-            .unwrap_or("Linux"),
-        "windows" => "Windows",
-        other => other,
-    };
+/// What is known about the environment the command will run in, added to the prompt as shell
+/// comments.
+#[derive(Debug, Default)]
+struct TranslateContext {
+    os: String,
+    shell: Option<Shell>,
+    cwd: Option<String>,
+    /// The first entries of the current directory, directories ending with `/`.
+    cwd_entries: Vec<String>,
+    cwd_entry_count: usize,
+    installed_tools: Vec<&'static str>,
+    missing_tools: Vec<&'static str>,
+    /// Recent successful commands, oldest first.
+    history: Vec<String>,
+}
+
+impl TranslateContext {
+    fn gather() -> Self {
+        let os = match std::env::consts::OS {
+            "macos" => "macOS",
+            "linux" => fig_util::system_info::linux::get_os_release()
+                .and_then(|a| a.name.as_deref())
+                .unwrap_or("Linux"),
+            "windows" => "Windows",
+            other => other,
+        };
+
+        let shell = Shell::current_shell()
+            .or_else(|| std::env::var_os("SHELL").and_then(|shell| Shell::try_find_shell(Path::new(&shell))));
+
+        let local_context = fig_settings::settings::get_bool_or(LOCAL_CONTEXT_KEY, true);
+        let cwd = std::env::current_dir().ok().filter(|_| local_context);
+        let (cwd_entries, cwd_entry_count) = cwd.as_deref().map(summarize_dir).unwrap_or_default();
+
+        let (installed_tools, missing_tools) = COMMON_TOOLS.iter().partition(|tool| which::which(tool).is_ok());
+
+        Self {
+            os: os.to_owned(),
+            shell,
+            cwd: cwd.map(|cwd| cwd.to_string_lossy().into_owned()),
+            cwd_entries,
+            cwd_entry_count,
+            installed_tools,
+            missing_tools,
+            history: if local_context { recent_commands() } else { vec![] },
+        }
+    }
+
+    fn comments(&self) -> Vec<String> {
+        let mut comments = vec![];
+        if let Some(shell) = self.shell {
+            comments.push(format!("# shell: {shell}"));
+        }
+        if let Some(cwd) = &self.cwd {
+            comments.push(format!("# current directory: {cwd}"));
+        }
+        if !self.cwd_entries.is_empty() {
+            let more = self.cwd_entry_count - self.cwd_entries.len();
+            let more = if more > 0 {
+                format!(" and {more} more")
+            } else {
+                String::new()
+            };
+            comments.push(format!("# files: {}{more}", self.cwd_entries.join(", ")));
+        }
+        if !self.installed_tools.is_empty() {
+            comments.push(format!("# installed: {}", self.installed_tools.join(", ")));
+        }
+        if !self.missing_tools.is_empty() {
+            comments.push(format!("# not installed: {}", self.missing_tools.join(", ")));
+        }
+        if !self.history.is_empty() {
+            comments.push("# recent commands:".into());
+            comments.extend(self.history.iter().map(|command| format!("#   {command}")));
+        }
+        comments
+    }
+
+    fn prompt(&self, question: &str) -> String {
+        let shell = match self.shell {
+            Some(shell) => format!("{shell} "),
+            None => String::new(),
+        };
+        let prompt_comment = format!(
+            "# A collection of {} {shell}shell one-liners that can be run interactively, they all must only be one line and line up with the comment above them",
+            self.os
+        );
 
-    let prompt_comment = format!(
-        "# A collection of {os} shell one-liners that can be run interactively, they all must only be one line and line up with the comment above them"
-    );
+        let mut header = vec![prompt_comment];
+        header.extend(self.comments());
 
-    let prompt = r#"# list all version of node on my path
+        let examples = match self.shell {
+            Some(Shell::Fish) => FISH_EXAMPLES,
+            _ => POSIX_EXAMPLES,
+        };
+
+        format!("{}\n\n{examples}{question}\n", header.join("\n"))
+    }
+
+    /// Whether `command` runs one of the common tools that isn't installed.
+    fn uses_missing_tool(&self, command: &str) -> bool {
+        breakdown(command)
+            .iter()
+            .any(|part| part.kind == PartKind::Program && self.missing_tools.contains(&part.text.as_str()))
+    }
+}
+
+const POSIX_EXAMPLES: &str = r#"# list all version of node on my path
 which -a node | xargs -I{} bash -c 'echo -n "{}: "; {} --version'
     
 # Generate all combination (e.g. A,T,C,G)
@@ -184,9 +334,67 @@ i=`wc -l $FILENAME|cut -d ' ' -f1`; cat $FILENAME| echo "scale=2;(`paste -sd+`)/
     
 #"#;
 
+const FISH_EXAMPLES: &str = r#"# list all version of node on my path
+for node in (which -a node); echo -n "$node: "; $node --version; end
+    
+# Generate all combination (e.g. A,T,C,G)
+for a in A T C G; for b in A T C G; echo $a$b; end; end
+    
+# Find average of input list/file of integers
+math (string join + (cat $FILENAME)) / (count (cat $FILENAME))
+    
+#"#;
+
+/// The sorted names of the first [MAX_CWD_ENTRIES] non hidden entries of `dir`, and the total
+/// number of entries.
+fn summarize_dir(dir: &Path) -> (Vec<String>, usize) {
+    let Ok(read_dir) = std::fs::read_dir(dir) else {
+        return (vec![], 0);
+    };
+
+    let mut entries = read_dir
+        .filter_map(|entry| entry.ok())
+        .map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            match entry.file_type().is_ok_and(|file_type| file_type.is_dir()) {
+                true => format!("{name}/"),
+                false => name,
+            }
+        })
+        .collect::<Vec<_>>();
+    let count = entries.len();
+
+    entries.retain(|name| !name.starts_with('.'));
+    entries.sort();
+    entries.truncate(MAX_CWD_ENTRIES);
+    (entries, count)
+}
+
+/// The most recent successful single line commands, oldest first.
+fn recent_commands() -> Vec<String> {
+    let where_expr = WhereExpression::all([
+        WhereExpression::Eq(HistoryColumn::ExitCode, "0".into()),
+        WhereExpression::NotLike(HistoryColumn::Command, format!("{CLI_BINARY_NAME} translate%")),
+    ]);
+    let order_by = vec![OrderBy::new(HistoryColumn::Id, Order::Desc)];
+    let Ok(rows) = History::new().rows(where_expr, order_by, HISTORY_COUNT, 0) else {
+        return vec![];
+    };
+
+    let mut commands = rows
+        .into_iter()
+        .filter_map(|row| row.command)
+        .filter(|command| !command.contains('\n') && command.len() <= MAX_HISTORY_COMMAND_LEN)
+        .map(|command| redact(&command))
+        .collect::<Vec<_>>();
+    commands.reverse();
+    commands
+}
+
+async fn generate_response(question: &str, n: i32, context: &TranslateContext) -> Result<CwResponse> {
     let mut input = RecommendationsInput {
         file_context: FileContext {
-            left_file_content: format!("{prompt_comment}\n\n{prompt}{question}\n"),
+            left_file_content: context.prompt(question),
             right_file_content: "".into(),
             filename: "commands.sh".into(),
             programming_language: ProgrammingLanguage {
@@ -211,9 +419,200 @@ i=`wc -l $FILENAME|cut -d ' ' -f1`; cat $FILENAME| echo "scale=2;(`paste -sd+`)/
             _ => break,
         }
     }
+
+    completions.retain(|completion| validate(completion) && !context.uses_missing_tool(completion));
     Ok(CwResponse { completions })
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(super) enum PartKind {
+    Program,
+    Flag,
+    Argument,
+    Assignment,
+    Operator,
+}
+
+/// A word or operator of a shell command, as written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct CommandPart {
+    pub text: String,
+    pub kind: PartKind,
+}
+
+impl CommandPart {
+    pub fn description(&self) -> &'static str {
+        match self.kind {
+            PartKind::Program => "command",
+            PartKind::Flag => "option",
+            PartKind::Argument => "argument",
+            PartKind::Assignment => "sets an environment variable for the command",
+            PartKind::Operator => match self.text.as_str() {
+                "|" => "pipes the output into the next command",
+                "||" => "runs the next command if this one fails",
+                "&&" => "runs the next command if this one succeeds",
+                ";" => "runs the next command afterwards",
+                "&" => "runs the command in the background",
+                ">" | "1>" => "writes the output to a file",
+                ">>" | "1>>" => "appends the output to a file",
+                "2>" => "writes the errors to a file",
+                "2>>" => "appends the errors to a file",
+                "<" => "reads the input from a file",
+                "<<" => "reads the input from the following lines",
+                "2>&1" => "sends the errors to the same place as the output",
+                _ => "redirects input or output",
+            },
+        }
+    }
+}
+
+fn is_operator(token: &str) -> bool {
+    token
+        .trim_start_matches(|c: char| c.is_ascii_digit())
+        .starts_with(['|', '&', ';', '<', '>'])
+}
+
+/// Splits `command` into words and operators. Quotes and escapes are kept, and operators inside
+/// quotes or subshells don't split words.
+fn tokenize(command: &str) -> Vec<String> {
+    let mut tokens = vec![];
+    let mut current = String::new();
+    let mut quote = None;
+    let mut depth = 0_usize;
+    let mut chars = command.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => {
+                quote = None;
+                current.push(c);
+            },
+            (Some('"') | None, '\\') => {
+                current.push(c);
+                current.extend(chars.next());
+            },
+            (Some(_), c) => current.push(c),
+            (None, '\'' | '"' | '`') => {
+                quote = Some(c);
+                current.push(c);
+            },
+            (None, '(') => {
+                depth += 1;
+                current.push(c);
+            },
+            (None, ')') => {
+                depth = depth.saturating_sub(1);
+                current.push(c);
+            },
+            (None, c) if depth > 0 => current.push(c),
+            (None, c) if c.is_whitespace() => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            },
+            (None, '|' | '&' | ';' | '<' | '>') => {
+                // File descriptors such as the `2` in `2>` are part of the operator
+                let mut operator = match current.chars().all(|c| c.is_ascii_digit()) && c != ';' {
+                    true => std::mem::take(&mut current),
+                    false => String::new(),
+                };
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+                operator.push(c);
+                if chars.peek() == Some(&c) && c != ';' {
+                    operator.extend(chars.next());
+                }
+                if c == '>' && chars.peek() == Some(&'&') {
+                    operator.extend(chars.next());
+                    while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+                        operator.push(digit);
+                    }
+                }
+                tokens.push(operator);
+            },
+            (None, c) => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+/// Breaks `command` down into the programs it runs, their options and arguments, and the
+/// operators between them.
+pub(super) fn breakdown(command: &str) -> Vec<CommandPart> {
+    let mut parts = vec![];
+    let mut expect_program = true;
+    let mut expect_target = false;
+
+    for text in tokenize(command) {
+        let kind = if is_operator(&text) {
+            // Redirects are followed by a file, unless they duplicate a descriptor like `2>&1`
+            let redirect = text.contains(['<', '>']);
+            expect_target = redirect && !text.ends_with(|c: char| c.is_ascii_digit());
+            expect_program |= !redirect;
+            PartKind::Operator
+        } else if expect_target {
+            expect_target = false;
+            PartKind::Argument
+        } else if expect_program && text.split_once('=').is_some_and(|(name, _)| is_variable_name(name)) {
+            PartKind::Assignment
+        } else if expect_program {
+            expect_program = false;
+            PartKind::Program
+        } else if text.starts_with('-') && text.len() > 1 {
+            PartKind::Flag
+        } else {
+            PartKind::Argument
+        };
+        parts.push(CommandPart { text, kind });
+    }
+    parts
+}
+
+fn is_variable_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Formats the [breakdown] of `command`, one part per line.
+fn format_breakdown(command: &str) -> String {
+    let parts = breakdown(command)
+        .into_iter()
+        .map(|part| {
+            let description = part.description().to_owned();
+            (part, description)
+        })
+        .collect::<Vec<_>>();
+    format_parts(&parts)
+}
+
+/// Formats the parts of a command next to their descriptions, one part per line.
+pub(super) fn format_parts(parts: &[(CommandPart, String)]) -> String {
+    let width = parts
+        .iter()
+        .map(|(part, _)| part.text.chars().count())
+        .max()
+        .unwrap_or_default()
+        .min(40);
+
+    parts
+        .iter()
+        .map(|(part, description)| {
+            let text = format!("{:width$}", part.text);
+            let text = match part.kind {
+                PartKind::Program => text.bright_magenta().to_string(),
+                _ => text,
+            };
+            format!("  {text}  {}", description.as_str().grey())
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
 fn warning_message(content: &str) {
     #[allow(clippy::type_complexity)]
     let warnings: &[(Regex, fn(&Captures<'_>) -> String)] = &[
@@ -350,7 +749,7 @@ impl TranslateArgs {
             eprintln!();
         }
 
-        let Self { input, n } = self;
+        let Self { input, n, explain } = self;
         let mut input = if input.is_empty() { None } else { Some(input.join(" ")) };
 
         let n = match n {
@@ -374,10 +773,16 @@ impl TranslateArgs {
                 },
             };
 
-            match &generate_response(&question, 1).await?.completions[..] {
+            match &generate_response(&question, 1, &TranslateContext::gather())
+                .await?
+                .completions[..]
+            {
                 [] => eyre::bail!("no valid completions were generated"),
                 [res, ..] => {
                     println!("{res}");
+                    if explain {
+                        eprintln!("{}", format_breakdown(res));
+                    }
                     return Ok(ExitCode::SUCCESS);
                 },
             };
@@ -390,6 +795,8 @@ impl TranslateArgs {
             std::process::exit(0);
         });
 
+        let context = TranslateContext::gather();
+
         'ask_loop: loop {
             let question = match input {
                 Some(ref input) => input.clone(),
@@ -414,7 +821,7 @@ impl TranslateArgs {
                 ]);
 
                 let response_time_start = Instant::now();
-                let res = match generate_response(&question, n).await {
+                let res = match generate_response(&question, n, &context).await {
                     Ok(res) => res,
                     Err(err) => {
                         spinner.stop_with_message("".into());
@@ -441,6 +848,10 @@ impl TranslateArgs {
 
                         spinner.stop_with_message(format!("{spinner_text}{}", highlighter(choice)));
                         println!();
+                        if explain {
+                            println!("{}", format_breakdown(choice));
+                            println!();
+                        }
                         warning_message(choice);
 
                         let actions: Vec<DialogActions> = fig_settings::settings::get("ai.menu-actions")
@@ -632,7 +1043,7 @@ mod test {
         ];
 
         for prompt in prompts {
-            let res = generate_response(prompt, 1).await.unwrap();
+            let res = generate_response(prompt, 1, &TranslateContext::gather()).await.unwrap();
             let first = res.completions.first().unwrap();
             std::println!("{prompt},{first}");
             tokio::time::sleep(Duration::from_secs(1)).await;
@@ -648,4 +1059,81 @@ mod test {
     fn test_highlighter() {
         std::println!("{}", highlighter("echo $PATH $ABC $USER $HOME $DEF"));
     }
+
+    #[test]
+    fn test_prompt() {
+        let context = TranslateContext {
+            os: "Linux".into(),
+            shell: Some(Shell::Fish),
+            cwd: Some("/repo".into()),
+            cwd_entries: vec!["Cargo.toml".into(), "src/".into()],
+            cwd_entry_count: 5,
+            installed_tools: vec!["cargo", "git"],
+            missing_tools: vec!["docker"],
+            history: vec!["git status".into(), "cargo build".into()],
+        };
+
+        let prompt = context.prompt("run the tests");
+        assert!(prompt.starts_with("# A collection of Linux fish shell one-liners"));
+        assert!(prompt.contains(
+            "# shell: fish\n# current directory: /repo\n# files: Cargo.toml, src/ and 3 more\n# installed: cargo, \
+             git\n# not installed: docker\n# recent commands:\n#   git status\n#   cargo build\n"
+        ));
+        assert!(prompt.contains(FISH_EXAMPLES));
+        assert!(prompt.ends_with("#run the tests\n"));
+
+        assert!(context.uses_missing_tool("cargo build && docker build ."));
+        assert!(!context.uses_missing_tool("echo docker"));
+        assert!(TranslateContext::default().prompt("x").contains(POSIX_EXAMPLES));
+    }
+
+    #[test]
+    fn test_summarize_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        std::fs::write(dir.path().join("README.md"), "").unwrap();
+        std::fs::write(dir.path().join(".env"), "").unwrap();
+
+        assert_eq!(summarize_dir(dir.path()), (vec!["README.md".into(), "src/".into()], 3));
+        assert_eq!(summarize_dir(&dir.path().join("missing")), (vec![], 0));
+    }
+
+    #[test]
+    fn test_breakdown() {
+        let parts = |command: &str| {
+            breakdown(command)
+                .into_iter()
+                .map(|part| (part.text, part.kind))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            parts(r#"FOO=1 find . -name "*.rs" | xargs wc -l > out.txt 2>&1 && echo "a && b" $(date +%s)"#),
+            vec![
+                ("FOO=1".into(), PartKind::Assignment),
+                ("find".into(), PartKind::Program),
+                (".".into(), PartKind::Argument),
+                ("-name".into(), PartKind::Flag),
+                (r#""*.rs""#.into(), PartKind::Argument),
+                ("|".into(), PartKind::Operator),
+                ("xargs".into(), PartKind::Program),
+                ("wc".into(), PartKind::Argument),
+                ("-l".into(), PartKind::Flag),
+                (">".into(), PartKind::Operator),
+                ("out.txt".into(), PartKind::Argument),
+                ("2>&1".into(), PartKind::Operator),
+                ("&&".into(), PartKind::Operator),
+                ("echo".into(), PartKind::Program),
+                (r#""a && b""#.into(), PartKind::Argument),
+                ("$(date +%s)".into(), PartKind::Argument),
+            ]
+        );
+
+        assert_eq!(parts("< in.txt sort"), vec![
+            ("<".into(), PartKind::Operator),
+            ("in.txt".into(), PartKind::Argument),
+            ("sort".into(), PartKind::Program),
+        ]);
+        assert!(format_breakdown("ls -la").contains("option"));
+    }
 }
@@ -12,6 +12,13 @@ const translateSettings: PrefSection[] = [
         type: "boolean",
         default: true,
       },
+      {
+        id: "ai.translate-local-context",
+        title: "Local Context",
+        description: `Send the current directory, its files and your recent commands, with secrets redacted, along with \`${CLI_BINARY_NAME} translate\` requests.`,
+        type: "boolean",
+        default: true,
+      },
       // {
       //   id: "ai.menu-actions",
       //   title: "Menu Actions",